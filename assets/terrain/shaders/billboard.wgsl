@@ -0,0 +1,84 @@
+#import bevy_core_pipeline::tonemapping::tone_mapping
+#import bevy_pbr::{
+    mesh_functions as mfns,
+    mesh_view_bindings::view,
+    mesh_types::MESH_FLAGS_SHADOW_RECEIVER_BIT,
+    pbr_types::{PbrInput, pbr_input_new},
+    pbr_functions as fns,
+    view_transformations::position_world_to_clip,
+}
+
+@group(2) @binding(1)
+var billboard: texture_2d<f32>;
+@group(2) @binding(2)
+var billboard_sampler: sampler;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) offset: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let base = mfns::mesh_position_local_to_world(
+        mfns::get_world_from_local(instance_index),
+        vec4<f32>(vertex.position, 1.0)
+    );
+
+    // Turn the quad around the vertical axis to face the camera, so that trees stay upright.
+    var to_camera = view.world_position.xz - base.xz;
+    if dot(to_camera, to_camera) < 0.0001 {
+        to_camera = vec2<f32>(0., 1.);
+    }
+    let facing = normalize(to_camera);
+    let right = vec3<f32>(facing.y, 0., -facing.x);
+
+    out.world_position = vec4<f32>(
+        base.xyz + right * vertex.offset.x + vec3<f32>(0., vertex.offset.y, 0.),
+        1.0
+    );
+    out.position = position_world_to_clip(out.world_position.xyz);
+    // Tilt the normal upwards so that billboards are lit much like the ground around them.
+    out.world_normal = normalize(vec3<f32>(facing.x, 1.0, facing.y));
+    out.uv = vertex.uv;
+    return out;
+}
+
+@fragment
+fn fragment(
+    @builtin(front_facing) is_front: bool,
+    mesh: VertexOutput,
+) -> @location(0) vec4<f32> {
+    let color = textureSample(billboard, billboard_sampler, mesh.uv);
+    if color.a < 0.5 {
+        discard;
+    }
+
+    var pbr_input: PbrInput = pbr_input_new();
+    pbr_input.material.base_color = vec4<f32>(color.rgb, 1.0);
+    pbr_input.material.metallic = 0.;
+    pbr_input.material.perceptual_roughness = 0.9;
+    pbr_input.frag_coord = mesh.position;
+    pbr_input.world_position = mesh.world_position;
+    pbr_input.world_normal = fns::prepare_world_normal(
+        mesh.world_normal,
+        false,
+        is_front,
+    );
+    pbr_input.flags |= MESH_FLAGS_SHADOW_RECEIVER_BIT;
+
+    pbr_input.is_orthographic = false;
+    pbr_input.N = normalize(pbr_input.world_normal);
+    pbr_input.V = fns::calculate_view(mesh.world_position, pbr_input.is_orthographic);
+
+    return tone_mapping(fns::apply_pbr_lighting(pbr_input), view.color_grading);
+}
@@ -13,25 +13,17 @@ var noise: texture_2d<f32>;
 @group(2) @binding(2)
 var noise_sampler: sampler;
 
+// One layer per ground surface type.
 @group(2) @binding(3)
-var grass: texture_2d<f32>;
+var surfaces: texture_2d_array<f32>;
 @group(2) @binding(4)
-var grass_sampler: sampler;
+var surfaces_sampler: sampler;
 
+// One layer per terrain effect layer.
 @group(2) @binding(5)
-var dirt: texture_2d<f32>;
+var effects: texture_2d_array<f32>;
 @group(2) @binding(6)
-var dirt_sampler: sampler;
-
-@group(2) @binding(7)
-var moss: texture_2d<f32>;
-@group(2) @binding(8)
-var moss_sampler: sampler;
-
-@group(2) @binding(9)
-var cobbles: texture_2d<f32>;
-@group(2) @binding(10)
-var cobbles_sampler: sampler;
+var effects_sampler: sampler;
 
 @group(2) @binding(20)
 var biomes: texture_2d<u32>;
@@ -44,6 +36,17 @@ var<uniform> water_color: vec4<f32>;
 @group(2) @binding(23)
 var<uniform> realm_offset: vec2<f32>;
 
+// Texture scale for each ground surface type, packed four to a vector.
+@group(2) @binding(24)
+var<uniform> surface_scale: array<vec4<f32>, 3>;
+
+// For each terrain effect layer: blend mode, texture scale, and whether it has a texture.
+@group(2) @binding(25)
+var<uniform> effect_params: array<vec4<f32>, 32>;
+
+@group(2) @binding(26)
+var<uniform> effect_tint: array<vec4<f32>, 32>;
+
 const NUM_GROUND_TYPES = 9u;
 const GT_ROCK = 0u;
 const GT_DIRT = 1u;
@@ -55,6 +58,13 @@ const GT_TUNDRA = 6u;
 const GT_SNOW = 7u;
 const GT_CHAPARRAL = 8u;
 
+// Terrain effect blend modes, matching `TerrainBlendMode`.
+const FX_UNDER = 0u;
+const FX_REPLACE = 1u;
+const FX_PAVING = 2u;
+const FX_COVER = 3u;
+const FX_TINT = 4u;
+
 const UV_ROT = mat2x2<f32>(
     vec2<f32>(0.8775825618903728, 0.479425538604203),
     vec2<f32>(-0.479425538604203, 0.8775825618903728));
@@ -92,7 +102,9 @@ struct VertexOutput {
     @location(5) biome_weight_2: vec4<f32>,
     // @location(6) biome_weights: BiomeWeights,
 
-    @location(7) terrain_style: vec4<f32>,
+    // Indices of the effect layers used by this parcel, and their strength at this vertex.
+    @location(7) @interpolate(flat) fx_layers: vec4<u32>,
+    @location(8) fx_strength: vec4<f32>,
 };
 
 struct BiomeSurfaceAttrs {
@@ -105,53 +117,48 @@ struct BiomeSurfaceAttrs {
     edge_t0: f32,
     edge_t1: f32,
 
-    // Texture scale
-    tx_scale: f32,
-
     // Darkened color which shows up at edges of top surface (near roads etc.).
     edge_tint: vec3<f32>,
 }
 
-const dirt_biome = BiomeSurfaceAttrs(
-    0.99,
-    0.1, 0.4, 0.5,
-    0.5, 0.45, 0.9,
-    0.1, // tx_scale
-    vec3<f32>(0.65, 0.65, 0.65));
-const grass_biome = BiomeSurfaceAttrs(
-    0.99,
-    0.5, 0.45, 0.55,
-    0.5, 0.48, 0.60,
-    0.35, // tx_scale
-    vec3<f32>(0.20, 0.5, 0.30));
-const moss_biome = BiomeSurfaceAttrs(
-    0.99,
-    0.8, 0.65, 0.67,
-    0.8, 0.65, 0.85,
-    0.45, // tx_scale
-    vec3<f32>(0.6, 0.6, 0.6));
-
-struct TerrainFxSurfaceAttrs {
-    // Roughness of this biome surface
-    // roughness: f32,
-    // blend_var: f32,
-    // blend_t0: f32,
-    // blend_t1: f32,
-    // edge_var: f32,
-    // edge_t0: f32,
-    // edge_t1: f32,
-
-    // Texture scale
-    tx_scale: f32,
-
-    // Darkened color which shows up at edges of top surface (near roads etc.).
-    // edge_tint: vec3<f32>,
+fn surface_attrs(surface: u32) -> BiomeSurfaceAttrs {
+    // Dirt, sand and tundra.
+    var attrs = BiomeSurfaceAttrs(
+        0.99,
+        0.1, 0.4, 0.5,
+        0.5, 0.45, 0.9,
+        vec3<f32>(0.65, 0.65, 0.65));
+    switch surface {
+        case GT_ROCK: {
+            attrs.roughness = 0.95;
+            attrs.edge_tint = vec3<f32>(0.55, 0.55, 0.55);
+        }
+        case GT_GRASS, GT_CHAPARRAL: {
+            attrs = BiomeSurfaceAttrs(
+                0.99,
+                0.5, 0.45, 0.55,
+                0.5, 0.48, 0.60,
+                vec3<f32>(0.20, 0.5, 0.30));
+        }
+        case GT_MOSS, GT_TAIGA: {
+            attrs = BiomeSurfaceAttrs(
+                0.99,
+                0.8, 0.65, 0.67,
+                0.8, 0.65, 0.85,
+                vec3<f32>(0.6, 0.6, 0.6));
+        }
+        case GT_SNOW: {
+            attrs = BiomeSurfaceAttrs(
+                0.7,
+                0.6, 0.45, 0.55,
+                0.6, 0.48, 0.70,
+                vec3<f32>(0.8, 0.8, 0.85));
+        }
+        default: {}
+    }
+    return attrs;
 }
 
-const cobbles_fx = TerrainFxSurfaceAttrs(
-    0.45, // tx_scale
-);
-
 struct SurfaceAccum {
     color: vec4<f32>,
     terrain_noise: f32,
@@ -182,6 +189,20 @@ fn blend_biome(
         blend);
 }
 
+fn surface_tx_scale(surface: u32) -> f32 {
+    return surface_scale[surface / 4u][surface % 4u];
+}
+
+fn surface_weight(mesh: VertexOutput, surface: u32) -> f32 {
+    var weights = mesh.biome_weight_2;
+    if surface < 4u {
+        weights = mesh.biome_weight_0;
+    } else if surface < 8u {
+        weights = mesh.biome_weight_1;
+    }
+    return weights[surface % 4u];
+}
+
 @vertex
 fn vertex(vertex: Vertex, @builtin(instance_index) instance_index: u32) -> VertexOutput {
     var out: VertexOutput;
@@ -240,11 +261,17 @@ fn vertex(vertex: Vertex, @builtin(instance_index) instance_index: u32) -> Verte
         vec4<f32>(vertex.position, 1.0)
     );
 
-    out.terrain_style = vec4(
-		f32(vertex.terrain_style.x & 0xffu) * 1.0 / 255.0,
-		f32((vertex.terrain_style.x >> 8u) & 0xffu) * 1.0 / 255.0,
-		f32((vertex.terrain_style.x >> 16u) & 0xffu) * 1.0 / 255.0,
-		f32((vertex.terrain_style.x >> 24u) & 0xffu) * 1.0 / 255.0
+    out.fx_layers = vec4<u32>(
+        vertex.terrain_style.x & 0xffu,
+        (vertex.terrain_style.x >> 8u) & 0xffu,
+        (vertex.terrain_style.x >> 16u) & 0xffu,
+        (vertex.terrain_style.x >> 24u) & 0xffu
+    );
+    out.fx_strength = vec4(
+		f32(vertex.terrain_style.y & 0xffu) * 1.0 / 255.0,
+		f32((vertex.terrain_style.y >> 8u) & 0xffu) * 1.0 / 255.0,
+		f32((vertex.terrain_style.y >> 16u) & 0xffu) * 1.0 / 255.0,
+		f32((vertex.terrain_style.y >> 24u) & 0xffu) * 1.0 / 255.0
 	);
 
     out.world_normal = mfns::mesh_normal_local_to_world(vertex.normal, instance_index);
@@ -255,8 +282,13 @@ fn vertex(vertex: Vertex, @builtin(instance_index) instance_index: u32) -> Verte
         biome_weight[2],
         biome_weight[3]
     );
-    out.biome_weight_1 = vec4<f32>(0., 0., 0., 0.);
-    out.biome_weight_2 = vec4<f32>(0., 0., 0., 0.);
+    out.biome_weight_1 = vec4<f32>(
+        biome_weight[4],
+        biome_weight[5],
+        biome_weight[6],
+        biome_weight[7]
+    );
+    out.biome_weight_2 = vec4<f32>(biome_weight[8], 0., 0., 0.);
     return out;
 }
 
@@ -267,11 +299,6 @@ fn fragment(
 ) -> @location(0) vec4<f32> {
     let uv = vec2<f32>(mesh.world_position.xz);
 
-	let fx_cobbles = mesh.terrain_style.x;
-	let fx_soil = mesh.terrain_style.y;
-	let fx_earth = mesh.terrain_style.z;
-	// let fx_path = mesh.terrain_style.w;
-
     let slope = 1.0 - pow(mesh.slope, 2.);
 
     var sfc: SurfaceAccum;
@@ -281,7 +308,8 @@ fn fragment(
     sfc.under_mix = 0.;
     sfc.under_noise = textureSample(noise, noise_sampler, fract(uv * 0.3)).x;
 
-    let dirt_color = textureSample(dirt, dirt_sampler, fract(uv * dirt_biome.tx_scale));
+    let dirt_color = textureSample(
+        surfaces, surfaces_sampler, fract(uv * surface_tx_scale(GT_DIRT)), GT_DIRT);
 
     // vec3 underColor = dirtColor.xyz;
     let under_roughness = 0.9 - (dirt_color.r - dirt_color.g - dirt_color.b) * 0.8; // Roughness for underlayers
@@ -293,78 +321,94 @@ fn fragment(
 
     var under_color = dirt_color.xyz;
 
-	// Packed and dried earth effects.
-    if fx_earth > 0. {
-        let fx_blend: f32 = smoothstep(0.2, 1.0, fx_earth + sfc.under_noise);
-        // under_color = dirt_color.rgb;
-		// float pathBlend = smoothstep(0., .5, fxPath);
-		// underColor = mix(
-		// 	dirtColor.xyz * 0.5,
-		// 	dirtColor.xyz * (1. - vBiomeWeight[Moss] * 0.5),
-		// 	pathBlend);
-		// underMix = max(underMix, smoothstep(0., 0.9, fxPath + underNoise * 0.6));
-		sfc.under_darken = max(
-            sfc.under_darken,
-            smoothstep(0.2, 0.9, fx_earth * 1.4 + sfc.under_noise * 1.4));
-		sfc.under_mix = max(sfc.under_mix, fx_blend);
-    }
-
-	// Tilled soil effects - farm fields, graves, etc.
-    if fx_soil > 0. {
-        let fx_blend: f32 = fx_soil;
-        under_color = dirt_color.rgb * vec3f(.7, .6, .6);
-		// float soilBlend = smoothstep(0., .3, fxSoil);
-		// vec4 soilColor = texture2D(dirtTexture, vPosition.xz * 0.5) * vec4(.7, .6, .6, 1.);
-		// underColor = mix(underColor, soilColor.rgb, soilBlend);
-		// underRoughness = mix(underRoughness, 0.9, soilBlend);
-		// underMix = max(underMix, smoothstep(0.3, 1.1, fxSoil * 2. + underNoise * 0.6));
-		sfc.under_darken = max(
-            sfc.under_darken,
-            smoothstep(0.3, 1.1, fx_soil * 2. + sfc.under_noise * 0.6));
-		sfc.under_mix = max(sfc.under_mix, fx_blend);
-    }
+    // Surface colors which cover the top surface, and tints applied to the final color.
+    var cover_color = vec3<f32>(0., 0., 0.);
+    var cover_mix = 0.;
+    var tint = vec3<f32>(1., 1., 1.);
 
-	// Cobblestone effects.
-    if fx_cobbles > 0. {
-        let cobbles_color = textureSample(cobbles, cobbles_sampler, fract(uv * UV_ROT * cobbles_fx.tx_scale));
-		let cracks = cobbles_color.r + cobbles_color.g + cobbles_color.b;
-        let fx_blend: f32 = smoothstep(0.9, 1.2, fx_cobbles + sfc.under_noise * 1.9 - 0.1 + cracks * 0.9);
-		let dark_blend = smoothstep(0.0, 1.0, fx_cobbles * 1.7 + sfc.under_noise * 0.8 - 0.05);
-		// underRoughness = mix(underRoughness, 1.0 - cracks * 0.7, roadBlend);
-		sfc.under_mix = max(sfc.under_mix, fx_blend);
-        under_color = mix(
-            mix(under_color, under_color * 0.7, dark_blend),
-            cobbles_color.rgb, fx_blend);
-		sfc.under_darken = max(sfc.under_darken, dark_blend);
+    // Terrain effects, in the order they appear in the effects table.
+    for (var slot = 0u; slot < 4u; slot++) {
+        let fx_strength = mesh.fx_strength[slot];
+        if fx_strength <= 0. {
+            continue;
+        }
+        let layer = mesh.fx_layers[slot];
+        let params = effect_params[layer];
+        var fx_color = dirt_color;
+        if params.z > 0. {
+            fx_color = textureSample(
+                effects, effects_sampler, fract(uv * UV_ROT * params.y), layer);
+        }
+        fx_color = vec4<f32>(fx_color.rgb * effect_tint[layer].rgb, fx_color.a);
+
+        switch u32(params.x) {
+            // Packed and dried earth effects - paths, trails.
+            case FX_UNDER: {
+                let fx_blend: f32 = smoothstep(0.2, 1.0, fx_strength + sfc.under_noise);
+                sfc.under_darken = max(
+                    sfc.under_darken,
+                    smoothstep(0.2, 0.9, fx_strength * 1.4 + sfc.under_noise * 1.4));
+                sfc.under_mix = max(sfc.under_mix, fx_blend);
+            }
+
+            // Tilled soil effects - farm fields, graves, mud, etc.
+            case FX_REPLACE: {
+                under_color = fx_color.rgb;
+                sfc.under_darken = max(
+                    sfc.under_darken,
+                    smoothstep(0.3, 1.1, fx_strength * 2. + sfc.under_noise * 0.6));
+                sfc.under_mix = max(sfc.under_mix, fx_strength);
+            }
+
+            // Cobblestone and gravel effects.
+            case FX_PAVING: {
+                let cracks = fx_color.r + fx_color.g + fx_color.b;
+                let fx_blend: f32 = smoothstep(
+                    0.9, 1.2, fx_strength + sfc.under_noise * 1.9 - 0.1 + cracks * 0.9);
+                let dark_blend = smoothstep(
+                    0.0, 1.0, fx_strength * 1.7 + sfc.under_noise * 0.8 - 0.05);
+                sfc.under_mix = max(sfc.under_mix, fx_blend);
+                under_color = mix(
+                    mix(under_color, under_color * 0.7, dark_blend),
+                    fx_color.rgb, fx_blend);
+                sfc.under_darken = max(sfc.under_darken, dark_blend);
+            }
+
+            // Snow drifts and the like.
+            case FX_COVER: {
+                let fx_blend = smoothstep(0.2, 0.8, fx_strength + sfc.under_noise * 0.4 - 0.2);
+                cover_color = fx_color.rgb;
+                cover_mix = max(cover_mix, fx_blend);
+            }
+
+            // Scorch marks and stains.
+            case FX_TINT: {
+                let fx_blend = smoothstep(0.0, 1.0, fx_strength + sfc.under_noise * 0.5 - 0.25);
+                tint *= mix(vec3<f32>(1., 1., 1.), fx_color.rgb, fx_blend);
+            }
+
+            default: {}
+        }
     }
 
     // No terrain fx underwater
     sfc.under_mix = max(0., min(sfc.under_mix, 2.0 + mesh.world_position.y * 3.));
     sfc.under_darken = max(0., min(sfc.under_darken, 2.0 + mesh.world_position.y * 3.));
 
-    // Dirt surface
-    let bw_dirt = mesh.biome_weight_0.y;
-    if bw_dirt > 0. {
-        blend_biome(&sfc, dirt_biome, bw_dirt, dirt_color.rgb);
-    }
-
-    // Grass surface
-    let bw_grass = mesh.biome_weight_0.z;
-    if bw_grass > 0. {
-        let grass_color = textureSample(grass, grass_sampler, fract(uv * UV_ROT * grass_biome.tx_scale));
-        blend_biome(&sfc, grass_biome, bw_grass, grass_color.rgb);
-    }
-
-    // Moss surface
-    let bw_moss = mesh.biome_weight_0.w;
-    if bw_moss > 0. {
-        let moss_color = textureSample(moss, moss_sampler, fract(uv * UV_ROT * moss_biome.tx_scale));
-        blend_biome(&sfc, moss_biome, bw_moss, moss_color.rgb);
+    // Blend the surface of each biome which touches this parcel.
+    for (var i = 0u; i < NUM_GROUND_TYPES; i++) {
+        let weight = surface_weight(mesh, i);
+        if weight > 0. {
+            let tx_color = textureSample(
+                surfaces, surfaces_sampler, fract(uv * UV_ROT * surface_tx_scale(i)), i);
+            blend_biome(&sfc, surface_attrs(i), weight, tx_color.rgb);
+        }
     }
 
     // Mix top layer and under layer.
-    let combined = mix(sfc.color, vec4<f32>(under_color, under_roughness), sfc.under_mix);
-    var diffuse_color = vec4<f32>(combined.xyz, 1.0);
+    var combined = mix(sfc.color, vec4<f32>(under_color, under_roughness), sfc.under_mix);
+    combined = mix(combined, vec4<f32>(cover_color, 0.8), cover_mix);
+    var diffuse_color = vec4<f32>(combined.xyz * tint, 1.0);
     let roughness = combined.w;
 
     // If underwater, then mix in dark blue
@@ -46,6 +46,7 @@ struct VertexOutput {
     @location(0) world_position: vec4<f32>,
     @location(1) world_normal: vec3<f32>,
     @location(2) depth: f32,
+    @location(3) motion: vec2<f32>,
 };
 
 struct WaveAccum {
@@ -55,6 +56,9 @@ struct WaveAccum {
 
 const PI: f32 = 3.14159;
 
+// Length of time, in seconds, over which flowing water is advected before the pattern resets.
+const FLOW_CYCLE: f32 = 2.0;
+
 fn add_wave(
     freq: f32,
     strength: f32,
@@ -107,22 +111,13 @@ fn vertex(vertex: Vertex, @builtin(instance_index) instance_index: u32) -> Verte
 
     out.world_normal = mfns::mesh_normal_local_to_world(normal, instance_index);
     out.depth = vertex.depth_motion.x;
+    out.motion = vertex.depth_motion.yz;
     return out;
 }
 
-@fragment
-fn fragment(
-    @builtin(front_facing) is_front: bool,
-    mesh: VertexOutput,
-) -> @location(0) vec4<f32> {
-    let uv = vec2<f32>(mesh.world_position.xz);
-
-    let water_depth = mesh.world_position.y + mesh.depth;
-    var normal = mesh.world_normal;
+// Surface normal perturbation from small waves, sampled at the given position.
+fn wave_chop(uv: vec2<f32>) -> vec3<f32> {
     var chop = vec3<f32>(0.);
-
-#ifdef FRAGMENT_WAVES
-    var d = 1. - min(1., length(uv) / 16.);
     var motion = vec2(0., 0.);
     var iter: f32 = 1.;
     var frequency = 0.05;
@@ -146,6 +141,28 @@ fn fragment(
         // weight *= 0.82;
         iter += 1232.399963;
     }
+    return chop;
+}
+
+@fragment
+fn fragment(
+    @builtin(front_facing) is_front: bool,
+    mesh: VertexOutput,
+) -> @location(0) vec4<f32> {
+    let uv = vec2<f32>(mesh.world_position.xz);
+
+    let water_depth = mesh.world_position.y + mesh.depth;
+    var normal = mesh.world_normal;
+    var chop = vec3<f32>(0.);
+
+#ifdef FRAGMENT_WAVES
+    // Carry the waves downstream. Two copies of the pattern, half a cycle apart, are blended
+    // so that each one can be reset while it is invisible.
+    let phase0 = fract(globals.time / FLOW_CYCLE);
+    let phase1 = fract(globals.time / FLOW_CYCLE + 0.5);
+    let chop0 = wave_chop(uv - mesh.motion * phase0 * FLOW_CYCLE);
+    let chop1 = wave_chop(uv - mesh.motion * phase1 * FLOW_CYCLE);
+    chop = mix(chop0, chop1, abs(1. - 2. * phase0));
     normal = normalize(normal + chop);
 #endif
 
@@ -0,0 +1,38 @@
+use bevy::{asset::LoadState, prelude::*};
+use panoply_exemplar::*;
+
+/// An actor placed in a precinct. Actor entities are children of the precinct entity, and
+/// are identified by their index in the precinct's list of actors.
+#[derive(Debug, Component, Clone, Default)]
+pub struct ActorElement {
+    pub index: usize,
+    pub iid: Option<String>,
+    pub exemplar: Handle<Exemplar>,
+    /// Facing direction, in radians.
+    pub facing: f32,
+    /// Position relative to the precinct.
+    pub position: Vec3,
+}
+
+#[derive(Component, Clone)]
+#[component(storage = "SparseSet")]
+pub struct ActorElementRebuildAspects;
+
+/// Apply the exemplar aspects to actors once their exemplars are loaded.
+pub fn update_actor_aspects(
+    mut commands: Commands,
+    q_actors: Query<(Entity, &ActorElement), With<ActorElementRebuildAspects>>,
+    server: Res<AssetServer>,
+) {
+    for (entity, actor) in q_actors.iter() {
+        if server.load_state(&actor.exemplar) == LoadState::Loaded {
+            commands
+                .entity(entity)
+                .add(UpdateAspects {
+                    exemplar: actor.exemplar.clone(),
+                    finish: (),
+                })
+                .remove::<ActorElementRebuildAspects>();
+        }
+    }
+}
@@ -29,28 +29,57 @@ pub struct ActorInstance {
 
     /// List of aspects for this instance.
     pub(crate) aspects: InstanceAspects,
+
+    /// Name of the precinct layer this actor belongs to.
+    pub(crate) layer: Option<String>,
     // These should probably be aspects.
-    // readonly layer?: string;
     // readonly groupId?: string;
     // readonly transient?: boolean;
     // readonly ally?: string;
 }
 
+/// Actors are always written as structs with named fields, leaving out the optional fields
+/// which aren't set. The exemplar is written as its asset path.
 impl Serialize for ActorInstance {
-    fn serialize<S>(&self, _serializer: S) -> Result<S::Ok, S::Error>
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        // use serde::ser::SerializeSeq;
-        todo!("Implement ActorInstance serialization")
-
-        // let mut seq = serializer.serialize_seq(Some(5))?;
-        // seq.serialize_element(&self.exemplar)?;
-        // seq.serialize_element(&self.realm)?;
-        // seq.serialize_element(&self.position)?;
-        // seq.serialize_element(&self.facing)?;
-        // seq.serialize_element(&self.iid)?;
-        // seq.end()
+        use serde::ser::{Error, SerializeStruct};
+
+        let Some(exemplar) = self.exemplar.path() else {
+            return Err(S::Error::custom("actor exemplar has no asset path"));
+        };
+        let mut len = 3;
+        if self.realm.is_some() {
+            len += 1;
+        }
+        if self.iid.is_some() {
+            len += 1;
+        }
+        if !self.aspects.is_empty() {
+            len += 1;
+        }
+        if self.layer.is_some() {
+            len += 1;
+        }
+        let mut state = serializer.serialize_struct("ActorInstance", len)?;
+        state.serialize_field("exemplar", &exemplar.to_string())?;
+        if let Some(realm) = &self.realm {
+            state.serialize_field("realm", realm)?;
+        }
+        state.serialize_field("position", &self.position)?;
+        state.serialize_field("facing", &self.facing)?;
+        if let Some(iid) = &self.iid {
+            state.serialize_field("iid", iid)?;
+        }
+        if !self.aspects.is_empty() {
+            state.serialize_field("aspects", &self.aspects)?;
+        }
+        if let Some(layer) = &self.layer {
+            state.serialize_field("layer", layer)?;
+        }
+        state.end()
     }
 }
 
@@ -63,6 +92,7 @@ enum Field {
     Facing,
     Iid,
     Aspects,
+    Layer,
 }
 
 struct ActorInstanceVisitor<'a, 'b> {
@@ -83,6 +113,9 @@ impl<'de, 'a, 'b> Visitor<'de> for ActorInstanceVisitor<'a, 'b> {
         A: serde::de::MapAccess<'de>,
     {
         let mut result = ActorInstance::default();
+        // Position and facing may legitimately be zero, so track whether they have been read.
+        let mut position: Option<Vec3> = None;
+        let mut facing: Option<f32> = None;
         while let Some(key) = map.next_key()? {
             match key {
                 Field::Exemplar => {
@@ -99,16 +132,16 @@ impl<'de, 'a, 'b> Visitor<'de> for ActorInstanceVisitor<'a, 'b> {
                     result.realm = Some(map.next_value()?);
                 }
                 Field::Position => {
-                    if result.realm.is_some() {
+                    if position.is_some() {
                         return Err(de::Error::duplicate_field("position"));
                     }
-                    result.position = map.next_value()?;
+                    position = Some(map.next_value()?);
                 }
                 Field::Facing => {
-                    if result.facing != f32::default() {
+                    if facing.is_some() {
                         return Err(de::Error::duplicate_field("facing"));
                     }
-                    result.facing = map.next_value()?;
+                    facing = Some(map.next_value()?);
                 }
                 Field::Iid => {
                     if result.iid.is_some() {
@@ -127,8 +160,16 @@ impl<'de, 'a, 'b> Visitor<'de> for ActorInstanceVisitor<'a, 'b> {
                             label_prefix: self.parent_label,
                         })?);
                 }
+                Field::Layer => {
+                    if result.layer.is_some() {
+                        return Err(de::Error::duplicate_field("layer"));
+                    }
+                    result.layer = Some(map.next_value()?);
+                }
             }
         }
+        result.position = position.unwrap_or_default();
+        result.facing = facing.unwrap_or_default();
         Ok(result)
     }
 }
@@ -146,7 +187,7 @@ impl<'de, 'a, 'b> DeserializeSeed<'de> for ActorInstanceDeserializer<'a, 'b> {
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_seq(ActorInstanceVisitor {
+        deserializer.deserialize_map(ActorInstanceVisitor {
             type_registry: self.type_registry,
             load_context: self.load_context,
             parent_label: self.parent_label,
@@ -203,3 +244,104 @@ impl<'de, 'a, 'b> DeserializeSeed<'de> for ActorInstanceListDeserializer<'a, 'b>
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        asset::{AssetApp, AssetPlugin, AssetServer},
+        prelude::*,
+    };
+
+    use super::*;
+    use crate::scenery::{
+        precinct_asset::{encode_precinct, load_precinct_bytes, PrecinctAsset},
+        precinct_format::PrecinctFormat,
+    };
+
+    fn exemplar_server() -> AssetServer {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Exemplar>();
+        app.world().resource::<AssetServer>().clone()
+    }
+
+    /// Write a precinct holding the sample actors to `path`, then load it back through the
+    /// precinct loader, which is the only way actors are deserialized.
+    fn roundtrip(path: &str) -> Vec<ActorInstance> {
+        let precinct = PrecinctAsset {
+            actors: sample_actors(&exemplar_server()),
+            ..default()
+        };
+        let format = PrecinctFormat::from_path(std::path::Path::new(path));
+        let bytes = encode_precinct(&precinct, format).unwrap();
+        load_precinct_bytes(path, bytes).actors
+    }
+
+    fn sample_actors(server: &AssetServer) -> Vec<ActorInstance> {
+        vec![
+            // Everything optional left out, at the origin and facing zero degrees.
+            ActorInstance {
+                exemplar: server.load("exemplars/guard.json#guard"),
+                ..default()
+            },
+            ActorInstance {
+                exemplar: server.load("exemplars/guard.json#captain"),
+                realm: Some("castle".to_string()),
+                position: Vec3::new(3.5, 1., -2.25),
+                facing: 90.,
+                iid: Some("captain".to_string()),
+                layer: Some("Night".to_string()),
+                ..default()
+            },
+        ]
+    }
+
+    fn assert_actors(actors: &[ActorInstance]) {
+        assert_eq!(actors.len(), 2);
+        let path = |actor: &ActorInstance| actor.exemplar.path().map(|p| p.to_string());
+        assert_eq!(
+            path(&actors[0]),
+            Some("exemplars/guard.json#guard".to_string())
+        );
+        assert_eq!(actors[0].position, Vec3::ZERO);
+        assert_eq!(actors[0].facing, 0.);
+        assert_eq!(actors[0].realm, None);
+        assert_eq!(actors[0].iid, None);
+        assert_eq!(actors[0].layer, None);
+        assert!(actors[0].aspects.is_empty());
+
+        assert_eq!(
+            path(&actors[1]),
+            Some("exemplars/guard.json#captain".to_string())
+        );
+        assert_eq!(actors[1].realm.as_deref(), Some("castle"));
+        assert_eq!(actors[1].position, Vec3::new(3.5, 1., -2.25));
+        assert_eq!(actors[1].facing, 90.);
+        assert_eq!(actors[1].iid.as_deref(), Some("captain"));
+        assert_eq!(actors[1].layer.as_deref(), Some("Night"));
+    }
+
+    #[test]
+    fn test_actor_msgpack_roundtrip() {
+        assert_actors(&roundtrip("test/p000-p000.msgpack"));
+    }
+
+    #[test]
+    fn test_actor_json_roundtrip() {
+        assert_actors(&roundtrip("test/p000-p000.precinct.json"));
+    }
+
+    #[test]
+    fn test_actor_omits_unset_fields() {
+        let actors = sample_actors(&exemplar_server());
+        let json = serde_json::to_value(&actors[0]).unwrap();
+        let mut keys: Vec<&str> = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort();
+        assert_eq!(keys, vec!["exemplar", "facing", "position"]);
+    }
+}
@@ -1,9 +1,11 @@
-use bevy::app::{App, Plugin};
+use bevy::app::{App, Plugin, Update};
 use panoply_exemplar::InstanceType;
 
 mod actor_aspect;
+mod actor_element;
 mod actor_instance;
 
+pub use actor_element::*;
 pub use actor_instance::*;
 
 use self::actor_aspect::{Armature, ColorSlots, Colors, Combatant, FeatureSlots, Features, Skin};
@@ -20,6 +22,7 @@ impl Plugin for ActorsPlugin {
             .register_type::<Colors>()
             .register_type::<FeatureSlots>()
             .register_type::<Features>()
-            .register_type::<Combatant>();
+            .register_type::<Combatant>()
+            .add_systems(Update, update_actor_aspects);
     }
 }
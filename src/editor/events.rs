@@ -1,7 +1,10 @@
-use bevy::prelude::*;
+use bevy::{math::IRect, prelude::*};
 use panoply_exemplar::Exemplar;
 
-use crate::{scenery::precinct_asset::PrecinctAsset, terrain::ShapeRef};
+use crate::{
+    scenery::precinct_asset::PrecinctAsset,
+    terrain::{terrain_autotile::TileIntent, terrain_generator::TerrainGenerator, ShapeRef},
+};
 
 /// Trigger event which changes the terrain for a parcel.
 #[derive(Clone, Debug, Event)]
@@ -11,6 +14,41 @@ pub struct ModifyTerrainMapEvent {
     pub shape: ShapeRef,
 }
 
+/// Trigger event which changes the bounds of a realm's terrain map. Parcels outside the old
+/// bounds are filled with the map defaults; parcels outside the new bounds are discarded.
+#[derive(Clone, Debug, Event)]
+pub struct ResizeTerrainMap {
+    pub realm: Entity,
+    pub bounds: IRect,
+}
+
+/// Trigger event which shifts a realm's terrain map relative to the world origin, and
+/// optionally moves the realm's precinct files along with it.
+#[derive(Clone, Debug, Event)]
+pub struct TranslateTerrainMap {
+    pub realm: Entity,
+    pub offset: IVec2,
+    pub move_precincts: bool,
+}
+
+/// Trigger event which fills a region of a realm's terrain map with procedurally generated
+/// terrain.
+#[derive(Clone, Debug, Event)]
+pub struct GenerateTerrainMap {
+    pub realm: Entity,
+    pub region: IRect,
+    pub generator: TerrainGenerator,
+}
+
+/// Trigger event which replaces the shapes of a set of parcels according to the painted
+/// intent, choosing contours so that the edges between parcels stay continuous.
+#[derive(Clone, Debug, Event)]
+pub struct AutoTileTerrainMap {
+    pub realm: Entity,
+    pub parcels: Vec<IVec2>,
+    pub intent: TileIntent,
+}
+
 /// Trigger event which does a boolean operation on floors.
 #[derive(Clone, Debug, Event)]
 pub struct FloorStampEvent {
@@ -33,6 +71,14 @@ pub struct ThumbnailsReady;
 #[derive(Clone, Debug, Event)]
 pub struct RotateSelection(pub i32);
 
+/// Delete the current selection.
+#[derive(Clone, Debug, Event)]
+pub struct DeleteSelection;
+
+/// Duplicate the current selection.
+#[derive(Clone, Debug, Event)]
+pub struct DuplicateSelection;
+
 #[derive(Clone, Debug, Event)]
 pub struct PlaceWalls {
     pub precinct: Handle<PrecinctAsset>,
@@ -49,3 +95,22 @@ pub struct RemoveWalls {
     pub tier: i16,
     pub area: Rect,
 }
+
+/// Trigger event which places a single fixture in a precinct.
+#[derive(Clone, Debug, Event)]
+pub struct PlaceFixture {
+    pub precinct: Handle<PrecinctAsset>,
+    /// Position relative to the precinct origin.
+    pub position: Vec3,
+    /// Facing direction, in degrees.
+    pub facing: f32,
+    pub exemplar: AssetId<Exemplar>,
+}
+
+/// Copy the current selection to the clipboard.
+#[derive(Clone, Debug, Event)]
+pub struct CopySelection;
+
+/// Paste the contents of the clipboard.
+#[derive(Clone, Debug, Event)]
+pub struct PasteSelection;
@@ -0,0 +1,507 @@
+//! Boolean operations on floor regions.
+
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use i_overlay::{
+    core::{fill_rule::FillRule, overlay::ShapeType, overlay_rule::OverlayRule},
+    i_float::point::IntPoint,
+};
+
+use crate::scenery::{floor_region::FloorRegionSer, precinct_asset::PrecinctAsset};
+
+pub(crate) fn vec2_to_intpoint(v: &Vec2) -> IntPoint {
+    IntPoint::new((v.x * 256.0) as i32, (v.y * 256.0) as i32)
+}
+
+pub(crate) fn intpoint_to_vec2(p: &IntPoint) -> Vec2 {
+    Vec2::new((p.x as f32) / 256.0, (p.y as f32) / 256.0)
+}
+
+/// Convert a floor region to an overlay shape: the outline followed by the holes.
+fn region_to_shape(region: &FloorRegionSer) -> Vec<Vec<IntPoint>> {
+    let mut shape: Vec<Vec<IntPoint>> = Vec::with_capacity(1 + region.holes.len());
+    shape.push(
+        region
+            .poly
+            .iter()
+            .rev()
+            .map(vec2_to_intpoint)
+            .collect::<Vec<_>>(),
+    );
+    shape.extend(
+        region
+            .holes
+            .iter()
+            .map(|hole| hole.iter().rev().map(vec2_to_intpoint).collect::<Vec<_>>()),
+    );
+    shape
+}
+
+/// Convert the output shapes of an overlay operation to floor regions.
+fn shapes_to_regions(
+    shapes: &[Vec<Vec<IntPoint>>],
+    surface_index: usize,
+    layer: &Option<String>,
+    out: &mut Vec<FloorRegionSer>,
+) {
+    for shape in shapes.iter() {
+        let poly = shape
+            .first()
+            .unwrap()
+            .iter()
+            .rev()
+            .map(intpoint_to_vec2)
+            .collect::<Vec<_>>();
+        let holes = shape
+            .iter()
+            .skip(1)
+            .map(|path| path.iter().rev().map(intpoint_to_vec2).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        out.push(FloorRegionSer {
+            surface_index,
+            poly,
+            holes,
+            layer: layer.clone(),
+        });
+    }
+}
+
+/// Stamp a shape onto the floors of a tier, returning the new list of floor regions.
+///
+/// Floors are merged per surface type and layer. The stamped shape is added to the group
+/// for the given surface type and layer, and cut out of every other group, except for
+/// groups in locked layers which are kept as they are. A surface index of `usize::MAX` only
+/// erases.
+pub(crate) fn stamp_floors(
+    precinct: &PrecinctAsset,
+    pfloors: &[FloorRegionSer],
+    stamp: &[Vec<Vec2>],
+    surface_index: usize,
+    layer: &Option<String>,
+) -> Vec<FloorRegionSer> {
+    let mut groups: BTreeSet<(usize, Option<String>)> = pfloors
+        .iter()
+        .map(|region| (region.surface_index, region.layer.clone()))
+        .collect();
+    if surface_index != usize::MAX {
+        groups.insert((surface_index, layer.clone()));
+    }
+    let mut pfloors_new: Vec<FloorRegionSer> = Vec::with_capacity(pfloors.len());
+    for (surface, group_layer) in groups {
+        let in_group = |region: &&FloorRegionSer| {
+            region.surface_index == surface && region.layer == group_layer
+        };
+        if precinct.is_layer_locked(group_layer.as_deref()) {
+            pfloors_new.extend(pfloors.iter().filter(in_group).cloned());
+            continue;
+        }
+
+        let mut ishape = i_overlay::core::overlay::Overlay::new(128);
+        for region in pfloors.iter().filter(in_group) {
+            ishape.add_shape(&region_to_shape(region), ShapeType::Subject);
+        }
+
+        // Map the stamp shape to overlay shape by converting the vertices to IntPoint.
+        ishape.add_shape(
+            &stamp
+                .iter()
+                .map(|path| path.iter().map(vec2_to_intpoint).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            ShapeType::Clip,
+        );
+
+        let graph = ishape.into_graph(FillRule::NonZero);
+        let v = if surface == surface_index && group_layer == *layer {
+            graph.extract_shapes(OverlayRule::Union)
+        } else {
+            graph.extract_shapes(OverlayRule::Difference)
+        };
+        shapes_to_regions(&v, surface, &group_layer, &mut pfloors_new);
+    }
+    pfloors_new
+}
+
+/// Return the parts of the floor regions which lie within a rectangle.
+pub(crate) fn clip_floors(pfloors: &[FloorRegionSer], rect: Rect) -> Vec<FloorRegionSer> {
+    let clip = [
+        Vec2::new(rect.min.x, rect.min.y),
+        Vec2::new(rect.max.x, rect.min.y),
+        Vec2::new(rect.max.x, rect.max.y),
+        Vec2::new(rect.min.x, rect.max.y),
+    ]
+    .iter()
+    .map(vec2_to_intpoint)
+    .collect::<Vec<_>>();
+    let mut result: Vec<FloorRegionSer> = Vec::new();
+    for region in pfloors.iter() {
+        let mut ishape = i_overlay::core::overlay::Overlay::new(32);
+        ishape.add_shape(&region_to_shape(region), ShapeType::Subject);
+        ishape.add_path(&clip, ShapeType::Clip);
+        let v = ishape
+            .into_graph(FillRule::NonZero)
+            .extract_shapes(OverlayRule::Intersect);
+        shapes_to_regions(&v, region.surface_index, &region.layer, &mut result);
+    }
+    result
+}
+
+/// Whether a point lies on any of the floor regions, outside of their holes.
+pub(crate) fn floors_contain(pfloors: &[FloorRegionSer], pt: Vec2) -> bool {
+    pfloors.iter().any(|region| region.contains_pt(pt))
+}
+
+/// A part of a floor region which can be picked and dragged. Rings are numbered with the
+/// outline first, followed by the holes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FloorPart {
+    /// A single vertex.
+    Vertex {
+        region: usize,
+        ring: usize,
+        index: usize,
+    },
+    /// The edge between a vertex and the one after it.
+    Edge {
+        region: usize,
+        ring: usize,
+        index: usize,
+    },
+    /// The whole region, including its holes.
+    Region(usize),
+}
+
+impl FloorPart {
+    pub(crate) fn region(&self) -> usize {
+        match self {
+            FloorPart::Vertex { region, .. } | FloorPart::Edge { region, .. } => *region,
+            FloorPart::Region(region) => *region,
+        }
+    }
+}
+
+/// Get one of the rings of a floor region: the outline for ring 0, otherwise a hole.
+pub(crate) fn region_ring(region: &FloorRegionSer, ring: usize) -> &[Vec2] {
+    match ring {
+        0 => &region.poly,
+        _ => &region.holes[ring - 1],
+    }
+}
+
+fn region_ring_mut(region: &mut FloorRegionSer, ring: usize) -> &mut Vec<Vec2> {
+    match ring {
+        0 => &mut region.poly,
+        _ => &mut region.holes[ring - 1],
+    }
+}
+
+/// Find the part of a floor nearest to a point: vertices within `radius` take precedence
+/// over edges, and edges over the interior of a region. Regions for which `pickable` returns
+/// false are ignored.
+pub(crate) fn pick_floor_part(
+    pfloors: &[FloorRegionSer],
+    pt: Vec2,
+    radius: f32,
+    pickable: impl Fn(&FloorRegionSer) -> bool,
+) -> Option<FloorPart> {
+    let mut nearest_vertex: Option<(f32, FloorPart)> = None;
+    let mut nearest_edge: Option<(f32, FloorPart)> = None;
+    for (region_index, region) in pfloors.iter().enumerate() {
+        if !pickable(region) {
+            continue;
+        }
+        for ring_index in 0..=region.holes.len() {
+            let ring = region_ring(region, ring_index);
+            for (index, v) in ring.iter().enumerate() {
+                let next = ring[(index + 1) % ring.len()];
+                let dist = v.distance(pt);
+                if dist <= radius && nearest_vertex.map_or(true, |(d, _)| dist < d) {
+                    let part = FloorPart::Vertex {
+                        region: region_index,
+                        ring: ring_index,
+                        index,
+                    };
+                    nearest_vertex = Some((dist, part));
+                }
+                let dist = distance_to_segment(pt, *v, next);
+                if dist <= radius && nearest_edge.map_or(true, |(d, _)| dist < d) {
+                    let part = FloorPart::Edge {
+                        region: region_index,
+                        ring: ring_index,
+                        index,
+                    };
+                    nearest_edge = Some((dist, part));
+                }
+            }
+        }
+    }
+    nearest_vertex
+        .or(nearest_edge)
+        .map(|(_, part)| part)
+        .or_else(|| {
+            pfloors
+                .iter()
+                .position(|region| pickable(region) && region.contains_pt(pt))
+                .map(FloorPart::Region)
+        })
+}
+
+fn distance_to_segment(pt: Vec2, a: Vec2, b: Vec2) -> f32 {
+    pt.distance(nearest_point_on_segment(pt, a, b))
+}
+
+fn nearest_point_on_segment(pt: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = b - a;
+    let len2 = ab.length_squared();
+    if len2 == 0. {
+        return a;
+    }
+    let t = ((pt - a).dot(ab) / len2).clamp(0., 1.);
+    a + ab * t
+}
+
+/// The point on an edge of a floor region which is nearest to `pt`, or `None` if the part
+/// isn't an edge.
+pub(crate) fn nearest_point_on_edge(
+    region: &FloorRegionSer,
+    part: FloorPart,
+    pt: Vec2,
+) -> Option<Vec2> {
+    let FloorPart::Edge { ring, index, .. } = part else {
+        return None;
+    };
+    let ring = region_ring(region, ring);
+    Some(nearest_point_on_segment(
+        pt,
+        ring[index],
+        ring[(index + 1) % ring.len()],
+    ))
+}
+
+/// Move a part of a floor region by an offset.
+pub(crate) fn drag_floor_part(
+    region: &FloorRegionSer,
+    part: FloorPart,
+    offset: Vec2,
+) -> FloorRegionSer {
+    let mut result = region.clone();
+    match part {
+        FloorPart::Vertex { ring, index, .. } => {
+            region_ring_mut(&mut result, ring)[index] += offset;
+        }
+        FloorPart::Edge { ring, index, .. } => {
+            let ring = region_ring_mut(&mut result, ring);
+            let next = (index + 1) % ring.len();
+            ring[index] += offset;
+            ring[next] += offset;
+        }
+        FloorPart::Region(_) => {
+            result.poly.iter_mut().for_each(|v| *v += offset);
+            result
+                .holes
+                .iter_mut()
+                .for_each(|hole| hole.iter_mut().for_each(|v| *v += offset));
+        }
+    }
+    result
+}
+
+/// Split an edge of a floor region by adding a vertex after `index`.
+pub(crate) fn insert_floor_vertex(
+    region: &FloorRegionSer,
+    ring: usize,
+    index: usize,
+    pt: Vec2,
+) -> FloorRegionSer {
+    let mut result = region.clone();
+    region_ring_mut(&mut result, ring).insert(index + 1, pt);
+    result
+}
+
+/// Remove a vertex from a floor region. A hole which is left with fewer than three vertices is
+/// removed entirely; returns `None` if the outline would be.
+pub(crate) fn delete_floor_vertex(
+    region: &FloorRegionSer,
+    ring: usize,
+    index: usize,
+) -> Option<FloorRegionSer> {
+    let mut result = region.clone();
+    let vertices = region_ring_mut(&mut result, ring);
+    vertices.remove(index);
+    if vertices.len() < 3 {
+        if ring == 0 {
+            return None;
+        }
+        result.holes.remove(ring - 1);
+    }
+    Some(result)
+}
+
+/// Check that a floor region is a single polygon within the given bounds, and that neither
+/// its outline nor its holes intersect themselves or each other. The region is simplified
+/// with `i_overlay`; a well-formed region comes back as one shape with the same holes and
+/// the same area.
+pub(crate) fn is_valid_floor_region(region: &FloorRegionSer, bounds: Rect) -> bool {
+    if region.poly.len() < 3 || region.holes.iter().any(|hole| hole.len() < 3) {
+        return false;
+    }
+    let in_bounds = |v: &Vec2| bounds.contains(*v);
+    if !region.poly.iter().all(in_bounds) || !region.holes.iter().flatten().all(in_bounds) {
+        return false;
+    }
+
+    let expected = ring_area(&region.poly).abs()
+        - region
+            .holes
+            .iter()
+            .map(|hole| ring_area(hole).abs())
+            .sum::<f32>();
+    if expected <= 0. {
+        return false;
+    }
+
+    let mut ishape = i_overlay::core::overlay::Overlay::new(32);
+    ishape.add_shape(&region_to_shape(region), ShapeType::Subject);
+    let shapes = ishape
+        .into_graph(FillRule::EvenOdd)
+        .extract_shapes(OverlayRule::Union);
+    let [shape] = shapes.as_slice() else {
+        return false;
+    };
+    if shape.len() != region.holes.len() + 1 {
+        return false;
+    }
+    let actual = shape
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let area = ring_area(&path.iter().map(intpoint_to_vec2).collect::<Vec<_>>()).abs();
+            if i == 0 {
+                area
+            } else {
+                -area
+            }
+        })
+        .sum::<f32>();
+    (actual - expected).abs() < 0.01
+}
+
+/// Signed area of a closed polygon.
+fn ring_area(ring: &[Vec2]) -> f32 {
+    let mut prev = match ring.last() {
+        Some(v) => *v,
+        None => return 0.,
+    };
+    let mut area = 0.;
+    for v in ring.iter() {
+        area += prev.perp_dot(*v);
+        prev = *v;
+    }
+    area * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_floors_contain() {
+        let region = FloorRegionSer {
+            surface_index: 0,
+            poly: vec![
+                Vec2::new(0., 0.),
+                Vec2::new(10., 0.),
+                Vec2::new(10., 10.),
+                Vec2::new(0., 10.),
+            ],
+            holes: vec![vec![
+                Vec2::new(4., 4.),
+                Vec2::new(4., 6.),
+                Vec2::new(6., 6.),
+                Vec2::new(6., 4.),
+            ]],
+            layer: None,
+        };
+        let floors = [region];
+        assert!(floors_contain(&floors, Vec2::new(1., 1.)));
+        assert!(floors_contain(&floors, Vec2::new(9.5, 5.)));
+        assert!(!floors_contain(&floors, Vec2::new(5., 5.)));
+        assert!(!floors_contain(&floors, Vec2::new(11., 5.)));
+        assert!(!floors_contain(&[], Vec2::new(1., 1.)));
+    }
+
+    fn square(min: f32, max: f32) -> Vec<Vec2> {
+        vec![
+            Vec2::new(min, min),
+            Vec2::new(max, min),
+            Vec2::new(max, max),
+            Vec2::new(min, max),
+        ]
+    }
+
+    #[test]
+    fn test_floor_region_editing() {
+        let bounds = Rect::new(-20., -20., 64., 64.);
+        let region = FloorRegionSer {
+            surface_index: 0,
+            poly: square(0., 10.),
+            holes: vec![square(4., 6.)],
+            layer: None,
+        };
+        assert!(is_valid_floor_region(&region, bounds));
+
+        let floors = [region.clone()];
+        let corner = pick_floor_part(&floors, Vec2::new(10.2, 9.9), 0.4, |_| true);
+        assert_eq!(
+            corner,
+            Some(FloorPart::Vertex {
+                region: 0,
+                ring: 0,
+                index: 2
+            })
+        );
+        let hole_edge = pick_floor_part(&floors, Vec2::new(5., 6.1), 0.4, |_| true);
+        assert_eq!(
+            hole_edge,
+            Some(FloorPart::Edge {
+                region: 0,
+                ring: 1,
+                index: 2
+            })
+        );
+        assert_eq!(
+            pick_floor_part(&floors, Vec2::new(2., 2.), 0.4, |_| true),
+            Some(FloorPart::Region(0))
+        );
+        assert_eq!(
+            pick_floor_part(&floors, Vec2::new(5., 5.), 0.4, |_| true),
+            None
+        );
+        assert_eq!(
+            pick_floor_part(&floors, Vec2::new(10.2, 9.9), 0.4, |_| false),
+            None
+        );
+        assert_eq!(
+            nearest_point_on_edge(&region, hole_edge.unwrap(), Vec2::new(5.5, 6.1)),
+            Some(Vec2::new(5.5, 6.))
+        );
+
+        // Dragging a corner across the opposite edge makes a bow tie.
+        let moved = drag_floor_part(&region, corner.unwrap(), Vec2::new(2., 2.));
+        assert!(is_valid_floor_region(&moved, bounds));
+        let twisted = drag_floor_part(&region, corner.unwrap(), Vec2::new(-12., 0.));
+        assert!(!is_valid_floor_region(&twisted, bounds));
+
+        // Holes can't leave the outline, and regions can't leave the precinct.
+        let hole = drag_floor_part(&region, hole_edge.unwrap(), Vec2::new(0., 6.));
+        assert!(!is_valid_floor_region(&hole, bounds));
+        let outside = drag_floor_part(&region, FloorPart::Region(0), Vec2::new(-30., 0.));
+        assert!(!is_valid_floor_region(&outside, bounds));
+
+        let inserted = insert_floor_vertex(&region, 0, 0, Vec2::new(5., -2.));
+        assert_eq!(inserted.poly[1], Vec2::new(5., -2.));
+        let deleted = delete_floor_vertex(&region, 1, 0).unwrap();
+        assert!(deleted.holes.is_empty());
+        assert!(delete_floor_vertex(&deleted, 0, 0).is_none());
+    }
+}
@@ -1,2 +1,3 @@
+pub mod floor_ops;
 pub mod pick_plane;
 pub mod search_filter;
@@ -3,7 +3,7 @@ use bevy_mod_preferences::{PreferencesGroup, PreferencesKey};
 use exemplars::ExemplarsHandleResource;
 use lib::pick_plane::PlanePickBackend;
 use ui::{
-    mode_realm,
+    mode_play, mode_realm,
     mode_scenery::EditSceneryPlugin,
     mode_terrain::EditTerrainPlugin,
     zoom_selector::{update_zoom_level, ZoomLevel},
@@ -18,6 +18,7 @@ mod camera;
 mod events;
 mod exemplars;
 mod lib;
+mod realm;
 pub mod renderers;
 mod scenery;
 mod terrain;
@@ -92,16 +93,23 @@ impl Plugin for EditorPlugin {
             .init_resource::<TerrainGroupsHandle>()
             .init_resource::<ZoomLevel>()
             .init_resource::<UndoStack>()
+            .init_resource::<scenery::PendingPrecinctConversions>()
             .register_type::<EditorSidebarWidth>()
             .register_type::<State<EditorMode>>()
             .register_type::<NextState<EditorMode>>()
             .register_type::<ZoomLevel>()
             .insert_state(ui::quick_nav::QuickNavOpen::default())
+            .insert_state(ui::create_realm::CreateRealmOpen::default())
+            .insert_state(ui::generate_terrain::GenerateTerrainOpen::default())
             .add_systems(OnEnter(EditorMode::Realm), mode_realm::enter)
             .add_systems(OnExit(EditorMode::Realm), mode_realm::exit)
+            .add_systems(OnEnter(EditorMode::Play), mode_play::enter)
+            .add_systems(OnExit(EditorMode::Play), mode_play::exit)
             .add_systems(
                 Startup,
                 (
+                    // The editor starts out editing layers, not playing.
+                    mode_play::exit,
                     renderers::setup_thumbnail_realm,
                     renderers::setup_thumbnail_camera.after(renderers::setup_thumbnail_realm),
                     renderers::setup_thumbnail_observer,
@@ -119,9 +127,14 @@ impl Plugin for EditorPlugin {
                     )
                         .chain(),
                     unsaved::receive_asset_saving,
+                    scenery::finish_precinct_conversions,
                     update_zoom_level,
                 ),
             )
+            .observe(terrain::resize_terrain_map)
+            .observe(terrain::translate_terrain_map)
+            .observe(terrain::generate_terrain_map)
+            .observe(terrain::autotile_terrain_map)
             .add_plugins((EditSceneryPlugin, EditTerrainPlugin, PlanePickBackend));
     }
 }
@@ -0,0 +1,6 @@
+mod realm_commands;
+
+pub(crate) use realm_commands::{
+    copy_precinct_files, realm_asset_path, unique_realm_name, CreateRealm, DeleteRealm,
+    DuplicateRealm,
+};
@@ -0,0 +1,307 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    asset::io::AssetSourceId, ecs::world::Command, prelude::*, render::view::RenderLayers,
+    tasks::block_on, utils::HashSet,
+};
+use futures_lite::{AsyncReadExt, StreamExt};
+
+use crate::{
+    editor::unsaved::{ModifiedState, UnsavedAssets},
+    scenery::{
+        precinct::Precinct, precinct_asset::PrecinctAsset, precinct_cache::PrecinctCache,
+        precinct_format::PrecinctFormat,
+    },
+    terrain::{ParcelCache, TerrainMap, TerrainMapAsset},
+    view::{layers::ReservedLayers, Viewpoint},
+    world::{Realm, RealmData},
+};
+
+pub(crate) fn realm_asset_path(name: &str) -> String {
+    format!("realms/{}.realm.json", name)
+}
+
+fn terrain_map_asset_path(name: &str) -> String {
+    format!("terrain/maps/{}.terrain", name)
+}
+
+fn precinct_dir_path(name: &str) -> String {
+    format!("scenery/precincts/{}", name)
+}
+
+/// Returns true if a realm with the given name already exists, either as a realm entity
+/// or as a file on disk.
+fn realm_exists(world: &mut World, name: &str) -> bool {
+    let mut q_realms = world.query::<&Realm>();
+    if q_realms.iter(world).any(|realm| realm.name == name) {
+        return true;
+    }
+    let server = world.resource::<AssetServer>();
+    let Ok(source) = server.get_source(AssetSourceId::Default) else {
+        return false;
+    };
+    let reader = source.reader();
+    [realm_asset_path(name), terrain_map_asset_path(name)]
+        .iter()
+        .any(|path| block_on(reader.read(Path::new(path))).is_ok())
+}
+
+/// Return a realm name based on `base` which is not used by any existing realm.
+pub(crate) fn unique_realm_name(world: &mut World, base: &str) -> String {
+    if !realm_exists(world, base) {
+        return base.to_string();
+    }
+    let mut index = 2;
+    loop {
+        let name = format!("{}-{}", base, index);
+        if !realm_exists(world, &name) {
+            return name;
+        }
+        index += 1;
+    }
+}
+
+/// Command which creates a new realm along with its terrain map. The new assets are added to
+/// the set of unsaved assets, and are written to disk on the next save.
+pub(crate) struct CreateRealm {
+    pub(crate) name: String,
+    pub(crate) data: RealmData,
+    pub(crate) map: TerrainMapAsset,
+    /// Name of an existing realm whose precinct files should be copied to the new realm when
+    /// it is saved.
+    pub(crate) copy_precincts_from: Option<String>,
+}
+
+impl Command for CreateRealm {
+    fn apply(self, world: &mut World) {
+        if realm_exists(world, &self.name) {
+            warn!("Realm [{}] already exists.", self.name);
+            return;
+        }
+
+        // Loading is the only way to get handles associated with the paths the assets will be
+        // saved to. The files don't exist yet, so the loads fail; the terrain systems ignore
+        // that for maps which are present in memory.
+        let server = world.resource::<AssetServer>().clone();
+        let realm_handle = server.load::<RealmData>(realm_asset_path(&self.name));
+        let map_handle = server.load::<TerrainMapAsset>(terrain_map_asset_path(&self.name));
+        world
+            .resource_mut::<Assets<RealmData>>()
+            .insert(realm_handle.id(), self.data);
+        world
+            .resource_mut::<Assets<TerrainMapAsset>>()
+            .insert(map_handle.id(), self.map);
+        let mut unsaved = world.resource_mut::<UnsavedAssets>();
+        unsaved.realms.insert(realm_handle, ModifiedState::Unsaved);
+        unsaved
+            .terrain_maps
+            .insert(map_handle, ModifiedState::Unsaved);
+
+        // Scenery files are copied when the new realm is first saved, so that nothing is
+        // left behind if it is deleted before then.
+        if let Some(from) = self.copy_precincts_from {
+            unsaved.precinct_copies.insert(self.name.clone(), from);
+        }
+        info!("Realm created: [{}].", self.name);
+    }
+}
+
+/// Copy the precinct files of one realm to another. Precincts of the destination which have
+/// unsaved edits are skipped, since saving them will write the file anyway. Precincts which
+/// are already loaded are reloaded from the copies.
+pub(crate) fn copy_precinct_files(world: &mut World, from: &str, to: &str) {
+    let server = world.resource::<AssetServer>().clone();
+    let Ok(source) = server.get_source(AssetSourceId::Default) else {
+        return;
+    };
+    let reader = source.reader();
+    let Ok(writer) = source.writer() else {
+        warn!("Asset source is not writable, precinct files were not copied.");
+        return;
+    };
+    let files: Vec<PathBuf> =
+        match block_on(reader.read_directory(Path::new(&precinct_dir_path(from)))) {
+            Ok(stream) => block_on(stream.collect()),
+            Err(_) => Vec::new(),
+        };
+    let edited: HashSet<PathBuf> = world
+        .resource::<UnsavedAssets>()
+        .precincts
+        .keys()
+        .filter_map(|handle| server.get_path(handle))
+        .map(|path| path.path().to_path_buf())
+        .collect();
+
+    let to_dir = PathBuf::from(precinct_dir_path(to));
+    let mut copied = 0;
+    for from_path in files {
+        let Some(file_name) = from_path.file_name() else {
+            continue;
+        };
+        let to_path = to_dir.join(file_name);
+        // The edited precinct may have been given the other format.
+        if PrecinctFormat::ALL
+            .iter()
+            .any(|format| edited.contains(&format.with_extension(&to_path)))
+        {
+            continue;
+        }
+        let result = block_on(async {
+            let mut read = reader.read(&from_path).await?;
+            let mut bytes = Vec::new();
+            read.read_to_end(&mut bytes).await?;
+            writer.write_bytes(&to_path, &bytes).await?;
+            Ok::<_, Box<dyn std::error::Error>>(())
+        });
+        match result {
+            Ok(()) => {
+                copied += 1;
+                if server
+                    .get_handle::<PrecinctAsset>(to_path.as_path())
+                    .is_some()
+                {
+                    server.reload(to_path);
+                }
+            }
+            Err(err) => warn!("Failed to copy {:?}: {}", from_path, err),
+        }
+    }
+    info!(
+        "Copied {} precinct files from [{}] to [{}].",
+        copied, from, to
+    );
+}
+
+/// Command which creates a copy of an existing realm, including its terrain and scenery.
+pub(crate) struct DuplicateRealm {
+    pub(crate) realm: Entity,
+}
+
+impl Command for DuplicateRealm {
+    fn apply(self, world: &mut World) {
+        let Some((name, data)) = world.get::<Realm>(self.realm).map(|r| {
+            let data = RealmData {
+                lighting: r.lighting,
+                seed: r.seed.seed(),
+                precinct_format: r.precinct_format,
+            };
+            (r.name.clone(), data)
+        }) else {
+            return;
+        };
+        let Some(map) = world.get::<TerrainMap>(self.realm).and_then(|tm| {
+            world
+                .resource::<Assets<TerrainMapAsset>>()
+                .get(&tm.handle)
+                .cloned()
+        }) else {
+            warn!("Realm [{}] has no terrain map to duplicate.", name);
+            return;
+        };
+        let new_name = unique_realm_name(world, &format!("{}-copy", name));
+        CreateRealm {
+            name: new_name,
+            data,
+            map,
+            copy_precincts_from: Some(name),
+        }
+        .apply(world);
+    }
+}
+
+/// Command which deletes a realm, including its terrain map and scenery files. This takes
+/// effect immediately and cannot be undone.
+pub(crate) struct DeleteRealm {
+    pub(crate) realm: Entity,
+}
+
+impl Command for DeleteRealm {
+    fn apply(self, world: &mut World) {
+        let Some((name, layer_index)) = world
+            .get::<Realm>(self.realm)
+            .map(|r| (r.name.clone(), r.layer_index))
+        else {
+            return;
+        };
+        let server = world.resource::<AssetServer>().clone();
+        let realm_path = realm_asset_path(&name);
+        let map_path = terrain_map_asset_path(&name);
+        let precinct_dir = precinct_dir_path(&name);
+
+        // Forget about any pending edits to this realm.
+        let mut unsaved = world.resource_mut::<UnsavedAssets>();
+        unsaved.realms.retain(|handle, _| {
+            server
+                .get_path(handle)
+                .map_or(true, |path| path.path() != Path::new(&realm_path))
+        });
+        unsaved.terrain_maps.retain(|handle, _| {
+            server
+                .get_path(handle)
+                .map_or(true, |path| path.path() != Path::new(&map_path))
+        });
+        unsaved.precincts.retain(|handle, _| {
+            server
+                .get_path(handle)
+                .map_or(true, |path| !path.path().starts_with(&precinct_dir))
+        });
+        unsaved.precinct_copies.remove(&name);
+
+        // Despawn everything that belongs to the realm.
+        let mut despawn = world
+            .resource_mut::<PrecinctCache>()
+            .evict_realm(self.realm);
+        despawn.extend(world.resource_mut::<ParcelCache>().evict_realm(self.realm));
+        let mut q_precincts = world.query::<(Entity, &Precinct)>();
+        despawn.extend(
+            q_precincts
+                .iter(world)
+                .filter(|(_, precinct)| precinct.realm == self.realm)
+                .map(|(entity, _)| entity),
+        );
+        for entity in despawn {
+            if let Some(entity) = world.get_entity_mut(entity) {
+                entity.despawn_recursive();
+            }
+        }
+        let mut viewpoint = world.resource_mut::<Viewpoint>();
+        if viewpoint.realm == Some(self.realm) {
+            viewpoint.realm = None;
+        }
+        let realm_layer = RenderLayers::layer(layer_index);
+        let mut q_lights =
+            world.query_filtered::<(Entity, &RenderLayers), With<DirectionalLight>>();
+        let lights: Vec<Entity> = q_lights
+            .iter(world)
+            .filter(|(_, layers)| layers.intersects(&realm_layer))
+            .map(|(entity, _)| entity)
+            .collect();
+        for entity in lights {
+            world.entity_mut(entity).despawn_recursive();
+        }
+        world.entity_mut(self.realm).despawn_recursive();
+        world.resource_mut::<ReservedLayers>().release(layer_index);
+
+        if let Some(handle) = server.get_handle::<RealmData>(&realm_path) {
+            world.resource_mut::<Assets<RealmData>>().remove(&handle);
+        }
+        if let Some(handle) = server.get_handle::<TerrainMapAsset>(&map_path) {
+            world
+                .resource_mut::<Assets<TerrainMapAsset>>()
+                .remove(&handle);
+        }
+
+        let Ok(source) = server.get_source(AssetSourceId::Default) else {
+            return;
+        };
+        let Ok(writer) = source.writer() else {
+            warn!("Asset source is not writable, realm files were not deleted.");
+            return;
+        };
+        // Files may not exist if the realm was never saved.
+        let _ = block_on(writer.remove(Path::new(&realm_path)));
+        let _ = block_on(writer.remove(Path::new(&map_path)));
+        let _ = block_on(writer.remove_directory(Path::new(&precinct_dir)));
+        info!("Realm deleted: [{}].", name);
+    }
+}
@@ -14,12 +14,13 @@ use bevy::{
 
 use crate::{
     editor::events::{ChangeContourEvent, ThumbnailsReady},
+    random::NoiseSeed,
     terrain::{
         create_ground_material,
         terrain_contours::{TerrainContoursHandle, TerrainContoursTableAsset},
         ComputeGroundMeshTask, ComputeWaterMeshTask, GroundMaterial, Parcel, ParcelFloraChanged,
-        ParcelTerrainFx, ParcelThumbnail, ParcelWaterChanged, RebuildParcelGroundMesh, ShapeRef,
-        TerrainFxVertexAttr, TerrainMap, PARCEL_TERRAIN_FX_AREA,
+        ParcelFloraMask, ParcelTerrainFx, ParcelThumbnail, ParcelWaterChanged,
+        RebuildParcelGroundMesh, ShapeRef, TerrainFxVertexAttr, TerrainMap, PARCEL_TERRAIN_FX_AREA,
     },
     view::layers::ReservedLayers,
     world::{HiddenRealm, Realm, RealmLighting},
@@ -71,6 +72,8 @@ pub fn setup_thumbnail_realm(
                     layer_index: layer,
                     name: "ThumbnailRealm".to_string(),
                     lighting: RealmLighting::Exterior,
+                    seed: NoiseSeed::ZERO,
+                    precinct_format: default(),
                     parcel_bounds: IRect::from_corners(IVec2::ZERO, IVec2::new(1, 1)),
                     precinct_bounds: IRect::from_corners(IVec2::ZERO, IVec2::ZERO),
                 },
@@ -265,12 +268,16 @@ pub fn update_terrain_thumbnails(
                     ShapeRef::default(),
                 ],
                 biomes: [0, 0, 0, 0],
+                water: default(),
                 ground_entity: None,
                 water_entity: None,
                 flora_entity: None,
+                billboard_entity: None,
+                flora_lod: default(),
                 terrain_fx: ParcelTerrainFx(
                     [TerrainFxVertexAttr::default(); PARCEL_TERRAIN_FX_AREA],
                 ),
+                flora_mask: ParcelFloraMask::default(),
             };
 
             let shape_pos = t.contour_id as f32 * PARCEL_SPACING;
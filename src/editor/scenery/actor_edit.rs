@@ -0,0 +1,290 @@
+use bevy::{ecs::world::Command, prelude::*};
+use panoply_exemplar::Exemplar;
+
+use crate::{
+    actors::ActorInstance,
+    editor::{
+        undo::{RedoEntry, UndoEntry, UndoStack},
+        unsaved::{ModifiedState, UnsavedAssets},
+    },
+    scenery::{precinct_asset::PrecinctAsset, PRECINCT_SIZE_F},
+};
+
+use super::scenery_edit::spawned_precincts;
+
+/// Identifies an actor by its index within a precinct.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct ActorRef {
+    pub(crate) precinct: Handle<PrecinctAsset>,
+    pub(crate) index: usize,
+}
+
+/// The actor which is selected in the editor.
+#[derive(Resource, Default, Clone, PartialEq)]
+pub(crate) struct SelectedActor(pub(crate) Option<ActorRef>);
+
+/// An operation on actors. Except for placement, edits apply to the selected actor.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ActorEdit {
+    /// Add a new actor at a world-space position, and select it.
+    Place {
+        realm: Entity,
+        exemplar: AssetId<Exemplar>,
+        position: Vec3,
+        /// Facing direction, in degrees.
+        facing: f32,
+        layer: Option<String>,
+    },
+    /// Move to a world-space position. Actors which cross a precinct boundary are transferred
+    /// to the neighboring precinct.
+    Move(Vec3),
+    /// Rotate by a number of quarter turns.
+    Rotate(i32),
+    /// Remove the actor.
+    Delete,
+    /// Change the instance identifier.
+    SetIid(Option<String>),
+}
+
+impl ActorEdit {
+    fn label(&self) -> &'static str {
+        match self {
+            ActorEdit::Place { .. } => "Place Actor",
+            ActorEdit::Move(_) => "Move Actor",
+            ActorEdit::Rotate(_) => "Rotate Actor",
+            ActorEdit::Delete => "Delete Actor",
+            ActorEdit::SetIid(_) => "Set Actor Id",
+        }
+    }
+}
+
+/// Command which applies an edit to the actors of one or two precincts as a single undoable
+/// operation. Actors in locked layers are left alone.
+pub(crate) struct EditActor(pub(crate) ActorEdit);
+
+impl Command for EditActor {
+    fn apply(self, world: &mut World) {
+        let edit = self.0;
+        let precincts = spawned_precincts(world);
+        let precinct_at = |realm: Entity, pos: Vec3| {
+            let coords = (pos.xz() / PRECINCT_SIZE_F).floor().as_ivec2();
+            precincts
+                .iter()
+                .find(|(r, c, _)| *r == realm && *c == coords)
+                .map(|(_, _, handle)| handle.clone())
+        };
+
+        // The actor being edited, and the precinct it ends up in.
+        let (source, target) = match &edit {
+            ActorEdit::Place {
+                realm, position, ..
+            } => (None, precinct_at(*realm, *position)),
+            ActorEdit::Move(position) => {
+                let Some(selected) = world.resource::<SelectedActor>().0.clone() else {
+                    return;
+                };
+                let Some((realm, _, _)) =
+                    precincts.iter().find(|(_, _, h)| *h == selected.precinct)
+                else {
+                    return;
+                };
+                let target = precinct_at(*realm, *position);
+                (Some(selected), target)
+            }
+            _ => {
+                let Some(selected) = world.resource::<SelectedActor>().0.clone() else {
+                    return;
+                };
+                let target = Some(selected.precinct.clone());
+                (Some(selected), target)
+            }
+        };
+        let Some(target) = target else {
+            warn!("Actors can't be placed in a precinct which isn't loaded.");
+            return;
+        };
+
+        let exemplar = match &edit {
+            ActorEdit::Place { exemplar, .. } => {
+                let Some(handle) = world.resource::<AssetServer>().get_id_handle(*exemplar) else {
+                    return;
+                };
+                Some(handle)
+            }
+            _ => None,
+        };
+
+        let mut changes: Vec<ActorChange> = Vec::new();
+        let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+        let mut record = |assets: &Assets<PrecinctAsset>, handle: &Handle<PrecinctAsset>| {
+            if !changes.iter().any(|c| c.precinct == *handle) {
+                changes.push(ActorChange {
+                    precinct: handle.clone(),
+                    before: assets
+                        .get(handle)
+                        .map(|a| a.actors.clone())
+                        .unwrap_or_default(),
+                    after: Vec::new(),
+                });
+            }
+        };
+
+        // Take the actor out of its precinct, or create a new one.
+        let mut actor = match (&edit, &source) {
+            (
+                ActorEdit::Place {
+                    position,
+                    facing,
+                    layer,
+                    ..
+                },
+                _,
+            ) => {
+                if assets.get(&target).is_none() {
+                    assets.insert(target.id(), PrecinctAsset::default());
+                }
+                let precinct = assets.get(&target).unwrap();
+                let layer = layer
+                    .as_ref()
+                    .filter(|name| precinct.layers.contains_key(*name))
+                    .cloned();
+                if precinct.is_layer_locked(layer.as_deref()) {
+                    return;
+                }
+                ActorInstance {
+                    exemplar: exemplar.unwrap(),
+                    position: *position,
+                    facing: *facing,
+                    layer,
+                    ..default()
+                }
+            }
+            (_, Some(selected)) => {
+                let Some(actor) = assets
+                    .get(&selected.precinct)
+                    .and_then(|precinct| precinct.actors.get(selected.index))
+                else {
+                    return;
+                };
+                let precinct = assets.get(&selected.precinct).unwrap();
+                if precinct.is_layer_locked(actor.layer.as_deref()) {
+                    return;
+                }
+                record(&assets, &selected.precinct);
+                assets
+                    .get_mut(&selected.precinct)
+                    .unwrap()
+                    .actors
+                    .remove(selected.index)
+            }
+            (_, None) => return,
+        };
+
+        let mut selection: Option<ActorRef> = None;
+        match &edit {
+            ActorEdit::Move(position) => actor.position = *position,
+            ActorEdit::Rotate(turns) => {
+                actor.facing = (actor.facing - *turns as f32 * 90.).rem_euclid(360.)
+            }
+            ActorEdit::SetIid(iid) => actor.iid.clone_from(iid),
+            ActorEdit::Place { .. } | ActorEdit::Delete => {}
+        }
+
+        if edit != ActorEdit::Delete {
+            if assets.get(&target).is_none() {
+                assets.insert(target.id(), PrecinctAsset::default());
+            }
+            record(&assets, &target);
+            let precinct = assets.get_mut(&target).unwrap();
+            // Actors which stay in the same precinct keep their place in the list.
+            let index = match &source {
+                Some(selected) if selected.precinct == target => selected.index,
+                _ => precinct.actors.len(),
+            };
+            precinct.actors.insert(index, actor);
+            selection = Some(ActorRef {
+                precinct: target,
+                index,
+            });
+        }
+
+        for change in changes.iter_mut() {
+            change.after = assets
+                .get(&change.precinct)
+                .map(|a| a.actors.clone())
+                .unwrap_or_default();
+        }
+        let mut unsaved = world.resource_mut::<UnsavedAssets>();
+        for change in changes.iter() {
+            unsaved
+                .precincts
+                .insert(change.precinct.clone(), ModifiedState::Unsaved);
+        }
+        world.resource_mut::<SelectedActor>().0 = selection;
+        world.resource_mut::<UndoStack>().push(UndoActorEdit {
+            label: edit.label(),
+            changes,
+        });
+    }
+}
+
+/// The actors of a single precinct before and after an edit.
+#[derive(Debug, Clone)]
+struct ActorChange {
+    precinct: Handle<PrecinctAsset>,
+    before: Vec<ActorInstance>,
+    after: Vec<ActorInstance>,
+}
+
+fn restore_actors(world: &mut World, changes: &[ActorChange], forward: bool) {
+    let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+    for change in changes.iter() {
+        if let Some(asset) = assets.get_mut(&change.precinct) {
+            asset.actors = if forward {
+                change.after.clone()
+            } else {
+                change.before.clone()
+            };
+        }
+    }
+    let mut unsaved = world.resource_mut::<UnsavedAssets>();
+    for change in changes.iter() {
+        unsaved
+            .precincts
+            .insert(change.precinct.clone(), ModifiedState::Unsaved);
+    }
+    world.resource_mut::<SelectedActor>().0 = None;
+}
+
+struct UndoActorEdit {
+    label: &'static str,
+    changes: Vec<ActorChange>,
+}
+
+impl UndoEntry for UndoActorEdit {
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn undo(&self, world: &mut World) -> Option<Box<dyn RedoEntry>> {
+        restore_actors(world, &self.changes, false);
+        Some(Box::new(UndoActorEdit {
+            label: self.label,
+            changes: self.changes.clone(),
+        }))
+    }
+}
+
+impl RedoEntry for UndoActorEdit {
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn redo(&self, world: &mut World) -> Option<Box<dyn UndoEntry>> {
+        restore_actors(world, &self.changes, true);
+        Some(Box::new(UndoActorEdit {
+            label: self.label,
+            changes: self.changes.clone(),
+        }))
+    }
+}
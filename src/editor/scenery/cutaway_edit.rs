@@ -0,0 +1,131 @@
+use bevy::{ecs::world::Command, prelude::*};
+
+use crate::{
+    editor::{
+        undo::{RedoEntry, UndoEntry, UndoStack},
+        unsaved::{ModifiedState, UnsavedAssets},
+    },
+    scenery::precinct_asset::PrecinctAsset,
+};
+
+/// A change to the cutaway rectangles of a tier.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CutawayEdit {
+    /// Add a rectangle, in precinct-relative coordinates.
+    Add(Rect),
+    /// Remove the rectangle at the given index.
+    Remove(usize),
+}
+
+impl CutawayEdit {
+    fn label(&self) -> &'static str {
+        match self {
+            CutawayEdit::Add(_) => "Add Cutaway",
+            CutawayEdit::Remove(_) => "Delete Cutaway",
+        }
+    }
+}
+
+/// Command which adds or removes a cutaway rectangle on one tier of a precinct, as an
+/// undoable operation. Adding a cutaway to a tier which doesn't exist yet creates the tier.
+pub(crate) struct EditCutaways {
+    pub(crate) precinct: Handle<PrecinctAsset>,
+    pub(crate) tier: i32,
+    pub(crate) edit: CutawayEdit,
+}
+
+impl Command for EditCutaways {
+    fn apply(self, world: &mut World) {
+        let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+        let Some(asset) = assets.get_mut(&self.precinct) else {
+            return;
+        };
+        let tier = match self.edit {
+            CutawayEdit::Add(_) if asset.find_tier(self.tier).is_none() => {
+                asset.add_tier(self.tier)
+            }
+            _ => match asset.find_tier_mut(self.tier) {
+                Some(tier) => tier,
+                None => return,
+            },
+        };
+        let before = tier.cutaways.clone();
+        match self.edit {
+            CutawayEdit::Add(rect) => tier.cutaways.push(rect),
+            CutawayEdit::Remove(index) if index < tier.cutaways.len() => {
+                tier.cutaways.remove(index);
+            }
+            CutawayEdit::Remove(_) => return,
+        }
+        let after = tier.cutaways.clone();
+
+        world
+            .resource_mut::<UnsavedAssets>()
+            .precincts
+            .insert(self.precinct.clone(), ModifiedState::Unsaved);
+        world.resource_mut::<UndoStack>().push(UndoCutawayEdit {
+            label: self.edit.label(),
+            precinct: self.precinct,
+            tier: self.tier,
+            before,
+            after,
+        });
+    }
+}
+
+/// The cutaways of a tier before and after an edit.
+struct UndoCutawayEdit {
+    label: &'static str,
+    precinct: Handle<PrecinctAsset>,
+    tier: i32,
+    before: Vec<Rect>,
+    after: Vec<Rect>,
+}
+
+impl UndoCutawayEdit {
+    fn restore(&self, world: &mut World, cutaways: &[Rect]) {
+        let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+        if let Some(tier) = assets
+            .get_mut(&self.precinct)
+            .and_then(|asset| asset.find_tier_mut(self.tier))
+        {
+            tier.cutaways = cutaways.to_vec();
+        }
+        world
+            .resource_mut::<UnsavedAssets>()
+            .precincts
+            .insert(self.precinct.clone(), ModifiedState::Unsaved);
+    }
+
+    fn copy(&self) -> Self {
+        Self {
+            label: self.label,
+            precinct: self.precinct.clone(),
+            tier: self.tier,
+            before: self.before.clone(),
+            after: self.after.clone(),
+        }
+    }
+}
+
+impl UndoEntry for UndoCutawayEdit {
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn undo(&self, world: &mut World) -> Option<Box<dyn RedoEntry>> {
+        self.restore(world, &self.before);
+        Some(Box::new(self.copy()))
+    }
+}
+
+impl RedoEntry for UndoCutawayEdit {
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn redo(&self, world: &mut World) -> Option<Box<dyn UndoEntry>> {
+        self.restore(world, &self.after);
+        Some(Box::new(self.copy()))
+    }
+}
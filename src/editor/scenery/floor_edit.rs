@@ -0,0 +1,167 @@
+use bevy::{ecs::world::Command, prelude::*};
+
+use crate::{
+    editor::{
+        lib::floor_ops::{
+            delete_floor_vertex, drag_floor_part, insert_floor_vertex, is_valid_floor_region,
+            FloorPart,
+        },
+        undo::{RedoEntry, UndoEntry, UndoStack},
+        unsaved::{ModifiedState, UnsavedAssets},
+    },
+    scenery::{floor_region::FloorRegionSer, precinct_asset::PrecinctAsset, PRECINCT_SIZE_F},
+};
+
+/// A change to the shape of one floor region.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum FloorEdit {
+    /// Move a vertex, an edge or a whole region by an offset.
+    Drag(FloorPart, Vec2),
+    /// Split an edge with a new vertex at the given position.
+    InsertVertex(FloorPart, Vec2),
+    /// Remove a vertex.
+    DeleteVertex(FloorPart),
+}
+
+impl FloorEdit {
+    fn label(&self) -> &'static str {
+        match self {
+            FloorEdit::Drag(FloorPart::Region(_), _) => "Move Floor",
+            FloorEdit::Drag(FloorPart::Edge { .. }, _) => "Move Floor Edge",
+            FloorEdit::Drag(FloorPart::Vertex { .. }, _) => "Move Floor Vertex",
+            FloorEdit::InsertVertex(..) => "Insert Floor Vertex",
+            FloorEdit::DeleteVertex(_) => "Delete Floor Vertex",
+        }
+    }
+
+    pub(crate) fn part(&self) -> FloorPart {
+        match self {
+            FloorEdit::Drag(part, _)
+            | FloorEdit::InsertVertex(part, _)
+            | FloorEdit::DeleteVertex(part) => *part,
+        }
+    }
+
+    /// Apply the edit to a floor region. Returns `None` if the edit doesn't fit the part, or
+    /// if the result would not be a valid region within the precinct.
+    pub(crate) fn apply(&self, region: &FloorRegionSer) -> Option<FloorRegionSer> {
+        let result = match *self {
+            FloorEdit::Drag(part, offset) => drag_floor_part(region, part, offset),
+            FloorEdit::InsertVertex(FloorPart::Edge { ring, index, .. }, position) => {
+                insert_floor_vertex(region, ring, index, position)
+            }
+            FloorEdit::DeleteVertex(FloorPart::Vertex { ring, index, .. }) => {
+                delete_floor_vertex(region, ring, index)?
+            }
+            _ => return None,
+        };
+        let bounds = Rect::new(0., 0., PRECINCT_SIZE_F, PRECINCT_SIZE_F);
+        is_valid_floor_region(&result, bounds).then_some(result)
+    }
+}
+
+/// Command which changes the shape of a floor region on one tier of a precinct, as an
+/// undoable operation. Edits which would leave the region self-intersecting are rejected,
+/// as are edits to floors in locked layers.
+pub(crate) struct EditFloor {
+    pub(crate) precinct: Handle<PrecinctAsset>,
+    pub(crate) tier: i32,
+    pub(crate) edit: FloorEdit,
+}
+
+impl Command for EditFloor {
+    fn apply(self, world: &mut World) {
+        let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+        let Some(asset) = assets.get_mut(&self.precinct) else {
+            return;
+        };
+        let locked = asset
+            .find_tier(self.tier)
+            .and_then(|tier| tier.pfloors.get(self.edit.part().region()))
+            .map(|region| asset.is_layer_locked(region.layer.as_deref()));
+        if locked != Some(false) {
+            return;
+        }
+        let tier = asset.find_tier_mut(self.tier).unwrap();
+        let index = self.edit.part().region();
+        let Some(region) = self.edit.apply(&tier.pfloors[index]) else {
+            warn!("Floor edit rejected: the region would not be a simple polygon.");
+            return;
+        };
+        if region == tier.pfloors[index] {
+            return;
+        }
+        let before = tier.pfloors.clone();
+        tier.pfloors[index] = region;
+        let after = tier.pfloors.clone();
+
+        world
+            .resource_mut::<UnsavedAssets>()
+            .precincts
+            .insert(self.precinct.clone(), ModifiedState::Unsaved);
+        world.resource_mut::<UndoStack>().push(UndoFloorEdit {
+            label: self.edit.label(),
+            precinct: self.precinct,
+            tier: self.tier,
+            before,
+            after,
+        });
+    }
+}
+
+/// The floors of a tier before and after an edit.
+struct UndoFloorEdit {
+    label: &'static str,
+    precinct: Handle<PrecinctAsset>,
+    tier: i32,
+    before: Vec<FloorRegionSer>,
+    after: Vec<FloorRegionSer>,
+}
+
+impl UndoFloorEdit {
+    fn restore(&self, world: &mut World, pfloors: &[FloorRegionSer]) {
+        let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+        if let Some(tier) = assets
+            .get_mut(&self.precinct)
+            .and_then(|asset| asset.find_tier_mut(self.tier))
+        {
+            tier.pfloors = pfloors.to_vec();
+        }
+        world
+            .resource_mut::<UnsavedAssets>()
+            .precincts
+            .insert(self.precinct.clone(), ModifiedState::Unsaved);
+    }
+
+    fn copy(&self) -> Self {
+        Self {
+            label: self.label,
+            precinct: self.precinct.clone(),
+            tier: self.tier,
+            before: self.before.clone(),
+            after: self.after.clone(),
+        }
+    }
+}
+
+impl UndoEntry for UndoFloorEdit {
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn undo(&self, world: &mut World) -> Option<Box<dyn RedoEntry>> {
+        self.restore(world, &self.before);
+        Some(Box::new(self.copy()))
+    }
+}
+
+impl RedoEntry for UndoFloorEdit {
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn redo(&self, world: &mut World) -> Option<Box<dyn UndoEntry>> {
+        self.restore(world, &self.after);
+        Some(Box::new(self.copy()))
+    }
+}
@@ -0,0 +1,22 @@
+mod actor_edit;
+mod cutaway_edit;
+mod floor_edit;
+mod precinct_convert;
+mod scenery_clipboard;
+mod scenery_edit;
+mod terrain_fx_edit;
+mod tier_edit;
+
+pub(crate) use actor_edit::{ActorEdit, ActorRef, EditActor, SelectedActor};
+pub(crate) use cutaway_edit::{CutawayEdit, EditCutaways};
+pub(crate) use floor_edit::{EditFloor, FloorEdit};
+pub(crate) use precinct_convert::{
+    finish_precinct_conversions, ConvertPrecincts, PendingPrecinctConversions,
+};
+pub(crate) use scenery_clipboard::{
+    receive_prefab, CopyScenery, LoadPrefab, PasteScenery, PendingPrefab, PrefabList,
+    RefreshPrefabs, SavePrefab, SceneryClipboard, UndoPrecinctEdit,
+};
+pub(crate) use scenery_edit::{EditScenery, SceneryEdit, SceneryRef, ScenerySelection};
+pub(crate) use terrain_fx_edit::{EndTerrainFxStroke, PaintTerrainFx, TerrainFxStroke};
+pub(crate) use tier_edit::{EditTiers, TierEdit};
@@ -0,0 +1,266 @@
+use std::path::PathBuf;
+
+use bevy::{
+    asset::{io::AssetSourceId, LoadState},
+    ecs::world::Command,
+    prelude::*,
+    tasks::block_on,
+    utils::HashMap,
+};
+use futures_lite::StreamExt;
+
+use crate::{
+    editor::{
+        realm::realm_asset_path,
+        unsaved::{ModifiedState, UnsavedAssets},
+    },
+    scenery::{
+        precinct::Precinct,
+        precinct_asset::{encode_precinct, PrecinctAsset},
+        precinct_cache::PrecinctCache,
+        precinct_format::PrecinctFormat,
+    },
+    world::{Realm, RealmData},
+};
+
+/// Command which converts the precinct files of one realm to the given format, and makes it
+/// the format in which the realm's new precincts are created. Files which already exist in
+/// the target format are left alone. Each file is loaded as a precinct and written back out,
+/// which takes a few frames; see [`finish_precinct_conversions`].
+pub(crate) struct ConvertPrecincts {
+    pub(crate) realm: Entity,
+    pub(crate) format: PrecinctFormat,
+}
+
+/// A precinct file which is being loaded so that it can be written in another format.
+struct PendingConversion {
+    realm: Entity,
+    handle: Handle<PrecinctAsset>,
+    from_path: PathBuf,
+    to_path: PathBuf,
+    format: PrecinctFormat,
+}
+
+/// Precinct conversions which are waiting for their files to load.
+#[derive(Resource, Default)]
+pub(crate) struct PendingPrecinctConversions {
+    files: Vec<PendingConversion>,
+    /// Number of files converted so far, for each realm.
+    converted: HashMap<Entity, usize>,
+}
+
+impl Command for ConvertPrecincts {
+    fn apply(self, world: &mut World) {
+        let Some(realm_name) = world.get::<Realm>(self.realm).map(|r| r.name.clone()) else {
+            return;
+        };
+        let realm_dir = PathBuf::from(format!("scenery/precincts/{}", realm_name));
+        let server = world.resource::<AssetServer>().clone();
+        if world
+            .resource::<UnsavedAssets>()
+            .precincts
+            .keys()
+            .any(|handle| {
+                server
+                    .get_path(handle)
+                    .is_some_and(|path| path.path().starts_with(&realm_dir))
+            })
+        {
+            warn!(
+                "Realm [{}] has unsaved scenery, precinct files were not converted.",
+                realm_name
+            );
+            return;
+        }
+        if world
+            .resource::<PendingPrecinctConversions>()
+            .files
+            .iter()
+            .any(|pending| pending.realm == self.realm)
+        {
+            warn!(
+                "Realm [{}] is already being converted, precinct files were not converted.",
+                realm_name
+            );
+            return;
+        }
+
+        let Ok(source) = server.get_source(AssetSourceId::Default) else {
+            return;
+        };
+        if source.writer().is_err() {
+            warn!("Asset source is not writable, precinct files were not converted.");
+            return;
+        }
+
+        // New precincts in this realm are created in the new format from now on.
+        if let Some(handle) = server.get_handle::<RealmData>(realm_asset_path(&realm_name)) {
+            let mut realms = world.resource_mut::<Assets<RealmData>>();
+            let changed = match realms.get_mut(&handle) {
+                Some(data) if data.precinct_format != self.format => {
+                    data.precinct_format = self.format;
+                    true
+                }
+                _ => false,
+            };
+            if changed {
+                world
+                    .resource_mut::<UnsavedAssets>()
+                    .realms
+                    .insert(handle, ModifiedState::Unsaved);
+            }
+        }
+
+        let reader = source.reader();
+        let from = self.format.opposite();
+        let from_suffix = format!(".{}", from.extension());
+
+        // Realms without scenery have no directory at all.
+        let files: Vec<PathBuf> = match block_on(reader.read_directory(&realm_dir)) {
+            Ok(stream) => block_on(stream.collect()),
+            Err(_) => Vec::new(),
+        };
+        let mut pending = Vec::new();
+        for from_path in files {
+            if !from_path.to_string_lossy().ends_with(&from_suffix) {
+                continue;
+            }
+            let to_path = self.format.with_extension(&from_path);
+            if block_on(reader.read(&to_path)).is_ok() {
+                warn!(
+                    "Precinct file {:?} already exists, {:?} was not converted.",
+                    to_path, from_path
+                );
+                continue;
+            }
+            pending.push(PendingConversion {
+                realm: self.realm,
+                handle: server.load(from_path.clone()),
+                from_path,
+                to_path,
+                format: self.format,
+            });
+        }
+        if pending.is_empty() {
+            info!("Realm [{}] has no precinct files to convert.", realm_name);
+            return;
+        }
+        let mut r_pending = world.resource_mut::<PendingPrecinctConversions>();
+        r_pending.converted.insert(self.realm, 0);
+        r_pending.files.extend(pending);
+    }
+}
+
+/// Write out precincts which have loaded for conversion, in their new format, and remove the
+/// old files. Once all of a realm's files are done, its precincts are despawned so that they
+/// are reloaded from the new files.
+pub(crate) fn finish_precinct_conversions(world: &mut World) {
+    if world
+        .resource::<PendingPrecinctConversions>()
+        .files
+        .is_empty()
+    {
+        return;
+    }
+    let server = world.resource::<AssetServer>().clone();
+    let files = std::mem::take(&mut world.resource_mut::<PendingPrecinctConversions>().files);
+    let mut still_pending = Vec::with_capacity(files.len());
+    let mut finished: Vec<(Entity, bool)> = Vec::new();
+    for pending in files {
+        let converted = match server.get_load_state(&pending.handle) {
+            Some(LoadState::Loaded) => convert_loaded(world, &server, &pending),
+            Some(LoadState::Failed(e)) => {
+                error!("Error loading precinct file {:?}: {}", pending.from_path, e);
+                false
+            }
+            _ => {
+                still_pending.push(pending);
+                continue;
+            }
+        };
+        finished.push((pending.realm, converted));
+    }
+
+    let mut r_pending = world.resource_mut::<PendingPrecinctConversions>();
+    r_pending.files = still_pending;
+    for (realm, converted) in finished.iter() {
+        if *converted {
+            *r_pending.converted.entry(*realm).or_default() += 1;
+        }
+    }
+    let mut done: Vec<(Entity, usize)> = Vec::new();
+    for (realm, _) in finished {
+        if !r_pending.files.iter().any(|pending| pending.realm == realm) {
+            if let Some(count) = r_pending.converted.remove(&realm) {
+                done.push((realm, count));
+            }
+        }
+    }
+
+    for (realm, count) in done {
+        if let Some(realm) = world.get::<Realm>(realm) {
+            info!(
+                "Converted {} precinct files of realm [{}] to {}.",
+                count,
+                realm.name,
+                realm.precinct_format.extension()
+            );
+        }
+
+        // Despawn the realm's precincts so that they are reloaded from the new paths.
+        let mut evicted = world.resource_mut::<PrecinctCache>().evict_realm(realm);
+        let mut q_precincts = world.query::<(Entity, &Precinct)>();
+        evicted.extend(
+            q_precincts
+                .iter(world)
+                .filter(|(_, precinct)| precinct.realm == realm)
+                .map(|(entity, _)| entity),
+        );
+        for entity in evicted {
+            if let Some(entity) = world.get_entity_mut(entity) {
+                entity.despawn_recursive();
+            }
+        }
+    }
+}
+
+/// Write a loaded precinct in its new format and remove the old file. Returns whether it was
+/// converted.
+fn convert_loaded(world: &World, server: &AssetServer, pending: &PendingConversion) -> bool {
+    let Some(precinct) = world
+        .resource::<Assets<PrecinctAsset>>()
+        .get(&pending.handle)
+    else {
+        return false;
+    };
+    if precinct.has_instance_aspects() {
+        warn!(
+            "Precinct file {:?} has scenery or actors with their own aspects, which can't be \
+             written yet; it was not converted.",
+            pending.from_path
+        );
+        return false;
+    }
+    let Ok(source) = server.get_source(AssetSourceId::Default) else {
+        return false;
+    };
+    let Ok(writer) = source.writer() else {
+        return false;
+    };
+    let result = block_on(async {
+        let bytes = encode_precinct(precinct, pending.format)?;
+        writer.write_bytes(&pending.to_path, &bytes).await?;
+        writer.remove(&pending.from_path).await?;
+        Ok::<_, Box<dyn std::error::Error>>(())
+    });
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            error!(
+                "Error converting precinct file {:?}: {}",
+                pending.from_path, e
+            );
+            false
+        }
+    }
+}
@@ -0,0 +1,675 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    asset::{
+        io::AssetSourceId,
+        saver::{AssetSaver, SavedAsset},
+        ErasedLoadedAsset, LoadState, LoadedAsset,
+    },
+    ecs::world::Command,
+    prelude::*,
+    tasks::block_on,
+    utils::HashSet,
+};
+use futures_lite::{AsyncWriteExt, StreamExt};
+
+use crate::{
+    editor::{
+        lib::floor_ops::{clip_floors, stamp_floors},
+        undo::{RedoEntry, UndoEntry, UndoStack},
+        unsaved::{ModifiedState, UnsavedAssets},
+    },
+    scenery::{
+        floor_region::FloorRegionSer,
+        precinct_asset::{PrecinctAsset, SceneryInstanceData, SceneryInstanceId},
+        prefab_asset::{
+            type_index, PrefabAsset, PrefabAssetSaver, PrefabFloor, PrefabScenery, PrefabTerrainFx,
+        },
+        PRECINCT_SIZE, PRECINCT_SIZE_F, TERRAIN_FX_MAP_SIZE, TIER_OFFSET,
+    },
+};
+
+use super::scenery_edit::{selected_scenery, spawned_precincts, SceneryRef, ScenerySelection};
+
+const PREFABS_DIR: &str = "scenery/prefabs";
+
+/// Scenery which has been copied in the editor, ready to be pasted.
+#[derive(Resource, Default)]
+pub(crate) struct SceneryClipboard(pub(crate) Option<PrefabAsset>);
+
+/// Names of the prefabs saved in the assets directory.
+#[derive(Resource, Default, Clone, PartialEq)]
+pub(crate) struct PrefabList(pub(crate) Vec<String>);
+
+fn prefab_asset_path(name: &str) -> PathBuf {
+    PathBuf::from(format!("{}/{}.prefab.json", PREFABS_DIR, name))
+}
+
+/// Command which copies the selected scenery to the clipboard, along with the floors and
+/// terrain effects of the selected tier which lie within the bounds of the selection.
+pub(crate) struct CopyScenery {
+    pub(crate) tier: i16,
+}
+
+impl Command for CopyScenery {
+    fn apply(self, world: &mut World) {
+        let precincts = spawned_precincts(world);
+        let Some((realm, sources)) = selected_scenery(world, &precincts, true) else {
+            return;
+        };
+        let bounds = sources.iter().fold(
+            Rect::from_center_size(sources[0].world_pos.xz(), Vec2::ZERO),
+            |r, s| r.union_point(s.world_pos.xz()),
+        );
+        // The origin is kept on the meter grid so that pasted walls stay aligned.
+        let origin = bounds.center().round();
+        let area = bounds.inflate(0.5);
+        let height = self.tier as f32 + TIER_OFFSET;
+
+        let mut prefab = PrefabAsset::default();
+        for source in sources.iter() {
+            prefab.scenery.push(PrefabScenery {
+                id: type_index(&mut prefab.scenery_types, &source.scenery_type),
+                facing: source.data.facing,
+                position: Vec3::new(
+                    source.world_pos.x - origin.x,
+                    source.world_pos.y - height,
+                    source.world_pos.z - origin.y,
+                ),
+                aspects: source.data.aspects.clone(),
+            });
+        }
+
+        let assets = world.resource::<Assets<PrecinctAsset>>();
+        for (_, coords, handle) in precincts.iter().filter(|(r, _, _)| *r == realm) {
+            let precinct_min = coords.as_vec2() * PRECINCT_SIZE_F;
+            let local_area = Rect::from_corners(area.min - precinct_min, area.max - precinct_min)
+                .intersect(Rect::new(0., 0., PRECINCT_SIZE_F, PRECINCT_SIZE_F));
+            if local_area.is_empty() {
+                continue;
+            }
+            let Some(asset) = assets.get(handle) else {
+                continue;
+            };
+
+            if let Some(tier) = asset.find_tier(self.tier as i32) {
+                let offset = precinct_min - origin;
+                for region in clip_floors(&tier.pfloors, local_area) {
+                    prefab.floors.push(PrefabFloor {
+                        tier: 0,
+                        surface_index: type_index(
+                            &mut prefab.floor_types,
+                            &asset.floor_types[region.surface_index],
+                        ),
+                        poly: region.poly.iter().map(|v| *v + offset).collect(),
+                        holes: region
+                            .holes
+                            .iter()
+                            .map(|hole| hole.iter().map(|v| *v + offset).collect())
+                            .collect(),
+                    });
+                }
+            }
+
+            copy_terrain_fx(asset, *coords, local_area, origin, &mut prefab);
+        }
+
+        world.resource_mut::<SceneryClipboard>().0 = Some(prefab);
+    }
+}
+
+/// Command which places a prefab on a tier, with its origin at the given world position and
+/// rotated by a number of quarter turns. New items go in the selected layer of each precinct,
+/// and the pasted scenery becomes the selection.
+pub(crate) struct PasteScenery {
+    pub(crate) prefab: PrefabAsset,
+    pub(crate) realm: Entity,
+    pub(crate) position: Vec2,
+    pub(crate) tier: i16,
+    pub(crate) turns: i32,
+    pub(crate) layer: Option<String>,
+}
+
+impl Command for PasteScenery {
+    fn apply(self, world: &mut World) {
+        let mut prefab = self.prefab;
+        if prefab.is_empty() {
+            return;
+        }
+        prefab.rotate(self.turns);
+        let origin = self.position.round();
+        let height = self.tier as f32 + TIER_OFFSET;
+        let precincts: Vec<(IVec2, Handle<PrecinctAsset>)> = spawned_precincts(world)
+            .into_iter()
+            .filter(|(realm, _, _)| *realm == self.realm)
+            .map(|(_, coords, handle)| (coords, handle))
+            .collect();
+        let precinct_at = |coords: IVec2| {
+            precincts
+                .iter()
+                .find(|(c, _)| *c == coords)
+                .map(|(_, handle)| handle.clone())
+        };
+
+        // All of the scenery has to land in loaded precincts.
+        let mut placements: Vec<(Handle<PrecinctAsset>, Vec3, &PrefabScenery)> =
+            Vec::with_capacity(prefab.scenery.len());
+        for scenery in prefab.scenery.iter() {
+            let world_pos = scenery.position.xz() + origin;
+            let coords = (world_pos / PRECINCT_SIZE_F).floor().as_ivec2();
+            let Some(handle) = precinct_at(coords) else {
+                warn!("Scenery can't be pasted into a precinct which isn't loaded.");
+                return;
+            };
+            let local = world_pos - coords.as_vec2() * PRECINCT_SIZE_F;
+            placements.push((
+                handle,
+                Vec3::new(local.x, height + scenery.position.y, local.y),
+                scenery,
+            ));
+        }
+
+        // Floors are split along precinct boundaries, to be stamped onto each precinct.
+        let mut floor_pieces: Vec<(Handle<PrecinctAsset>, &PrefabFloor, Vec<FloorRegionSer>)> =
+            Vec::new();
+        for floor in prefab.floors.iter() {
+            let poly: Vec<Vec2> = floor.poly.iter().map(|v| *v + origin).collect();
+            let holes: Vec<Vec<Vec2>> = floor
+                .holes
+                .iter()
+                .map(|hole| hole.iter().map(|v| *v + origin).collect())
+                .collect();
+            let Some(first) = poly.first() else {
+                continue;
+            };
+            let bounds = poly
+                .iter()
+                .fold(Rect::from_center_size(*first, Vec2::ZERO), |r, v| {
+                    r.union_point(*v)
+                });
+            for (coords, handle) in precincts.iter() {
+                let precinct_min = coords.as_vec2() * PRECINCT_SIZE_F;
+                let precinct_rect =
+                    Rect::from_corners(precinct_min, precinct_min + Vec2::splat(PRECINCT_SIZE_F));
+                let overlap = bounds.intersect(precinct_rect);
+                if overlap.width() <= 0. || overlap.height() <= 0. {
+                    continue;
+                }
+                let region = FloorRegionSer {
+                    surface_index: 0,
+                    poly: poly.iter().map(|v| *v - precinct_min).collect(),
+                    holes: holes
+                        .iter()
+                        .map(|hole| hole.iter().map(|v| *v - precinct_min).collect())
+                        .collect(),
+                    layer: None,
+                };
+                let pieces = clip_floors(
+                    &[region],
+                    Rect::new(0., 0., PRECINCT_SIZE_F, PRECINCT_SIZE_F),
+                );
+                if !pieces.is_empty() {
+                    floor_pieces.push((handle.clone(), floor, pieces));
+                }
+            }
+        }
+
+        // Terrain effects are written to every precinct which stores the vertex, including
+        // the skirts of the neighbors.
+        let mut fx_writes: Vec<(Handle<PrecinctAsset>, IVec2, &String)> = Vec::new();
+        for fx in prefab.terrain_fx.iter() {
+            for (coords, local) in terrain_fx_targets(origin.as_ivec2() + fx.offset) {
+                if let Some(handle) = precinct_at(coords) {
+                    fx_writes.push((handle, local, &prefab.terrain_fx_types[fx.id]));
+                }
+            }
+        }
+
+        // Paste all or nothing, rather than silently leaving out part of the prefab.
+        {
+            let assets = world.resource::<Assets<PrecinctAsset>>();
+            let locked = placements
+                .iter()
+                .map(|(handle, _, _)| handle)
+                .chain(floor_pieces.iter().map(|(handle, _, _)| handle))
+                .chain(fx_writes.iter().map(|(handle, _, _)| handle))
+                .any(|handle| {
+                    assets
+                        .get(handle)
+                        .is_some_and(|asset| asset.is_layer_locked(self.layer.as_deref()))
+                });
+            if locked {
+                warn!("Scenery can't be pasted into a locked layer.");
+                return;
+            }
+        }
+
+        let mut edit = PrecinctEdit::new(world.resource_mut::<Assets<PrecinctAsset>>());
+        let mut selection = HashSet::<SceneryRef>::with_capacity(placements.len());
+        for (handle, position, scenery) in placements {
+            let Some((asset, layer)) = edit.get_mut(&handle, &self.layer) else {
+                continue;
+            };
+            let scenery_type = &prefab.scenery_types[scenery.id];
+            let id = match asset.scenery_type_index(scenery_type) {
+                Some(id) => id,
+                None => asset.add_scenery_type(scenery_type.clone()),
+            };
+            let iid = SceneryInstanceId::Internal(asset.next_scenery_id());
+            asset.add_scenery_instance(SceneryInstanceData {
+                id,
+                facing: scenery.facing,
+                position,
+                iid: iid.clone(),
+                aspects: scenery.aspects.clone(),
+                layer,
+            });
+            selection.insert(SceneryRef {
+                precinct: handle,
+                iid,
+            });
+        }
+
+        for (handle, floor, pieces) in floor_pieces {
+            let Some((asset, layer)) = edit.get_mut(&handle, &self.layer) else {
+                continue;
+            };
+            let level = self.tier as i32 + floor.tier;
+            let floor_type = &prefab.floor_types[floor.surface_index];
+            let surface_index = match asset.floor_type_index(floor_type) {
+                Some(index) => index,
+                None => asset.add_floor_type(floor_type.clone()),
+            };
+            if asset.find_tier(level).is_none() {
+                asset.add_tier(level);
+            }
+            for piece in pieces {
+                let mut shape = vec![piece.poly];
+                shape.extend(piece.holes);
+                let pfloors = &asset.find_tier(level).unwrap().pfloors;
+                let pfloors = stamp_floors(asset, pfloors, &shape, surface_index, &layer);
+                asset.find_tier_mut(level).unwrap().pfloors = pfloors;
+            }
+        }
+
+        for (handle, local, fx_type) in fx_writes {
+            if let Some((asset, _)) = edit.get_mut(&handle, &self.layer) {
+                asset.paint_terrain_fx(local, 0, Some(fx_type));
+            }
+        }
+
+        let before = edit.before;
+        let after: Vec<(Handle<PrecinctAsset>, PrecinctAsset)> = {
+            let assets = world.resource::<Assets<PrecinctAsset>>();
+            before
+                .iter()
+                .map(|(handle, _)| (handle.clone(), assets.get(handle).unwrap().clone()))
+                .collect()
+        };
+        let mut unsaved = world.resource_mut::<UnsavedAssets>();
+        for (handle, _) in before.iter() {
+            unsaved
+                .precincts
+                .insert(handle.clone(), ModifiedState::Unsaved);
+        }
+        world.resource_mut::<ScenerySelection>().0 = selection;
+        world.resource_mut::<UndoStack>().push(UndoPrecinctEdit {
+            label: "Paste Scenery",
+            before,
+            after,
+        });
+    }
+}
+
+/// Add the terrain effects of a precinct which lie within an area to a prefab. Each precinct
+/// owns the vertices from its origin up to, but not including, its far edges; the rest of its
+/// map is a skirt copied from the neighbors, and is left for them to contribute.
+fn copy_terrain_fx(
+    asset: &PrecinctAsset,
+    coords: IVec2,
+    local_area: Rect,
+    origin: Vec2,
+    prefab: &mut PrefabAsset,
+) {
+    if asset.terrain_fx.is_none() {
+        return;
+    }
+    let map = asset.decode_terrain_fx();
+    let min = local_area.min.ceil().as_ivec2().max(IVec2::ZERO);
+    let max = local_area
+        .max
+        .floor()
+        .as_ivec2()
+        .min(IVec2::splat(PRECINCT_SIZE - 1));
+    for z in min.y..=max.y {
+        for x in min.x..=max.x {
+            // Map entries start one vertex before the precinct's origin.
+            let fx = map[(x + 1) as usize + (z + 1) as usize * TERRAIN_FX_MAP_SIZE];
+            if fx == 0 {
+                continue;
+            }
+            prefab.terrain_fx.push(PrefabTerrainFx {
+                offset: IVec2::new(x, z) + coords * PRECINCT_SIZE - origin.as_ivec2(),
+                id: type_index(
+                    &mut prefab.terrain_fx_types,
+                    &asset.terrain_fx_types[fx as usize - 1],
+                ),
+            });
+        }
+    }
+}
+
+/// The precincts whose terrain effect maps include a world-space vertex, along with the
+/// vertex's local coordinates in each: its owner, plus the neighbors whose skirts it is in.
+fn terrain_fx_targets(vertex: IVec2) -> impl Iterator<Item = (IVec2, IVec2)> {
+    let owner = vertex.div_euclid(IVec2::splat(PRECINCT_SIZE));
+    (-1..=1)
+        .flat_map(move |dz| (-1..=1).map(move |dx| owner + IVec2::new(dx, dz)))
+        .map(move |coords| (coords, vertex - coords * PRECINCT_SIZE))
+        .filter(|(_, local)| {
+            local.cmpge(IVec2::splat(-1)).all() && local.cmple(IVec2::splat(PRECINCT_SIZE)).all()
+        })
+}
+
+/// Tracks the precincts modified by an edit, keeping a copy of each as it was before.
+struct PrecinctEdit<'w> {
+    assets: Mut<'w, Assets<PrecinctAsset>>,
+    before: Vec<(Handle<PrecinctAsset>, PrecinctAsset)>,
+}
+
+impl<'w> PrecinctEdit<'w> {
+    fn new(assets: Mut<'w, Assets<PrecinctAsset>>) -> Self {
+        Self {
+            assets,
+            before: Vec::new(),
+        }
+    }
+
+    /// Get a precinct for editing, along with the layer that new items should go in. Returns
+    /// `None` if that layer is locked.
+    fn get_mut(
+        &mut self,
+        handle: &Handle<PrecinctAsset>,
+        layer: &Option<String>,
+    ) -> Option<(&mut PrecinctAsset, Option<String>)> {
+        if self.assets.get(handle).is_none() {
+            self.assets.insert(handle.id(), PrecinctAsset::default());
+        }
+        let asset = self.assets.get_mut(handle).unwrap();
+        let layer = layer
+            .as_ref()
+            .filter(|name| asset.layers.contains_key(*name))
+            .cloned();
+        if asset.is_layer_locked(layer.as_deref()) {
+            return None;
+        }
+        if !self.before.iter().any(|(h, _)| h == handle) {
+            self.before.push((handle.clone(), asset.clone()));
+        }
+        Some((asset, layer))
+    }
+}
+
+/// Undo entry which restores whole precincts.
+pub(crate) struct UndoPrecinctEdit {
+    pub(crate) label: &'static str,
+    pub(crate) before: Vec<(Handle<PrecinctAsset>, PrecinctAsset)>,
+    pub(crate) after: Vec<(Handle<PrecinctAsset>, PrecinctAsset)>,
+}
+
+fn restore_precincts(world: &mut World, precincts: &[(Handle<PrecinctAsset>, PrecinctAsset)]) {
+    let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+    for (handle, asset) in precincts.iter() {
+        assets.insert(handle.id(), asset.clone());
+    }
+    let mut unsaved = world.resource_mut::<UnsavedAssets>();
+    for (handle, _) in precincts.iter() {
+        unsaved
+            .precincts
+            .insert(handle.clone(), ModifiedState::Unsaved);
+    }
+    world.resource_mut::<ScenerySelection>().0.clear();
+}
+
+impl UndoEntry for UndoPrecinctEdit {
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn undo(&self, world: &mut World) -> Option<Box<dyn RedoEntry>> {
+        restore_precincts(world, &self.before);
+        Some(Box::new(UndoPrecinctEdit {
+            label: self.label,
+            before: self.before.clone(),
+            after: self.after.clone(),
+        }))
+    }
+}
+
+impl RedoEntry for UndoPrecinctEdit {
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn redo(&self, world: &mut World) -> Option<Box<dyn UndoEntry>> {
+        restore_precincts(world, &self.after);
+        Some(Box::new(UndoPrecinctEdit {
+            label: self.label,
+            before: self.before.clone(),
+            after: self.after.clone(),
+        }))
+    }
+}
+
+/// Command which reads the names of the saved prefabs into [`PrefabList`].
+pub(crate) struct RefreshPrefabs;
+
+impl Command for RefreshPrefabs {
+    fn apply(self, world: &mut World) {
+        let server = world.resource::<AssetServer>().clone();
+        let Ok(source) = server.get_source(AssetSourceId::Default) else {
+            return;
+        };
+        let files: Vec<PathBuf> =
+            match block_on(source.reader().read_directory(Path::new(PREFABS_DIR))) {
+                Ok(stream) => block_on(stream.collect()),
+                Err(_) => Vec::new(),
+            };
+        let mut names: Vec<String> = files
+            .iter()
+            .filter_map(|path| path.file_name()?.to_str()?.strip_suffix(".prefab.json"))
+            .map(String::from)
+            .collect();
+        names.sort();
+        let mut list = world.resource_mut::<PrefabList>();
+        if list.0 != names {
+            list.0 = names;
+        }
+    }
+}
+
+/// Command which saves the contents of the clipboard as a prefab with the given name,
+/// replacing any existing prefab of that name.
+pub(crate) struct SavePrefab(pub(crate) String);
+
+impl Command for SavePrefab {
+    fn apply(self, world: &mut World) {
+        let name = self.0.trim();
+        if name.is_empty() || name.contains(['/', '\\', '.']) {
+            warn!("Invalid prefab name: [{}].", name);
+            return;
+        }
+        let Some(prefab) = world.resource::<SceneryClipboard>().0.clone() else {
+            return;
+        };
+
+        let server = world.resource::<AssetServer>().clone();
+        let Ok(source) = server.get_source(AssetSourceId::Default) else {
+            return;
+        };
+        let Ok(writer) = source.writer() else {
+            warn!("Asset source is not writable, prefab was not saved.");
+            return;
+        };
+        let path = prefab_asset_path(name);
+        let result = block_on(async {
+            let mut write = writer.write(&path).await?;
+            let loaded = LoadedAsset::new_with_dependencies(prefab, None);
+            let erased = ErasedLoadedAsset::from(loaded);
+            let saved = SavedAsset::from_loaded(&erased).unwrap();
+            PrefabAssetSaver.save(&mut *write, saved, &()).await?;
+            write.close().await?;
+            Ok::<_, Box<dyn std::error::Error>>(())
+        });
+        match result {
+            Ok(()) => info!("Saved prefab [{}].", name),
+            Err(e) => error!("Error saving prefab [{}]: {}", name, e),
+        }
+        RefreshPrefabs.apply(world);
+    }
+}
+
+/// Prefab which has been requested from the asset server, and which goes into the clipboard
+/// once it has loaded.
+#[derive(Resource, Default)]
+pub(crate) struct PendingPrefab(Option<Handle<PrefabAsset>>);
+
+/// Command which loads a saved prefab into the clipboard.
+pub(crate) struct LoadPrefab(pub(crate) String);
+
+impl Command for LoadPrefab {
+    fn apply(self, world: &mut World) {
+        let handle = world
+            .resource::<AssetServer>()
+            .load::<PrefabAsset>(prefab_asset_path(&self.0));
+        world.resource_mut::<PendingPrefab>().0 = Some(handle);
+    }
+}
+
+/// Copy the pending prefab to the clipboard once it has loaded. The handle is dropped
+/// afterwards, so loading the prefab again reads the file again.
+pub(crate) fn receive_prefab(
+    r_server: Res<AssetServer>,
+    r_prefabs: Res<Assets<PrefabAsset>>,
+    mut r_pending: ResMut<PendingPrefab>,
+    mut r_clipboard: ResMut<SceneryClipboard>,
+) {
+    let Some(handle) = &r_pending.0 else {
+        return;
+    };
+    if let Some(prefab) = r_prefabs.get(handle) {
+        r_clipboard.0 = Some(prefab.clone());
+        r_pending.0 = None;
+    } else if let Some(LoadState::Failed(e)) = r_server.get_load_state(handle) {
+        error!("Error loading prefab {:?}: {}", handle.path(), e);
+        r_pending.0 = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Paste the terrain effects of a prefab into a set of precincts, the same way
+    /// [`PasteScenery`] does.
+    fn paste_terrain_fx(
+        prefab: &PrefabAsset,
+        origin: IVec2,
+        precincts: &mut [(IVec2, PrecinctAsset)],
+    ) {
+        for fx in prefab.terrain_fx.iter() {
+            for (coords, local) in terrain_fx_targets(origin + fx.offset) {
+                if let Some((_, asset)) = precincts.iter_mut().find(|(c, _)| *c == coords) {
+                    asset.paint_terrain_fx(local, 0, Some(&prefab.terrain_fx_types[fx.id]));
+                }
+            }
+        }
+    }
+
+    fn fx_at(asset: &PrecinctAsset, local: IVec2) -> u16 {
+        let map = asset.decode_terrain_fx();
+        map[(local.x + 1) as usize + (local.y + 1) as usize * TERRAIN_FX_MAP_SIZE]
+    }
+
+    #[test]
+    fn test_terrain_fx_targets() {
+        let targets: Vec<_> = terrain_fx_targets(IVec2::new(0, 5)).collect();
+        assert_eq!(targets.len(), 2);
+        assert!(targets.contains(&(IVec2::ZERO, IVec2::new(0, 5))));
+        assert!(targets.contains(&(IVec2::new(-1, 0), IVec2::new(PRECINCT_SIZE, 5))));
+
+        let targets: Vec<_> = terrain_fx_targets(IVec2::new(PRECINCT_SIZE - 1, 5)).collect();
+        assert_eq!(targets.len(), 2);
+        assert!(targets.contains(&(IVec2::new(1, 0), IVec2::new(-1, 5))));
+
+        // A corner vertex is in four maps.
+        assert_eq!(terrain_fx_targets(IVec2::ZERO).count(), 4);
+        assert_eq!(terrain_fx_targets(IVec2::new(5, 5)).count(), 1);
+    }
+
+    #[test]
+    fn test_copy_paste_terrain_fx() {
+        let mut source = PrecinctAsset::default();
+        source.paint_terrain_fx(IVec2::new(3, 5), 0, Some("road"));
+        let mut prefab = PrefabAsset::default();
+        let area = Rect::new(0., 0., 8., 8.);
+        copy_terrain_fx(&source, IVec2::ZERO, area, Vec2::new(2., 2.), &mut prefab);
+        assert_eq!(prefab.terrain_fx.len(), 1);
+        assert_eq!(prefab.terrain_fx[0].offset, IVec2::new(1, 3));
+
+        let mut precincts = vec![(IVec2::ZERO, PrecinctAsset::default())];
+        paste_terrain_fx(&prefab, IVec2::new(10, 10), &mut precincts);
+        assert_eq!(fx_at(&precincts[0].1, IVec2::new(11, 13)), 1);
+        assert_eq!(fx_at(&precincts[0].1, IVec2::new(10, 12)), 0);
+    }
+
+    #[test]
+    fn test_paste_rotated_terrain_fx() {
+        let mut prefab = PrefabAsset {
+            terrain_fx_types: vec!["road".to_string()],
+            terrain_fx: vec![PrefabTerrainFx {
+                offset: IVec2::new(1, 3),
+                id: 0,
+            }],
+            scenery_types: vec!["wall".to_string()],
+            scenery: vec![PrefabScenery {
+                id: 0,
+                facing: 0.,
+                position: Vec3::new(1., 0., 3.),
+                aspects: default(),
+            }],
+            ..default()
+        };
+        prefab.rotate(1);
+        // The effect stays under the scenery it was copied with.
+        assert_eq!(
+            prefab.terrain_fx[0].offset.as_vec2(),
+            prefab.scenery[0].position.xz()
+        );
+
+        let mut precincts = vec![(IVec2::ZERO, PrecinctAsset::default())];
+        paste_terrain_fx(&prefab, IVec2::new(10, 10), &mut precincts);
+        assert_eq!(fx_at(&precincts[0].1, IVec2::new(7, 11)), 1);
+    }
+
+    #[test]
+    fn test_paste_terrain_fx_across_seam() {
+        let prefab = PrefabAsset {
+            terrain_fx_types: vec!["road".to_string()],
+            terrain_fx: vec![PrefabTerrainFx {
+                offset: IVec2::ZERO,
+                id: 0,
+            }],
+            ..default()
+        };
+        let mut precincts = vec![
+            (IVec2::ZERO, PrecinctAsset::default()),
+            (IVec2::new(1, 0), PrecinctAsset::default()),
+        ];
+        paste_terrain_fx(&prefab, IVec2::new(PRECINCT_SIZE, 4), &mut precincts);
+        // The far skirt of the left precinct matches the near edge of the right one.
+        assert_eq!(fx_at(&precincts[0].1, IVec2::new(PRECINCT_SIZE, 4)), 1);
+        assert_eq!(fx_at(&precincts[1].1, IVec2::new(0, 4)), 1);
+        assert_eq!(fx_at(&precincts[0].1, IVec2::new(PRECINCT_SIZE - 1, 4)), 0);
+    }
+}
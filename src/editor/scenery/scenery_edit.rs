@@ -0,0 +1,346 @@
+use bevy::{ecs::world::Command, prelude::*, utils::HashSet};
+
+use crate::{
+    editor::{
+        undo::{RedoEntry, UndoEntry, UndoStack},
+        unsaved::{ModifiedState, UnsavedAssets},
+    },
+    scenery::{
+        precinct::Precinct,
+        precinct_asset::{PrecinctAsset, SceneryInstanceData, SceneryInstanceId},
+        PRECINCT_SIZE_F,
+    },
+};
+
+/// Identifies a scenery instance within a precinct.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct SceneryRef {
+    pub(crate) precinct: Handle<PrecinctAsset>,
+    pub(crate) iid: SceneryInstanceId,
+}
+
+/// Scenery instances which are selected in the editor.
+#[derive(Resource, Default, Clone, PartialEq)]
+pub(crate) struct ScenerySelection(pub(crate) HashSet<SceneryRef>);
+
+/// An operation on the selected scenery.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SceneryEdit {
+    /// Move by a world-space offset. Instances which cross a precinct boundary are transferred
+    /// to the neighboring precinct.
+    Move(Vec3),
+    /// Rotate by a number of quarter turns around the center of the selection.
+    Rotate(i32),
+    /// Remove the selected instances.
+    Delete,
+    /// Copy the selected instances, offset by the given amount, and select the copies.
+    Duplicate(Vec3),
+}
+
+impl SceneryEdit {
+    fn label(&self) -> &'static str {
+        match self {
+            SceneryEdit::Move(_) => "Move Scenery",
+            SceneryEdit::Rotate(_) => "Rotate Scenery",
+            SceneryEdit::Delete => "Delete Scenery",
+            SceneryEdit::Duplicate(_) => "Duplicate Scenery",
+        }
+    }
+}
+
+/// Command which applies an edit to the selected scenery as a single undoable operation.
+/// Instances in locked layers are left alone.
+pub(crate) struct EditScenery(pub(crate) SceneryEdit);
+
+/// A scenery instance collected from the selection.
+pub(super) struct SelectedScenery {
+    pub(super) precinct: Handle<PrecinctAsset>,
+    pub(super) scenery_type: String,
+    pub(super) world_pos: Vec3,
+    pub(super) data: SceneryInstanceData,
+}
+
+/// Realm, grid coordinates and asset of every spawned precinct.
+pub(super) fn spawned_precincts(world: &mut World) -> Vec<(Entity, IVec2, Handle<PrecinctAsset>)> {
+    let mut q_precincts = world.query::<&Precinct>();
+    q_precincts
+        .iter(world)
+        .map(|p| (p.realm, p.coords, p.asset.clone()))
+        .collect()
+}
+
+/// Collect the selected scenery instances, along with the realm they are in. Instances in
+/// locked layers are left out unless `include_locked` is set.
+pub(super) fn selected_scenery(
+    world: &World,
+    precincts: &[(Entity, IVec2, Handle<PrecinctAsset>)],
+    include_locked: bool,
+) -> Option<(Entity, Vec<SelectedScenery>)> {
+    let selection = &world.resource::<ScenerySelection>().0;
+    let assets = world.resource::<Assets<PrecinctAsset>>();
+    let mut sources: Vec<SelectedScenery> = Vec::with_capacity(selection.len());
+    let mut realm: Option<Entity> = None;
+    for sref in selection.iter() {
+        let Some((precinct_realm, coords, _)) =
+            precincts.iter().find(|(_, _, h)| *h == sref.precinct)
+        else {
+            continue;
+        };
+        let Some(asset) = assets.get(&sref.precinct) else {
+            continue;
+        };
+        let Some(data) = asset.scenery.iter().find(|s| s.iid == sref.iid) else {
+            continue;
+        };
+        if !include_locked && asset.is_layer_locked(data.layer.as_deref()) {
+            continue;
+        }
+        realm = Some(*precinct_realm);
+        let origin = coords.as_vec2() * PRECINCT_SIZE_F;
+        sources.push(SelectedScenery {
+            precinct: sref.precinct.clone(),
+            scenery_type: asset.scenery_types[data.id].clone(),
+            world_pos: data.position + Vec3::new(origin.x, 0., origin.y),
+            data: data.clone(),
+        });
+    }
+    realm.map(|realm| (realm, sources))
+}
+
+impl Command for EditScenery {
+    fn apply(self, world: &mut World) {
+        let edit = self.0;
+        let precincts = spawned_precincts(world);
+        let Some((realm, sources)) = selected_scenery(world, &precincts, false) else {
+            return;
+        };
+
+        // Compute where each instance ends up.
+        let center = rotation_center(&sources);
+        let mut targets: Vec<(Handle<PrecinctAsset>, Vec3, f32)> =
+            Vec::with_capacity(sources.len());
+        for source in sources.iter() {
+            let (world_pos, facing) = match edit {
+                SceneryEdit::Move(offset) | SceneryEdit::Duplicate(offset) => {
+                    (source.world_pos + offset, source.data.facing)
+                }
+                SceneryEdit::Rotate(turns) => (
+                    rotate_quarter_turns(source.world_pos, center, turns),
+                    (source.data.facing - turns as f32 * 90.).rem_euclid(360.),
+                ),
+                SceneryEdit::Delete => continue,
+            };
+            let coords = (world_pos.xz() / PRECINCT_SIZE_F).floor().as_ivec2();
+            let Some((_, _, handle)) = precincts
+                .iter()
+                .find(|(r, c, _)| *r == realm && *c == coords)
+            else {
+                warn!("Scenery can't be moved to a precinct which isn't loaded.");
+                return;
+            };
+            let origin = coords.as_vec2() * PRECINCT_SIZE_F;
+            targets.push((
+                handle.clone(),
+                world_pos - Vec3::new(origin.x, 0., origin.y),
+                facing,
+            ));
+        }
+
+        // Remove the originals, unless they are being duplicated.
+        let mut changes: Vec<SceneryChange> = Vec::new();
+        let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+        if !matches!(edit, SceneryEdit::Duplicate(_)) {
+            for source in sources.iter() {
+                let asset = assets.get_mut(&source.precinct).unwrap();
+                let removed = asset.remove_scenery_elements(|s| s.iid == source.data.iid);
+                change_for(&mut changes, &source.precinct)
+                    .removed
+                    .extend(removed);
+            }
+        }
+
+        // Add the instances at their new locations.
+        let mut new_selection = HashSet::<SceneryRef>::with_capacity(targets.len());
+        for (source, (handle, position, facing)) in sources.iter().zip(targets) {
+            if assets.get(&handle).is_none() {
+                assets.insert(handle.id(), PrecinctAsset::default());
+            }
+            let asset = assets.get_mut(&handle).unwrap();
+            let id = match asset.scenery_type_index(&source.scenery_type) {
+                Some(id) => id,
+                None => asset.add_scenery_type(source.scenery_type.clone()),
+            };
+            // Instances keep their id unless it could clash with an existing one.
+            let iid = match (&edit, &source.data.iid) {
+                (SceneryEdit::Duplicate(_), _) => {
+                    SceneryInstanceId::Internal(asset.next_scenery_id())
+                }
+                (_, SceneryInstanceId::Internal(_)) if handle != source.precinct => {
+                    SceneryInstanceId::Internal(asset.next_scenery_id())
+                }
+                (_, iid) => iid.clone(),
+            };
+            let instance = SceneryInstanceData {
+                id,
+                facing,
+                position,
+                iid: iid.clone(),
+                ..source.data.clone()
+            };
+            asset.add_scenery_instance(instance.clone());
+            change_for(&mut changes, &handle).added.push(instance);
+            new_selection.insert(SceneryRef {
+                precinct: handle,
+                iid,
+            });
+        }
+
+        let mut unsaved = world.resource_mut::<UnsavedAssets>();
+        for change in changes.iter() {
+            unsaved
+                .precincts
+                .insert(change.precinct.clone(), ModifiedState::Unsaved);
+        }
+        world.resource_mut::<ScenerySelection>().0 = new_selection;
+        world.resource_mut::<UndoStack>().push(UndoSceneryEdit {
+            label: edit.label(),
+            changes,
+        });
+    }
+}
+
+/// Center of rotation for a set of instances: the middle of their bounds, snapped to the half
+/// meter so that grid-aligned instances stay aligned.
+fn rotation_center(sources: &[SelectedScenery]) -> Vec2 {
+    let Some(first) = sources.first() else {
+        return Vec2::ZERO;
+    };
+    let bounds = sources.iter().fold(
+        Rect::from_center_size(first.world_pos.xz(), Vec2::ZERO),
+        |r, s| r.union_point(s.world_pos.xz()),
+    );
+    (bounds.center() * 2.).round() / 2.
+}
+
+/// Rotate a position around a vertical axis by quarter turns, in the same direction as
+/// [`RotateSelection`](crate::editor::events::RotateSelection) turns the facing.
+fn rotate_quarter_turns(pos: Vec3, center: Vec2, turns: i32) -> Vec3 {
+    let rel = pos.xz() - center;
+    let rotated = match turns.rem_euclid(4) {
+        0 => rel,
+        1 => Vec2::new(-rel.y, rel.x),
+        2 => -rel,
+        _ => Vec2::new(rel.y, -rel.x),
+    } + center;
+    Vec3::new(rotated.x, pos.y, rotated.y)
+}
+
+/// Scenery added to and removed from a single precinct.
+#[derive(Debug, Clone)]
+struct SceneryChange {
+    precinct: Handle<PrecinctAsset>,
+    added: Vec<SceneryInstanceData>,
+    removed: Vec<SceneryInstanceData>,
+}
+
+fn change_for<'a>(
+    changes: &'a mut Vec<SceneryChange>,
+    precinct: &Handle<PrecinctAsset>,
+) -> &'a mut SceneryChange {
+    let index = match changes.iter().position(|c| &c.precinct == precinct) {
+        Some(index) => index,
+        None => {
+            changes.push(SceneryChange {
+                precinct: precinct.clone(),
+                added: Vec::new(),
+                removed: Vec::new(),
+            });
+            changes.len() - 1
+        }
+    };
+    &mut changes[index]
+}
+
+/// Replace the `from` instances with the `to` instances in each changed precinct.
+fn swap_scenery(world: &mut World, changes: &[SceneryChange], forward: bool) {
+    let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+    for change in changes.iter() {
+        let (from, to) = if forward {
+            (&change.removed, &change.added)
+        } else {
+            (&change.added, &change.removed)
+        };
+        let Some(asset) = assets.get_mut(&change.precinct) else {
+            continue;
+        };
+        let to_remove = HashSet::<SceneryInstanceId>::from_iter(from.iter().map(|s| s.iid.clone()));
+        asset.remove_scenery_elements(|s| to_remove.contains(&s.iid));
+        for instance in to.iter() {
+            asset.add_scenery_instance(instance.clone());
+        }
+    }
+    let mut unsaved = world.resource_mut::<UnsavedAssets>();
+    for change in changes.iter() {
+        unsaved
+            .precincts
+            .insert(change.precinct.clone(), ModifiedState::Unsaved);
+    }
+    world.resource_mut::<ScenerySelection>().0.clear();
+}
+
+struct UndoSceneryEdit {
+    label: &'static str,
+    changes: Vec<SceneryChange>,
+}
+
+impl UndoEntry for UndoSceneryEdit {
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn undo(&self, world: &mut World) -> Option<Box<dyn RedoEntry>> {
+        swap_scenery(world, &self.changes, false);
+        Some(Box::new(UndoSceneryEdit {
+            label: self.label,
+            changes: self.changes.clone(),
+        }))
+    }
+}
+
+impl RedoEntry for UndoSceneryEdit {
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn redo(&self, world: &mut World) -> Option<Box<dyn UndoEntry>> {
+        swap_scenery(world, &self.changes, true);
+        Some(Box::new(UndoSceneryEdit {
+            label: self.label,
+            changes: self.changes.clone(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_quarter_turns() {
+        let center = Vec2::new(10., 20.);
+        let pos = Vec3::new(12., 1., 20.5);
+        assert_eq!(
+            rotate_quarter_turns(pos, center, 1),
+            Vec3::new(9.5, 1., 22.)
+        );
+        assert_eq!(
+            rotate_quarter_turns(pos, center, -1),
+            Vec3::new(10.5, 1., 18.)
+        );
+        assert_eq!(rotate_quarter_turns(pos, center, 4), pos);
+        assert_eq!(
+            rotate_quarter_turns(rotate_quarter_turns(pos, center, 3), center, 1),
+            pos
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use bevy::{ecs::world::Command, prelude::*};
+
+use crate::{
+    editor::{
+        undo::{RedoEntry, UndoEntry, UndoStack},
+        unsaved::{ModifiedState, UnsavedAssets},
+    },
+    scenery::{precinct_asset::PrecinctAsset, PRECINCT_SIZE},
+};
+
+use super::scenery_edit::spawned_precincts;
+
+/// Terrain effects of a single precinct.
+#[derive(Debug, Clone, PartialEq)]
+struct TerrainFxSnapshot {
+    types: Vec<String>,
+    encoded: Option<Vec<i16>>,
+}
+
+impl TerrainFxSnapshot {
+    fn of(asset: Option<&PrecinctAsset>) -> Self {
+        match asset {
+            Some(asset) => Self {
+                types: asset.terrain_fx_types.clone(),
+                encoded: asset.terrain_fx.clone(),
+            },
+            None => Self {
+                types: Vec::new(),
+                encoded: None,
+            },
+        }
+    }
+}
+
+/// The terrain effects of a single precinct before and after a stroke.
+#[derive(Debug, Clone)]
+struct TerrainFxChange {
+    precinct: Handle<PrecinctAsset>,
+    before: TerrainFxSnapshot,
+    after: TerrainFxSnapshot,
+}
+
+/// Precincts touched by the brush stroke in progress, along with their terrain effects from
+/// before the stroke began.
+#[derive(Resource, Default)]
+pub(crate) struct TerrainFxStroke(Vec<TerrainFxChange>);
+
+/// Command which paints a terrain effect, or erases effects if `fx_type` is `None`, over a
+/// disc of terrain vertices. Every loaded precinct whose map (including its skirt) overlaps
+/// the disc is painted, so that the edges of neighboring precincts agree.
+pub(crate) struct PaintTerrainFx {
+    pub(crate) realm: Entity,
+    /// World-space vertex at the center of the brush.
+    pub(crate) center: IVec2,
+    pub(crate) radius: i32,
+    /// Exemplar path of the effect to paint.
+    pub(crate) fx_type: Option<String>,
+}
+
+impl Command for PaintTerrainFx {
+    fn apply(self, world: &mut World) {
+        let precincts = spawned_precincts(world);
+        world.resource_scope(|world, mut assets: Mut<Assets<PrecinctAsset>>| {
+            let mut stroke = world.resource_mut::<TerrainFxStroke>();
+            for (_, coords, handle) in precincts.iter().filter(|(r, _, _)| *r == self.realm) {
+                let center = self.center - *coords * PRECINCT_SIZE;
+                if center.min_element() < -self.radius - 1
+                    || center.max_element() > PRECINCT_SIZE + self.radius
+                {
+                    continue;
+                }
+                if assets.get(handle).is_none() {
+                    // Erasing can't change a precinct which has no effects.
+                    if self.fx_type.is_none() {
+                        continue;
+                    }
+                    assets.insert(handle.id(), PrecinctAsset::default());
+                }
+                let asset = assets.get(handle).unwrap();
+                if !stroke.0.iter().any(|c| c.precinct == *handle) {
+                    stroke.0.push(TerrainFxChange {
+                        precinct: handle.clone(),
+                        before: TerrainFxSnapshot::of(Some(asset)),
+                        after: TerrainFxSnapshot::of(None),
+                    });
+                }
+                let asset = assets.get_mut(handle).unwrap();
+                asset.paint_terrain_fx(center, self.radius, self.fx_type.as_deref());
+            }
+        });
+    }
+}
+
+/// Command which finishes the current brush stroke, making it a single undoable operation and
+/// marking the precincts it changed as unsaved.
+pub(crate) struct EndTerrainFxStroke;
+
+impl Command for EndTerrainFxStroke {
+    fn apply(self, world: &mut World) {
+        let mut changes = std::mem::take(&mut world.resource_mut::<TerrainFxStroke>().0);
+        let assets = world.resource::<Assets<PrecinctAsset>>();
+        for change in changes.iter_mut() {
+            change.after = TerrainFxSnapshot::of(assets.get(&change.precinct));
+        }
+        changes.retain(|change| change.before != change.after);
+        if !changes.is_empty() {
+            let mut unsaved = world.resource_mut::<UnsavedAssets>();
+            for change in changes.iter() {
+                unsaved
+                    .precincts
+                    .insert(change.precinct.clone(), ModifiedState::Unsaved);
+            }
+            world
+                .resource_mut::<UndoStack>()
+                .push(UndoTerrainFxEdit { changes });
+        }
+    }
+}
+
+fn restore_terrain_fx(world: &mut World, changes: &[TerrainFxChange], forward: bool) {
+    let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+    for change in changes.iter() {
+        if let Some(asset) = assets.get_mut(&change.precinct) {
+            let snapshot = if forward {
+                &change.after
+            } else {
+                &change.before
+            };
+            asset.terrain_fx_types.clone_from(&snapshot.types);
+            asset.terrain_fx.clone_from(&snapshot.encoded);
+        }
+    }
+    let mut unsaved = world.resource_mut::<UnsavedAssets>();
+    for change in changes.iter() {
+        unsaved
+            .precincts
+            .insert(change.precinct.clone(), ModifiedState::Unsaved);
+    }
+}
+
+struct UndoTerrainFxEdit {
+    changes: Vec<TerrainFxChange>,
+}
+
+impl UndoEntry for UndoTerrainFxEdit {
+    fn label(&self) -> &str {
+        "Paint Terrain Effects"
+    }
+
+    fn undo(&self, world: &mut World) -> Option<Box<dyn RedoEntry>> {
+        restore_terrain_fx(world, &self.changes, false);
+        Some(Box::new(UndoTerrainFxEdit {
+            changes: self.changes.clone(),
+        }))
+    }
+}
+
+impl RedoEntry for UndoTerrainFxEdit {
+    fn label(&self) -> &str {
+        "Paint Terrain Effects"
+    }
+
+    fn redo(&self, world: &mut World) -> Option<Box<dyn UndoEntry>> {
+        restore_terrain_fx(world, &self.changes, true);
+        Some(Box::new(UndoTerrainFxEdit {
+            changes: self.changes.clone(),
+        }))
+    }
+}
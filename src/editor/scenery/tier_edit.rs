@@ -0,0 +1,140 @@
+use bevy::{ecs::world::Command, prelude::*};
+
+use crate::{
+    actors::ActorInstance,
+    editor::{
+        undo::{RedoEntry, UndoEntry, UndoStack},
+        unsaved::{ModifiedState, UnsavedAssets},
+    },
+    scenery::precinct_asset::{PrecinctAsset, SceneryInstanceData, TierSer},
+};
+
+/// A change to the tiers of a precinct.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TierEdit {
+    /// Insert an empty tier at the given level, moving the ones above up.
+    Insert(i32),
+    /// Delete the tier at the given level, moving the ones above down.
+    Delete(i32),
+    /// Copy the tier at the given level into a new tier above it.
+    Copy(i32),
+}
+
+impl TierEdit {
+    fn label(&self) -> &'static str {
+        match self {
+            TierEdit::Insert(_) => "Insert Tier",
+            TierEdit::Delete(_) => "Delete Tier",
+            TierEdit::Copy(_) => "Copy Tier",
+        }
+    }
+}
+
+/// Command which inserts, deletes or copies a tier of a precinct, as an undoable operation.
+/// Since the tiers above are renumbered, the scenery and actors standing on their floors move
+/// too.
+pub(crate) struct EditTiers {
+    pub(crate) precinct: Handle<PrecinctAsset>,
+    /// World-space position of the precinct's origin.
+    pub(crate) origin: Vec2,
+    pub(crate) edit: TierEdit,
+}
+
+impl Command for EditTiers {
+    fn apply(self, world: &mut World) {
+        let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+        let Some(asset) = assets.get_mut(&self.precinct) else {
+            return;
+        };
+        let before = TierContents::from_asset(asset);
+        match self.edit {
+            TierEdit::Insert(level) => asset.insert_tier(level, self.origin),
+            TierEdit::Delete(level) => asset.delete_tier(level, self.origin),
+            TierEdit::Copy(level) => asset.copy_tier(level, self.origin),
+        }
+        let after = TierContents::from_asset(asset);
+
+        world
+            .resource_mut::<UnsavedAssets>()
+            .precincts
+            .insert(self.precinct.clone(), ModifiedState::Unsaved);
+        world.resource_mut::<UndoStack>().push(UndoTierEdit {
+            label: self.edit.label(),
+            precinct: self.precinct,
+            before,
+            after,
+        });
+    }
+}
+
+/// Everything in a precinct which a tier edit can change.
+#[derive(Clone)]
+struct TierContents {
+    tiers: Vec<TierSer>,
+    scenery: Vec<SceneryInstanceData>,
+    actors: Vec<ActorInstance>,
+}
+
+impl TierContents {
+    fn from_asset(asset: &PrecinctAsset) -> Self {
+        Self {
+            tiers: asset.tiers.clone(),
+            scenery: asset.scenery.clone(),
+            actors: asset.actors.clone(),
+        }
+    }
+}
+
+/// The contents of a precinct before and after a tier edit.
+struct UndoTierEdit {
+    label: &'static str,
+    precinct: Handle<PrecinctAsset>,
+    before: TierContents,
+    after: TierContents,
+}
+
+impl UndoTierEdit {
+    fn restore(&self, world: &mut World, contents: &TierContents) {
+        let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+        if let Some(asset) = assets.get_mut(&self.precinct) {
+            asset.tiers.clone_from(&contents.tiers);
+            asset.scenery.clone_from(&contents.scenery);
+            asset.actors.clone_from(&contents.actors);
+        }
+        world
+            .resource_mut::<UnsavedAssets>()
+            .precincts
+            .insert(self.precinct.clone(), ModifiedState::Unsaved);
+    }
+
+    fn copy(&self) -> Self {
+        Self {
+            label: self.label,
+            precinct: self.precinct.clone(),
+            before: self.before.clone(),
+            after: self.after.clone(),
+        }
+    }
+}
+
+impl UndoEntry for UndoTierEdit {
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn undo(&self, world: &mut World) -> Option<Box<dyn RedoEntry>> {
+        self.restore(world, &self.before);
+        Some(Box::new(self.copy()))
+    }
+}
+
+impl RedoEntry for UndoTierEdit {
+    fn label(&self) -> &str {
+        self.label
+    }
+
+    fn redo(&self, world: &mut World) -> Option<Box<dyn UndoEntry>> {
+        self.restore(world, &self.after);
+        Some(Box::new(self.copy()))
+    }
+}
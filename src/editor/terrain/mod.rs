@@ -1,5 +1,15 @@
 mod terrain_contour_undo;
+mod terrain_map_autotile;
+mod terrain_map_generate;
+mod terrain_map_resize;
 mod terrain_map_undo;
+mod terrain_seams;
 
 pub(crate) use terrain_contour_undo::UndoTerrainContourEdit;
+pub(crate) use terrain_map_autotile::autotile_terrain_map;
+pub(crate) use terrain_map_generate::generate_terrain_map;
+pub(crate) use terrain_map_resize::{resize_terrain_map, translate_terrain_map};
 pub(crate) use terrain_map_undo::UndoTerrainMapEdit;
+pub(crate) use terrain_seams::{
+    update_contour_edges, update_terrain_seams, ContourEdgeTable, TerrainSeams,
+};
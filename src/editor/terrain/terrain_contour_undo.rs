@@ -22,7 +22,7 @@ impl UndoEntry for UndoTerrainContourEdit {
         self.label
     }
 
-    fn undo(&self, world: &mut World) -> Box<dyn RedoEntry> {
+    fn undo(&self, world: &mut World) -> Option<Box<dyn RedoEntry>> {
         let mut assets = world
             .get_resource_mut::<Assets<TerrainContoursTableAsset>>()
             .unwrap();
@@ -36,12 +36,12 @@ impl UndoEntry for UndoTerrainContourEdit {
             .terrain_contours
             .insert(self.handle.clone(), unsaved::ModifiedState::Unsaved);
         world.commands().trigger(ChangeContourEvent(self.index));
-        Box::new(RedoTerrainContourEdit {
+        Some(Box::new(RedoTerrainContourEdit {
             label: self.label,
             handle: self.handle.clone(),
             index: self.index,
             data,
-        })
+        }))
     }
 }
 
@@ -58,7 +58,7 @@ impl RedoEntry for RedoTerrainContourEdit {
         self.label
     }
 
-    fn redo(&self, world: &mut World) -> Box<dyn UndoEntry> {
+    fn redo(&self, world: &mut World) -> Option<Box<dyn UndoEntry>> {
         let mut assets = world
             .get_resource_mut::<Assets<TerrainContoursTableAsset>>()
             .unwrap();
@@ -72,11 +72,11 @@ impl RedoEntry for RedoTerrainContourEdit {
             .terrain_contours
             .insert(self.handle.clone(), unsaved::ModifiedState::Unsaved);
         world.commands().trigger(ChangeContourEvent(self.index));
-        Box::new(UndoTerrainContourEdit {
+        Some(Box::new(UndoTerrainContourEdit {
             label: self.label,
             handle: self.handle.clone(),
             index: self.index,
             data,
-        })
+        }))
     }
 }
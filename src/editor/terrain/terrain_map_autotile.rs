@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+
+use crate::{
+    editor::{
+        events::AutoTileTerrainMap,
+        undo::UndoStack,
+        unsaved::{ModifiedState, UnsavedAssets},
+    },
+    terrain::{
+        terrain_autotile::autotile,
+        terrain_contours::{TerrainContoursHandle, TerrainContoursTableAsset},
+        terrain_groups::{TerrainGroupsAsset, TerrainGroupsHandle},
+        TerrainMap, TerrainMapAsset,
+    },
+};
+
+use super::{ContourEdgeTable, UndoTerrainMapEdit};
+
+/// Observer which replaces the shapes of the painted parcels with automatically chosen
+/// contours.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn autotile_terrain_map(
+    trigger: Trigger<AutoTileTerrainMap>,
+    q_realms: Query<&TerrainMap>,
+    mut r_terrain_maps: ResMut<Assets<TerrainMapAsset>>,
+    r_contours_handle: Res<TerrainContoursHandle>,
+    r_contours: Res<Assets<TerrainContoursTableAsset>>,
+    r_groups_handle: Res<TerrainGroupsHandle>,
+    r_groups: Res<Assets<TerrainGroupsAsset>>,
+    r_edges: Res<ContourEdgeTable>,
+    mut r_undo_stack: ResMut<UndoStack>,
+    mut r_unsaved: ResMut<UnsavedAssets>,
+) {
+    let event = trigger.event();
+    if event.parcels.is_empty() {
+        return;
+    }
+    let Ok(terrain) = q_realms.get(event.realm) else {
+        return;
+    };
+    let (Some(contours), Some(groups)) = (
+        r_contours.get(&r_contours_handle.0),
+        r_groups.get(&r_groups_handle.0),
+    ) else {
+        return;
+    };
+    let Some(map) = r_terrain_maps.get(terrain.handle.id()) else {
+        return;
+    };
+
+    // Work on a copy so that the map asset is only modified if something changes.
+    let before = map.clone();
+    let mut after = before.clone();
+    let seams = autotile(
+        &mut after,
+        &event.parcels,
+        event.intent,
+        &contours.0.read().unwrap(),
+        &r_edges.0,
+        &groups.0.read().unwrap(),
+    );
+    if seams > 0 {
+        warn!(
+            "Auto-tiling left {} seams between mismatched parcels.",
+            seams
+        );
+    }
+    if after.shapes == before.shapes {
+        return;
+    }
+    *r_terrain_maps.get_mut(terrain.handle.id()).unwrap() = after.clone();
+    r_undo_stack.push(UndoTerrainMapEdit {
+        label: "Auto-Tile Terrain",
+        handle: terrain.handle.clone(),
+        before,
+        after,
+    });
+    r_unsaved
+        .terrain_maps
+        .insert(terrain.handle.clone(), ModifiedState::Unsaved);
+}
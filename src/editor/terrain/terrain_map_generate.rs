@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+
+use crate::{
+    editor::{
+        events::GenerateTerrainMap,
+        undo::UndoStack,
+        unsaved::{ModifiedState, UnsavedAssets},
+    },
+    terrain::{
+        biome::{BiomesAsset, BiomesHandle},
+        terrain_contours::{TerrainContoursHandle, TerrainContoursTableAsset},
+        terrain_generator::BiomeRule,
+        terrain_groups::{TerrainGroupsAsset, TerrainGroupsHandle},
+        TerrainMap, TerrainMapAsset,
+    },
+};
+
+use super::{ContourEdgeTable, UndoTerrainMapEdit};
+
+/// Biomes assigned by generated elevation and moisture when the generator doesn't specify
+/// any: (biome name, max elevation, max moisture). The first match wins.
+const DEFAULT_BIOME_RULES: &[(&str, f32, f32)] = &[
+    ("desert", 0.6, 0.3),
+    ("chaparral", 0.7, 0.45),
+    ("meadow", 0.55, 1.),
+    ("forest", 0.7, 1.),
+    ("taiga", 0.85, 1.),
+    ("arctic", 1., 1.),
+];
+
+/// Observer which fills a realm's terrain map with generated terrain.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn generate_terrain_map(
+    trigger: Trigger<GenerateTerrainMap>,
+    q_realms: Query<&TerrainMap>,
+    mut r_terrain_maps: ResMut<Assets<TerrainMapAsset>>,
+    r_contours_handle: Res<TerrainContoursHandle>,
+    r_contours: Res<Assets<TerrainContoursTableAsset>>,
+    r_groups_handle: Res<TerrainGroupsHandle>,
+    r_groups: Res<Assets<TerrainGroupsAsset>>,
+    r_edges: Res<ContourEdgeTable>,
+    r_biomes_handle: Res<BiomesHandle>,
+    r_biomes: Res<Assets<BiomesAsset>>,
+    mut r_undo_stack: ResMut<UndoStack>,
+    mut r_unsaved: ResMut<UnsavedAssets>,
+) {
+    let event = trigger.event();
+    let Ok(terrain) = q_realms.get(event.realm) else {
+        return;
+    };
+    let (Some(_), Some(groups)) = (
+        r_contours.get(&r_contours_handle.0),
+        r_groups.get(&r_groups_handle.0),
+    ) else {
+        warn!("Terrain contours and groups must be loaded before generating terrain.");
+        return;
+    };
+    let Some(map) = r_terrain_maps.get_mut(terrain.handle.id()) else {
+        return;
+    };
+
+    let mut generator = event.generator.clone();
+    if generator.biomes.is_empty() {
+        if let Some(biomes) = r_biomes.get(&r_biomes_handle.0) {
+            let table = biomes.0.lock().unwrap();
+            generator.biomes = DEFAULT_BIOME_RULES
+                .iter()
+                .filter_map(|(name, max_elevation, max_moisture)| {
+                    let index = table.biomes.iter().position(|b| b.name == *name)?;
+                    Some(BiomeRule {
+                        biome: index as u8,
+                        max_elevation: *max_elevation,
+                        max_moisture: *max_moisture,
+                    })
+                })
+                .collect();
+        }
+    }
+
+    let before = map.clone();
+    let mismatched = generator.generate(map, event.region, &r_edges.0, &groups.0.read().unwrap());
+    if mismatched > 0 {
+        warn!(
+            "Generated terrain has {} parcels with mismatched edges.",
+            mismatched
+        );
+    }
+    r_undo_stack.push(UndoTerrainMapEdit {
+        label: "Generate Terrain",
+        handle: terrain.handle.clone(),
+        before,
+        after: map.clone(),
+    });
+    r_unsaved
+        .terrain_maps
+        .insert(terrain.handle.clone(), ModifiedState::Unsaved);
+}
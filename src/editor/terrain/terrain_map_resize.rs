@@ -0,0 +1,416 @@
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    asset::{
+        io::AssetSourceId,
+        saver::{AssetSaver, SavedAsset},
+        ErasedLoadedAsset, LoadedAsset,
+    },
+    ecs::world::Command,
+    math::IRect,
+    prelude::*,
+    tasks::block_on,
+};
+use futures_lite::AsyncWriteExt;
+
+use crate::{
+    editor::{
+        events::{ResizeTerrainMap, TranslateTerrainMap},
+        undo::{RedoEntry, UndoEntry, UndoStack},
+        unsaved::{ModifiedState, SaveCommand, UnsavedAssets},
+    },
+    scenery::{
+        precinct::Precinct,
+        precinct_asset::PrecinctAsset,
+        precinct_cache::{precinct_asset_path, PrecinctCache},
+        precinct_format::PrecinctFormat,
+    },
+    terrain::{
+        convert_parcel_to_precinct, TerrainMap, TerrainMapAsset, TerrainMapSaver,
+        TerrainMapSaverError, PARCELS_PER_PRECINCT,
+    },
+    world::Realm,
+};
+
+use super::UndoTerrainMapEdit;
+
+/// Observer which extends or crops a realm's terrain map.
+pub(crate) fn resize_terrain_map(
+    trigger: Trigger<ResizeTerrainMap>,
+    q_realms: Query<(&TerrainMap, &Realm)>,
+    server: Res<AssetServer>,
+    mut r_terrain_maps: ResMut<Assets<TerrainMapAsset>>,
+    mut r_undo_stack: ResMut<UndoStack>,
+    mut r_unsaved: ResMut<UnsavedAssets>,
+) {
+    let event = trigger.event();
+    if event.bounds.is_empty() {
+        warn!("Terrain map bounds cannot be empty: {:?}", event.bounds);
+        return;
+    }
+    let Ok((terrain, realm)) = q_realms.get(event.realm) else {
+        return;
+    };
+    let Some(map) = r_terrain_maps.get_mut(terrain.handle.id()) else {
+        return;
+    };
+    if map.bounds == event.bounds {
+        return;
+    }
+
+    // Scenery outside of the new bounds is left on disk, but will no longer be shown.
+    let cropped = cropped_precincts(
+        &server,
+        &realm.name,
+        convert_parcel_to_precinct(&map.bounds),
+        convert_parcel_to_precinct(&event.bounds),
+    );
+    if !cropped.is_empty() {
+        warn!(
+            "Realm [{}] has scenery outside of the new terrain bounds, in precincts {:?}. \
+             The files are kept but will not be shown.",
+            realm.name, cropped
+        );
+    }
+    let before = map.clone();
+    *map = before.resized(event.bounds);
+    r_undo_stack.push(UndoTerrainMapEdit {
+        label: "Resize Terrain Map",
+        handle: terrain.handle.clone(),
+        before,
+        after: map.clone(),
+    });
+    r_unsaved
+        .terrain_maps
+        .insert(terrain.handle.clone(), ModifiedState::Unsaved);
+}
+
+/// Coordinates of the precincts within `old` but outside of `new` which have a file, in
+/// either format.
+fn cropped_precincts(server: &AssetServer, realm_name: &str, old: IRect, new: IRect) -> Vec<IVec2> {
+    let Ok(source) = server.get_source(AssetSourceId::Default) else {
+        return Vec::new();
+    };
+    let reader = source.reader();
+    let mut cropped = Vec::new();
+    for z in old.min.y..old.max.y {
+        for x in old.min.x..old.max.x {
+            let coords = IVec2::new(x, z);
+            if coords.cmpge(new.min).all() && coords.cmplt(new.max).all() {
+                continue;
+            }
+            if PrecinctFormat::ALL.into_iter().any(|format| {
+                let path = precinct_asset_path(realm_name, coords, format);
+                block_on(reader.read(Path::new(&path))).is_ok()
+            }) {
+                cropped.push(coords);
+            }
+        }
+    }
+    cropped
+}
+
+/// Observer which shifts a realm's terrain map relative to the world origin.
+pub(crate) fn translate_terrain_map(trigger: Trigger<TranslateTerrainMap>, mut commands: Commands) {
+    let event = trigger.event();
+    if event.offset == IVec2::ZERO {
+        return;
+    }
+
+    // Precinct files can only be moved in whole-precinct increments.
+    let precinct_offset = if event.move_precincts {
+        if event.offset.x % PARCELS_PER_PRECINCT != 0 || event.offset.y % PARCELS_PER_PRECINCT != 0
+        {
+            warn!(
+                "Scenery can only be moved in multiples of {} parcels.",
+                PARCELS_PER_PRECINCT
+            );
+            return;
+        }
+        Some(event.offset / PARCELS_PER_PRECINCT)
+    } else {
+        None
+    };
+    commands.add(TranslateTerrain {
+        realm: event.realm,
+        offset: event.offset,
+        precinct_offset,
+    });
+}
+
+/// Command which shifts a realm's terrain map, moving the precinct files first if requested.
+/// If the files can't be moved, the map is left as it is.
+struct TranslateTerrain {
+    realm: Entity,
+    offset: IVec2,
+    precinct_offset: Option<IVec2>,
+}
+
+impl Command for TranslateTerrain {
+    fn apply(self, world: &mut World) {
+        let Some(handle) = world
+            .get::<TerrainMap>(self.realm)
+            .map(|tm| tm.handle.clone())
+        else {
+            return;
+        };
+        let Some(before) = world
+            .resource::<Assets<TerrainMapAsset>>()
+            .get(&handle)
+            .cloned()
+        else {
+            return;
+        };
+        let mut after = before.clone();
+        after.translate(self.offset);
+        let entry = UndoTerrainMapTranslate {
+            realm: self.realm,
+            handle,
+            before,
+            after,
+            precinct_offset: self.precinct_offset,
+        };
+        if entry.apply(world, &entry.before, &entry.after, entry.precinct_offset) {
+            world.resource_mut::<UndoStack>().push(entry);
+        }
+    }
+}
+
+/// Undo entry for translating a terrain map, which may also have moved the precinct files.
+#[derive(Clone)]
+pub(crate) struct UndoTerrainMapTranslate {
+    realm: Entity,
+    handle: Handle<TerrainMapAsset>,
+    before: TerrainMapAsset,
+    after: TerrainMapAsset,
+    precinct_offset: Option<IVec2>,
+}
+
+impl UndoTerrainMapTranslate {
+    /// Replace the map `from` with `to`, moving the precinct files by `offset` precincts if
+    /// given. Moved files are already on disk, so the map is saved along with them, to keep
+    /// the two in step. Returns false, having changed nothing, if either can't be done.
+    fn apply(
+        &self,
+        world: &mut World,
+        from: &TerrainMapAsset,
+        to: &TerrainMapAsset,
+        offset: Option<IVec2>,
+    ) -> bool {
+        if !world
+            .resource::<Assets<TerrainMapAsset>>()
+            .contains(self.handle.id())
+        {
+            return false;
+        }
+        if let Some(offset) = offset {
+            let bounds = convert_parcel_to_precinct(&from.bounds);
+            if !move_precinct_files(world, self.realm, bounds, offset) {
+                warn!("Precinct files could not be moved, the terrain map was not changed.");
+                return false;
+            }
+            if !save_terrain_map(world, &self.handle, to) {
+                // Put the files back where the map on disk expects them.
+                let moved = convert_parcel_to_precinct(&to.bounds);
+                move_precinct_files(world, self.realm, moved, -offset);
+                warn!("Terrain map could not be saved, the terrain map was not changed.");
+                return false;
+            }
+        }
+
+        world
+            .resource_mut::<Assets<TerrainMapAsset>>()
+            .insert(&self.handle, to.clone());
+        let mut unsaved = world.resource_mut::<UnsavedAssets>();
+        if offset.is_none() {
+            unsaved
+                .terrain_maps
+                .insert(self.handle.clone(), ModifiedState::Unsaved);
+        } else if matches!(
+            unsaved.terrain_maps.get(&self.handle),
+            Some(ModifiedState::Unsaved)
+        ) {
+            unsaved.terrain_maps.remove(&self.handle);
+        }
+        true
+    }
+}
+
+impl UndoEntry for UndoTerrainMapTranslate {
+    fn label(&self) -> &str {
+        "Translate Terrain Map"
+    }
+
+    fn undo(&self, world: &mut World) -> Option<Box<dyn RedoEntry>> {
+        let offset = self.precinct_offset.map(|o| -o);
+        self.apply(world, &self.after, &self.before, offset)
+            .then(|| Box::new(self.clone()) as Box<dyn RedoEntry>)
+    }
+}
+
+impl RedoEntry for UndoTerrainMapTranslate {
+    fn label(&self) -> &str {
+        "Translate Terrain Map"
+    }
+
+    fn redo(&self, world: &mut World) -> Option<Box<dyn UndoEntry>> {
+        self.apply(world, &self.before, &self.after, self.precinct_offset)
+            .then(|| Box::new(self.clone()) as Box<dyn UndoEntry>)
+    }
+}
+
+/// Write a terrain map to its file straight away, rather than on the next save. Returns
+/// whether it was written.
+fn save_terrain_map(
+    world: &World,
+    handle: &Handle<TerrainMapAsset>,
+    map: &TerrainMapAsset,
+) -> bool {
+    let server = world.resource::<AssetServer>();
+    let Some(path) = server.get_path(handle) else {
+        return false;
+    };
+    let Ok(source) = server.get_source(path.source()) else {
+        return false;
+    };
+    let Ok(writer) = source.writer() else {
+        warn!("Asset source is not writable, the terrain map was not saved.");
+        return false;
+    };
+    let loaded = LoadedAsset::new_with_dependencies(map.clone(), None);
+    let erased = ErasedLoadedAsset::from(loaded);
+    let Some(saved) = SavedAsset::from_loaded(&erased) else {
+        return false;
+    };
+    let temp_path = SaveCommand::get_temp_file_path(&path);
+    let result: Result<(), TerrainMapSaverError> = block_on(async {
+        let mut write = writer.write(&temp_path).await?;
+        TerrainMapSaver.save(&mut *write, saved, &()).await?;
+        write.close().await?;
+        writer.rename(&temp_path, path.path()).await?;
+        Ok(())
+    });
+    if let Err(e) = result {
+        error!("Error saving terrain map {:?}: {}", path, e);
+        return false;
+    }
+    true
+}
+
+/// Rename all of the precinct files of a realm within `bounds`, in either format, so that
+/// they are shifted by `offset` precincts. Files are written immediately, not when the
+/// editor next saves, so the terrain map must be saved along with them. Any loaded precincts
+/// for the realm are despawned so that they will be reloaded from their new locations.
+///
+/// Nothing is moved if the realm has unsaved scenery, if the asset source isn't writable, or
+/// if a file would be moved on top of one which isn't itself being moved. Returns whether the
+/// files were moved; once renaming has started, individual failures are only logged.
+fn move_precinct_files(world: &mut World, realm: Entity, bounds: IRect, offset: IVec2) -> bool {
+    let Some(realm_name) = world.get::<Realm>(realm).map(|r| r.name.clone()) else {
+        return false;
+    };
+
+    // Don't move files out from under unsaved edits.
+    let prefix = format!("scenery/precincts/{}/", realm_name);
+    let server = world.resource::<AssetServer>().clone();
+    if world
+        .resource::<UnsavedAssets>()
+        .precincts
+        .keys()
+        .any(|handle| {
+            server
+                .get_path(handle)
+                .is_some_and(|path| path.path().starts_with(&prefix))
+        })
+    {
+        warn!(
+            "Realm [{}] has unsaved scenery, precinct files were not moved.",
+            realm_name
+        );
+        return false;
+    }
+
+    let Ok(source) = server.get_source(AssetSourceId::Default) else {
+        return false;
+    };
+    let Ok(writer) = source.writer() else {
+        warn!("Asset source is not writable, precinct files were not moved.");
+        return false;
+    };
+    let reader = source.reader();
+    let exists = |path: &Path| block_on(reader.read(path)).is_ok();
+
+    // Work out which files to move. Precincts that have no file are simply skipped.
+    let mut moves: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for z in bounds.min.y..bounds.max.y {
+        for x in bounds.min.x..bounds.max.x {
+            let coords = IVec2::new(x, z);
+            for format in PrecinctFormat::ALL {
+                let from = PathBuf::from(precinct_asset_path(&realm_name, coords, format));
+                if exists(&from) {
+                    let to = precinct_asset_path(&realm_name, coords + offset, format);
+                    moves.push((from, PathBuf::from(to)));
+                }
+            }
+        }
+    }
+    // Refuse to move a file on top of another precinct, in either format, unless that one is
+    // being moved out of the way as well.
+    let in_the_way = moves.iter().find_map(|(_, to)| {
+        PrecinctFormat::ALL
+            .into_iter()
+            .map(|format| format.with_extension(to))
+            .find(|path| exists(path) && !moves.iter().any(|(from, _)| from == path))
+    });
+    if let Some(path) = in_the_way {
+        warn!(
+            "Precinct file {:?} is in the way, precinct files were not moved.",
+            path
+        );
+        return false;
+    }
+
+    // Despawn the realm's precincts so that they are reloaded from the new paths.
+    let mut evicted = world.resource_mut::<PrecinctCache>().evict_realm(realm);
+    let mut q_precincts = world.query::<(Entity, &Precinct)>();
+    evicted.extend(
+        q_precincts
+            .iter(world)
+            .filter(|(_, precinct)| precinct.realm == realm)
+            .map(|(entity, _)| entity),
+    );
+    for entity in evicted {
+        if let Some(entity) = world.get_entity_mut(entity) {
+            entity.despawn_recursive();
+        }
+    }
+
+    // Move in two passes via temporary names, since source and destination ranges may
+    // overlap.
+    let mut staged: Vec<(PathBuf, &Path)> = Vec::with_capacity(moves.len());
+    for (from, to) in moves.iter() {
+        let temp = PathBuf::from(format!("{}.moving", from.display()));
+        match block_on(writer.rename(from, &temp)) {
+            Ok(()) => staged.push((temp, to.as_path())),
+            Err(e) => error!("Error moving precinct file {:?}: {:?}", from, e),
+        }
+    }
+    for (temp, to) in staged.iter() {
+        if let Err(e) = block_on(writer.rename(temp, to)) {
+            error!("Error moving precinct file {:?}: {:?}", to, e);
+        }
+    }
+    info!(
+        "Moved {} precinct files for realm [{}].",
+        staged.len(),
+        realm_name
+    );
+
+    // Anything else still holding a precinct at the new location needs to see the new data.
+    for (_, to) in staged.iter() {
+        if server.get_handle::<PrecinctAsset>(*to).is_some() {
+            server.reload(*to);
+        }
+    }
+    true
+}
@@ -21,7 +21,7 @@ impl UndoEntry for UndoTerrainMapEdit {
         self.label
     }
 
-    fn undo(&self, world: &mut World) -> Box<dyn RedoEntry> {
+    fn undo(&self, world: &mut World) -> Option<Box<dyn RedoEntry>> {
         let mut assets = world.get_resource_mut::<Assets<TerrainMapAsset>>().unwrap();
         let map = assets.get_mut(self.handle.id()).unwrap();
         *map = self.before.clone();
@@ -29,7 +29,7 @@ impl UndoEntry for UndoTerrainMapEdit {
         unsaved
             .terrain_maps
             .insert(self.handle.clone(), unsaved::ModifiedState::Unsaved);
-        Box::new(self.clone())
+        Some(Box::new(self.clone()))
     }
 }
 
@@ -38,7 +38,7 @@ impl RedoEntry for UndoTerrainMapEdit {
         self.label
     }
 
-    fn redo(&self, world: &mut World) -> Box<dyn UndoEntry> {
+    fn redo(&self, world: &mut World) -> Option<Box<dyn UndoEntry>> {
         let mut assets = world.get_resource_mut::<Assets<TerrainMapAsset>>().unwrap();
         let map = assets.get_mut(self.handle.id()).unwrap();
         *map = self.after.clone();
@@ -46,6 +46,6 @@ impl RedoEntry for UndoTerrainMapEdit {
         unsaved
             .terrain_maps
             .insert(self.handle.clone(), unsaved::ModifiedState::Unsaved);
-        Box::new(self.clone())
+        Some(Box::new(self.clone()))
     }
 }
@@ -0,0 +1,174 @@
+use bevy::{math::IRect, prelude::*};
+
+use crate::{
+    editor::SelectedParcel,
+    terrain::{
+        terrain_contours::{TerrainContoursHandle, TerrainContoursTableAsset},
+        terrain_edges::{ContourEdgeIndex, Seam},
+        Parcel, ShapeRef, TerrainMap, TerrainMapAsset,
+    },
+    view::Viewpoint,
+};
+
+/// Edge profiles of all terrain contours, rebuilt whenever the contours are edited.
+#[derive(Resource, Default)]
+pub(crate) struct ContourEdgeTable(pub ContourEdgeIndex);
+
+/// Seams between parcels in the current realm, and the shapes which would fit the selected
+/// parcel without creating new seams.
+#[derive(Resource, Default, PartialEq)]
+pub(crate) struct TerrainSeams {
+    pub(crate) realm: Option<Entity>,
+    pub(crate) parcel: Option<Entity>,
+    pub(crate) seams: Vec<Seam>,
+    pub(crate) compatible: Vec<ShapeRef>,
+}
+
+pub(crate) fn update_contour_edges(
+    mut events: EventReader<AssetEvent<TerrainContoursTableAsset>>,
+    r_handle: Res<TerrainContoursHandle>,
+    r_contours: Res<Assets<TerrainContoursTableAsset>>,
+    mut r_edges: ResMut<ContourEdgeTable>,
+) {
+    let mut changed = false;
+    for ev in events.read() {
+        match ev {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::LoadedWithDependencies { id }
+                if *id == r_handle.0.id() =>
+            {
+                changed = true;
+            }
+            _ => {}
+        }
+    }
+    if changed {
+        if let Some(contours) = r_contours.get(&r_handle.0) {
+            r_edges.0 = ContourEdgeIndex::new(&contours.0.read().unwrap());
+        }
+    }
+}
+
+/// Shapes of the terrain map that the seams were last found in, so that after an edit only
+/// the seams around the parcels which changed need to be found again.
+#[derive(Default)]
+pub(crate) struct SeamsSnapshot {
+    realm: Option<Entity>,
+    bounds: IRect,
+    shapes: Vec<u16>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn update_terrain_seams(
+    mut map_events: EventReader<AssetEvent<TerrainMapAsset>>,
+    r_edges: Res<ContourEdgeTable>,
+    r_viewpoint: Res<Viewpoint>,
+    r_selected: Res<SelectedParcel>,
+    r_terrain_maps: Res<Assets<TerrainMapAsset>>,
+    q_realms: Query<&TerrainMap>,
+    q_parcels: Query<&Parcel>,
+    mut r_seams: ResMut<TerrainSeams>,
+    mut snapshot: Local<SeamsSnapshot>,
+) {
+    let maps_changed = map_events.read().count() > 0;
+    if !maps_changed
+        && !r_edges.is_changed()
+        && r_seams.realm == r_viewpoint.realm
+        && r_seams.parcel == r_selected.0
+    {
+        return;
+    }
+
+    let map_for = |realm: Entity| {
+        q_realms
+            .get(realm)
+            .ok()
+            .and_then(|terrain| r_terrain_maps.get(&terrain.handle))
+    };
+    let seams = match r_viewpoint.realm.and_then(map_for) {
+        Some(map) => {
+            let incremental = !r_edges.is_changed()
+                && snapshot.realm == r_viewpoint.realm
+                && r_seams.realm == r_viewpoint.realm
+                && snapshot.bounds == map.bounds;
+            let seams = if incremental {
+                match changed_shapes(map.bounds, &snapshot.shapes, &map.shapes) {
+                    // Seams belong to the parcel to their west or north, so the seams of the
+                    // parcels just before the changed ones need to be found again as well.
+                    Some(rect) => {
+                        let rect = IRect {
+                            min: rect.min - IVec2::ONE,
+                            max: rect.max,
+                        };
+                        let mut seams: Vec<Seam> = r_seams
+                            .seams
+                            .iter()
+                            .filter(|seam| {
+                                seam.coords.cmplt(rect.min).any()
+                                    || seam.coords.cmpge(rect.max).any()
+                            })
+                            .copied()
+                            .collect();
+                        seams.extend(r_edges.0.find_seams_in(map, rect));
+                        seams.sort_by_key(|seam| (seam.coords.y, seam.coords.x, seam.side as u8));
+                        seams
+                    }
+                    None => r_seams.seams.clone(),
+                }
+            } else {
+                r_edges.0.find_seams(map)
+            };
+            if incremental {
+                snapshot.shapes.copy_from_slice(&map.shapes);
+            } else {
+                *snapshot = SeamsSnapshot {
+                    realm: r_viewpoint.realm,
+                    bounds: map.bounds,
+                    shapes: map.shapes.clone(),
+                };
+            }
+            seams
+        }
+        None => {
+            *snapshot = SeamsSnapshot::default();
+            Vec::new()
+        }
+    };
+    let compatible = r_selected
+        .0
+        .and_then(|parcel| q_parcels.get(parcel).ok())
+        .and_then(|parcel| {
+            map_for(parcel.realm).map(|map| r_edges.0.compatible_shapes(map, parcel.coords))
+        })
+        .unwrap_or_default();
+
+    if r_seams.realm == r_viewpoint.realm && seams.len() > r_seams.seams.len() {
+        warn!(
+            "Terrain map has {} seams between mismatched parcels.",
+            seams.len()
+        );
+    }
+    r_seams.set_if_neq(TerrainSeams {
+        realm: r_viewpoint.realm,
+        parcel: r_selected.0,
+        seams,
+        compatible,
+    });
+}
+
+/// Bounds of the parcels whose shapes differ between two versions of a map with the given
+/// bounds, or `None` if they are the same.
+fn changed_shapes(bounds: IRect, before: &[u16], after: &[u16]) -> Option<IRect> {
+    let width = bounds.width().max(1) as usize;
+    before
+        .iter()
+        .zip(after.iter())
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+        .map(|(index, _)| bounds.min + IVec2::new((index % width) as i32, (index / width) as i32))
+        .fold(None, |rect: Option<IRect>, pt| {
+            let parcel = IRect::from_corners(pt, pt + IVec2::ONE);
+            Some(rect.map_or(parcel, |rect| rect.union(parcel)))
+        })
+}
@@ -0,0 +1,80 @@
+use bevy::{prelude::*, ui};
+use bevy_quill::prelude::*;
+use bevy_quill_obsidian::prelude::*;
+
+use crate::terrain::biome::{BiomesAsset, BiomesHandle};
+
+/// View context component which stores the currently selected biome index.
+#[derive(Component)]
+struct SelectedBiomeIndex(u8);
+
+/// A list of biomes from the biomes table, used to choose the biome to paint.
+#[derive(Clone, PartialEq)]
+pub struct BiomeChooser {
+    pub selected: u8,
+    pub on_change: Callback<u8>,
+}
+
+impl ViewTemplate for BiomeChooser {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let on_change = self.on_change;
+        cx.insert(SelectedBiomeIndex(self.selected));
+        let bm_handle = cx.use_resource::<BiomesHandle>().0.clone();
+        let bm_assets = cx.use_resource_untracked::<Assets<BiomesAsset>>();
+        let biomes: Vec<BiomeListItem> = bm_assets
+            .get(&bm_handle)
+            .map(|biomes| {
+                let lock = biomes.0.lock().unwrap();
+                lock.biomes
+                    .iter()
+                    .enumerate()
+                    .map(|(index, biome)| BiomeListItem {
+                        index: index as u8,
+                        name: biome.name.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        ListView::new().style(style_list).children(For::each_cmp(
+            biomes,
+            |a, b| a.index == b.index && a.name == b.name,
+            move |biome| BiomeRow {
+                index: biome.index,
+                name: biome.name.clone(),
+                on_change,
+            },
+        ))
+    }
+}
+
+fn style_list(ss: &mut StyleBuilder) {
+    ss.min_height(ui::Val::Px(64.)).flex_grow(1.);
+}
+
+#[derive(Clone, PartialEq)]
+struct BiomeListItem {
+    index: u8,
+    name: String,
+}
+
+#[derive(Clone, PartialEq)]
+struct BiomeRow {
+    index: u8,
+    name: String,
+    on_change: Callback<u8>,
+}
+
+impl ViewTemplate for BiomeRow {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let selected = cx.use_inherited_component::<SelectedBiomeIndex>().unwrap();
+        ListRow::new(self.index)
+            .selected(selected.0 == self.index)
+            .children(format!("{}: {}", self.index, self.name))
+            .on_click(self.on_change)
+    }
+}
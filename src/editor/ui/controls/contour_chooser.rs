@@ -10,6 +10,7 @@ use crate::{
     editor::{
         events::{ModifyTerrainMapEvent, ThumbnailsReady},
         renderers::TerrainThumbnail,
+        terrain::TerrainSeams,
         SelectedParcel,
     },
     terrain::{
@@ -50,6 +51,9 @@ fn style_item_id(ss: &mut StyleBuilder) {
 pub struct ContourChooser {
     /// Additional styles to be applied to the list view.
     pub style: StyleHandle,
+
+    /// If true, only list contours which fit the selected parcel without creating seams.
+    pub compatible_only: bool,
 }
 
 impl ContourChooser {
@@ -63,18 +67,33 @@ impl ContourChooser {
         self.style = style.into_handle();
         self
     }
+
+    /// Only list contours which fit the selected parcel.
+    pub fn compatible_only(mut self, compatible_only: bool) -> Self {
+        self.compatible_only = compatible_only;
+        self
+    }
 }
 
 impl ViewTemplate for ContourChooser {
     type View = ScrollView;
     fn create(&self, cx: &mut Cx) -> Self::View {
         let selected_parcel = cx.use_resource::<SelectedParcel>().0;
-        let selected_contour = selected_parcel
+        let selected_shape = selected_parcel
             .and_then(|parcel| cx.use_component::<Parcel>(parcel))
-            .map(|p| p.center_shape().shape as usize);
+            .map(|p| p.center_shape());
+        let selected_contour = selected_shape.map(|s| s.shape as usize);
+        let rotation = selected_shape.map_or(0, |s| s.rotation);
+
+        // Shapes which fit the selected parcel, if we're filtering by them.
+        let compatible = if self.compatible_only && selected_parcel.is_some() {
+            Some(cx.use_resource::<TerrainSeams>().compatible.clone())
+        } else {
+            None
+        };
 
-        let on_click: Callback<usize> = cx.create_callback(
-            move |shape_id: In<usize>,
+        let on_click: Callback<ShapeRef> = cx.create_callback(
+            move |shape: In<ShapeRef>,
                   q_parcels: Query<&Parcel>,
                   mut commands: Commands,
                   r_selected: Res<SelectedParcel>| {
@@ -87,10 +106,7 @@ impl ViewTemplate for ContourChooser {
                 commands.trigger(ModifyTerrainMapEvent {
                     realm: parcel.realm,
                     coords: parcel.coords,
-                    shape: ShapeRef {
-                        shape: *shape_id as u16,
-                        rotation: parcel.center_shape().rotation,
-                    },
+                    shape: *shape,
                 });
             },
         );
@@ -106,11 +122,31 @@ impl ViewTemplate for ContourChooser {
                     .0
                     .iter()
                     .flat_map(|group| {
-                        group.contours.iter().map(|shape| ContourListEntry {
-                            shape_id: *shape,
-                            bg_color: group.color.0,
-                            selected: Some(*shape) == selected_contour,
-                            on_click,
+                        group.contours.iter().filter_map(|shape| {
+                            // Keep the current rotation if it fits, otherwise use the first
+                            // rotation that does.
+                            let rotation = match compatible {
+                                Some(ref compatible) => {
+                                    let mut rotations = compatible
+                                        .iter()
+                                        .filter(|s| s.shape as usize == *shape)
+                                        .map(|s| s.rotation);
+                                    let first = rotations.next()?;
+                                    if first == rotation || rotations.any(|r| r == rotation) {
+                                        rotation
+                                    } else {
+                                        first
+                                    }
+                                }
+                                None => rotation,
+                            };
+                            Some(ContourListEntry {
+                                shape_id: *shape,
+                                rotation,
+                                bg_color: group.color.0,
+                                selected: Some(*shape) == selected_contour,
+                                on_click,
+                            })
                         })
                     })
                     .collect::<Vec<_>>();
@@ -127,6 +163,7 @@ impl ViewTemplate for ContourChooser {
                     .style(style_listview_inner)
                     .children(For::each(items, |item| ContourListEntry {
                         shape_id: item.shape_id,
+                        rotation: item.rotation,
                         bg_color: item.bg_color,
                         selected: item.selected,
                         on_click: item.on_click,
@@ -141,10 +178,11 @@ struct ThumbnailRef(Option<Entity>);
 #[derive(Clone, PartialEq)]
 struct ContourListEntry {
     shape_id: usize,
+    rotation: u8,
     bg_color: Srgba,
     // name: String,
     selected: bool,
-    on_click: Callback<usize>,
+    on_click: Callback<ShapeRef>,
 }
 
 impl ViewTemplate for ContourListEntry {
@@ -152,6 +190,10 @@ impl ViewTemplate for ContourListEntry {
     fn create(&self, cx: &mut Cx) -> Self::View {
         let on_click = self.on_click;
         let shape_id = self.shape_id;
+        let shape = ShapeRef {
+            shape: shape_id as u16,
+            rotation: self.rotation,
+        };
         let bg_color = self.bg_color;
 
         let owner = cx.owner();
@@ -193,13 +235,13 @@ impl ViewTemplate for ContourListEntry {
         Element::<NodeBundle>::new()
             .style(style_item)
             .insert_dyn(
-                move |_| {
+                move |shape| {
                     On::<Pointer<Click>>::run(move |mut commands: Commands| {
                         // ev.stop_propagation();
-                        commands.run_callback(on_click, shape_id);
+                        commands.run_callback(on_click, shape);
                     })
                 },
-                (),
+                shape,
             )
             .style_dyn(
                 move |selected, sb| {
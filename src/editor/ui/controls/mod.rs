@@ -1,9 +1,13 @@
 mod attribute_list;
+mod biome_chooser;
 mod contour_chooser;
 mod exemplar_chooser;
 mod location_chooser;
+mod name_input;
 
 pub use attribute_list::*;
+pub use biome_chooser::BiomeChooser;
 pub use contour_chooser::ContourChooser;
 pub use exemplar_chooser::ExemplarChooser;
 pub use location_chooser::*;
+pub use name_input::NameInput;
@@ -0,0 +1,95 @@
+use bevy::{a11y::Focus, input::keyboard::Key, prelude::*, ui};
+use bevy_mod_picking::prelude::{Click, Listener, ListenerMut, On, Pointer};
+use bevy_mod_stylebuilder::*;
+use bevy_quill::prelude::*;
+use bevy_quill_obsidian::{
+    colors,
+    focus::{KeyCharEvent, KeyPressEvent, TabIndex},
+};
+
+/// Single-line text field for short names. Characters are typed while the field has keyboard
+/// focus; every keystroke reports the new text through `on_change`.
+#[derive(Clone, PartialEq)]
+pub struct NameInput {
+    pub value: String,
+    /// Text shown, dimmed, while the field is empty.
+    pub placeholder: &'static str,
+    pub on_change: Callback<String>,
+}
+
+impl ViewTemplate for NameInput {
+    type View = impl View;
+
+    fn create(&self, _cx: &mut Cx) -> Self::View {
+        let empty = self.value.is_empty();
+        let text = if empty {
+            self.placeholder.to_string()
+        } else {
+            self.value.clone()
+        };
+        Element::<NodeBundle>::new()
+            .named("NameInput")
+            .style(style_name_input)
+            .style_dyn(
+                |empty, sb| {
+                    sb.color(if empty {
+                        colors::DIM
+                    } else {
+                        colors::FOREGROUND
+                    });
+                },
+                empty,
+            )
+            .insert(TabIndex(0))
+            .insert_dyn(
+                |(value, on_change)| {
+                    let typed = value.clone();
+                    (
+                        On::<Pointer<Click>>::run(
+                            |ev: Listener<Pointer<Click>>, mut focus: ResMut<Focus>| {
+                                focus.0 = Some(ev.target);
+                            },
+                        ),
+                        On::<KeyCharEvent>::run(
+                            move |mut ev: ListenerMut<KeyCharEvent>, mut commands: Commands| {
+                                // Keys typed into the field shouldn't trigger editor shortcuts.
+                                ev.stop_propagation();
+                                let mut text = typed.clone();
+                                match &ev.key {
+                                    Key::Character(chars) => {
+                                        text.extend(chars.chars().filter(|c| !c.is_control()))
+                                    }
+                                    Key::Space => text.push(' '),
+                                    _ => return,
+                                }
+                                commands.run_callback(on_change, text);
+                            },
+                        ),
+                        On::<KeyPressEvent>::run(
+                            move |mut ev: ListenerMut<KeyPressEvent>, mut commands: Commands| {
+                                ev.stop_propagation();
+                                if ev.key_code == KeyCode::Backspace {
+                                    let mut text = value.clone();
+                                    text.pop();
+                                    commands.run_callback(on_change, text);
+                                }
+                            },
+                        ),
+                    )
+                },
+                (self.value.clone(), self.on_change),
+            )
+            .children(text)
+    }
+}
+
+fn style_name_input(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .align_items(ui::AlignItems::Center)
+        .flex_grow(1.)
+        .min_height(24)
+        .padding((6, 3))
+        .background_color(colors::U1)
+        .border(1)
+        .border_color(colors::U3);
+}
@@ -0,0 +1,218 @@
+use bevy::{math::IRect, prelude::*, ui};
+use bevy_quill::prelude::*;
+use bevy_quill_obsidian::{controls::Button, prelude::*, typography};
+
+use crate::{
+    editor::realm::{unique_realm_name, CreateRealm},
+    terrain::TerrainMapAsset,
+    world::{RealmData, RealmLighting},
+};
+
+use super::controls::BiomeChooser;
+
+#[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct CreateRealmOpen(pub bool);
+
+/// Settings for a new realm.
+#[derive(Clone, Copy, PartialEq)]
+struct NewRealmSettings {
+    /// Size of the terrain map, in parcels.
+    size: IVec2,
+    lighting: RealmLighting,
+    default_shape: u16,
+    default_biome: u8,
+    /// Seed for procedural variation.
+    seed: u32,
+}
+
+impl Default for NewRealmSettings {
+    fn default() -> Self {
+        Self {
+            size: IVec2::new(16, 16),
+            lighting: RealmLighting::Exterior,
+            default_shape: 0,
+            default_biome: 0,
+            seed: 0,
+        }
+    }
+}
+
+const MAX_REALM_SIZE: i32 = 256;
+
+#[derive(Clone, PartialEq)]
+pub struct CreateRealmDialog;
+
+impl ViewTemplate for CreateRealmDialog {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let settings = cx.create_mutable(NewRealmSettings::default());
+        let value = settings.get(cx);
+        let open = cx.use_resource::<State<CreateRealmOpen>>().0;
+        let close = cx.create_callback(move |mut open: ResMut<NextState<CreateRealmOpen>>| {
+            open.set(CreateRealmOpen(false));
+        });
+        let change_size = |cx: &mut Cx, delta: IVec2| {
+            cx.create_callback(move |world: &mut World| {
+                let mut s = settings.get(world);
+                s.size = (s.size + delta).clamp(IVec2::ONE, IVec2::splat(MAX_REALM_SIZE));
+                settings.set(world, s);
+            })
+        };
+        let set_lighting = |cx: &mut Cx, lighting: RealmLighting| {
+            cx.create_callback(move |world: &mut World| {
+                let mut s = settings.get(world);
+                s.lighting = lighting;
+                settings.set(world, s);
+            })
+        };
+        let change_shape = |cx: &mut Cx, delta: i32| {
+            cx.create_callback(move |world: &mut World| {
+                let mut s = settings.get(world);
+                s.default_shape = (s.default_shape as i32 + delta).max(0) as u16;
+                settings.set(world, s);
+            })
+        };
+        let change_seed = |cx: &mut Cx, delta: i32| {
+            cx.create_callback(move |world: &mut World| {
+                let mut s = settings.get(world);
+                s.seed = s.seed.wrapping_add_signed(delta);
+                settings.set(world, s);
+            })
+        };
+
+        Dialog::new()
+            .width(ui::Val::Px(400.))
+            .open(open)
+            .on_close(close)
+            .children((
+                DialogHeader::new().children("Create Realm"),
+                DialogBody::new().children(
+                    Element::<NodeBundle>::new()
+                        .style((typography::text_default, style_settings))
+                        .children((
+                            (
+                                "Width",
+                                Button::new()
+                                    .children("-")
+                                    .disabled(value.size.x <= 1)
+                                    .on_click(change_size(cx, IVec2::NEG_X)),
+                                format!("{}", value.size.x),
+                                Button::new()
+                                    .children("+")
+                                    .disabled(value.size.x >= MAX_REALM_SIZE)
+                                    .on_click(change_size(cx, IVec2::X)),
+                            ),
+                            (
+                                "Height",
+                                Button::new()
+                                    .children("-")
+                                    .disabled(value.size.y <= 1)
+                                    .on_click(change_size(cx, IVec2::NEG_Y)),
+                                format!("{}", value.size.y),
+                                Button::new()
+                                    .children("+")
+                                    .disabled(value.size.y >= MAX_REALM_SIZE)
+                                    .on_click(change_size(cx, IVec2::Y)),
+                            ),
+                            (
+                                "Default Shape",
+                                Button::new()
+                                    .children("-")
+                                    .disabled(value.default_shape == 0)
+                                    .on_click(change_shape(cx, -1)),
+                                format!("{}", value.default_shape),
+                                Button::new().children("+").on_click(change_shape(cx, 1)),
+                            ),
+                            (
+                                "Seed",
+                                Button::new().children("-").on_click(change_seed(cx, -1)),
+                                format!("{}", value.seed),
+                                Button::new().children("+").on_click(change_seed(cx, 1)),
+                            ),
+                            (
+                                "Lighting",
+                                Button::new()
+                                    .children("Exterior")
+                                    .variant(if value.lighting == RealmLighting::Exterior {
+                                        ButtonVariant::Selected
+                                    } else {
+                                        ButtonVariant::Default
+                                    })
+                                    .on_click(set_lighting(cx, RealmLighting::Exterior)),
+                                Button::new()
+                                    .children("Interior")
+                                    .variant(if value.lighting == RealmLighting::Interior {
+                                        ButtonVariant::Selected
+                                    } else {
+                                        ButtonVariant::Default
+                                    })
+                                    .on_click(set_lighting(cx, RealmLighting::Interior)),
+                            ),
+                            Element::<NodeBundle>::new()
+                                .style(style_full_row)
+                                .children("Default Biome"),
+                            Element::<NodeBundle>::new().style(style_full_row).children(
+                                BiomeChooser {
+                                    selected: value.default_biome,
+                                    on_change: cx.create_callback(
+                                        move |biome: In<u8>, world: &mut World| {
+                                            let mut s = settings.get(world);
+                                            s.default_biome = *biome;
+                                            settings.set(world, s);
+                                        },
+                                    ),
+                                },
+                            ),
+                        )),
+                ),
+                DialogFooter::new().children((
+                    Button::new().children("Cancel").on_click(close),
+                    Button::new()
+                        .children("Create")
+                        .variant(ButtonVariant::Primary)
+                        .autofocus(true)
+                        .on_click(cx.create_callback(move |world: &mut World| {
+                            let mut open = world
+                                .get_resource_mut::<NextState<CreateRealmOpen>>()
+                                .unwrap();
+                            open.set(CreateRealmOpen(false));
+                            let s = settings.get(world);
+                            let name = unique_realm_name(world, "new-realm");
+                            world.commands().add(CreateRealm {
+                                name,
+                                data: RealmData {
+                                    lighting: s.lighting,
+                                    seed: s.seed,
+                                    ..default()
+                                },
+                                map: TerrainMapAsset::new(
+                                    IRect::from_corners(IVec2::ZERO, s.size),
+                                    s.default_shape,
+                                    s.default_biome,
+                                ),
+                                copy_precincts_from: None,
+                            });
+                        })),
+                )),
+            ))
+    }
+}
+
+fn style_settings(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Grid)
+        .grid_template_columns(vec![
+            ui::RepeatedGridTrack::auto(1),
+            ui::RepeatedGridTrack::flex(3, 1.),
+        ])
+        .align_items(ui::AlignItems::Center)
+        .gap(4);
+}
+
+fn style_full_row(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .flex_direction(ui::FlexDirection::Column)
+        .align_items(ui::AlignItems::Stretch)
+        .grid_column_start(1)
+        .grid_column_span(4);
+}
@@ -0,0 +1,224 @@
+use bevy::{prelude::*, ui};
+use bevy_quill::prelude::*;
+use bevy_quill_obsidian::{controls::Button, prelude::*, typography};
+
+use crate::{
+    editor::events::GenerateTerrainMap,
+    terrain::{
+        terrain_generator::TerrainGenerator,
+        terrain_groups::{TerrainGroupsAsset, TerrainGroupsHandle},
+    },
+    view::Viewpoint,
+    world::Realm,
+};
+
+#[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct GenerateTerrainOpen(pub bool);
+
+const MIN_FEATURE_SIZE: i32 = 2;
+const MAX_FEATURE_SIZE: i32 = 64;
+
+/// Dialog which fills the terrain map of the current realm with generated terrain.
+#[derive(Clone, PartialEq)]
+pub struct GenerateTerrainDialog;
+
+impl ViewTemplate for GenerateTerrainDialog {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let seed = cx.create_mutable::<i32>(0);
+        let feature_size = cx.create_mutable::<i32>(8);
+        // Names of the groups to exclude from generation.
+        let excluded = cx.create_mutable::<Vec<String>>(Vec::new());
+        let seed_value = seed.get(cx);
+        let feature_size_value = feature_size.get(cx);
+        let excluded_value = excluded.get_clone(cx);
+        let open = cx.use_resource::<State<GenerateTerrainOpen>>().0;
+        let groups_handle = cx.use_resource::<TerrainGroupsHandle>().0.clone();
+        let group_names: Vec<String> = cx
+            .use_resource::<Assets<TerrainGroupsAsset>>()
+            .get(&groups_handle)
+            .map(|groups| {
+                groups
+                    .0
+                    .read()
+                    .unwrap()
+                    .0
+                    .iter()
+                    .filter(|group| group.visible && !group.contours.is_empty())
+                    .map(|group| group.name.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let close = cx.create_callback(move |mut open: ResMut<NextState<GenerateTerrainOpen>>| {
+            open.set(GenerateTerrainOpen(false));
+        });
+
+        Dialog::new()
+            .width(ui::Val::Px(400.))
+            .open(open)
+            .on_close(close)
+            .children((
+                DialogHeader::new().children("Generate Terrain"),
+                DialogBody::new().children((
+                    Element::<NodeBundle>::new()
+                        .style((typography::text_default, style_settings))
+                        .children((
+                            (
+                                "Seed",
+                                Button::new().children("-").on_click(cx.create_callback(
+                                    move |world: &mut World| {
+                                        let value = seed.get(world);
+                                        seed.set(world, value - 1);
+                                    },
+                                )),
+                                format!("{}", seed_value),
+                                Button::new().children("+").on_click(cx.create_callback(
+                                    move |world: &mut World| {
+                                        let value = seed.get(world);
+                                        seed.set(world, value + 1);
+                                    },
+                                )),
+                            ),
+                            (
+                                "Feature Size",
+                                Button::new()
+                                    .children("-")
+                                    .disabled(feature_size_value <= MIN_FEATURE_SIZE)
+                                    .on_click(cx.create_callback(move |world: &mut World| {
+                                        let value = feature_size.get(world);
+                                        feature_size.set(world, (value / 2).max(MIN_FEATURE_SIZE));
+                                    })),
+                                format!("{}", feature_size_value),
+                                Button::new()
+                                    .children("+")
+                                    .disabled(feature_size_value >= MAX_FEATURE_SIZE)
+                                    .on_click(cx.create_callback(move |world: &mut World| {
+                                        let value = feature_size.get(world);
+                                        feature_size.set(world, (value * 2).min(MAX_FEATURE_SIZE));
+                                    })),
+                            ),
+                        )),
+                    Element::<NodeBundle>::new()
+                        .style(typography::text_default)
+                        .children("Terrain Groups"),
+                    ScrollView::new()
+                        .style(style_group_list)
+                        .scroll_enable_y(true)
+                        .children(
+                            Element::<NodeBundle>::new()
+                                .style(style_group_list_inner)
+                                .children(For::each(group_names, move |name| GroupToggle {
+                                    name: name.clone(),
+                                    checked: !excluded_value.contains(name),
+                                    excluded,
+                                })),
+                        ),
+                )),
+                DialogFooter::new().children((
+                    Button::new().children("Cancel").on_click(close),
+                    Button::new()
+                        .children("Generate")
+                        .variant(ButtonVariant::Primary)
+                        .autofocus(true)
+                        .on_click(cx.create_callback(move |world: &mut World| {
+                            let mut open = world
+                                .get_resource_mut::<NextState<GenerateTerrainOpen>>()
+                                .unwrap();
+                            open.set(GenerateTerrainOpen(false));
+                            let Some(realm) = world.resource::<Viewpoint>().realm else {
+                                return;
+                            };
+                            let Some(region) = world.get::<Realm>(realm).map(|r| r.parcel_bounds)
+                            else {
+                                return;
+                            };
+                            let excluded = excluded.get_clone(world);
+                            let groups_handle = world.resource::<TerrainGroupsHandle>().0.clone();
+                            let groups: Vec<String> = world
+                                .resource::<Assets<TerrainGroupsAsset>>()
+                                .get(&groups_handle)
+                                .map(|groups| {
+                                    groups
+                                        .0
+                                        .read()
+                                        .unwrap()
+                                        .0
+                                        .iter()
+                                        .filter(|group| {
+                                            group.visible && !excluded.contains(&group.name)
+                                        })
+                                        .map(|group| group.name.clone())
+                                        .collect()
+                                })
+                                .unwrap_or_default();
+                            if groups.is_empty() {
+                                warn!("No terrain groups selected.");
+                                return;
+                            }
+                            world.commands().trigger(GenerateTerrainMap {
+                                realm,
+                                region,
+                                generator: TerrainGenerator {
+                                    seed: seed.get(world),
+                                    feature_size: feature_size.get(world) as f32,
+                                    groups,
+                                    ..default()
+                                },
+                            });
+                        })),
+                )),
+            ))
+    }
+}
+
+/// Checkbox which includes or excludes a terrain group from generation.
+#[derive(Clone, PartialEq)]
+struct GroupToggle {
+    name: String,
+    checked: bool,
+    excluded: Mutable<Vec<String>>,
+}
+
+impl ViewTemplate for GroupToggle {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let name = self.name.clone();
+        let excluded = self.excluded;
+        Checkbox::new()
+            .label(self.name.clone())
+            .checked(self.checked)
+            .on_change(
+                cx.create_callback(move |checked: In<bool>, world: &mut World| {
+                    let mut list = excluded.get_clone(world);
+                    list.retain(|n| *n != name);
+                    if !*checked {
+                        list.push(name.clone());
+                    }
+                    excluded.set_clone(world, list);
+                }),
+            )
+    }
+}
+
+fn style_settings(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Grid)
+        .grid_template_columns(vec![
+            ui::RepeatedGridTrack::auto(1),
+            ui::RepeatedGridTrack::flex(3, 1.),
+        ])
+        .align_items(ui::AlignItems::Center)
+        .gap(4);
+}
+
+fn style_group_list(ss: &mut StyleBuilder) {
+    ss.height(ui::Val::Px(200.)).margin_top(4);
+}
+
+fn style_group_list_inner(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .flex_direction(ui::FlexDirection::Column)
+        .align_items(ui::AlignItems::Stretch)
+        .gap(2);
+}
@@ -1,4 +1,6 @@
 mod controls;
+pub mod create_realm;
+pub mod generate_terrain;
 pub mod mode_meta;
 pub mod mode_play;
 pub mod mode_realm;
@@ -7,9 +9,18 @@ pub mod mode_selector;
 pub mod mode_terrain;
 mod overlays;
 pub mod save_button;
+mod scenery_actors;
+mod scenery_layers;
+mod scenery_prefabs;
+mod scenery_tiers;
+pub mod tool_actor_place;
+pub mod tool_cutaway_draw;
+pub mod tool_fixture_create;
 pub mod tool_floor_create;
 pub mod tool_floor_edit;
+pub mod tool_scenery_select;
 pub mod tool_terrain_edit;
+pub mod tool_terrain_fx_paint;
 pub mod tool_wall_create;
 pub mod zoom_selector;
 
@@ -26,6 +37,8 @@ use bevy_quill_obsidian::{
     focus::{DefaultKeyListener, KeyPressEvent, TabGroup},
     prelude::Spacer,
 };
+use create_realm::CreateRealmDialog;
+use generate_terrain::GenerateTerrainDialog;
 use mode_selector::{EditorModalControls, ModeSelector};
 use quick_nav::{QuickNavDialog, QuickNavOpen};
 use save_button::SaveButton;
@@ -36,7 +49,10 @@ use crate::{
     view::{viewport::ViewportInsetElement, HudCamera},
 };
 
-use super::{events::RotateSelection, EditorSidebarWidth};
+use super::{
+    events::{CopySelection, DeleteSelection, DuplicateSelection, PasteSelection, RotateSelection},
+    EditorSidebarWidth,
+};
 
 pub fn setup_editor_view(mut commands: Commands, q_camera: Query<Entity, With<HudCamera>>) {
     let camera = q_camera.get_single().expect("HudCamera not found");
@@ -82,6 +98,16 @@ impl ViewTemplate for EditorView {
                                 commands.trigger(RotateSelection(-1));
                             } else if ev.key_code == KeyCode::BracketRight {
                                 commands.trigger(RotateSelection(1));
+                            } else if ev.key_code == KeyCode::Delete
+                                || ev.key_code == KeyCode::Backspace
+                            {
+                                commands.trigger(DeleteSelection);
+                            } else if ev.key_code == KeyCode::KeyD && (command || ctrl) {
+                                commands.trigger(DuplicateSelection);
+                            } else if ev.key_code == KeyCode::KeyC && (command || ctrl) {
+                                commands.trigger(CopySelection);
+                            } else if ev.key_code == KeyCode::KeyV && (command || ctrl) {
+                                commands.trigger(PasteSelection);
                             } else if ev.key_code == KeyCode::KeyZ && (command || ctrl) {
                                 if shift {
                                     commands.add(RedoCommand);
@@ -127,6 +153,8 @@ impl ViewTemplate for EditorView {
                     .style(style_game_view)
                     .insert(ViewportInsetElement),
                 QuickNavDialog,
+                CreateRealmDialog,
+                GenerateTerrainDialog,
             ))
     }
 }
@@ -2,6 +2,18 @@ use bevy::prelude::*;
 use bevy_quill::prelude::*;
 // use bevy_quill_obsidian::prelude::*;
 
+use crate::scenery::precinct_layers::LayerConditions;
+
+/// Precinct layers are shown as they would be in the game while playing.
+pub fn enter(mut r_conditions: ResMut<LayerConditions>) {
+    r_conditions.set_editing(false);
+}
+
+/// Outside of play mode, layers follow their visibility toggles.
+pub fn exit(mut r_conditions: ResMut<LayerConditions>) {
+    r_conditions.set_editing(true);
+}
+
 #[derive(Clone, PartialEq)]
 pub(crate) struct EditModePlayControls;
 
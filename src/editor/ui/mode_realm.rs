@@ -1,11 +1,27 @@
-use bevy::{prelude::*, ui};
+use bevy::{math::IRect, prelude::*, ui};
 use bevy_quill::prelude::*;
 use bevy_quill_obsidian::{
+    colors,
     prelude::{Button, *},
     typography, RoundedCorners,
 };
 
-use crate::editor::ui::overlays::MapBoundsOverlay;
+use crate::{
+    editor::{
+        events::{ResizeTerrainMap, TranslateTerrainMap},
+        realm::{DeleteRealm, DuplicateRealm},
+        scenery::ConvertPrecincts,
+        ui::{
+            create_realm::CreateRealmOpen, generate_terrain::GenerateTerrainOpen,
+            overlays::MapBoundsOverlay,
+        },
+        unsaved::UnsavedAssets,
+    },
+    scenery::precinct_format::PrecinctFormat,
+    terrain::{TerrainMapLoadError, PARCELS_PER_PRECINCT},
+    view::Viewpoint,
+    world::Realm,
+};
 
 use super::controls::{
     style_attribute_key, style_attribute_list, style_attribute_value, LocationChooser,
@@ -33,6 +49,23 @@ impl ViewTemplate for EditModeRealmControls {
         let on_select = cx.create_callback(move |key: In<String>, world: &mut World| {
             selected.set_clone(world, Some(key.clone()));
         });
+        let realm_id = cx.use_resource::<Viewpoint>().realm;
+        let realm = realm_id.and_then(|r| cx.use_component::<Realm>(r));
+        let realm_name = realm
+            .map(|r| r.name.clone())
+            .unwrap_or_else(|| "--".to_string());
+        let realm_size = realm
+            .map(|r| {
+                format!(
+                    "({}, {})",
+                    r.parcel_bounds.width(),
+                    r.parcel_bounds.height()
+                )
+            })
+            .unwrap_or_else(|| "(0, 0)".to_string());
+        let map_error = realm_id
+            .and_then(|r| cx.use_component::<TerrainMapLoadError>(r))
+            .map(|e| e.0.clone());
 
         Element::<NodeBundle>::new().style(style_panel).children((
             Element::<NodeBundle>::new()
@@ -53,7 +86,7 @@ impl ViewTemplate for EditModeRealmControls {
                             style_attribute_value,
                             RoundedCorners::TopRight.to_border_style(4.).into_handle(),
                         ))
-                        .children("--"),
+                        .children(realm_name),
                     Element::<NodeBundle>::new()
                         .style((
                             style_attribute_key,
@@ -67,14 +100,37 @@ impl ViewTemplate for EditModeRealmControls {
                                 .to_border_style(4.)
                                 .into_handle(),
                         ))
-                        .children("(0, 0)"),
+                        .children(realm_size),
                 )),
             Flex::column(|sb| {
                 sb.gap(8).flex_grow(1.).align_items(ui::AlignItems::Stretch);
             })
             .children((
-                Button::new().children("Create Realm...").disabled(true),
+                Cond::new(
+                    map_error.is_some(),
+                    Element::<NodeBundle>::new()
+                        .style((typography::text_default, style_map_error))
+                        .children(map_error.unwrap_or_default()),
+                    (),
+                ),
+                Button::new()
+                    .children("Create Realm...")
+                    .on_click(cx.create_callback(
+                        |mut open: ResMut<NextState<CreateRealmOpen>>| {
+                            open.set(CreateRealmOpen(true));
+                        },
+                    )),
                 Button::new().children("Go To Realm..."),
+                Button::new()
+                    .children("Generate Terrain...")
+                    .on_click(cx.create_callback(
+                        |mut open: ResMut<NextState<GenerateTerrainOpen>>| {
+                            open.set(GenerateTerrainOpen(true));
+                        },
+                    )),
+                RealmFileControls,
+                MapBoundsControls,
+                PrecinctFormatControls,
             )),
             Element::<NodeBundle>::new()
                 .style(style_navpoint_controls)
@@ -98,6 +154,243 @@ impl ViewTemplate for EditModeRealmControls {
     }
 }
 
+/// Buttons for duplicating and deleting the current realm. Deleting asks for confirmation.
+#[derive(Clone, PartialEq)]
+struct RealmFileControls;
+
+impl ViewTemplate for RealmFileControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let confirm_delete = cx.create_mutable::<bool>(false);
+        let realm = cx.use_resource::<Viewpoint>().realm;
+
+        Flex::row(|sb| {
+            sb.gap(4).align_items(ui::AlignItems::Center);
+        })
+        .children(Cond::new(
+            confirm_delete.get(cx),
+            (
+                Button::new()
+                    .children("Confirm Delete")
+                    .variant(ButtonVariant::Danger)
+                    .style(style_grow)
+                    .on_click(cx.create_callback(move |world: &mut World| {
+                        confirm_delete.set(world, false);
+                        if let Some(realm) = realm {
+                            world.commands().add(DeleteRealm { realm });
+                        }
+                    })),
+                Button::new()
+                    .children("Cancel")
+                    .style(style_grow)
+                    .on_click(cx.create_callback(move |world: &mut World| {
+                        confirm_delete.set(world, false);
+                    })),
+            ),
+            (
+                Button::new()
+                    .children("Duplicate Realm")
+                    .disabled(realm.is_none())
+                    .style(style_grow)
+                    .on_click(cx.create_callback(move |mut commands: Commands| {
+                        if let Some(realm) = realm {
+                            commands.add(DuplicateRealm { realm });
+                        }
+                    })),
+                Button::new()
+                    .children("Delete Realm")
+                    .disabled(realm.is_none())
+                    .style(style_grow)
+                    .on_click(cx.create_callback(move |world: &mut World| {
+                        confirm_delete.set(world, true);
+                    })),
+            ),
+        ))
+    }
+}
+
+/// Buttons which convert the current realm's precinct files to JSON, so that scenery changes
+/// can be reviewed, or back to msgpack. Disabled while there is unsaved scenery.
+#[derive(Clone, PartialEq)]
+struct PrecinctFormatControls;
+
+impl ViewTemplate for PrecinctFormatControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let realm = cx.use_resource::<Viewpoint>().realm;
+        let has_unsaved = !cx.use_resource::<UnsavedAssets>().precincts.is_empty();
+        let mut convert_button = |label: &'static str, format: PrecinctFormat| {
+            Button::new()
+                .children(label)
+                .disabled(realm.is_none() || has_unsaved)
+                .style(style_grow)
+                .on_click(cx.create_callback(move |mut commands: Commands| {
+                    if let Some(realm) = realm {
+                        commands.add(ConvertPrecincts { realm, format });
+                    }
+                }))
+        };
+
+        Flex::row(|sb| {
+            sb.gap(4);
+        })
+        .children((
+            convert_button("Scenery to JSON", PrecinctFormat::Json),
+            convert_button("Scenery to Msgpack", PrecinctFormat::Msgpack),
+        ))
+    }
+}
+
+/// Which edge of the terrain map to extend or crop.
+#[derive(Clone, Copy, PartialEq)]
+enum MapEdge {
+    MinX,
+    MaxX,
+    MinZ,
+    MaxZ,
+}
+
+impl MapEdge {
+    /// Move this edge of the bounds outward by `amount` parcels (inward if negative).
+    fn adjust(self, mut bounds: IRect, amount: i32) -> IRect {
+        match self {
+            MapEdge::MinX => bounds.min.x -= amount,
+            MapEdge::MaxX => bounds.max.x += amount,
+            MapEdge::MinZ => bounds.min.y -= amount,
+            MapEdge::MaxZ => bounds.max.y += amount,
+        }
+        bounds
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MapBoundsOp {
+    Extend(MapEdge),
+    Crop(MapEdge),
+    Shift(IVec2),
+}
+
+/// Controls for growing, shrinking and moving the terrain map of the current realm.
+#[derive(Clone, PartialEq)]
+struct MapBoundsControls;
+
+impl ViewTemplate for MapBoundsControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let move_scenery = cx.create_mutable::<bool>(false);
+        let move_scenery_value = move_scenery.get(cx);
+        let realm_id = cx.use_resource::<Viewpoint>().realm;
+        let bounds = realm_id
+            .and_then(|r| cx.use_component::<Realm>(r))
+            .map(|r| r.parcel_bounds)
+            .unwrap_or_default();
+        let has_realm = realm_id.is_some();
+        let can_crop_x = has_realm && bounds.width() > 1;
+        let can_crop_z = has_realm && bounds.height() > 1;
+        let button = |label: &'static str, op: MapBoundsOp, enabled: bool| MapBoundsButton {
+            label,
+            op,
+            move_scenery: move_scenery_value,
+            disabled: !enabled,
+        };
+
+        Element::<NodeBundle>::new()
+            .style((typography::text_default, style_map_bounds))
+            .children((
+                (
+                    "Extend",
+                    button("-X", MapBoundsOp::Extend(MapEdge::MinX), has_realm),
+                    button("+X", MapBoundsOp::Extend(MapEdge::MaxX), has_realm),
+                    button("-Z", MapBoundsOp::Extend(MapEdge::MinZ), has_realm),
+                    button("+Z", MapBoundsOp::Extend(MapEdge::MaxZ), has_realm),
+                ),
+                (
+                    "Crop",
+                    button("-X", MapBoundsOp::Crop(MapEdge::MinX), can_crop_x),
+                    button("+X", MapBoundsOp::Crop(MapEdge::MaxX), can_crop_x),
+                    button("-Z", MapBoundsOp::Crop(MapEdge::MinZ), can_crop_z),
+                    button("+Z", MapBoundsOp::Crop(MapEdge::MaxZ), can_crop_z),
+                ),
+                (
+                    "Shift",
+                    button("-X", MapBoundsOp::Shift(IVec2::NEG_X), has_realm),
+                    button("+X", MapBoundsOp::Shift(IVec2::X), has_realm),
+                    button("-Z", MapBoundsOp::Shift(IVec2::NEG_Y), has_realm),
+                    button("+Z", MapBoundsOp::Shift(IVec2::Y), has_realm),
+                ),
+                Checkbox::new()
+                    .label("Move Scenery")
+                    .checked(move_scenery_value)
+                    .style(style_map_bounds_checkbox)
+                    .on_change(
+                        cx.create_callback(move |checked: In<bool>, world: &mut World| {
+                            move_scenery.set(world, *checked);
+                        }),
+                    ),
+            ))
+    }
+}
+
+/// Button which applies a single edit to the terrain map bounds of the current realm.
+#[derive(Clone, PartialEq)]
+struct MapBoundsButton {
+    label: &'static str,
+    op: MapBoundsOp,
+    move_scenery: bool,
+    disabled: bool,
+}
+
+impl ViewTemplate for MapBoundsButton {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let op = self.op;
+        let move_scenery = self.move_scenery;
+        Button::new()
+            .children(self.label)
+            .disabled(self.disabled)
+            .on_click(cx.create_callback(
+                move |r_viewpoint: Res<Viewpoint>,
+                      q_realms: Query<&Realm>,
+                      mut commands: Commands| {
+                    let Some(realm) = r_viewpoint.realm else {
+                        return;
+                    };
+                    let Ok(realm_data) = q_realms.get(realm) else {
+                        return;
+                    };
+                    let bounds = realm_data.parcel_bounds;
+                    match op {
+                        MapBoundsOp::Extend(edge) => commands.trigger(ResizeTerrainMap {
+                            realm,
+                            bounds: edge.adjust(bounds, 1),
+                        }),
+                        MapBoundsOp::Crop(edge) => commands.trigger(ResizeTerrainMap {
+                            realm,
+                            bounds: edge.adjust(bounds, -1),
+                        }),
+                        MapBoundsOp::Shift(dir) => {
+                            // Scenery moves a whole precinct at a time.
+                            let step = if move_scenery {
+                                PARCELS_PER_PRECINCT
+                            } else {
+                                1
+                            };
+                            commands.trigger(TranslateTerrainMap {
+                                realm,
+                                offset: dir * step,
+                                move_precincts: move_scenery,
+                            })
+                        }
+                    }
+                },
+            ))
+    }
+}
+
 fn style_panel(ss: &mut StyleBuilder) {
     ss.display(ui::Display::Grid)
         .grid_template_columns(vec![
@@ -123,3 +416,25 @@ fn style_navpoint_controls(ss: &mut StyleBuilder) {
         .flex_grow(1.)
         .align_items(ui::AlignItems::Stretch);
 }
+
+fn style_map_error(ss: &mut StyleBuilder) {
+    ss.color(colors::X_RED);
+}
+
+fn style_grow(ss: &mut StyleBuilder) {
+    ss.flex_grow(1.);
+}
+
+fn style_map_bounds(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Grid)
+        .grid_template_columns(vec![
+            ui::RepeatedGridTrack::auto(1),
+            ui::RepeatedGridTrack::flex(4, 1.),
+        ])
+        .align_items(ui::AlignItems::Center)
+        .gap(4);
+}
+
+fn style_map_bounds_checkbox(ss: &mut StyleBuilder) {
+    ss.grid_column_start(1).grid_column_span(5);
+}
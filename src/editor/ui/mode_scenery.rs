@@ -1,15 +1,22 @@
 use crate::{
     actors::ACTOR_TYPE,
     editor::{
-        events::{PlaceWalls, RemoveWalls, RotateSelection},
+        events::{PlaceFixture, PlaceWalls, RemoveWalls, RotateSelection},
+        lib::floor_ops::FloorPart,
+        scenery::{
+            receive_prefab, ActorRef, PendingPrefab, PrefabList, SceneryClipboard,
+            ScenerySelection, SelectedActor, TerrainFxStroke,
+        },
         undo::{RedoEntry, UndoEntry, UndoStack},
         unsaved, EditorMode,
     },
     scenery::{
+        cutaway::TierCeiling,
+        floor_region::FloorRegionSer,
         precinct::Precinct,
         precinct_asset::{PrecinctAsset, SceneryInstanceData, SceneryInstanceId},
         scenery_element::{SceneryElement, SceneryElementRebuildAspects},
-        FIXTURE_TYPE, FLOOR_TYPE, PRECINCT_SIZE_F, TIER_OFFSET, WALL_TYPE,
+        FIXTURE_TYPE, FLOOR_TYPE, PRECINCT_SIZE_F, TERRAIN_FX_TYPE, TIER_OFFSET, WALL_TYPE,
     },
 };
 use bevy::{prelude::*, render::view::RenderLayers, ui, utils::hashbrown::HashSet};
@@ -18,7 +25,12 @@ use bevy_quill::prelude::*;
 use bevy_quill_obsidian::{prelude::*, RoundedCorners};
 use panoply_exemplar::Exemplar;
 
-use super::{controls::ExemplarChooser, tool_floor_create, tool_floor_edit, tool_wall_create};
+use super::{
+    controls::ExemplarChooser, scenery_actors::ActorControls, scenery_layers::LayerControls,
+    scenery_prefabs::PrefabControls, scenery_tiers::TierControls, tool_actor_place,
+    tool_cutaway_draw, tool_fixture_create, tool_floor_create, tool_floor_edit,
+    tool_scenery_select, tool_terrain_fx_paint, tool_wall_create,
+};
 
 pub(crate) struct EditSceneryPlugin;
 
@@ -27,14 +39,24 @@ impl Plugin for EditSceneryPlugin {
         app.insert_state(SceneryTool::default())
             .insert_state(FloorTool::default())
             .insert_state(WallSnap::default())
+            .insert_state(FixtureSnap::default())
             .add_computed_state::<SceneryOverlay>()
             .enable_state_scoped_entities::<SceneryTool>()
             .enable_state_scoped_entities::<FloorTool>()
             .enable_state_scoped_entities::<WallSnap>()
             .init_resource::<SelectedPrecinct>()
             .init_resource::<SelectedTier>()
+            .init_resource::<HideUpperTiers>()
             .init_resource::<SelectedFacing>()
+            .init_resource::<SelectedLayer>()
             .init_resource::<SceneryDragState>()
+            .init_resource::<ScenerySelectState>()
+            .init_resource::<FloorEditState>()
+            .init_resource::<CutawayEditState>()
+            .init_resource::<ScenerySelection>()
+            .init_resource::<SceneryClipboard>()
+            .init_resource::<PrefabList>()
+            .init_resource::<PendingPrefab>()
             .init_resource::<FloorType>()
             .init_resource::<FloorFilter>()
             .init_resource::<WallType>()
@@ -43,12 +65,21 @@ impl Plugin for EditSceneryPlugin {
             .init_resource::<FixtureFilter>()
             .init_resource::<ActorType>()
             .init_resource::<ActorFilter>()
+            .init_resource::<SelectedActor>()
+            .init_resource::<ActorPlaceState>()
+            .init_resource::<TerrainFxType>()
+            .init_resource::<TerrainFxFilter>()
+            .init_resource::<TerrainFxBrushRadius>()
+            .init_resource::<TerrainFxBrushState>()
+            .init_resource::<TerrainFxStroke>()
             .register_type::<State<SceneryTool>>()
             .register_type::<NextState<SceneryTool>>()
             .register_type::<State<FloorTool>>()
             .register_type::<NextState<FloorTool>>()
             .register_type::<State<WallSnap>>()
             .register_type::<NextState<WallSnap>>()
+            .register_type::<State<FixtureSnap>>()
+            .register_type::<NextState<FixtureSnap>>()
             .register_type::<FloorType>()
             .register_type::<FloorFilter>()
             .register_type::<WallType>()
@@ -57,8 +88,13 @@ impl Plugin for EditSceneryPlugin {
             .register_type::<FixtureFilter>()
             .register_type::<ActorType>()
             .register_type::<ActorFilter>()
+            .register_type::<TerrainFxType>()
+            .register_type::<TerrainFxFilter>()
+            .register_type::<TerrainFxBrushRadius>()
             .register_type::<SelectedTier>()
+            .register_type::<HideUpperTiers>()
             .register_type::<SelectedFacing>()
+            .register_type::<SelectedLayer>()
             .add_systems(
                 OnEnter(SceneryOverlay::FloorCreate),
                 tool_floor_create::enter,
@@ -68,16 +104,66 @@ impl Plugin for EditSceneryPlugin {
             .add_systems(OnExit(SceneryOverlay::FloorDraw), tool_floor_edit::exit)
             .add_systems(OnEnter(SceneryOverlay::PlaceWall), tool_wall_create::enter)
             .add_systems(OnExit(SceneryOverlay::PlaceWall), tool_wall_create::exit)
+            .add_systems(
+                OnEnter(SceneryOverlay::PlaceFixture),
+                tool_fixture_create::enter,
+            )
+            .add_systems(
+                OnExit(SceneryOverlay::PlaceFixture),
+                tool_fixture_create::exit,
+            )
+            .add_systems(OnEnter(SceneryOverlay::PlaceActor), tool_actor_place::enter)
+            .add_systems(OnExit(SceneryOverlay::PlaceActor), tool_actor_place::exit)
+            .add_systems(
+                OnEnter(SceneryOverlay::DrawTerrainFx),
+                tool_terrain_fx_paint::enter,
+            )
+            .add_systems(
+                OnExit(SceneryOverlay::DrawTerrainFx),
+                tool_terrain_fx_paint::exit,
+            )
+            .add_systems(
+                OnEnter(SceneryOverlay::DrawCutaways),
+                tool_cutaway_draw::enter,
+            )
+            .add_systems(
+                OnExit(SceneryOverlay::DrawCutaways),
+                tool_cutaway_draw::exit,
+            )
+            .add_systems(
+                OnEnter(SceneryOverlay::Interact),
+                tool_scenery_select::enter_interact,
+            )
+            .add_systems(OnExit(SceneryOverlay::Interact), tool_scenery_select::exit)
+            .add_systems(
+                OnEnter(SceneryOverlay::RectSelect),
+                tool_scenery_select::enter_rect_select,
+            )
+            .add_systems(
+                OnExit(SceneryOverlay::RectSelect),
+                tool_scenery_select::exit,
+            )
             .add_systems(
                 Update,
                 (
                     tool_floor_create::update.run_if(in_state(SceneryOverlay::FloorCreate)),
                     tool_floor_edit::update.run_if(in_state(SceneryOverlay::FloorDraw)),
                     tool_wall_create::update.run_if(in_state(SceneryOverlay::PlaceWall)),
+                    tool_fixture_create::update.run_if(in_state(SceneryOverlay::PlaceFixture)),
+                    tool_actor_place::update.run_if(in_state(SceneryOverlay::PlaceActor)),
+                    tool_terrain_fx_paint::update.run_if(in_state(SceneryOverlay::DrawTerrainFx)),
+                    tool_cutaway_draw::update.run_if(in_state(SceneryOverlay::DrawCutaways)),
+                    tool_scenery_select::update.run_if(
+                        in_state(SceneryOverlay::Interact)
+                            .or_else(in_state(SceneryOverlay::RectSelect)),
+                    ),
                     update.run_if(in_state(EditorMode::Scenery)),
+                    update_tier_ceiling,
+                    receive_prefab,
                 ),
             )
             .observe(place_walls)
+            .observe(place_fixture)
             .observe(remove_walls);
     }
 }
@@ -89,10 +175,31 @@ pub struct SelectedPrecinct(pub Option<Entity>);
 #[reflect(@PreferencesGroup("editor"), @PreferencesKey("selected_tier"))]
 pub struct SelectedTier(pub i16);
 
+/// Whether tiers above the selected one are hidden while editing scenery.
+#[derive(Resource, Default, Reflect)]
+#[reflect(@PreferencesGroup("editor"), @PreferencesKey("hide_upper_tiers"))]
+pub struct HideUpperTiers(pub bool);
+
 #[derive(Resource, Default, Reflect)]
 #[reflect(@PreferencesGroup("editor"), @PreferencesKey("selected_facing"))]
 pub struct SelectedFacing(pub i32);
 
+/// Name of the precinct layer that newly placed scenery and floors are assigned to.
+#[derive(Resource, Default, Reflect)]
+#[reflect(@PreferencesGroup("editor"), @PreferencesKey("selected_layer"))]
+pub struct SelectedLayer(pub Option<String>);
+
+impl SelectedLayer {
+    /// The layer to assign new items in the given precinct to. Precincts that don't have a
+    /// layer with the selected name put new items in the default layer.
+    pub(crate) fn layer_for(&self, precinct: &PrecinctAsset) -> Option<String> {
+        self.0
+            .as_ref()
+            .filter(|name| precinct.layers.contains_key(*name))
+            .cloned()
+    }
+}
+
 #[derive(Resource, Default, Reflect)]
 // #[reflect(@PreferencesGroup("editor"), @PreferencesKey("floor_type"))]
 pub struct FloorType(pub Option<AssetId<Exemplar>>);
@@ -125,6 +232,40 @@ pub struct ActorType(pub Option<AssetId<Exemplar>>);
 #[reflect(@PreferencesGroup("editor"), @PreferencesKey("wall_type_filter"))]
 pub struct ActorFilter(pub String);
 
+#[derive(Resource, Default, Reflect)]
+// #[reflect(@PreferencesGroup("editor"), @PreferencesKey("terrain_fx_type"))]
+pub struct TerrainFxType(pub Option<AssetId<Exemplar>>);
+
+#[derive(Resource, Default, Reflect)]
+#[reflect(@PreferencesGroup("editor"), @PreferencesKey("terrain_fx_type_filter"))]
+pub struct TerrainFxFilter(pub String);
+
+/// Radius of the terrain effect brush, in meters.
+#[derive(Resource, Reflect)]
+#[reflect(@PreferencesGroup("editor"), @PreferencesKey("terrain_fx_brush_radius"))]
+pub struct TerrainFxBrushRadius(pub i32);
+
+impl Default for TerrainFxBrushRadius {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+const MAX_TERRAIN_FX_BRUSH_RADIUS: i32 = 4;
+
+/// State of the terrain effect brush while hovering or painting.
+#[derive(Resource, Default)]
+pub(crate) struct TerrainFxBrushState {
+    /// Realm being painted.
+    pub(crate) realm: Option<Entity>,
+    /// World-space terrain vertex under the cursor.
+    pub(crate) cursor: Option<IVec2>,
+    /// Height of the terrain under the cursor.
+    pub(crate) height: f32,
+    /// Whether a stroke is in progress.
+    pub(crate) painting: bool,
+}
+
 #[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Reflect)]
 #[reflect(Default, @PreferencesGroup("editor"), @PreferencesKey("scenery_tool"))]
 pub enum SceneryTool {
@@ -137,6 +278,7 @@ pub enum SceneryTool {
     SceneryEdit,
     EditLayers,
     SceneryRect,
+    Cutaways,
 }
 
 #[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Reflect)]
@@ -160,6 +302,15 @@ pub enum WallSnap {
     Quarter,
 }
 
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Reflect)]
+#[reflect(Default, @PreferencesGroup("editor"), @PreferencesKey("fixture_snap"))]
+pub enum FixtureSnap {
+    #[default]
+    Grid,
+    Fine,
+    Free,
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum SceneryOverlay {
     FloorDraw,
@@ -168,6 +319,7 @@ pub enum SceneryOverlay {
     PlaceFixture,
     PlaceActor,
     DrawTerrainFx,
+    DrawCutaways,
     Interact,
     RectSelect,
 }
@@ -183,6 +335,97 @@ pub(crate) struct SceneryDragState {
     pub(crate) cursor_exemplar: Option<AssetId<Exemplar>>,
     pub(crate) cursor_model: Option<Entity>,
     pub(crate) cursor_layer: usize,
+    /// Height of the cursor model above the selected tier.
+    pub(crate) cursor_elevation: f32,
+}
+
+/// State of the floor editing tool.
+#[derive(Resource, Default, Clone, PartialEq)]
+pub(crate) struct FloorEditState {
+    /// World-space position of the selected precinct's origin.
+    pub(crate) origin: Vec2,
+    /// Part of a floor under the cursor, or being dragged.
+    pub(crate) part: Option<FloorPart>,
+    /// Vertex which was last clicked, and which the delete key removes.
+    pub(crate) selected: Option<FloorPart>,
+    /// Whether the part is being dragged.
+    pub(crate) dragging: bool,
+    /// Precinct-relative position where the drag started, snapped to the half meter.
+    pub(crate) anchor_pos: Vec2,
+    /// Precinct-relative position of the cursor, snapped to the half meter.
+    pub(crate) cursor_pos: Vec2,
+    /// Precinct-relative position of the cursor, unsnapped.
+    pub(crate) pick_pos: Vec2,
+    /// Floors of the selected tier, with the drag in progress applied.
+    pub(crate) floors: Vec<FloorRegionSer>,
+    /// Whether the drag in progress would result in a valid floor.
+    pub(crate) valid: bool,
+}
+
+/// State of the cutaway editing tool.
+#[derive(Resource, Default, Clone, PartialEq)]
+pub(crate) struct CutawayEditState {
+    /// World-space position of the selected precinct's origin.
+    pub(crate) origin: Vec2,
+    /// Cutaway rectangles of the selected tier, relative to the precinct.
+    pub(crate) rects: Vec<Rect>,
+    /// Index of the rectangle which was last clicked, and which the delete key removes.
+    pub(crate) selected: Option<usize>,
+    /// Whether a new rectangle is being drawn.
+    pub(crate) dragging: bool,
+    /// Precinct-relative position where the drag started, snapped to the meter.
+    pub(crate) anchor_pos: Vec2,
+    /// Precinct-relative position of the cursor, snapped to the meter.
+    pub(crate) cursor_pos: Vec2,
+}
+
+impl CutawayEditState {
+    /// The rectangle being drawn, if any.
+    pub(crate) fn marquee(&self) -> Option<Rect> {
+        self.dragging
+            .then(|| Rect::from_corners(self.anchor_pos, self.cursor_pos))
+            .filter(|rect| rect.width() > 0. && rect.height() > 0.)
+    }
+}
+
+/// State of the scenery selection tools.
+#[derive(Resource, Default, Clone, PartialEq)]
+pub(crate) struct ScenerySelectState {
+    /// Whether the selection is being moved, or a selection rectangle drawn.
+    pub(crate) dragging: bool,
+    /// World-space position where the drag started.
+    pub(crate) anchor_pos: Vec2,
+    /// World-space position of the cursor.
+    pub(crate) cursor_pos: Vec2,
+    /// Grid-snapped offset of the selection being moved.
+    pub(crate) offset: Vec2,
+    /// Selection rectangle being drawn, in world space.
+    pub(crate) marquee: Option<Rect>,
+    /// World-space positions of the selected scenery, including the drag offset.
+    pub(crate) highlights: Vec<Vec3>,
+}
+
+/// An actor as shown by the actor placement tool.
+#[derive(Clone, PartialEq)]
+pub(crate) struct ActorMarker {
+    pub(crate) actor: ActorRef,
+    /// World-space position of the actor.
+    pub(crate) position: Vec3,
+    /// Facing direction, in degrees.
+    pub(crate) facing: f32,
+    /// Whether the actor is in a locked layer.
+    pub(crate) locked: bool,
+}
+
+/// State of the actor placement tool.
+#[derive(Resource, Default, Clone, PartialEq)]
+pub(crate) struct ActorPlaceState {
+    /// Whether the selected actor is being dragged.
+    pub(crate) dragging: bool,
+    /// World-space position where an actor would be placed, if the cursor is over a precinct.
+    pub(crate) cursor_pos: Option<Vec3>,
+    /// Actors in the viewed realm.
+    pub(crate) markers: Vec<ActorMarker>,
 }
 
 impl ComputedStates for SceneryOverlay {
@@ -205,6 +448,7 @@ impl ComputedStates for SceneryOverlay {
             SceneryTool::TerrainFxDraw => Some(SceneryOverlay::DrawTerrainFx),
             SceneryTool::SceneryEdit => Some(SceneryOverlay::Interact),
             SceneryTool::SceneryRect => Some(SceneryOverlay::RectSelect),
+            SceneryTool::Cutaways => Some(SceneryOverlay::DrawCutaways),
             _ => None,
         }
     }
@@ -325,7 +569,12 @@ impl ViewTemplate for EditModeSceneryControls {
                     ToolButton::new()
                         .children("Cut")
                         .corners(RoundedCorners::Left)
-                        .selected(st == SceneryTool::FloorDraw),
+                        .selected(st == SceneryTool::Cutaways)
+                        .on_click(cx.create_callback(
+                            |mut mode: ResMut<NextState<SceneryTool>>| {
+                                mode.set(SceneryTool::Cutaways);
+                            },
+                        )),
                     ToolIconButton::new(
                         "embedded://bevy_quill_obsidian/assets/icons/chevron_down.png",
                     )
@@ -346,9 +595,7 @@ impl ViewTemplate for EditModeSceneryControls {
                         },
                     )),
                 )),
-            ListView::new().style(|sb: &mut StyleBuilder| {
-                sb.grid_row_start(3).grid_row_end(4).min_height(48);
-            }),
+            TierControls,
             Element::<NodeBundle>::new()
                 .style(style_chooser_panel)
                 .children((Switch::new(st)
@@ -360,13 +607,42 @@ impl ViewTemplate for EditModeSceneryControls {
                         SceneryTool::WallDraw,
                         (WallSnapSelector, WallExemplarChooser),
                     )
-                    .case(SceneryTool::FixtureDraw, FixtureExemplarChooser)
-                    .case(SceneryTool::ActorPlacement, ActorExemplarChooser)
+                    .case(
+                        SceneryTool::FixtureDraw,
+                        (FixtureSnapSelector, FixtureExemplarChooser),
+                    )
+                    .case(
+                        SceneryTool::ActorPlacement,
+                        (FixtureSnapSelector, ActorControls, ActorExemplarChooser),
+                    )
+                    .case(
+                        SceneryTool::TerrainFxDraw,
+                        (TerrainFxBrushControls, TerrainFxExemplarChooser),
+                    )
+                    .case(SceneryTool::EditLayers, LayerControls)
+                    .case(SceneryTool::SceneryEdit, PrefabControls)
+                    .case(SceneryTool::SceneryRect, PrefabControls)
                     .fallback(()),)),
         ))
     }
 }
 
+/// Hide the tiers above the selected one, if enabled, for as long as scenery is being edited.
+fn update_tier_ceiling(
+    r_mode: Res<State<EditorMode>>,
+    r_selected_tier: Res<SelectedTier>,
+    r_hide_upper: Res<HideUpperTiers>,
+    mut r_ceiling: ResMut<TierCeiling>,
+) {
+    let ceiling = TierCeiling(
+        (*r_mode.get() == EditorMode::Scenery && r_hide_upper.0)
+            .then_some(r_selected_tier.0 as i32),
+    );
+    if *r_ceiling != ceiling {
+        *r_ceiling = ceiling;
+    }
+}
+
 fn style_panel(ss: &mut StyleBuilder) {
     ss.display(ui::Display::Grid)
         .grid_template_columns(vec![
@@ -509,6 +785,92 @@ impl ViewTemplate for WallSnapSelector {
     }
 }
 
+#[derive(Clone, PartialEq)]
+pub(crate) struct FixtureSnapSelector;
+
+impl ViewTemplate for FixtureSnapSelector {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let st = *cx.use_resource::<State<FixtureSnap>>().get();
+
+        ToolPalette::new()
+            .columns(3)
+            .style(|sb: &mut StyleBuilder| {
+                sb.align_self(ui::AlignSelf::Start);
+            })
+            .children((
+                ToolIconButton::new("editor/icons/grid-normal.png")
+                    .size(Vec2::new(16., 16.))
+                    .corners(RoundedCorners::Left)
+                    .selected(st == FixtureSnap::Grid)
+                    .on_click(
+                        cx.create_callback(|mut mode: ResMut<NextState<FixtureSnap>>| {
+                            mode.set(FixtureSnap::Grid);
+                        }),
+                    ),
+                ToolIconButton::new("editor/icons/grid-fine.png")
+                    .size(Vec2::new(16., 16.))
+                    .selected(st == FixtureSnap::Fine)
+                    .on_click(
+                        cx.create_callback(|mut mode: ResMut<NextState<FixtureSnap>>| {
+                            mode.set(FixtureSnap::Fine);
+                        }),
+                    ),
+                ToolIconButton::new("editor/icons/pointer.png")
+                    .size(Vec2::new(16., 16.))
+                    .corners(RoundedCorners::Right)
+                    .selected(st == FixtureSnap::Free)
+                    .on_click(
+                        cx.create_callback(|mut mode: ResMut<NextState<FixtureSnap>>| {
+                            mode.set(FixtureSnap::Free);
+                        }),
+                    ),
+            ))
+    }
+}
+
+/// Brush size for the terrain effect tool.
+#[derive(Clone, PartialEq)]
+pub(crate) struct TerrainFxBrushControls;
+
+impl ViewTemplate for TerrainFxBrushControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let radius = cx.use_resource::<TerrainFxBrushRadius>().0;
+
+        Element::<NodeBundle>::new()
+            .style(style_brush_controls)
+            .children((
+                Button::new()
+                    .children("-")
+                    .disabled(radius <= 0)
+                    .on_click(
+                        cx.create_callback(|mut radius: ResMut<TerrainFxBrushRadius>| {
+                            radius.0 = (radius.0 - 1).max(0);
+                        }),
+                    ),
+                format!("Radius: {}", radius),
+                Button::new()
+                    .children("+")
+                    .disabled(radius >= MAX_TERRAIN_FX_BRUSH_RADIUS)
+                    .on_click(
+                        cx.create_callback(|mut radius: ResMut<TerrainFxBrushRadius>| {
+                            radius.0 = (radius.0 + 1).min(MAX_TERRAIN_FX_BRUSH_RADIUS);
+                        }),
+                    ),
+            ))
+    }
+}
+
+fn style_brush_controls(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .flex_direction(ui::FlexDirection::Row)
+        .align_items(ui::AlignItems::Center)
+        .gap(4);
+}
+
 #[derive(Clone, PartialEq)]
 pub(crate) struct FloorExemplarChooser;
 
@@ -605,6 +967,30 @@ impl ViewTemplate for ActorExemplarChooser {
     }
 }
 
+#[derive(Clone, PartialEq)]
+pub(crate) struct TerrainFxExemplarChooser;
+
+impl ViewTemplate for TerrainFxExemplarChooser {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let on_change = cx.create_callback(
+            |key: In<Option<AssetId<Exemplar>>>, mut selected: ResMut<TerrainFxType>| {
+                selected.0 = *key;
+            },
+        );
+        let selected = cx.use_resource::<TerrainFxType>();
+        let filter = cx.use_resource::<TerrainFxFilter>();
+        ExemplarChooser {
+            selected: selected.0,
+            instance_type: TERRAIN_FX_TYPE,
+            filter: filter.0.clone(),
+            style: style_exemplar_chooser.into_handle(),
+            on_change,
+        }
+    }
+}
+
 fn update(
     mut commands: Commands,
     q_precints: Query<&Precinct>,
@@ -628,7 +1014,7 @@ fn update(
             while x <= area.max.x {
                 let mut z = area.min.y;
                 while z <= area.max.y {
-                    coords.push(Vec3::new(x, 0., z));
+                    coords.push(Vec3::new(x, r_drag_state.cursor_elevation, z));
                     z += 1.0;
                 }
                 x += 1.0;
@@ -718,31 +1104,32 @@ impl UndoEntry for UndoPlaceWalls {
         self.label
     }
 
-    fn undo(&self, world: &mut World) -> Box<dyn RedoEntry> {
+    fn undo(&self, world: &mut World) -> Option<Box<dyn RedoEntry>> {
         let mut precinct_assets = world.get_resource_mut::<Assets<PrecinctAsset>>().unwrap();
         let precinct = precinct_assets.get_mut(self.precinct.id()).unwrap();
         let to_remove = HashSet::<SceneryInstanceId>::from_iter(
             self.added.iter().map(|scenery| scenery.iid.clone()),
         );
-        precinct.remove_scenery_elements(|iid, _, _| to_remove.contains(iid));
+        precinct.remove_scenery_elements(|scenery| to_remove.contains(&scenery.iid));
         for scenery in self.removed.iter() {
             precinct.add_scenery_element(
                 scenery.id,
                 scenery.facing,
                 scenery.position,
                 Some(scenery.iid.clone()),
+                scenery.layer.clone(),
             );
         }
         let mut unsaved = world.get_resource_mut::<unsaved::UnsavedAssets>().unwrap();
         unsaved
             .precincts
             .insert(self.precinct.clone(), unsaved::ModifiedState::Unsaved);
-        Box::new(UndoPlaceWalls {
+        Some(Box::new(UndoPlaceWalls {
             label: self.label,
             precinct: self.precinct.clone(),
             added: self.added.clone(),
             removed: self.removed.clone(),
-        })
+        }))
     }
 }
 
@@ -751,31 +1138,32 @@ impl RedoEntry for UndoPlaceWalls {
         self.label
     }
 
-    fn redo(&self, world: &mut World) -> Box<dyn UndoEntry> {
+    fn redo(&self, world: &mut World) -> Option<Box<dyn UndoEntry>> {
         let mut precinct_assets = world.get_resource_mut::<Assets<PrecinctAsset>>().unwrap();
         let precinct = precinct_assets.get_mut(self.precinct.id()).unwrap();
         let to_remove = HashSet::<SceneryInstanceId>::from_iter(
             self.removed.iter().map(|scenery| scenery.iid.clone()),
         );
-        precinct.remove_scenery_elements(|iid, _, _| to_remove.contains(iid));
+        precinct.remove_scenery_elements(|scenery| to_remove.contains(&scenery.iid));
         for scenery in self.added.iter() {
             precinct.add_scenery_element(
                 scenery.id,
                 scenery.facing,
                 scenery.position,
                 Some(scenery.iid.clone()),
+                scenery.layer.clone(),
             );
         }
         let mut unsaved = world.get_resource_mut::<unsaved::UnsavedAssets>().unwrap();
         unsaved
             .precincts
             .insert(self.precinct.clone(), unsaved::ModifiedState::Unsaved);
-        Box::new(UndoPlaceWalls {
+        Some(Box::new(UndoPlaceWalls {
             label: self.label,
             precinct: self.precinct.clone(),
             added: self.added.clone(),
             removed: self.removed.clone(),
-        })
+        }))
     }
 }
 
@@ -783,6 +1171,7 @@ fn place_walls(
     trigger: Trigger<PlaceWalls>,
     mut r_precinct_assets: ResMut<Assets<PrecinctAsset>>,
     r_server: Res<AssetServer>,
+    r_selected_layer: Res<SelectedLayer>,
     mut r_undo_stack: ResMut<UndoStack>,
     mut r_unsaved: ResMut<unsaved::UnsavedAssets>,
 ) {
@@ -794,7 +1183,13 @@ fn place_walls(
             r_precinct_assets.get_mut(event.precinct.id()).unwrap()
         }
     };
-    let exemplar_path = r_server.get_path(event.exemplar).unwrap().to_string();
+    let layer = r_selected_layer.layer_for(precinct);
+    if precinct.is_layer_locked(layer.as_deref()) {
+        return;
+    }
+    let Some(exemplar_path) = r_server.get_path(event.exemplar).map(|p| p.to_string()) else {
+        return;
+    };
     let archetype_id = match precinct.scenery_type_index(&exemplar_path) {
         Some(id) => id,
         None => precinct.add_scenery_type(exemplar_path),
@@ -802,9 +1197,13 @@ fn place_walls(
     let height = event.tier as f32 + TIER_OFFSET;
     // TODO: For undo purposes, TBA
     let mut added: Vec<SceneryInstanceData> = Vec::new();
-    let removed = precinct.remove_scenery_elements(|_, _, pos| {
+    // Items in locked layers are left alone.
+    let locked = locked_layers(precinct);
+    let removed = precinct.remove_scenery_elements(|scenery| {
+        let pos = &scenery.position;
         // TODO: Check for wall type?
-        pos.x >= event.area.min.x
+        !scenery.layer.as_ref().is_some_and(|l| locked.contains(l))
+            && pos.x >= event.area.min.x
             && pos.x <= event.area.max.x
             && pos.y > height - 0.5
             && pos.y < height + 0.5
@@ -817,11 +1216,18 @@ fn place_walls(
         while z <= event.area.max.y {
             let position = Vec3::new(x, height, z);
             added.push(SceneryInstanceData {
-                iid: precinct.add_scenery_element(archetype_id, event.facing, position, None),
+                iid: precinct.add_scenery_element(
+                    archetype_id,
+                    event.facing,
+                    position,
+                    None,
+                    layer.clone(),
+                ),
                 id: archetype_id,
                 facing: event.facing,
                 position,
                 aspects: Default::default(),
+                layer: layer.clone(),
             });
             z += 1.0;
         }
@@ -841,6 +1247,7 @@ fn place_walls(
 fn remove_walls(
     trigger: Trigger<RemoveWalls>,
     mut r_precinct_assets: ResMut<Assets<PrecinctAsset>>,
+    r_selected_layer: Res<SelectedLayer>,
     mut r_undo_stack: ResMut<UndoStack>,
     mut r_unsaved: ResMut<unsaved::UnsavedAssets>,
 ) {
@@ -853,9 +1260,13 @@ fn remove_walls(
         }
     };
     let height = event.tier as f32 + TIER_OFFSET;
-    let removed = precinct.remove_scenery_elements(|_, _, pos| {
+    // Items in locked layers are left alone.
+    let locked = locked_layers(precinct);
+    let removed = precinct.remove_scenery_elements(|scenery| {
+        let pos = &scenery.position;
         // TODO: Check for wall type?
-        pos.x >= event.area.min.x
+        !scenery.layer.as_ref().is_some_and(|l| locked.contains(l))
+            && pos.x >= event.area.min.x
             && pos.x <= event.area.max.x
             && pos.y > height - 0.5
             && pos.y < height + 0.5
@@ -873,3 +1284,65 @@ fn remove_walls(
         .precincts
         .insert(event.precinct.clone(), unsaved::ModifiedState::Unsaved);
 }
+
+fn place_fixture(
+    trigger: Trigger<PlaceFixture>,
+    mut r_precinct_assets: ResMut<Assets<PrecinctAsset>>,
+    r_server: Res<AssetServer>,
+    r_selected_layer: Res<SelectedLayer>,
+    mut r_undo_stack: ResMut<UndoStack>,
+    mut r_unsaved: ResMut<unsaved::UnsavedAssets>,
+) {
+    let event = trigger.event();
+    let precinct = match r_precinct_assets.get_mut(event.precinct.id()) {
+        Some(precinct) => precinct,
+        None => {
+            r_precinct_assets.insert(event.precinct.id(), PrecinctAsset::default());
+            r_precinct_assets.get_mut(event.precinct.id()).unwrap()
+        }
+    };
+    let layer = r_selected_layer.layer_for(precinct);
+    if precinct.is_layer_locked(layer.as_deref()) {
+        return;
+    }
+    let Some(exemplar_path) = r_server.get_path(event.exemplar).map(|p| p.to_string()) else {
+        return;
+    };
+    let archetype_id = match precinct.scenery_type_index(&exemplar_path) {
+        Some(id) => id,
+        None => precinct.add_scenery_type(exemplar_path),
+    };
+    let iid = precinct.add_scenery_element(
+        archetype_id,
+        event.facing,
+        event.position,
+        None,
+        layer.clone(),
+    );
+    r_undo_stack.push(UndoPlaceWalls {
+        label: "Place Fixture",
+        precinct: event.precinct.clone(),
+        added: vec![SceneryInstanceData {
+            iid,
+            id: archetype_id,
+            facing: event.facing,
+            position: event.position,
+            aspects: Default::default(),
+            layer,
+        }],
+        removed: Vec::new(),
+    });
+    r_unsaved
+        .precincts
+        .insert(event.precinct.clone(), unsaved::ModifiedState::Unsaved);
+}
+
+/// Names of the layers in the precinct which can't be edited.
+fn locked_layers(precinct: &PrecinctAsset) -> HashSet<String> {
+    precinct
+        .layers
+        .iter()
+        .filter(|(_, data)| data.locked)
+        .map(|(name, _)| name.clone())
+        .collect()
+}
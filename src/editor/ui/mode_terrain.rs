@@ -1,13 +1,23 @@
 use crate::{
-    editor::{events::ModifyTerrainMapEvent, EditorMode, SelectedParcel},
-    terrain::{Parcel, ShapeRef},
+    editor::{
+        events::ModifyTerrainMapEvent,
+        terrain::{update_contour_edges, update_terrain_seams, ContourEdgeTable, TerrainSeams},
+        EditorMode, SelectedParcel,
+    },
+    terrain::{
+        contour_brush::ContourBrush, terrain_autotile::TileIntent, Parcel, ShapeRef,
+        DEFAULT_WATER_LEVEL,
+    },
 };
 use bevy::{prelude::*, ui};
 use bevy_mod_preferences::{PreferencesGroup, PreferencesKey};
 use bevy_quill::prelude::*;
-use bevy_quill_obsidian::{prelude::*, size::Size, RoundedCorners};
+use bevy_quill_obsidian::{colors, prelude::*, size::Size, RoundedCorners};
 
-use super::{controls::ContourChooser, tool_terrain_edit};
+use super::{
+    controls::{BiomeChooser, ContourChooser},
+    tool_terrain_edit,
+};
 
 #[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Reflect)]
 #[reflect(Default, @PreferencesGroup("editor"), @PreferencesKey("terrain_tool"))]
@@ -19,12 +29,106 @@ pub(crate) enum TerrainTool {
     LowerRect,
     FlattenDraw,
     FlattenRect,
+    SmoothDraw,
     DrawTrees,
     DrawShrubs,
     DrawHerbs,
     EraseFlora,
+    DrawWater,
+    EraseWater,
+    PaintBiome,
+    FillBiome,
+    AutoTile,
+    WaterLevel,
+    WaterFlow,
 }
 
+/// Index of the biome to paint with the biome brush.
+#[derive(Resource, Default, Reflect)]
+#[reflect(@PreferencesGroup("editor"), @PreferencesKey("selected_biome"))]
+pub(crate) struct SelectedBiome(pub u8);
+
+/// Radius of the biome brush, in parcels.
+#[derive(Resource, Reflect)]
+#[reflect(@PreferencesGroup("editor"), @PreferencesKey("biome_brush_radius"))]
+pub(crate) struct BiomeBrushRadius(pub i32);
+
+impl Default for BiomeBrushRadius {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+const MAX_BIOME_BRUSH_RADIUS: i32 = 8;
+
+/// Size and softness of the brush used to sculpt terrain contours.
+#[derive(Resource, Reflect)]
+#[reflect(@PreferencesGroup("editor"), @PreferencesKey("sculpt_brush"))]
+pub(crate) struct SculptBrushSettings {
+    /// Radius of the brush, in terrain units.
+    pub(crate) radius: i32,
+    /// Percentage of the radius over which the brush fades out.
+    pub(crate) falloff: i32,
+}
+
+impl Default for SculptBrushSettings {
+    fn default() -> Self {
+        Self {
+            radius: 0,
+            falloff: 50,
+        }
+    }
+}
+
+impl SculptBrushSettings {
+    pub(crate) fn brush(&self) -> ContourBrush {
+        ContourBrush {
+            radius: self.radius as f32,
+            falloff: self.falloff as f32 * 0.01,
+        }
+    }
+}
+
+const MAX_SCULPT_BRUSH_RADIUS: i32 = 8;
+const FALLOFF_STEP: i32 = 25;
+
+/// Settings for the brushes which paint water level and flow.
+#[derive(Resource, Reflect)]
+#[reflect(@PreferencesGroup("editor"), @PreferencesKey("water_brush"))]
+pub(crate) struct WaterBrushSettings {
+    /// Radius of the brush, in parcels.
+    pub(crate) radius: i32,
+    /// Water level painted by the level brush.
+    pub(crate) level: f32,
+    /// Speed of the current painted by the flow brush, in meters per second.
+    pub(crate) speed: f32,
+}
+
+impl Default for WaterBrushSettings {
+    fn default() -> Self {
+        Self {
+            radius: 0,
+            level: DEFAULT_WATER_LEVEL,
+            speed: 1.,
+        }
+    }
+}
+
+const MAX_WATER_BRUSH_RADIUS: i32 = 8;
+const WATER_LEVEL_STEP: f32 = 0.25;
+const WATER_SPEED_STEP: f32 = 0.5;
+const MAX_WATER_SPEED: f32 = 8.;
+
+/// What the auto-tiling brush paints.
+#[derive(Resource, Default, Reflect)]
+#[reflect(@PreferencesGroup("editor"), @PreferencesKey("auto_tile_intent"))]
+pub(crate) struct AutoTileIntent(pub TileIntent);
+
+/// Whether the contour chooser should only list shapes which fit the selected parcel.
+#[derive(Resource, Default, Reflect)]
+#[reflect(@PreferencesGroup("editor"), @PreferencesKey("compatible_contours_only"))]
+pub(crate) struct CompatibleContoursOnly(pub bool);
+
 pub(crate) struct EditTerrainPlugin;
 
 impl Plugin for EditTerrainPlugin {
@@ -33,11 +137,39 @@ impl Plugin for EditTerrainPlugin {
             .enable_state_scoped_entities::<TerrainTool>()
             .register_type::<State<TerrainTool>>()
             .register_type::<NextState<TerrainTool>>()
+            .init_resource::<SelectedBiome>()
+            .init_resource::<BiomeBrushRadius>()
+            .init_resource::<tool_terrain_edit::BiomeBrushState>()
+            .init_resource::<tool_terrain_edit::AutoTileStrokeState>()
+            .init_resource::<tool_terrain_edit::WaterBrushState>()
+            .init_resource::<WaterBrushSettings>()
+            .init_resource::<AutoTileIntent>()
+            .init_resource::<SculptBrushSettings>()
+            .init_resource::<CompatibleContoursOnly>()
+            .init_resource::<ContourEdgeTable>()
+            .init_resource::<TerrainSeams>()
+            .register_type::<SelectedBiome>()
+            .register_type::<BiomeBrushRadius>()
+            .register_type::<TileIntent>()
+            .register_type::<AutoTileIntent>()
+            .register_type::<SculptBrushSettings>()
+            .register_type::<WaterBrushSettings>()
+            .register_type::<CompatibleContoursOnly>()
             .add_systems(OnEnter(EditorMode::Terrain), tool_terrain_edit::enter)
             .add_systems(OnExit(EditorMode::Terrain), tool_terrain_edit::exit)
             .add_systems(
                 Update,
-                tool_terrain_edit::hover.run_if(in_state(EditorMode::Terrain)),
+                (
+                    (
+                        tool_terrain_edit::hover,
+                        tool_terrain_edit::hover_biome,
+                        tool_terrain_edit::hover_autotile,
+                        tool_terrain_edit::hover_water,
+                        update_terrain_seams.after(update_contour_edges),
+                    )
+                        .run_if(in_state(EditorMode::Terrain)),
+                    update_contour_edges,
+                ),
             );
     }
 }
@@ -194,15 +326,362 @@ impl ViewTemplate for EditModeTerrainControls {
                         },
                     )),
             )),
-            ContourChooser::new().style(|sb: &mut StyleBuilder| {
-                sb.grid_row_span(3);
-            }),
-            ListView::new(),
+            ContourControls,
+            SculptControls,
+            AutoTileControls,
+            WaterControls,
+            BiomeControls,
             ListView::new(),
         ))
     }
 }
 
+#[derive(Clone, PartialEq)]
+struct ContourControls;
+
+impl ViewTemplate for ContourControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let compatible_only = cx.use_resource::<CompatibleContoursOnly>().0;
+        let seam_count = cx.use_resource::<TerrainSeams>().seams.len();
+        let on_toggle = cx.create_callback(
+            |checked: In<bool>, mut compatible: ResMut<CompatibleContoursOnly>| {
+                compatible.0 = *checked;
+            },
+        );
+
+        Element::<NodeBundle>::new()
+            .style(style_contour_controls)
+            .children((
+                Element::<NodeBundle>::new()
+                    .style(style_contour_toolbar)
+                    .children((
+                        Checkbox::new()
+                            .label("Compatible Only")
+                            .checked(compatible_only)
+                            .on_change(on_toggle),
+                        Spacer,
+                        Cond::new(
+                            seam_count > 0,
+                            Element::<NodeBundle>::new()
+                                .style(style_seam_warning)
+                                .children(format!("Seams: {}", seam_count)),
+                            (),
+                        ),
+                    )),
+                ContourChooser::new()
+                    .compatible_only(compatible_only)
+                    .style(|sb: &mut StyleBuilder| {
+                        sb.flex_grow(1.);
+                    }),
+            ))
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct SculptControls;
+
+impl ViewTemplate for SculptControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let st = *cx.use_resource::<State<TerrainTool>>().get();
+        let radius = cx.use_resource::<SculptBrushSettings>().radius;
+        let falloff = cx.use_resource::<SculptBrushSettings>().falloff;
+        let tool_button = |cx: &mut Cx, label: &'static str, tool: TerrainTool| {
+            Button::new()
+                .children(label)
+                .variant(if st == tool {
+                    ButtonVariant::Selected
+                } else {
+                    ButtonVariant::Default
+                })
+                .on_click(
+                    cx.create_callback(move |mut mode: ResMut<NextState<TerrainTool>>| {
+                        mode.set(tool);
+                    }),
+                )
+        };
+        let change_radius = |cx: &mut Cx, delta: i32| {
+            cx.create_callback(move |mut settings: ResMut<SculptBrushSettings>| {
+                settings.radius = (settings.radius + delta).clamp(0, MAX_SCULPT_BRUSH_RADIUS);
+            })
+        };
+        let change_falloff = |cx: &mut Cx, delta: i32| {
+            cx.create_callback(move |mut settings: ResMut<SculptBrushSettings>| {
+                settings.falloff = (settings.falloff + delta).clamp(0, 100);
+            })
+        };
+
+        Element::<NodeBundle>::new()
+            .style(style_biome_controls)
+            .children((
+                Element::<NodeBundle>::new()
+                    .style(style_biome_toolbar)
+                    .children((
+                        tool_button(cx, "Smooth", TerrainTool::SmoothDraw),
+                        Spacer,
+                        tool_button(cx, "Water", TerrainTool::DrawWater),
+                        tool_button(cx, "Dry", TerrainTool::EraseWater),
+                    )),
+                Element::<NodeBundle>::new()
+                    .style(style_biome_toolbar)
+                    .children((
+                        Button::new()
+                            .children("-")
+                            .disabled(radius <= 0)
+                            .on_click(change_radius(cx, -1)),
+                        format!("Radius: {}", radius),
+                        Button::new()
+                            .children("+")
+                            .disabled(radius >= MAX_SCULPT_BRUSH_RADIUS)
+                            .on_click(change_radius(cx, 1)),
+                        Spacer,
+                        Button::new()
+                            .children("-")
+                            .disabled(falloff <= 0)
+                            .on_click(change_falloff(cx, -FALLOFF_STEP)),
+                        format!("Falloff: {}%", falloff),
+                        Button::new()
+                            .children("+")
+                            .disabled(falloff >= 100)
+                            .on_click(change_falloff(cx, FALLOFF_STEP)),
+                    )),
+            ))
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct AutoTileControls;
+
+impl ViewTemplate for AutoTileControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let st = *cx.use_resource::<State<TerrainTool>>().get();
+        let intent = cx.use_resource::<AutoTileIntent>().0;
+        let intent_button = |cx: &mut Cx, label: &'static str, value: TileIntent| {
+            Button::new()
+                .children(label)
+                .variant(if st == TerrainTool::AutoTile && intent == value {
+                    ButtonVariant::Selected
+                } else {
+                    ButtonVariant::Default
+                })
+                .style(style_grow)
+                .on_click(cx.create_callback(
+                    move |mut mode: ResMut<NextState<TerrainTool>>,
+                          mut intent: ResMut<AutoTileIntent>| {
+                        mode.set(TerrainTool::AutoTile);
+                        intent.0 = value;
+                    },
+                ))
+        };
+
+        Element::<NodeBundle>::new()
+            .style(style_biome_controls)
+            .children((
+                "Auto-Tile",
+                Element::<NodeBundle>::new()
+                    .style(style_biome_toolbar)
+                    .children((
+                        intent_button(cx, "Raise", TileIntent::Raise),
+                        intent_button(cx, "Lower", TileIntent::Lower),
+                        intent_button(cx, "Flat", TileIntent::Flat),
+                        intent_button(cx, "Water", TileIntent::Water),
+                    )),
+            ))
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct WaterControls;
+
+impl ViewTemplate for WaterControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let st = *cx.use_resource::<State<TerrainTool>>().get();
+        let settings = cx.use_resource::<WaterBrushSettings>();
+        let (radius, level, speed) = (settings.radius, settings.level, settings.speed);
+        let tool_button = |cx: &mut Cx, label: &'static str, tool: TerrainTool| {
+            Button::new()
+                .children(label)
+                .variant(if st == tool {
+                    ButtonVariant::Selected
+                } else {
+                    ButtonVariant::Default
+                })
+                .style(style_grow)
+                .on_click(
+                    cx.create_callback(move |mut mode: ResMut<NextState<TerrainTool>>| {
+                        mode.set(tool);
+                    }),
+                )
+        };
+        let change_radius = |cx: &mut Cx, delta: i32| {
+            cx.create_callback(move |mut settings: ResMut<WaterBrushSettings>| {
+                settings.radius = (settings.radius + delta).clamp(0, MAX_WATER_BRUSH_RADIUS);
+            })
+        };
+        let change_level = |cx: &mut Cx, delta: f32| {
+            cx.create_callback(move |mut settings: ResMut<WaterBrushSettings>| {
+                settings.level += delta;
+            })
+        };
+        let change_speed = |cx: &mut Cx, delta: f32| {
+            cx.create_callback(move |mut settings: ResMut<WaterBrushSettings>| {
+                settings.speed = (settings.speed + delta).clamp(0., MAX_WATER_SPEED);
+            })
+        };
+
+        Element::<NodeBundle>::new()
+            .style(style_biome_controls)
+            .children((
+                Element::<NodeBundle>::new()
+                    .style(style_biome_toolbar)
+                    .children((
+                        "Water",
+                        tool_button(cx, "Level", TerrainTool::WaterLevel),
+                        tool_button(cx, "Flow", TerrainTool::WaterFlow),
+                        Button::new()
+                            .children("-")
+                            .disabled(radius <= 0)
+                            .on_click(change_radius(cx, -1)),
+                        format!("Radius: {}", radius),
+                        Button::new()
+                            .children("+")
+                            .disabled(radius >= MAX_WATER_BRUSH_RADIUS)
+                            .on_click(change_radius(cx, 1)),
+                    )),
+                Element::<NodeBundle>::new()
+                    .style(style_biome_toolbar)
+                    .children((
+                        Button::new()
+                            .children("-")
+                            .on_click(change_level(cx, -WATER_LEVEL_STEP)),
+                        format!("Level: {:.2}", level),
+                        Button::new()
+                            .children("+")
+                            .on_click(change_level(cx, WATER_LEVEL_STEP)),
+                        Spacer,
+                        Button::new()
+                            .children("-")
+                            .disabled(speed <= 0.)
+                            .on_click(change_speed(cx, -WATER_SPEED_STEP)),
+                        format!("Speed: {:.1}", speed),
+                        Button::new()
+                            .children("+")
+                            .disabled(speed >= MAX_WATER_SPEED)
+                            .on_click(change_speed(cx, WATER_SPEED_STEP)),
+                    )),
+            ))
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct BiomeControls;
+
+impl ViewTemplate for BiomeControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let st = *cx.use_resource::<State<TerrainTool>>().get();
+        let selected_biome = cx.use_resource::<SelectedBiome>().0;
+        let radius = cx.use_resource::<BiomeBrushRadius>().0;
+
+        Element::<NodeBundle>::new()
+            .style(style_biome_controls)
+            .children((
+                Element::<NodeBundle>::new()
+                    .style(style_biome_toolbar)
+                    .children((
+                        ToolPalette::new().columns(2).size(Size::Xl).children((
+                            ToolIconButton::new("editor/icons/pencil.png")
+                                .size(Vec2::new(32., 24.))
+                                .tint(false)
+                                .corners(RoundedCorners::Left)
+                                .selected(st == TerrainTool::PaintBiome)
+                                .on_click(cx.create_callback(
+                                    |mut mode: ResMut<NextState<TerrainTool>>| {
+                                        mode.set(TerrainTool::PaintBiome);
+                                    },
+                                )),
+                            ToolIconButton::new("editor/icons/terrain.png")
+                                .size(Vec2::new(32., 24.))
+                                .tint(false)
+                                .corners(RoundedCorners::Right)
+                                .selected(st == TerrainTool::FillBiome)
+                                .on_click(cx.create_callback(
+                                    |mut mode: ResMut<NextState<TerrainTool>>| {
+                                        mode.set(TerrainTool::FillBiome);
+                                    },
+                                )),
+                        )),
+                        Spacer,
+                        Button::new().children("-").disabled(radius <= 0).on_click(
+                            cx.create_callback(|mut radius: ResMut<BiomeBrushRadius>| {
+                                radius.0 = (radius.0 - 1).max(0);
+                            }),
+                        ),
+                        format!("Radius: {}", radius),
+                        Button::new()
+                            .children("+")
+                            .disabled(radius >= MAX_BIOME_BRUSH_RADIUS)
+                            .on_click(cx.create_callback(
+                                |mut radius: ResMut<BiomeBrushRadius>| {
+                                    radius.0 = (radius.0 + 1).min(MAX_BIOME_BRUSH_RADIUS);
+                                },
+                            )),
+                    )),
+                BiomeChooser {
+                    selected: selected_biome,
+                    on_change: cx.create_callback(
+                        |biome: In<u8>, mut selected: ResMut<SelectedBiome>| {
+                            selected.0 = *biome;
+                        },
+                    ),
+                },
+            ))
+    }
+}
+
+fn style_contour_controls(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .flex_direction(ui::FlexDirection::Column)
+        .grid_row_span(6)
+        .gap(4);
+}
+
+fn style_contour_toolbar(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .flex_direction(ui::FlexDirection::Row)
+        .align_items(ui::AlignItems::Center)
+        .gap(4);
+}
+
+fn style_seam_warning(ss: &mut StyleBuilder) {
+    ss.color(colors::X_RED);
+}
+
+fn style_grow(ss: &mut StyleBuilder) {
+    ss.flex_grow(1.);
+}
+
+fn style_biome_controls(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .flex_direction(ui::FlexDirection::Column)
+        .gap(4);
+}
+
+fn style_biome_toolbar(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .flex_direction(ui::FlexDirection::Row)
+        .align_items(ui::AlignItems::Center)
+        .gap(4);
+}
+
 fn style_panel(ss: &mut StyleBuilder) {
     ss.display(ui::Display::Grid)
         .grid_template_columns(vec![
@@ -210,7 +689,7 @@ fn style_panel(ss: &mut StyleBuilder) {
             ui::RepeatedGridTrack::flex(1, 1.),
         ])
         .grid_template_rows(vec![
-            ui::RepeatedGridTrack::auto(1),
+            ui::RepeatedGridTrack::auto(4),
             ui::RepeatedGridTrack::flex(1, 1.),
             ui::RepeatedGridTrack::flex(1, 1.),
         ])
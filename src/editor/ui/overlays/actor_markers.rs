@@ -0,0 +1,105 @@
+use bevy::{
+    color::{palettes, Alpha},
+    prelude::*,
+    render::view::RenderLayers,
+};
+use bevy_quill::prelude::*;
+use bevy_quill_overlays::{Overlay, PolygonOptions, ShapeOrientation};
+
+use crate::{
+    editor::{scenery::SelectedActor, ui::mode_scenery::ActorPlaceState},
+    view::Viewpoint,
+    world::Realm,
+};
+
+/// Draws a marker for each actor in the viewed realm: an arrow pointing the way the actor
+/// faces. The selected actor is highlighted, and follows the cursor while being dragged.
+#[derive(Clone, PartialEq)]
+pub struct ActorMarkersOverlay;
+
+impl ViewTemplate for ActorMarkersOverlay {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let state = cx.use_resource::<ActorPlaceState>();
+        let selected = cx.use_resource::<SelectedActor>().0.clone();
+        let realm = cx.use_resource::<Viewpoint>().realm;
+        let layer = match realm.and_then(|realm| cx.use_component::<Realm>(realm)) {
+            Some(realm) => realm.layer.clone(),
+            None => RenderLayers::none(),
+        };
+
+        let mut markers: Vec<(Vec3, f32)> = Vec::new();
+        let mut highlight: Option<(Vec3, f32)> = None;
+        for marker in state.markers.iter() {
+            if selected.as_ref() == Some(&marker.actor) {
+                let position = match state.cursor_pos {
+                    Some(pos) if state.dragging => pos,
+                    _ => marker.position,
+                };
+                highlight = Some((position, marker.facing));
+            } else {
+                markers.push((marker.position, marker.facing));
+            }
+        }
+
+        (
+            Overlay::new()
+                .named("ActorMarkersOverlay")
+                .shape_dyn(
+                    |markers, sb| {
+                        sb.with_orientation(ShapeOrientation::YPositive)
+                            .with_stroke_width(0.05);
+                        for (position, facing) in markers.iter() {
+                            sb.stroke_polygon_3d(
+                                &marker_outline(*position, *facing),
+                                PolygonOptions {
+                                    closed: true,
+                                    ..default()
+                                },
+                            );
+                        }
+                    },
+                    markers,
+                )
+                .color(palettes::css::LIGHT_SKY_BLUE.with_alpha(0.8))
+                .underlay(0.8)
+                .insert_dyn(|layer| layer, layer.clone()),
+            Overlay::new()
+                .named("SelectedActorOverlay")
+                .shape_dyn(
+                    |highlight, sb| {
+                        sb.with_orientation(ShapeOrientation::YPositive)
+                            .with_stroke_width(0.08);
+                        if let Some((position, facing)) = highlight {
+                            sb.stroke_polygon_3d(
+                                &marker_outline(position, facing),
+                                PolygonOptions {
+                                    closed: true,
+                                    ..default()
+                                },
+                            );
+                        }
+                    },
+                    highlight,
+                )
+                .color(palettes::css::GOLD.with_alpha(0.9))
+                .underlay(0.8)
+                .insert_dyn(|layer| layer, layer),
+        )
+    }
+}
+
+/// Arrow-shaped outline of an actor standing at `position`, facing `facing` degrees.
+fn marker_outline(position: Vec3, facing: f32) -> Vec<Vec3> {
+    let rotation = Quat::from_rotation_y(facing.to_radians());
+    [
+        Vec3::new(0., 0., -0.5),
+        Vec3::new(0.35, 0., 0.35),
+        Vec3::new(0., 0., 0.15),
+        Vec3::new(-0.35, 0., 0.35),
+    ]
+    .iter()
+    .map(|v| position + rotation * *v + Vec3::Y * 0.02)
+    .collect()
+}
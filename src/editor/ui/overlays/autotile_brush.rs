@@ -0,0 +1,56 @@
+use bevy::{
+    color::{palettes, Alpha},
+    math::{IVec2, Rect, Vec2},
+    prelude::*,
+    render::view::RenderLayers,
+};
+use bevy_quill::{Cx, View, ViewTemplate};
+use bevy_quill_overlays::{Overlay, ShapeOrientation};
+
+use crate::{
+    editor::ui::tool_terrain_edit::AutoTileStrokeState, terrain::PARCEL_SIZE_F, view::Viewpoint,
+    world::Realm,
+};
+
+/// Outlines the parcel under the cursor and the parcels painted by the auto-tiling brush.
+#[derive(Clone, PartialEq)]
+pub struct AutoTileBrushOverlay;
+
+impl ViewTemplate for AutoTileBrushOverlay {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let stroke = cx.use_resource::<AutoTileStrokeState>();
+        let mut parcels: Vec<IVec2> = stroke.parcels.clone();
+        if let Some(cursor) = stroke.cursor {
+            if !parcels.contains(&cursor) {
+                parcels.push(cursor);
+            }
+        }
+        let realm = cx.use_resource::<Viewpoint>().realm;
+        let layer = match realm.and_then(|r| cx.use_component::<Realm>(r)) {
+            Some(realm) => realm.layer.clone(),
+            None => RenderLayers::none(),
+        };
+
+        Overlay::new()
+            .named("AutoTileBrushOverlay")
+            .shape_dyn(
+                |parcels, sb| {
+                    sb.with_orientation(ShapeOrientation::YPositive)
+                        .with_stroke_width(0.3);
+                    for pt in parcels.iter() {
+                        let min = pt.as_vec2() * PARCEL_SIZE_F;
+                        sb.stroke_rect(
+                            Rect::from_corners(min, min + Vec2::splat(PARCEL_SIZE_F)).inflate(-0.5),
+                        );
+                    }
+                },
+                parcels,
+            )
+            .color(palettes::css::AQUA.with_alpha(0.9))
+            .underlay(0.8)
+            .insert(Transform::from_xyz(0., 0.02, 0.))
+            .insert_dyn(|layer| layer, layer)
+    }
+}
@@ -0,0 +1,94 @@
+use bevy::{
+    color::{palettes, Alpha},
+    math::{IVec2, Rect, Vec2},
+    prelude::*,
+    render::view::RenderLayers,
+};
+use bevy_quill::{Cond, Cx, View, ViewTemplate};
+use bevy_quill_overlays::{Overlay, PolygonOptions, ShapeOrientation};
+
+use crate::{
+    editor::ui::{
+        mode_terrain::{BiomeBrushRadius, TerrainTool},
+        tool_terrain_edit::BiomeBrushState,
+    },
+    terrain::PARCEL_SIZE_F,
+    view::Viewpoint,
+    world::Realm,
+};
+
+/// Number of segments used to draw the brush outline.
+const BRUSH_SEGMENTS: usize = 32;
+
+#[derive(Clone, PartialEq)]
+pub struct BiomeBrushOverlay;
+
+impl ViewTemplate for BiomeBrushOverlay {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let cursor = cx.use_resource::<BiomeBrushState>().cursor;
+        let tool = *cx.use_resource::<State<TerrainTool>>().get();
+        let radius = cx.use_resource::<BiomeBrushRadius>().0;
+        let radius = match tool {
+            TerrainTool::PaintBiome => radius,
+            _ => 0,
+        };
+        Cond::new(
+            cursor.is_some(),
+            cursor.map(|cursor| BiomeBrushCursor { cursor, radius }),
+            (),
+        )
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct BiomeBrushCursor {
+    pub cursor: IVec2,
+    pub radius: i32,
+}
+
+impl ViewTemplate for BiomeBrushCursor {
+    type View = impl View;
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let viewpoint = cx.use_resource::<Viewpoint>();
+        let realm = viewpoint.realm.and_then(|r| cx.use_component::<Realm>(r));
+        let layer = match realm {
+            Some(realm) => realm.layer.clone(),
+            None => RenderLayers::none(),
+        };
+
+        Overlay::new()
+            .named("BiomeBrushOverlay")
+            .shape_dyn(
+                |(cursor, radius), sb| {
+                    let center = cursor.as_vec2() * PARCEL_SIZE_F;
+                    sb.with_orientation(ShapeOrientation::YPositive)
+                        .with_stroke_width(0.3)
+                        .stroke_rect(Rect::from_center_size(center, Vec2::splat(1.0)));
+                    if radius > 0 {
+                        let r = (radius as f32 + 0.5) * PARCEL_SIZE_F;
+                        let outline: Vec<Vec2> = (0..BRUSH_SEGMENTS)
+                            .map(|i| {
+                                let angle =
+                                    i as f32 * std::f32::consts::TAU / BRUSH_SEGMENTS as f32;
+                                center + Vec2::new(angle.cos(), angle.sin()) * r
+                            })
+                            .collect();
+                        sb.stroke_polygon(
+                            &outline,
+                            PolygonOptions {
+                                closed: true,
+                                ..default()
+                            },
+                        );
+                    }
+                },
+                (self.cursor, self.radius),
+            )
+            .color(palettes::css::ORANGE.with_alpha(0.9))
+            .underlay(0.8)
+            .insert(Transform::from_xyz(0., 0.02, 0.))
+            .insert_dyn(|layer| layer, layer)
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::{
+    color::{palettes, Alpha},
+    prelude::*,
+    render::view::RenderLayers,
+};
+use bevy_quill::prelude::*;
+use bevy_quill_overlays::{Overlay, ShapeOrientation};
+
+use crate::{
+    editor::ui::mode_scenery::{CutawayEditState, SelectedTier},
+    view::Viewpoint,
+    world::Realm,
+};
+
+/// Outlines the cutaway rectangles of the selected tier, along with the one being drawn.
+/// The selected rectangle is highlighted.
+#[derive(Clone, PartialEq)]
+pub struct CutawaysOverlay;
+
+impl ViewTemplate for CutawaysOverlay {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let state = cx.use_resource::<CutawayEditState>();
+        let rects = state.rects.clone();
+        let selected = state.selected.and_then(|index| rects.get(index).copied());
+        let marquee = state.marquee();
+        let origin = state.origin;
+        let realm = cx.use_resource::<Viewpoint>().realm;
+        let layer = match realm.and_then(|realm| cx.use_component::<Realm>(realm)) {
+            Some(realm) => realm.layer.clone(),
+            None => RenderLayers::none(),
+        };
+        let tier = cx.use_resource::<SelectedTier>().0;
+
+        let transform =
+            Transform::from_translation(Vec3::new(origin.x, tier as f32 + 0.013, origin.y));
+        (
+            Overlay::new()
+                .named("CutawaysOverlay")
+                .shape_dyn(
+                    |rects, sb| {
+                        sb.with_orientation(ShapeOrientation::YPositive)
+                            .with_stroke_width(0.08);
+                        for rect in rects.iter() {
+                            sb.stroke_rect(*rect);
+                        }
+                    },
+                    rects,
+                )
+                .color(palettes::css::AQUA.with_alpha(0.8))
+                .underlay(0.8)
+                .transform(transform)
+                .insert_dyn(|layer| layer, layer.clone()),
+            Overlay::new()
+                .named("CutawaysHighlightOverlay")
+                .shape_dyn(
+                    |rects, sb| {
+                        sb.with_orientation(ShapeOrientation::YPositive)
+                            .with_stroke_width(0.12);
+                        for rect in rects.iter().flatten() {
+                            sb.stroke_rect(*rect);
+                        }
+                    },
+                    [selected, marquee],
+                )
+                .color(palettes::css::GOLD.with_alpha(0.9))
+                .underlay(0.8)
+                .transform(transform)
+                .insert_dyn(|layer| layer, layer),
+        )
+    }
+}
@@ -0,0 +1,124 @@
+use bevy::{
+    color::{palettes, Alpha},
+    prelude::*,
+    render::view::RenderLayers,
+};
+use bevy_quill::prelude::*;
+use bevy_quill_overlays::{Overlay, PolygonOptions, ShapeOrientation};
+
+use crate::{
+    editor::{
+        lib::floor_ops::{region_ring, FloorPart},
+        ui::mode_scenery::{FloorEditState, SelectedTier},
+    },
+    view::Viewpoint,
+    world::Realm,
+};
+
+/// Outlines the floors of the selected tier, with handles on the vertices. The part of a
+/// floor under the cursor, or being dragged, is highlighted; it turns red if the edit would
+/// not be allowed.
+#[derive(Clone, PartialEq)]
+pub struct FloorEditOverlay;
+
+impl ViewTemplate for FloorEditOverlay {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let state = cx.use_resource::<FloorEditState>();
+        let floors = state.floors.clone();
+        let part = state.part;
+        let valid = state.valid;
+        let origin = state.origin;
+        let realm = cx.use_resource::<Viewpoint>().realm;
+        let layer = match realm.and_then(|realm| cx.use_component::<Realm>(realm)) {
+            Some(realm) => realm.layer.clone(),
+            None => RenderLayers::none(),
+        };
+        let tier = cx.use_resource::<SelectedTier>().0;
+
+        let transform =
+            Transform::from_translation(Vec3::new(origin.x, tier as f32 + 0.013, origin.y));
+        let highlight = if valid {
+            palettes::css::GOLD.with_alpha(0.9)
+        } else {
+            palettes::css::RED.with_alpha(0.9)
+        };
+        (
+            Overlay::new()
+                .named("FloorEditOverlay")
+                .shape_dyn(
+                    |floors, sb| {
+                        sb.with_orientation(ShapeOrientation::YPositive)
+                            .with_stroke_width(0.05);
+                        for region in floors.iter() {
+                            for ring in 0..=region.holes.len() {
+                                let ring = region_ring(region, ring);
+                                sb.stroke_polygon(
+                                    ring,
+                                    PolygonOptions {
+                                        closed: true,
+                                        ..default()
+                                    },
+                                );
+                                for v in ring.iter() {
+                                    sb.stroke_rect(Rect::from_center_size(*v, Vec2::splat(0.3)));
+                                }
+                            }
+                        }
+                    },
+                    floors.clone(),
+                )
+                .color(palettes::css::SILVER.with_alpha(0.8))
+                .underlay(0.8)
+                .transform(transform)
+                .insert_dyn(|layer| layer, layer.clone()),
+            Overlay::new()
+                .named("FloorEditHighlightOverlay")
+                .shape_dyn(
+                    |(floors, part), sb| {
+                        sb.with_orientation(ShapeOrientation::YPositive)
+                            .with_stroke_width(0.1);
+                        let Some(part) = part else {
+                            return;
+                        };
+                        let Some(region) = floors.get(part.region()) else {
+                            return;
+                        };
+                        match part {
+                            FloorPart::Vertex { ring, index, .. } => {
+                                let v = region_ring(region, ring)[index];
+                                sb.stroke_rect(Rect::from_center_size(v, Vec2::splat(0.4)));
+                            }
+                            FloorPart::Edge { ring, index, .. } => {
+                                let ring = region_ring(region, ring);
+                                sb.stroke_polygon(
+                                    &[ring[index], ring[(index + 1) % ring.len()]],
+                                    PolygonOptions {
+                                        closed: false,
+                                        ..default()
+                                    },
+                                );
+                            }
+                            FloorPart::Region(_) => {
+                                for ring in 0..=region.holes.len() {
+                                    sb.stroke_polygon(
+                                        region_ring(region, ring),
+                                        PolygonOptions {
+                                            closed: true,
+                                            ..default()
+                                        },
+                                    );
+                                }
+                            }
+                        }
+                    },
+                    (floors, part),
+                )
+                .color(highlight)
+                .underlay(0.8)
+                .transform(transform)
+                .insert_dyn(|layer| layer, layer),
+        )
+    }
+}
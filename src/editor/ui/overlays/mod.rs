@@ -1,13 +1,31 @@
+mod actor_markers;
+mod autotile_brush;
+mod biome_brush;
+mod cutaways;
+mod floor_edit;
 mod floor_stamp;
 mod map_bounds;
+mod scenery_selection;
 mod selected_parcel;
 mod selected_precinct;
 mod terrain_cursor;
+mod terrain_fx_brush;
+mod terrain_seams;
 mod wall_draw;
+mod water_brush;
 
+pub use actor_markers::ActorMarkersOverlay;
+pub use autotile_brush::AutoTileBrushOverlay;
+pub use biome_brush::BiomeBrushOverlay;
+pub use cutaways::CutawaysOverlay;
+pub use floor_edit::FloorEditOverlay;
 pub use floor_stamp::FloorStampOverlay;
 pub use map_bounds::MapBoundsOverlay;
+pub use scenery_selection::ScenerySelectionOverlay;
 pub use selected_parcel::SelectedParcelOverlay;
 pub use selected_precinct::SelectedPrecinctOverlay;
 pub use terrain_cursor::TerrainCursorOverlay;
+pub use terrain_fx_brush::TerrainFxBrushOverlay;
+pub use terrain_seams::TerrainSeamsOverlay;
 pub use wall_draw::WallDrawOverlay;
+pub use water_brush::WaterBrushOverlay;
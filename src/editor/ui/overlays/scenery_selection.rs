@@ -0,0 +1,54 @@
+use bevy::{
+    color::{palettes, Alpha},
+    prelude::*,
+    render::view::RenderLayers,
+};
+use bevy_quill::prelude::*;
+use bevy_quill_overlays::{Overlay, ShapeOrientation};
+
+use crate::{
+    editor::ui::mode_scenery::{ScenerySelectState, SelectedTier},
+    view::Viewpoint,
+    world::Realm,
+};
+
+/// Outlines the selected scenery elements, and the selection rectangle while one is drawn.
+#[derive(Clone, PartialEq)]
+pub struct ScenerySelectionOverlay;
+
+impl ViewTemplate for ScenerySelectionOverlay {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let state = cx.use_resource::<ScenerySelectState>();
+        let highlights = state.highlights.clone();
+        let marquee = state.marquee;
+        let realm = cx.use_resource::<Viewpoint>().realm;
+        let layer = match realm.and_then(|realm| cx.use_component::<Realm>(realm)) {
+            Some(realm) => realm.layer.clone(),
+            None => RenderLayers::none(),
+        };
+        let tier = cx.use_resource::<SelectedTier>().0;
+
+        let height = tier as f32 + 0.012;
+        Overlay::new()
+            .named("ScenerySelectionOverlay")
+            .shape_dyn(
+                |(highlights, marquee), sb| {
+                    sb.with_orientation(ShapeOrientation::YPositive)
+                        .with_stroke_width(0.06);
+                    for pos in highlights.iter() {
+                        sb.stroke_rect(Rect::from_center_size(pos.xz(), Vec2::ONE));
+                    }
+                    if let Some(rect) = marquee {
+                        sb.stroke_rect(rect);
+                    }
+                },
+                (highlights, marquee),
+            )
+            .color(palettes::css::GOLD.with_alpha(0.9))
+            .underlay(0.8)
+            .transform(Transform::from_translation(Vec3::new(0., height, 0.)))
+            .insert_dyn(|layer| layer, layer)
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::{
+    color::{palettes, Alpha},
+    prelude::*,
+    render::view::RenderLayers,
+};
+use bevy_quill::prelude::*;
+use bevy_quill_overlays::{Overlay, PolygonOptions, ShapeOrientation};
+
+use crate::{
+    editor::ui::mode_scenery::{TerrainFxBrushRadius, TerrainFxBrushState},
+    view::Viewpoint,
+    world::Realm,
+};
+
+/// Number of segments used to draw the brush outline.
+const BRUSH_SEGMENTS: usize = 32;
+
+/// Outlines the terrain vertices which the terrain effect brush will paint.
+#[derive(Clone, PartialEq)]
+pub struct TerrainFxBrushOverlay;
+
+impl ViewTemplate for TerrainFxBrushOverlay {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let brush = cx.use_resource::<TerrainFxBrushState>();
+        let cursor = brush.cursor;
+        let height = brush.height;
+        let radius = cx.use_resource::<TerrainFxBrushRadius>().0;
+        let realm = cx.use_resource::<Viewpoint>().realm;
+        let layer = match realm.and_then(|realm| cx.use_component::<Realm>(realm)) {
+            Some(realm) => realm.layer.clone(),
+            None => RenderLayers::none(),
+        };
+
+        Overlay::new()
+            .named("TerrainFxBrushOverlay")
+            .shape_dyn(
+                |(cursor, radius), sb| {
+                    let Some(cursor) = cursor else {
+                        return;
+                    };
+                    let center = cursor.as_vec2();
+                    let r = radius as f32 + 0.5;
+                    let outline: Vec<Vec2> = (0..BRUSH_SEGMENTS)
+                        .map(|i| {
+                            let angle = i as f32 * std::f32::consts::TAU / BRUSH_SEGMENTS as f32;
+                            center + Vec2::new(angle.cos(), angle.sin()) * r
+                        })
+                        .collect();
+                    sb.with_orientation(ShapeOrientation::YPositive)
+                        .with_stroke_width(0.08)
+                        .stroke_polygon(
+                            &outline,
+                            PolygonOptions {
+                                closed: true,
+                                ..default()
+                            },
+                        );
+                },
+                (cursor, radius),
+            )
+            .color(palettes::css::ORANGE.with_alpha(0.9))
+            .underlay(0.8)
+            .transform(Transform::from_translation(Vec3::new(
+                0.,
+                height + 0.02,
+                0.,
+            )))
+            .insert_dyn(|layer| layer, layer)
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::{
+    color::{palettes, Alpha},
+    math::Vec3,
+    render::view::RenderLayers,
+};
+use bevy_quill::{Cx, View, ViewTemplate};
+use bevy_quill_overlays::{LinesBuilder, Overlay};
+
+use crate::{
+    editor::terrain::{ContourEdgeTable, TerrainSeams},
+    terrain::{terrain_edges::Side, PARCEL_HEIGHT_SCALE, PARCEL_SIZE_F, PARCEL_SIZE_U},
+    view::Viewpoint,
+    world::Realm,
+};
+
+/// Highlights the seams between parcels whose edges don't match.
+#[derive(Clone, PartialEq)]
+pub struct TerrainSeamsOverlay;
+
+impl ViewTemplate for TerrainSeamsOverlay {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let realm = cx.use_resource::<Viewpoint>().realm;
+        let layer = match realm.and_then(|r| cx.use_component::<Realm>(r)) {
+            Some(realm) => realm.layer.clone(),
+            None => RenderLayers::none(),
+        };
+        let seams = cx.use_resource::<TerrainSeams>();
+        let seams = if seams.realm == realm {
+            seams.seams.clone()
+        } else {
+            Vec::new()
+        };
+        let edges = &cx.use_resource::<ContourEdgeTable>().0;
+
+        // Trace the edge of the parcel on both sides of each seam, so that the gap between
+        // them is visible.
+        let mut lines: Vec<(Vec3, Vec3)> = Vec::new();
+        for seam in seams.iter() {
+            let (Some(near), Some(far)) = (edges.get(seam.shape), edges.get(seam.neighbor)) else {
+                continue;
+            };
+            let origin = (seam.coords + seam.side.offset()).as_vec2() * PARCEL_SIZE_F;
+            let point = |i: usize, height: i8| {
+                let h = height as f32 * PARCEL_HEIGHT_SCALE + 0.05;
+                match seam.side {
+                    Side::East => Vec3::new(origin.x, h, origin.y + i as f32),
+                    _ => Vec3::new(origin.x + i as f32, h, origin.y),
+                }
+            };
+            for profile in [near.edge(seam.side), far.edge(seam.side.opposite())] {
+                for i in 0..PARCEL_SIZE_U {
+                    lines.push((point(i, profile[i]), point(i + 1, profile[i + 1])));
+                }
+            }
+        }
+
+        Overlay::new()
+            .named("TerrainSeamsOverlay")
+            .mesh_dyn(
+                |lines, sb: &mut LinesBuilder| {
+                    for (a, b) in lines.iter() {
+                        sb.line(*a, *b);
+                    }
+                },
+                lines,
+            )
+            .color(palettes::css::RED.with_alpha(0.8))
+            .insert_dyn(|layer| layer, layer)
+    }
+}
@@ -0,0 +1,139 @@
+use bevy::{prelude::*, ui};
+use bevy_quill::prelude::*;
+use bevy_quill_obsidian::prelude::*;
+
+use crate::{
+    editor::scenery::{ActorEdit, EditActor, SelectedActor},
+    scenery::precinct_asset::PrecinctAsset,
+};
+
+/// Panel which shows the selected actor, and lets the user assign or clear its instance id,
+/// or delete it.
+#[derive(Clone, PartialEq)]
+pub(crate) struct ActorControls;
+
+impl ViewTemplate for ActorControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let selected = cx.use_resource::<SelectedActor>().0.clone();
+        let actor = selected.as_ref().and_then(|selected| {
+            cx.use_resource::<Assets<PrecinctAsset>>()
+                .get(&selected.precinct)
+                .and_then(|precinct| precinct.actors.get(selected.index))
+                .cloned()
+        });
+        let summary = match &actor {
+            Some(actor) => format!(
+                "Actor: {} ({})",
+                actor
+                    .exemplar
+                    .path()
+                    .and_then(|path| path.label())
+                    .unwrap_or("unknown"),
+                actor.iid.as_deref().unwrap_or("no id")
+            ),
+            None => "No actor selected".to_string(),
+        };
+        let has_actor = actor.is_some();
+        let has_iid = actor.as_ref().is_some_and(|actor| actor.iid.is_some());
+
+        let on_assign = cx.create_callback(
+            |mut commands: Commands,
+             r_selected: Res<SelectedActor>,
+             r_precincts: Res<Assets<PrecinctAsset>>| {
+                let Some(selected) = r_selected.0.as_ref() else {
+                    return;
+                };
+                let Some(actor) = r_precincts
+                    .get(&selected.precinct)
+                    .and_then(|precinct| precinct.actors.get(selected.index))
+                else {
+                    return;
+                };
+                let stem = actor
+                    .exemplar
+                    .path()
+                    .and_then(|path| path.label().map(|label| label.to_string()))
+                    .unwrap_or_else(|| "actor".to_string());
+                let iid = unique_iid(
+                    &stem,
+                    r_precincts
+                        .iter()
+                        .flat_map(|(_, precinct)| precinct.actors.iter())
+                        .filter_map(|actor| actor.iid.as_deref()),
+                );
+                commands.add(EditActor(ActorEdit::SetIid(Some(iid))));
+            },
+        );
+        let on_clear = cx.create_callback(|mut commands: Commands| {
+            commands.add(EditActor(ActorEdit::SetIid(None)));
+        });
+        let on_delete = cx.create_callback(|mut commands: Commands| {
+            commands.add(EditActor(ActorEdit::Delete));
+        });
+
+        Element::<NodeBundle>::new()
+            .style(style_actor_controls)
+            .children((
+                summary,
+                Element::<NodeBundle>::new()
+                    .style(style_actor_buttons)
+                    .children((
+                        Button::new()
+                            .children("Assign Id")
+                            .disabled(!has_actor || has_iid)
+                            .on_click(on_assign),
+                        Button::new()
+                            .children("Clear Id")
+                            .disabled(!has_iid)
+                            .on_click(on_clear),
+                        Button::new()
+                            .children("Delete")
+                            .disabled(!has_actor)
+                            .on_click(on_delete),
+                    )),
+            ))
+    }
+}
+
+/// Returns the first id of the form `<stem>-<n>` which isn't already in use.
+fn unique_iid<'a>(stem: &str, existing: impl Iterator<Item = &'a str>) -> String {
+    let prefix = format!("{}-", stem);
+    let next = existing
+        .filter_map(|iid| iid.strip_prefix(&prefix))
+        .filter_map(|n| n.parse::<u32>().ok())
+        .max()
+        .map_or(1, |n| n + 1);
+    format!("{}{}", prefix, next)
+}
+
+fn style_actor_controls(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .flex_direction(ui::FlexDirection::Column)
+        .align_items(ui::AlignItems::Stretch)
+        .gap(8);
+}
+
+fn style_actor_buttons(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .flex_direction(ui::FlexDirection::Row)
+        .gap(4);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_iid() {
+        assert_eq!(unique_iid("guard", std::iter::empty()), "guard-1");
+        assert_eq!(
+            unique_iid(
+                "guard",
+                ["guard-1", "guard-3", "cat-7", "guard-x"].into_iter()
+            ),
+            "guard-4"
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use bevy::{prelude::*, ui};
+use bevy_quill::prelude::*;
+use bevy_quill_obsidian::prelude::*;
+
+use crate::{
+    editor::{
+        scenery::UndoPrecinctEdit,
+        undo::UndoStack,
+        unsaved::{ModifiedState, UnsavedAssets},
+    },
+    scenery::{
+        precinct::Precinct,
+        precinct_asset::{LayerData, PrecinctAsset},
+    },
+};
+
+use super::{
+    controls::NameInput,
+    mode_scenery::{SelectedLayer, SelectedPrecinct},
+};
+
+/// Which of a layer's settings a checkbox controls.
+#[derive(Clone, Copy, PartialEq)]
+enum LayerFlag {
+    Hidden,
+    Locked,
+    EditorOnly,
+}
+
+impl LayerFlag {
+    fn get(self, layer: &LayerData) -> bool {
+        match self {
+            LayerFlag::Hidden => layer.hidden,
+            LayerFlag::Locked => layer.locked,
+            LayerFlag::EditorOnly => layer.editor_only,
+        }
+    }
+
+    fn set(self, layer: &mut LayerData, value: bool) {
+        match self {
+            LayerFlag::Hidden => layer.hidden = value,
+            LayerFlag::Locked => layer.locked = value,
+            LayerFlag::EditorOnly => layer.editor_only = value,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            LayerFlag::Hidden => "Hide Layer",
+            LayerFlag::Locked => "Lock Layer",
+            LayerFlag::EditorOnly => "Set Layer Editor Only",
+        }
+    }
+}
+
+/// Apply an edit to the precinct asset as an undoable operation, and mark it as unsaved.
+fn edit_precinct(
+    world: &mut World,
+    handle: &Handle<PrecinctAsset>,
+    label: &'static str,
+    edit: impl FnOnce(&mut PrecinctAsset),
+) {
+    let mut assets = world.resource_mut::<Assets<PrecinctAsset>>();
+    let precinct = match assets.get_mut(handle) {
+        Some(precinct) => precinct,
+        None => {
+            assets.insert(handle.id(), PrecinctAsset::default());
+            assets.get_mut(handle).unwrap()
+        }
+    };
+    let before = precinct.clone();
+    edit(precinct);
+    let after = precinct.clone();
+    world
+        .resource_mut::<UnsavedAssets>()
+        .precincts
+        .insert(handle.clone(), ModifiedState::Unsaved);
+    world.resource_mut::<UndoStack>().push(UndoPrecinctEdit {
+        label,
+        before: vec![(handle.clone(), before)],
+        after: vec![(handle.clone(), after)],
+    });
+}
+
+/// Panel which lists the layers of the selected precinct. The selected layer is the one that
+/// newly placed scenery and floors are assigned to.
+#[derive(Clone, PartialEq)]
+pub(crate) struct LayerControls;
+
+impl ViewTemplate for LayerControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let precinct_id = cx.use_resource::<SelectedPrecinct>().0;
+        let handle = precinct_id
+            .and_then(|p| cx.use_component::<Precinct>(p))
+            .map(|p| p.asset.clone());
+        let layers: Vec<(String, LayerData)> = handle
+            .as_ref()
+            .and_then(|h| cx.use_resource::<Assets<PrecinctAsset>>().get(h))
+            .map(|asset| {
+                asset
+                    .layers
+                    .iter()
+                    .map(|(name, data)| (name.clone(), data.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let selected_name = cx.use_resource::<SelectedLayer>().0.clone();
+        let selected = selected_name
+            .as_ref()
+            .and_then(|name| layers.iter().find(|(n, _)| n == name))
+            .cloned();
+
+        // Condition text being typed, not yet applied to the layer.
+        let draft = cx.create_mutable::<Option<String>>(None);
+        let condition = selected
+            .as_ref()
+            .and_then(|(_, data)| data.condition.clone())
+            .unwrap_or_default();
+        let draft_value = draft.get_clone(cx);
+        let condition_value = draft_value.clone().unwrap_or_else(|| condition.clone());
+
+        let on_select = cx.create_callback(move |name: In<Option<String>>, world: &mut World| {
+            world.resource_mut::<SelectedLayer>().0 = name.0;
+            draft.set_clone(world, None);
+        });
+        let add_handle = handle.clone();
+        let on_add = cx.create_callback(move |world: &mut World| {
+            let Some(handle) = &add_handle else {
+                return;
+            };
+            let mut name = String::new();
+            edit_precinct(world, handle, "Add Layer", |precinct| {
+                name = precinct.add_layer("Layer");
+            });
+            world.resource_mut::<SelectedLayer>().0 = Some(name);
+        });
+        let remove_handle = handle.clone();
+        let remove_name = selected.as_ref().map(|(name, _)| name.clone());
+        let on_remove = cx.create_callback(move |world: &mut World| {
+            let (Some(handle), Some(name)) = (&remove_handle, &remove_name) else {
+                return;
+            };
+            edit_precinct(world, handle, "Remove Layer", |precinct| {
+                precinct.remove_layer(name)
+            });
+            world.resource_mut::<SelectedLayer>().0 = None;
+        });
+        let flag_checkbox = |cx: &mut Cx, label: &'static str, flag: LayerFlag| {
+            let handle = handle.clone();
+            let layer = selected.clone();
+            let on_change = cx.create_callback(move |checked: In<bool>, world: &mut World| {
+                let (Some(handle), Some((name, _))) = (&handle, &layer) else {
+                    return;
+                };
+                edit_precinct(world, handle, flag.label(), |precinct| {
+                    if let Some(data) = precinct.layers.get_mut(name) {
+                        flag.set(data, *checked);
+                    }
+                });
+            });
+            Checkbox::new()
+                .label(label)
+                .disabled(selected.is_none())
+                .checked(selected.as_ref().is_some_and(|(_, data)| flag.get(data)))
+                .on_change(on_change)
+        };
+        let hidden = flag_checkbox(cx, "Hidden", LayerFlag::Hidden);
+        let locked = flag_checkbox(cx, "Locked", LayerFlag::Locked);
+        let editor_only = flag_checkbox(cx, "Editor Only", LayerFlag::EditorOnly);
+        let set_condition = |cx: &mut Cx, clear: bool| {
+            let handle = handle.clone();
+            let layer = selected.clone();
+            cx.create_callback(move |world: &mut World| {
+                let (Some(handle), Some((name, _))) = (&handle, &layer) else {
+                    return;
+                };
+                let text = if clear { None } else { draft.get_clone(world) };
+                let condition = text
+                    .map(|text| text.trim().to_string())
+                    .filter(|text| !text.is_empty());
+                draft.set_clone(world, None);
+                edit_precinct(world, handle, "Set Layer Condition", |precinct| {
+                    if let Some(data) = precinct.layers.get_mut(name) {
+                        data.condition = condition;
+                    }
+                });
+            })
+        };
+        let on_apply_condition = set_condition(cx, false);
+        let on_clear_condition = set_condition(cx, true);
+        let on_condition_change = cx.create_callback(move |text: In<String>, world: &mut World| {
+            draft.set_clone(world, Some(text.0));
+        });
+
+        Element::<NodeBundle>::new()
+            .style(style_layer_controls)
+            .children((
+                Flex::row(|sb| {
+                    sb.gap(4).align_items(ui::AlignItems::Center);
+                })
+                .children((
+                    Button::new()
+                        .children("Add Layer")
+                        .disabled(handle.is_none())
+                        .style(style_grow)
+                        .on_click(on_add),
+                    Button::new()
+                        .children("Remove Layer")
+                        .disabled(selected.is_none())
+                        .style(style_grow)
+                        .on_click(on_remove),
+                )),
+                ListView::new().style(style_layer_list).children((
+                    ListRow::new(None)
+                        .selected(selected.is_none())
+                        .children("(default)")
+                        .on_click(on_select),
+                    For::each(layers, move |(name, data)| LayerRow {
+                        name: name.clone(),
+                        data: data.clone(),
+                        selected: selected_name.as_ref() == Some(name),
+                        on_click: on_select,
+                    }),
+                )),
+                hidden,
+                locked,
+                editor_only,
+                Flex::row(|sb| {
+                    sb.gap(4).align_items(ui::AlignItems::Center);
+                })
+                .children((
+                    NameInput {
+                        value: condition_value,
+                        placeholder: "Shown when story flag...",
+                        on_change: on_condition_change,
+                    },
+                    Button::new()
+                        .children("Set")
+                        .disabled(
+                            selected.is_none()
+                                || draft_value.as_ref().map_or(true, |text| *text == condition),
+                        )
+                        .on_click(on_apply_condition),
+                    Button::new()
+                        .children("Clear")
+                        .disabled(condition.is_empty())
+                        .on_click(on_clear_condition),
+                )),
+            ))
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct LayerRow {
+    name: String,
+    data: LayerData,
+    selected: bool,
+    on_click: Callback<Option<String>>,
+}
+
+impl ViewTemplate for LayerRow {
+    type View = impl View;
+
+    fn create(&self, _cx: &mut Cx) -> Self::View {
+        let mut flags: Vec<&str> = Vec::new();
+        if self.data.hidden {
+            flags.push("hidden");
+        }
+        if self.data.locked {
+            flags.push("locked");
+        }
+        if self.data.editor_only {
+            flags.push("editor only");
+        }
+        if self.data.condition.is_some() {
+            flags.push("conditional");
+        }
+        let label = if flags.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} ({})", self.name, flags.join(", "))
+        };
+        ListRow::new(Some(self.name.clone()))
+            .selected(self.selected)
+            .children(label)
+            .on_click(self.on_click)
+    }
+}
+
+fn style_layer_controls(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .flex_direction(ui::FlexDirection::Column)
+        .align_items(ui::AlignItems::Stretch)
+        .gap(8)
+        .min_height(0)
+        .flex_grow(1.);
+}
+
+fn style_layer_list(ss: &mut StyleBuilder) {
+    ss.min_height(ui::Val::Px(96.)).flex_grow(1.);
+}
+
+fn style_grow(ss: &mut StyleBuilder) {
+    ss.flex_grow(1.);
+}
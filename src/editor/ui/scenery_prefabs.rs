@@ -0,0 +1,88 @@
+use bevy::{prelude::*, ui};
+use bevy_quill::prelude::*;
+use bevy_quill_obsidian::prelude::*;
+
+use crate::editor::scenery::{LoadPrefab, PrefabList, SavePrefab, SceneryClipboard};
+
+use super::controls::NameInput;
+
+/// Panel which shows what is in the scenery clipboard, and lists the saved prefabs. Clicking
+/// a prefab loads it into the clipboard, ready to be pasted, and fills in its name so that it
+/// can be saved back under the same name.
+#[derive(Clone, PartialEq)]
+pub(crate) struct PrefabControls;
+
+impl ViewTemplate for PrefabControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let summary = match &cx.use_resource::<SceneryClipboard>().0 {
+            Some(prefab) => format!(
+                "Clipboard: {} scenery, {} floors, {} terrain effects",
+                prefab.scenery.len(),
+                prefab.floors.len(),
+                prefab.terrain_fx.len()
+            ),
+            None => "Clipboard is empty".to_string(),
+        };
+        let has_clipboard = cx.use_resource::<SceneryClipboard>().0.is_some();
+        let prefabs = cx.use_resource::<PrefabList>().0.clone();
+        let name = cx.create_mutable(String::new());
+        let name_value = name.get_clone(cx);
+
+        let on_name_change = cx.create_callback(move |text: In<String>, world: &mut World| {
+            name.set_clone(world, text.0);
+        });
+        let on_save = cx.create_callback(move |world: &mut World| {
+            let name = name.get_clone(world);
+            world.commands().add(SavePrefab(name));
+        });
+        let on_load = cx.create_callback(move |selected: In<Option<String>>, world: &mut World| {
+            if let Some(selected) = selected.0 {
+                name.set_clone(world, selected.clone());
+                world.commands().add(LoadPrefab(selected));
+            }
+        });
+
+        Element::<NodeBundle>::new()
+            .style(style_prefab_controls)
+            .children((
+                summary,
+                Flex::row(|sb| {
+                    sb.gap(4).align_items(ui::AlignItems::Center);
+                })
+                .children((
+                    NameInput {
+                        value: name_value.clone(),
+                        placeholder: "Prefab name",
+                        on_change: on_name_change,
+                    },
+                    Button::new()
+                        .children("Save Prefab")
+                        .disabled(!has_clipboard || name_value.trim().is_empty())
+                        .on_click(on_save),
+                )),
+                ListView::new().style(style_prefab_list).children(For::each(
+                    prefabs,
+                    move |name| {
+                        ListRow::new(Some(name.clone()))
+                            .children(name.clone())
+                            .on_click(on_load)
+                    },
+                )),
+            ))
+    }
+}
+
+fn style_prefab_controls(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .flex_direction(ui::FlexDirection::Column)
+        .align_items(ui::AlignItems::Stretch)
+        .gap(8)
+        .min_height(0)
+        .flex_grow(1.);
+}
+
+fn style_prefab_list(ss: &mut StyleBuilder) {
+    ss.min_height(ui::Val::Px(96.)).flex_grow(1.);
+}
@@ -0,0 +1,128 @@
+use bevy::{prelude::*, ui};
+use bevy_quill::prelude::*;
+use bevy_quill_obsidian::prelude::*;
+
+use crate::{
+    editor::scenery::{EditTiers, TierEdit},
+    scenery::{precinct::Precinct, precinct_asset::PrecinctAsset, PRECINCT_SIZE_F},
+};
+
+use super::mode_scenery::{HideUpperTiers, SelectedPrecinct, SelectedTier};
+
+/// Panel which lists the tiers of the selected precinct, highest first. Clicking a tier
+/// selects it; the buttons insert, copy or delete the selected tier, renumbering the ones
+/// above.
+#[derive(Clone, PartialEq)]
+pub(crate) struct TierControls;
+
+impl ViewTemplate for TierControls {
+    type View = impl View;
+
+    fn create(&self, cx: &mut Cx) -> Self::View {
+        let precinct_id = cx.use_resource::<SelectedPrecinct>().0;
+        let precinct = precinct_id.and_then(|p| cx.use_component::<Precinct>(p));
+        let handle = precinct.map(|p| p.asset.clone());
+        let origin = precinct
+            .map(|p| p.coords.as_vec2() * PRECINCT_SIZE_F)
+            .unwrap_or_default();
+        let selected = cx.use_resource::<SelectedTier>().0 as i32;
+        let mut tiers: Vec<(i32, usize)> = handle
+            .as_ref()
+            .and_then(|h| cx.use_resource::<Assets<PrecinctAsset>>().get(h))
+            .map(|asset| {
+                asset
+                    .tiers
+                    .iter()
+                    .map(|tier| (tier.level, tier.pfloors.len()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let exists = tiers.iter().any(|(level, _)| *level == selected);
+        if !exists {
+            tiers.push((selected, 0));
+        }
+        tiers.sort_by(|a, b| b.0.cmp(&a.0));
+        let hide_upper = cx.use_resource::<HideUpperTiers>().0;
+
+        let on_select = cx.create_callback(|level: In<i32>, mut selected: ResMut<SelectedTier>| {
+            selected.0 = level.0 as i16;
+        });
+        let edit_button = |cx: &mut Cx, label: &'static str, edit: TierEdit, enabled: bool| {
+            let handle = handle.clone();
+            let on_click = cx.create_callback(
+                move |mut commands: Commands, mut selected: ResMut<SelectedTier>| {
+                    let Some(precinct) = handle.clone() else {
+                        return;
+                    };
+                    commands.add(EditTiers {
+                        precinct,
+                        origin,
+                        edit,
+                    });
+                    // Follow the copy, which is placed above the original.
+                    if let TierEdit::Copy(_) = edit {
+                        selected.0 = (selected.0 + 1).clamp(-8, 16);
+                    }
+                },
+            );
+            Button::new()
+                .children(label)
+                .disabled(handle.is_none() || !enabled)
+                .style(style_grow)
+                .on_click(on_click)
+        };
+        let insert = edit_button(cx, "Insert", TierEdit::Insert(selected), true);
+        let copy = edit_button(cx, "Copy", TierEdit::Copy(selected), exists);
+        let delete = edit_button(cx, "Delete", TierEdit::Delete(selected), exists);
+        let on_hide_upper =
+            cx.create_callback(|checked: In<bool>, mut hide: ResMut<HideUpperTiers>| {
+                hide.0 = *checked;
+            });
+
+        Element::<NodeBundle>::new()
+            .style(style_tier_controls)
+            .children((
+                ListView::new().style(style_tier_list).children(For::each(
+                    tiers,
+                    move |(level, floors)| {
+                        ListRow::new(*level)
+                            .selected(*level == selected)
+                            .children(match floors {
+                                0 => format!("Tier {}", level),
+                                1 => format!("Tier {} (1 floor)", level),
+                                n => format!("Tier {} ({} floors)", level, n),
+                            })
+                            .on_click(on_select)
+                    },
+                )),
+                Flex::row(|sb| {
+                    sb.gap(4).align_items(ui::AlignItems::Center);
+                })
+                .children((insert, copy, delete)),
+                Checkbox::new()
+                    .label("Hide Upper Tiers")
+                    .checked(hide_upper)
+                    .on_change(on_hide_upper),
+            ))
+    }
+}
+
+fn style_tier_controls(ss: &mut StyleBuilder) {
+    ss.display(ui::Display::Flex)
+        .flex_direction(ui::FlexDirection::Column)
+        .align_items(ui::AlignItems::Stretch)
+        .gap(4)
+        .min_height(0)
+        .grid_row_start(3)
+        .grid_row_end(4);
+}
+
+fn style_tier_list(ss: &mut StyleBuilder) {
+    ss.min_height(48)
+        .max_height(ui::Val::Px(160.))
+        .flex_grow(1.);
+}
+
+fn style_grow(ss: &mut StyleBuilder) {
+    ss.flex_grow(1.);
+}
@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use bevy_mod_picking::{focus::HoverMap, prelude::PointerId};
+use bevy_quill::View;
+
+use crate::{
+    editor::{
+        events::{DeleteSelection, RotateSelection},
+        scenery::{ActorEdit, ActorRef, EditActor, SelectedActor},
+    },
+    scenery::{precinct::Precinct, precinct_asset::PrecinctAsset, PRECINCT_SIZE_F},
+    terrain::{
+        terrain_contours::TerrainContoursHandle, terrain_contours::TerrainContoursTableAsset,
+        Parcel,
+    },
+    view::{
+        picking::{PickAction, PickEvent},
+        Viewpoint,
+    },
+};
+
+use super::{
+    mode_scenery::{
+        ActorMarker, ActorPlaceState, ActorType, FixtureSnap, SceneryOverlay, SelectedFacing,
+        SelectedLayer, SelectedPrecinct, SelectedTier,
+    },
+    overlays::{ActorMarkersOverlay, SelectedPrecinctOverlay},
+    tool_fixture_create::{placement_height, snap_position},
+};
+
+/// How far from the cursor, in meters, an actor can be clicked.
+const PICK_RADIUS: f32 = 0.75;
+
+#[derive(Clone, Component)]
+pub struct PrecinctOverlay;
+
+pub fn enter(mut commands: Commands) {
+    commands.spawn((SelectedPrecinctOverlay.to_root(), PrecinctOverlay));
+    commands.spawn((ActorMarkersOverlay.to_root(), PrecinctOverlay));
+    commands.spawn((
+        StateScoped(SceneryOverlay::PlaceActor),
+        Observer::new(on_pick_event),
+    ));
+    // Rotating turns the selected actor, or the facing of new actors if none is selected.
+    commands.spawn((
+        StateScoped(SceneryOverlay::PlaceActor),
+        Observer::new(
+            |trigger: Trigger<RotateSelection>,
+             mut commands: Commands,
+             r_selected_actor: Res<SelectedActor>,
+             mut r_facing: ResMut<SelectedFacing>| {
+                let turns = trigger.event().0;
+                if r_selected_actor.0.is_some() {
+                    commands.add(EditActor(ActorEdit::Rotate(turns)));
+                } else {
+                    r_facing.0 = (r_facing.0 + turns).rem_euclid(4);
+                }
+            },
+        ),
+    ));
+    commands.spawn((
+        StateScoped(SceneryOverlay::PlaceActor),
+        Observer::new(
+            |_trigger: Trigger<DeleteSelection>, mut commands: Commands| {
+                commands.add(EditActor(ActorEdit::Delete));
+            },
+        ),
+    ));
+}
+
+pub fn exit(
+    mut commands: Commands,
+    q_overlays: Query<Entity, With<PrecinctOverlay>>,
+    mut r_state: ResMut<ActorPlaceState>,
+) {
+    q_overlays.iter().for_each(|e| commands.entity(e).despawn());
+    *r_state = ActorPlaceState::default();
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update(
+    q_precincts: Query<&Precinct>,
+    q_parcels: Query<&Parcel>,
+    r_precinct_assets: Res<Assets<PrecinctAsset>>,
+    r_contours_handle: Res<TerrainContoursHandle>,
+    r_contours: Res<Assets<TerrainContoursTableAsset>>,
+    r_viewpoint: Res<Viewpoint>,
+    r_selected_tier: Res<SelectedTier>,
+    r_hover_map: Res<HoverMap>,
+    r_snap: Res<State<FixtureSnap>>,
+    mut r_state: ResMut<ActorPlaceState>,
+) {
+    let mut state = r_state.clone();
+    let Some(realm) = r_viewpoint.realm else {
+        return;
+    };
+
+    // Cursor position, at the height an actor would be placed there.
+    state.cursor_pos = r_hover_map
+        .get(&PointerId::Mouse)
+        .and_then(|p| p.values().find_map(|hit_data| hit_data.position))
+        .and_then(|pos| {
+            let pos = snap_position(pos.xz(), *r_snap.get());
+            let precinct = q_precincts
+                .iter()
+                .find(|p| p.realm == realm && p.contains_pt(Vec3::new(pos.x, 0., pos.y)))?;
+            let precinct_min = precinct.coords.as_vec2() * PRECINCT_SIZE_F;
+            let height = placement_height(
+                precinct,
+                pos - precinct_min,
+                r_selected_tier.0,
+                &r_precinct_assets,
+                &q_parcels,
+                r_contours.get(&r_contours_handle.0),
+            );
+            Some(Vec3::new(pos.x, height, pos.y))
+        });
+
+    state.markers.clear();
+    for precinct in q_precincts.iter().filter(|p| p.realm == realm) {
+        let Some(asset) = r_precinct_assets.get(&precinct.asset) else {
+            continue;
+        };
+        for (index, actor) in asset.actors.iter().enumerate() {
+            state.markers.push(ActorMarker {
+                actor: ActorRef {
+                    precinct: precinct.asset.clone(),
+                    index,
+                },
+                position: actor.position,
+                facing: actor.facing,
+                locked: asset.is_layer_locked(actor.layer.as_deref()),
+            });
+        }
+    }
+
+    if *r_state != state {
+        *r_state = state;
+    }
+}
+
+/// The unlocked actor nearest to a world-space position, if any is in reach.
+fn pick_actor(markers: &[ActorMarker], pos: Vec3) -> Option<ActorRef> {
+    markers
+        .iter()
+        .filter(|marker| !marker.locked)
+        .map(|marker| (marker, marker.position.xz().distance(pos.xz())))
+        .filter(|(_, dist)| *dist < PICK_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(marker, _)| marker.actor.clone())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn on_pick_event(
+    trigger: Trigger<PickEvent>,
+    mut commands: Commands,
+    q_precincts: Query<(Entity, &Precinct)>,
+    r_viewpoint: Res<Viewpoint>,
+    r_actor_type: Res<ActorType>,
+    r_selected_facing: Res<SelectedFacing>,
+    r_selected_layer: Res<SelectedLayer>,
+    mut r_selected_precinct: ResMut<SelectedPrecinct>,
+    mut r_selected_actor: ResMut<SelectedActor>,
+    mut r_state: ResMut<ActorPlaceState>,
+) {
+    let event = trigger.event();
+    let Some(realm) = r_viewpoint.realm else {
+        return;
+    };
+
+    match event.action {
+        // Clicking an actor selects it; clicking anywhere else places a new one.
+        PickAction::Down(pos) => {
+            let precinct_id = q_precincts
+                .iter()
+                .find(|(_, p)| p.realm == realm && p.contains_pt(pos))
+                .map(|(e, _)| e);
+            if r_selected_precinct.0 != precinct_id {
+                r_selected_precinct.0 = precinct_id;
+            }
+
+            if let Some(hit) = pick_actor(&r_state.markers, pos) {
+                r_selected_actor.0 = Some(hit);
+                return;
+            }
+            match (r_actor_type.0, r_state.cursor_pos) {
+                (Some(exemplar), Some(position)) => {
+                    commands.add(EditActor(ActorEdit::Place {
+                        realm,
+                        exemplar,
+                        position,
+                        facing: (r_selected_facing.0 as f32 * -90.0).rem_euclid(360.0),
+                        layer: r_selected_layer.0.clone(),
+                    }));
+                }
+                _ => r_selected_actor.0 = None,
+            }
+        }
+
+        PickAction::DragStart { pos, .. } => {
+            r_state.dragging = r_selected_actor.0.is_some()
+                && pick_actor(&r_state.markers, pos) == r_selected_actor.0;
+        }
+
+        PickAction::DragEnd => {
+            if !r_state.dragging {
+                return;
+            }
+            r_state.dragging = false;
+            if let Some(position) = r_state.cursor_pos {
+                commands.add(EditActor(ActorEdit::Move(position)));
+            }
+        }
+
+        PickAction::Leave | PickAction::RightClick | PickAction::DblClick | PickAction::Drag => {}
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+use bevy_mod_picking::{focus::HoverMap, prelude::PointerId};
+use bevy_quill::View;
+
+use crate::{
+    editor::{
+        events::DeleteSelection,
+        lib::pick_plane::PlanePick,
+        scenery::{CutawayEdit, EditCutaways},
+    },
+    scenery::{precinct::Precinct, precinct_asset::PrecinctAsset, PRECINCT_SIZE_F},
+    view::picking::{PickAction, PickEvent},
+};
+
+use super::{
+    mode_scenery::{CutawayEditState, SceneryOverlay, SelectedPrecinct, SelectedTier},
+    overlays::{CutawaysOverlay, SelectedPrecinctOverlay},
+};
+
+#[derive(Clone, Component)]
+pub struct PrecinctOverlay;
+
+pub fn enter(mut commands: Commands, q_camera: Query<Entity, With<crate::view::PrimaryCamera>>) {
+    commands.spawn((SelectedPrecinctOverlay.to_root(), PrecinctOverlay));
+    commands.spawn((CutawaysOverlay.to_root(), PrecinctOverlay));
+    commands.spawn((
+        StateScoped(SceneryOverlay::DrawCutaways),
+        Observer::new(on_pick_event),
+    ));
+    commands.spawn((
+        StateScoped(SceneryOverlay::DrawCutaways),
+        Observer::new(
+            |_trigger: Trigger<DeleteSelection>,
+             mut commands: Commands,
+             q_precincts: Query<&Precinct>,
+             r_selected_precinct: Res<SelectedPrecinct>,
+             r_selected_tier: Res<SelectedTier>,
+             mut r_state: ResMut<CutawayEditState>| {
+                let (Some(index), Some(precinct)) = (
+                    r_state.selected.take(),
+                    r_selected_precinct
+                        .0
+                        .and_then(|id| q_precincts.get(id).ok()),
+                ) else {
+                    return;
+                };
+                commands.add(EditCutaways {
+                    precinct: precinct.asset.clone(),
+                    tier: r_selected_tier.0 as i32,
+                    edit: CutawayEdit::Remove(index),
+                });
+            },
+        ),
+    ));
+
+    for camera in q_camera.iter() {
+        commands.entity(camera).insert(PlanePick);
+    }
+}
+
+pub fn exit(
+    mut commands: Commands,
+    q_overlays: Query<Entity, With<PrecinctOverlay>>,
+    q_camera: Query<Entity, With<crate::view::PrimaryCamera>>,
+    mut r_state: ResMut<CutawayEditState>,
+) {
+    q_overlays.iter().for_each(|e| commands.entity(e).despawn());
+    for camera in q_camera.iter() {
+        commands.entity(camera).remove::<PlanePick>();
+    }
+    *r_state = CutawayEditState::default();
+}
+
+pub fn update(
+    r_selected_precinct: Res<SelectedPrecinct>,
+    r_selected_tier: Res<SelectedTier>,
+    r_hover_map: Res<HoverMap>,
+    r_precinct_assets: Res<Assets<PrecinctAsset>>,
+    q_precincts: Query<&Precinct>,
+    mut r_state: ResMut<CutawayEditState>,
+) {
+    let mut state = r_state.clone();
+    state.rects.clear();
+    if let Some(precinct) = r_selected_precinct
+        .0
+        .and_then(|id| q_precincts.get(id).ok())
+    {
+        state.origin = precinct.coords.as_vec2() * PRECINCT_SIZE_F;
+        if let Some(tier) = r_precinct_assets
+            .get(&precinct.asset)
+            .and_then(|asset| asset.find_tier(r_selected_tier.0 as i32))
+        {
+            state.rects.clone_from(&tier.cutaways);
+        }
+    } else {
+        state.dragging = false;
+    }
+    if state
+        .selected
+        .is_some_and(|index| index >= state.rects.len())
+    {
+        state.selected = None;
+    }
+
+    if let Some(pos) = r_hover_map
+        .get(&PointerId::Mouse)
+        .and_then(|p| p.values().find_map(|hit_data| hit_data.position))
+    {
+        state.cursor_pos = (pos.xz() - state.origin)
+            .round()
+            .clamp(Vec2::ZERO, Vec2::splat(PRECINCT_SIZE_F));
+    }
+
+    if *r_state != state {
+        *r_state = state;
+    }
+}
+
+pub fn on_pick_event(
+    trigger: Trigger<PickEvent>,
+    mut commands: Commands,
+    q_precincts: Query<(Entity, &Precinct)>,
+    r_selected_tier: Res<SelectedTier>,
+    mut r_selected_precinct: ResMut<SelectedPrecinct>,
+    mut r_state: ResMut<CutawayEditState>,
+) {
+    match trigger.event().action {
+        // Clicking inside a cutaway selects it; the topmost one wins where they overlap.
+        PickAction::Down(pos) => {
+            let pos = pos.xz() - r_state.origin;
+            r_state.selected = r_state.rects.iter().rposition(|rect| rect.contains(pos));
+        }
+
+        PickAction::DragStart { realm, pos } => {
+            let precinct_id = q_precincts
+                .iter()
+                .find(|(_, p)| p.realm == realm && p.contains_pt(pos))
+                .map(|(e, _)| e);
+            if r_selected_precinct.0 != precinct_id {
+                r_selected_precinct.0 = precinct_id;
+            } else if precinct_id.is_some() {
+                r_state.dragging = true;
+                r_state.anchor_pos = r_state.cursor_pos;
+            }
+        }
+
+        PickAction::DragEnd => {
+            let marquee = r_state.marquee();
+            r_state.dragging = false;
+            let (Some(rect), Some((_, precinct))) = (
+                marquee,
+                r_selected_precinct
+                    .0
+                    .and_then(|id| q_precincts.get(id).ok()),
+            ) else {
+                return;
+            };
+            r_state.selected = Some(r_state.rects.len());
+            commands.add(EditCutaways {
+                precinct: precinct.asset.clone(),
+                tier: r_selected_tier.0 as i32,
+                edit: CutawayEdit::Add(rect),
+            });
+        }
+
+        PickAction::Leave | PickAction::RightClick | PickAction::DblClick | PickAction::Drag => {}
+    }
+}
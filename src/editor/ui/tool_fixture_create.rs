@@ -0,0 +1,281 @@
+use bevy::prelude::*;
+use bevy_mod_picking::{focus::HoverMap, prelude::PointerId};
+use bevy_quill::View;
+
+use crate::{
+    editor::{
+        events::{PlaceFixture, RotateSelection},
+        lib::floor_ops::floors_contain,
+    },
+    scenery::{precinct::Precinct, precinct_asset::PrecinctAsset, PRECINCT_SIZE_F, TIER_OFFSET},
+    terrain::{
+        terrain_contours::{TerrainContoursHandle, TerrainContoursTableAsset},
+        Parcel, PARCEL_SIZE_F, PARCEL_SIZE_U,
+    },
+    view::{
+        picking::{PickAction, PickEvent},
+        Viewpoint,
+    },
+    world::Realm,
+};
+
+use super::{
+    mode_scenery::{
+        FixtureSnap, FixtureType, SceneryDragState, SceneryOverlay, SelectedFacing,
+        SelectedPrecinct, SelectedTier,
+    },
+    overlays::SelectedPrecinctOverlay,
+};
+
+#[derive(Clone, Component)]
+pub struct PrecinctOverlay;
+
+pub fn enter(mut commands: Commands) {
+    commands.spawn((SelectedPrecinctOverlay.to_root(), PrecinctOverlay));
+    commands.spawn((
+        StateScoped(SceneryOverlay::PlaceFixture),
+        Observer::new(on_pick_event),
+    ));
+    commands.spawn((
+        StateScoped(SceneryOverlay::PlaceFixture),
+        Observer::new(
+            |trigger: Trigger<RotateSelection>, mut facing: ResMut<SelectedFacing>| {
+                facing.0 = (facing.0 + trigger.event().0).rem_euclid(4);
+            },
+        ),
+    ));
+}
+
+pub fn exit(
+    mut commands: Commands,
+    q_overlays: Query<Entity, With<PrecinctOverlay>>,
+    mut r_drag_state: ResMut<SceneryDragState>,
+) {
+    q_overlays.iter().for_each(|e| commands.entity(e).despawn());
+    r_drag_state.cursor_exemplar = None;
+    r_drag_state.cursor_elevation = 0.;
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update(
+    q_precincts: Query<&Precinct>,
+    q_parcels: Query<&Parcel>,
+    q_realms: Query<&Realm>,
+    r_precinct_assets: Res<Assets<PrecinctAsset>>,
+    r_contours_handle: Res<TerrainContoursHandle>,
+    r_contours: Res<Assets<TerrainContoursTableAsset>>,
+    r_selected_precinct: Res<SelectedPrecinct>,
+    r_selected_fixture: Res<FixtureType>,
+    r_selected_tier: Res<SelectedTier>,
+    mut r_drag_state: ResMut<SceneryDragState>,
+    r_hover_map: Res<HoverMap>,
+    r_snap: Res<State<FixtureSnap>>,
+) {
+    let mut drag_state = r_drag_state.clone();
+    drag_state.precinct = r_selected_precinct.0;
+    drag_state.cursor_exemplar = None;
+
+    if let Some(precinct) = r_selected_precinct
+        .0
+        .and_then(|precinct_id| q_precincts.get(precinct_id).ok())
+    {
+        let Ok(realm) = q_realms.get(precinct.realm) else {
+            return;
+        };
+        let precinct_min = precinct.coords.as_vec2() * PRECINCT_SIZE_F;
+        if let Some(pos) = r_hover_map
+            .get(&PointerId::Mouse)
+            .and_then(|p| p.values().find_map(|hit_data| hit_data.position))
+        {
+            let pickpos = snap_in_precinct(pos.xz(), *r_snap.get(), precinct_min);
+
+            let tier_height = r_selected_tier.0 as f32 + TIER_OFFSET;
+            let height = placement_height(
+                precinct,
+                pickpos,
+                r_selected_tier.0,
+                &r_precinct_assets,
+                &q_parcels,
+                r_contours.get(&r_contours_handle.0),
+            );
+
+            drag_state.cursor_exemplar = r_selected_fixture.0;
+            drag_state.anchor_pos = pickpos;
+            drag_state.cursor_pos = pickpos;
+            drag_state.cursor_elevation = height - tier_height;
+            drag_state.cursor_layer = realm.layer_index;
+        }
+    }
+
+    if *r_drag_state != drag_state {
+        *r_drag_state = drag_state;
+    }
+}
+
+/// Snap a world-space position: to cell centers, to the quarter meter, or not at all.
+pub(super) fn snap_position(pos: Vec2, snap: FixtureSnap) -> Vec2 {
+    match snap {
+        FixtureSnap::Grid => (pos - 0.5).round() + 0.5,
+        FixtureSnap::Fine => (pos * 4.).round() / 4.,
+        FixtureSnap::Free => pos,
+    }
+}
+
+/// Snap a world-space position, and return it relative to the precinct whose minimum corner is
+/// `precinct_min`. Positions on or past the far edges belong to the neighboring precinct, so
+/// they are pulled back to the last snap point inside this one.
+pub(super) fn snap_in_precinct(pos: Vec2, snap: FixtureSnap, precinct_min: Vec2) -> Vec2 {
+    let (min, max) = match snap {
+        FixtureSnap::Grid => (0.5, PRECINCT_SIZE_F - 0.5),
+        FixtureSnap::Fine => (0., PRECINCT_SIZE_F - 0.25),
+        FixtureSnap::Free => (0., PRECINCT_SIZE_F - FREE_EDGE_INSET),
+    };
+    (snap_position(pos, snap) - precinct_min).clamp(Vec2::splat(min), Vec2::splat(max))
+}
+
+/// How far inside the far edges of a precinct unsnapped items are kept.
+const FREE_EDGE_INSET: f32 = 1. / 64.;
+
+/// Height to place an item at, given its position within a precinct. Items stand on a floor
+/// of the selected tier if there is one, otherwise they sit on the terrain.
+pub(super) fn placement_height(
+    precinct: &Precinct,
+    pos: Vec2,
+    tier: i16,
+    precinct_assets: &Assets<PrecinctAsset>,
+    q_parcels: &Query<&Parcel>,
+    contours: Option<&TerrainContoursTableAsset>,
+) -> f32 {
+    let tier_height = tier as f32 + TIER_OFFSET;
+    let on_floor = precinct_assets
+        .get(&precinct.asset)
+        .and_then(|asset| asset.find_tier(tier as i32))
+        .is_some_and(|tier| floors_contain(&tier.pfloors, pos));
+    if on_floor {
+        return tier_height;
+    }
+    let precinct_min = precinct.coords.as_vec2() * PRECINCT_SIZE_F;
+    contours
+        .and_then(|contours| {
+            terrain_height(q_parcels, contours, precinct.realm, pos + precinct_min)
+        })
+        .unwrap_or(tier_height)
+}
+
+/// Height of the terrain at a world-space position, interpolated between the contour
+/// vertices of the parcel underneath.
+fn terrain_height(
+    q_parcels: &Query<&Parcel>,
+    contours: &TerrainContoursTableAsset,
+    realm: Entity,
+    pos: Vec2,
+) -> Option<f32> {
+    let coords = (pos / PARCEL_SIZE_F).floor().as_ivec2();
+    let parcel = q_parcels
+        .iter()
+        .find(|p| p.realm == realm && p.coords == coords)?;
+    let shape_ref = parcel.center_shape();
+    let lock = contours.0.read().unwrap();
+    let contour = lock.get(shape_ref.shape as usize);
+    let local =
+        (pos - coords.as_vec2() * PARCEL_SIZE_F).clamp(Vec2::ZERO, Vec2::splat(PARCEL_SIZE_F));
+    let x0 = (local.x.floor() as usize).min(PARCEL_SIZE_U - 1);
+    let z0 = (local.y.floor() as usize).min(PARCEL_SIZE_U - 1);
+    let fx = local.x - x0 as f32;
+    let fz = local.y - z0 as f32;
+    let h = |x: usize, z: usize| contour.height_at(x, z, shape_ref.rotation);
+    let near = h(x0, z0) + (h(x0 + 1, z0) - h(x0, z0)) * fx;
+    let far = h(x0, z0 + 1) + (h(x0 + 1, z0 + 1) - h(x0, z0 + 1)) * fx;
+    Some(near + (far - near) * fz)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn on_pick_event(
+    trigger: Trigger<PickEvent>,
+    mut commands: Commands,
+    q_precincts: Query<(Entity, &Precinct)>,
+    mut r_selected_precinct: ResMut<SelectedPrecinct>,
+    r_selected_facing: Res<SelectedFacing>,
+    r_selected_tier: Res<SelectedTier>,
+    r_drag_state: Res<SceneryDragState>,
+    r_viewpoint: Res<Viewpoint>,
+) {
+    let event = trigger.event();
+    let PickAction::Down(pos) = event.action else {
+        return;
+    };
+    let Some(realm) = r_viewpoint.realm else {
+        return;
+    };
+
+    // The first click in a precinct selects it; after that, clicks place fixtures.
+    let precinct = q_precincts
+        .iter()
+        .find(|(_, p)| p.realm == realm && p.contains_pt(pos));
+    let precinct_id = precinct.map(|(e, _)| e);
+    if r_selected_precinct.0 != precinct_id {
+        r_selected_precinct.0 = precinct_id;
+        return;
+    }
+    let (Some((_, precinct)), Some(exemplar)) = (precinct, r_drag_state.cursor_exemplar) else {
+        return;
+    };
+    commands.trigger(PlaceFixture {
+        precinct: precinct.asset.clone(),
+        position: Vec3::new(
+            r_drag_state.cursor_pos.x,
+            r_selected_tier.0 as f32 + TIER_OFFSET + r_drag_state.cursor_elevation,
+            r_drag_state.cursor_pos.y,
+        ),
+        facing: (r_selected_facing.0 as f32 * -90.0).rem_euclid(360.0),
+        exemplar,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snap_position() {
+        let pos = Vec2::new(3.3, -1.9);
+        assert_eq!(snap_position(pos, FixtureSnap::Grid), Vec2::new(3.5, -1.5));
+        assert_eq!(snap_position(pos, FixtureSnap::Fine), Vec2::new(3.25, -2.));
+        assert_eq!(snap_position(pos, FixtureSnap::Free), pos);
+    }
+
+    #[test]
+    fn test_snap_in_precinct() {
+        let min = Vec2::splat(PRECINCT_SIZE_F);
+        let inside = min + Vec2::new(2.1, 5.9);
+        assert_eq!(
+            snap_in_precinct(inside, FixtureSnap::Grid, min),
+            Vec2::new(2.5, 5.5)
+        );
+
+        // The far edges belong to the next precinct.
+        let edge = min + Vec2::splat(PRECINCT_SIZE_F);
+        for snap in [FixtureSnap::Grid, FixtureSnap::Fine, FixtureSnap::Free] {
+            let pos = snap_in_precinct(edge, snap, min);
+            assert!(pos.x < PRECINCT_SIZE_F && pos.y < PRECINCT_SIZE_F);
+        }
+        assert_eq!(
+            snap_in_precinct(edge, FixtureSnap::Grid, min),
+            Vec2::splat(PRECINCT_SIZE_F - 0.5)
+        );
+        assert_eq!(
+            snap_in_precinct(edge, FixtureSnap::Fine, min),
+            Vec2::splat(PRECINCT_SIZE_F - 0.25)
+        );
+
+        // Below the near edges, grid positions stay on cell centers.
+        assert_eq!(
+            snap_in_precinct(min - Vec2::ONE, FixtureSnap::Grid, min),
+            Vec2::splat(0.5)
+        );
+        assert_eq!(
+            snap_in_precinct(min - Vec2::ONE, FixtureSnap::Free, min),
+            Vec2::ZERO
+        );
+    }
+}
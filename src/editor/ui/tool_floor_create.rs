@@ -1,23 +1,24 @@
 use bevy::prelude::*;
 use bevy_mod_picking::{focus::HoverMap, prelude::PointerId};
 use bevy_quill::View;
-use i_overlay::{
-    core::{fill_rule::FillRule, overlay::ShapeType, overlay_rule::OverlayRule},
-    i_float::point::IntPoint,
-};
+use i_overlay::core::{fill_rule::FillRule, overlay::ShapeType, overlay_rule::OverlayRule};
 
 use crate::{
-    editor::{events::FloorStampEvent, lib::pick_plane::PlanePick},
-    scenery::{
-        floor_region::FloorRegionSer, precinct::Precinct, precinct_asset::PrecinctAsset,
-        PRECINCT_SIZE_F,
+    editor::{
+        events::FloorStampEvent,
+        lib::{
+            floor_ops::{intpoint_to_vec2, stamp_floors, vec2_to_intpoint},
+            pick_plane::PlanePick,
+        },
     },
+    scenery::{precinct::Precinct, precinct_asset::PrecinctAsset, PRECINCT_SIZE_F},
     view::picking::PickEvent,
 };
 
 use super::{
     mode_scenery::{
-        FloorTool, FloorType, SceneryDragState, SceneryOverlay, SelectedPrecinct, SelectedTier,
+        FloorTool, FloorType, SceneryDragState, SceneryOverlay, SelectedLayer, SelectedPrecinct,
+        SelectedTier,
     },
     overlays::{FloorStampOverlay, SelectedPrecinctOverlay},
 };
@@ -214,19 +215,12 @@ fn rect_to_vertices(r: Rect) -> Vec<Vec2> {
     ]
 }
 
-fn vec2_to_intpoint(v: &Vec2) -> IntPoint {
-    IntPoint::new((v.x * 256.0) as i32, (v.y * 256.0) as i32)
-}
-
-fn intpoint_to_vec2(p: &IntPoint) -> Vec2 {
-    Vec2::new((p.x as f32) / 256.0, (p.y as f32) / 256.0)
-}
-
 pub fn on_stamp_floor(
     trigger: Trigger<FloorStampEvent>,
     q_precincts: Query<&Precinct>,
     mut r_precinct_assets: ResMut<Assets<PrecinctAsset>>,
     r_server: ResMut<AssetServer>,
+    r_selected_layer: Res<SelectedLayer>,
 ) {
     let event = trigger.event();
     let Ok(precinct) = q_precincts.get(event.precinct) else {
@@ -249,72 +243,22 @@ pub fn on_stamp_floor(
         }
         None => usize::MAX,
     };
-    let surface_ct = precinct_asset.floor_types.len();
+    let layer = r_selected_layer.layer_for(precinct_asset);
+    if precinct_asset.is_layer_locked(layer.as_deref()) {
+        return;
+    }
     let pfloors_old = match precinct_asset.find_tier(event.tier) {
         Some(tier) => tier.pfloors.clone(),
         None => Vec::new(),
     };
-    let mut pfloors_new: Vec<FloorRegionSer> = Vec::with_capacity(pfloors_old.capacity());
-    for surface in 0..surface_ct {
-        let mut ishape = i_overlay::core::overlay::Overlay::new(128);
-        for region in pfloors_old.iter() {
-            if region.surface_index == surface {
-                let mut shape: Vec<Vec<IntPoint>> = Vec::with_capacity(1 + region.holes.len());
-                shape.push(
-                    region
-                        .poly
-                        .iter()
-                        .rev()
-                        .map(vec2_to_intpoint)
-                        .collect::<Vec<_>>(),
-                );
-                shape.extend(
-                    region
-                        .holes
-                        .iter()
-                        .map(|hole| hole.iter().rev().map(vec2_to_intpoint).collect::<Vec<_>>()),
-                );
-                ishape.add_shape(&shape, ShapeType::Subject);
-            }
-        }
 
-        // Map event shape to overlay shape by converting the vertices to IntPoint.
-        ishape.add_shape(
-            &event
-                .shape
-                .iter()
-                .map(|path| path.iter().map(vec2_to_intpoint).collect::<Vec<_>>())
-                .collect::<Vec<_>>(),
-            ShapeType::Clip,
-        );
-
-        let graph = ishape.into_graph(FillRule::NonZero);
-        let v = if surface == surface_index {
-            graph.extract_shapes(OverlayRule::Union)
-        } else {
-            graph.extract_shapes(OverlayRule::Difference)
-        };
-
-        for shape in v.iter() {
-            let poly = shape
-                .first()
-                .unwrap()
-                .iter()
-                .rev()
-                .map(intpoint_to_vec2)
-                .collect::<Vec<_>>();
-            let holes = shape
-                .iter()
-                .skip(1)
-                .map(|path| path.iter().rev().map(intpoint_to_vec2).collect::<Vec<_>>())
-                .collect::<Vec<_>>();
-            pfloors_new.push(FloorRegionSer {
-                surface_index: surface,
-                poly,
-                holes,
-            });
-        }
-    }
+    let pfloors_new = stamp_floors(
+        precinct_asset,
+        &pfloors_old,
+        &event.shape,
+        surface_index,
+        &layer,
+    );
 
     // println!("pfloors_new: {:?}", pfloors_new);
     if pfloors_old != pfloors_new {
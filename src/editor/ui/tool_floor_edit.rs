@@ -1,50 +1,226 @@
 use bevy::prelude::*;
-use bevy_mod_picking::focus::HoverMap;
+use bevy_mod_picking::{focus::HoverMap, prelude::PointerId};
+use bevy_quill::View;
 
-use crate::view::picking::PickEvent;
+use crate::{
+    editor::{
+        events::DeleteSelection,
+        lib::{
+            floor_ops::{drag_floor_part, nearest_point_on_edge, pick_floor_part, FloorPart},
+            pick_plane::PlanePick,
+        },
+        scenery::{EditFloor, FloorEdit},
+    },
+    scenery::{precinct::Precinct, precinct_asset::PrecinctAsset, PRECINCT_SIZE_F},
+    view::picking::{PickAction, PickEvent},
+};
 
-use super::mode_scenery::SceneryOverlay;
+use super::{
+    mode_scenery::{FloorEditState, FloorTool, SceneryOverlay, SelectedPrecinct, SelectedTier},
+    overlays::{FloorEditOverlay, SelectedPrecinctOverlay},
+};
+
+/// How close, in meters, the cursor has to be to a vertex or edge to pick it.
+const PICK_RADIUS: f32 = 0.4;
 
 #[derive(Clone, Component)]
 pub struct PrecinctOverlay;
 
-pub fn enter(mut commands: Commands) {
-    //     // commands.spawn((SelectedParcelOverlay.to_root(), PrecinctOverlay));
-    //     // commands.spawn((TerrainCursorOverlay.to_root(), PrecinctOverlay));
+pub fn enter(mut commands: Commands, q_camera: Query<Entity, With<crate::view::PrimaryCamera>>) {
+    commands.spawn((SelectedPrecinctOverlay.to_root(), PrecinctOverlay));
+    commands.spawn((FloorEditOverlay.to_root(), PrecinctOverlay));
     commands.spawn((
         StateScoped(SceneryOverlay::FloorDraw),
         Observer::new(on_pick_event),
     ));
-    //     // commands.spawn((
-    //     //     StateScoped(EditorMode::Terrain),
-    //     //     Observer::new(tool_terrain_edit::on_modify_terrain),
-    //     // ));
+    commands.spawn((
+        StateScoped(SceneryOverlay::FloorDraw),
+        Observer::new(
+            |_trigger: Trigger<DeleteSelection>,
+             mut commands: Commands,
+             q_precincts: Query<&Precinct>,
+             r_selected_precinct: Res<SelectedPrecinct>,
+             r_selected_tier: Res<SelectedTier>,
+             mut r_state: ResMut<FloorEditState>| {
+                let (Some(part), Some(precinct)) = (
+                    r_state.selected.take(),
+                    r_selected_precinct
+                        .0
+                        .and_then(|id| q_precincts.get(id).ok()),
+                ) else {
+                    return;
+                };
+                commands.add(EditFloor {
+                    precinct: precinct.asset.clone(),
+                    tier: r_selected_tier.0 as i32,
+                    edit: FloorEdit::DeleteVertex(part),
+                });
+            },
+        ),
+    ));
+
+    for camera in q_camera.iter() {
+        commands.entity(camera).insert(PlanePick);
+    }
 }
 
-pub fn exit(mut commands: Commands, q_overlays: Query<Entity, With<PrecinctOverlay>>) {
+pub fn exit(
+    mut commands: Commands,
+    q_overlays: Query<Entity, With<PrecinctOverlay>>,
+    q_camera: Query<Entity, With<crate::view::PrimaryCamera>>,
+    mut r_state: ResMut<FloorEditState>,
+) {
     q_overlays.iter().for_each(|e| commands.entity(e).despawn());
+    for camera in q_camera.iter() {
+        commands.entity(camera).remove::<PlanePick>();
+    }
+    *r_state = FloorEditState::default();
 }
 
 pub fn update(
-    mut commands: Commands,
-    // r_selected_parcel: Res<SelectedParcel>,
-    // mut r_drag_state: ResMut<TerrainDragState>,
+    r_selected_precinct: Res<SelectedPrecinct>,
+    r_selected_tier: Res<SelectedTier>,
     r_hover_map: Res<HoverMap>,
-    // r_tool: Res<State<TerrainTool>>,
-    // r_contours_handle: Res<TerrainContoursHandle>,
-    // r_contours_asset: ResMut<Assets<TerrainContoursTableAsset>>,
-    // q_parcels: Query<&Parcel>,
+    r_tool: Res<State<FloorTool>>,
+    r_precinct_assets: Res<Assets<PrecinctAsset>>,
+    q_precincts: Query<&Precinct>,
+    mut r_state: ResMut<FloorEditState>,
 ) {
+    let mut state = r_state.clone();
+    state.floors.clear();
+    state.valid = true;
+    let precinct = r_selected_precinct
+        .0
+        .and_then(|id| q_precincts.get(id).ok());
+    let (Some(precinct), FloorTool::Move) = (precinct, *r_tool.get()) else {
+        state.part = None;
+        state.dragging = false;
+        if *r_state != state {
+            *r_state = state;
+        }
+        return;
+    };
+
+    state.origin = precinct.coords.as_vec2() * PRECINCT_SIZE_F;
+    let asset = r_precinct_assets.get(&precinct.asset);
+    if let Some(tier) = asset.and_then(|asset| asset.find_tier(r_selected_tier.0 as i32)) {
+        state.floors.clone_from(&tier.pfloors);
+    }
+
+    if let Some(pos) = r_hover_map
+        .get(&PointerId::Mouse)
+        .and_then(|p| p.values().find_map(|hit_data| hit_data.position))
+    {
+        let pos = pos.xz() - state.origin;
+        state.cursor_pos = (pos * 2.).round() * 0.5;
+        state.pick_pos = pos;
+        if !state.dragging {
+            // Floors in hidden or locked layers can't be edited.
+            state.part = pick_floor_part(&state.floors, pos, PICK_RADIUS, |floor| {
+                asset.map_or(true, |asset| {
+                    asset.is_layer_editable(floor.layer.as_deref())
+                })
+            });
+        }
+    }
+
+    // Show the drag in progress, whether or not it would be accepted.
+    if let (true, Some(part)) = (state.dragging, state.part) {
+        let edit = FloorEdit::Drag(part, state.cursor_pos - state.anchor_pos);
+        if let Some(region) = state.floors.get(part.region()) {
+            state.valid = edit.apply(region).is_some();
+            state.floors[part.region()] =
+                drag_floor_part(region, part, state.cursor_pos - state.anchor_pos);
+        }
+    }
+
+    if *r_state != state {
+        *r_state = state;
+    }
 }
 
 pub fn on_pick_event(
     trigger: Trigger<PickEvent>,
     mut commands: Commands,
-    // q_parcels: Query<(Entity, &Parcel)>,
-    // r_tool: Res<State<TerrainTool>>,
-    // mut r_selected_parcel: ResMut<SelectedParcel>,
-    // mut r_drag_state: ResMut<TerrainDragState>,
-    // r_contours_handle: Res<TerrainContoursHandle>,
-    // r_contours_asset: ResMut<Assets<TerrainContoursTableAsset>>,
+    q_precincts: Query<(Entity, &Precinct)>,
+    r_tool: Res<State<FloorTool>>,
+    r_selected_tier: Res<SelectedTier>,
+    mut r_selected_precinct: ResMut<SelectedPrecinct>,
+    mut r_state: ResMut<FloorEditState>,
 ) {
+    if *r_tool.get() != FloorTool::Move {
+        return;
+    }
+    let selected = r_selected_precinct
+        .0
+        .and_then(|id| q_precincts.get(id).ok())
+        .map(|(_, precinct)| precinct);
+    let tier = r_selected_tier.0 as i32;
+
+    match trigger.event().action {
+        PickAction::Down(_) => {
+            r_state.selected = r_state
+                .part
+                .filter(|part| matches!(part, FloorPart::Vertex { .. }));
+        }
+
+        // Double-clicking an edge splits it, and double-clicking a vertex removes it.
+        PickAction::DblClick => {
+            let (Some(precinct), Some(part)) = (selected, r_state.part) else {
+                return;
+            };
+            let edit = match part {
+                FloorPart::Edge { .. } => {
+                    // Insert on the edge itself, so that splitting it doesn't bend it.
+                    let region = &r_state.floors[part.region()];
+                    let Some(position) = nearest_point_on_edge(region, part, r_state.pick_pos)
+                    else {
+                        return;
+                    };
+                    FloorEdit::InsertVertex(part, position)
+                }
+                FloorPart::Vertex { .. } => FloorEdit::DeleteVertex(part),
+                FloorPart::Region(_) => return,
+            };
+            r_state.selected = None;
+            commands.add(EditFloor {
+                precinct: precinct.asset.clone(),
+                tier,
+                edit,
+            });
+        }
+
+        PickAction::DragStart { realm, pos } => {
+            let precinct_id = q_precincts
+                .iter()
+                .find(|(_, p)| p.realm == realm && p.contains_pt(pos))
+                .map(|(e, _)| e);
+            if r_selected_precinct.0 != precinct_id {
+                r_selected_precinct.0 = precinct_id;
+            } else if r_state.part.is_some() {
+                r_state.dragging = true;
+                r_state.anchor_pos = r_state.cursor_pos;
+            }
+        }
+
+        PickAction::DragEnd => {
+            if !r_state.dragging {
+                return;
+            }
+            r_state.dragging = false;
+            let offset = r_state.cursor_pos - r_state.anchor_pos;
+            let (Some(precinct), Some(part)) = (selected, r_state.part) else {
+                return;
+            };
+            if offset != Vec2::ZERO {
+                commands.add(EditFloor {
+                    precinct: precinct.asset.clone(),
+                    tier,
+                    edit: FloorEdit::Drag(part, offset),
+                });
+            }
+        }
+
+        PickAction::Leave | PickAction::RightClick | PickAction::Drag => {}
+    }
 }
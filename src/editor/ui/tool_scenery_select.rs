@@ -0,0 +1,346 @@
+use bevy::prelude::*;
+use bevy_mod_picking::{focus::HoverMap, prelude::PointerId};
+use bevy_quill::View;
+
+use crate::{
+    editor::{
+        events::{
+            CopySelection, DeleteSelection, DuplicateSelection, PasteSelection, RotateSelection,
+        },
+        scenery::{
+            CopyScenery, EditScenery, PasteScenery, RefreshPrefabs, SceneryClipboard, SceneryEdit,
+            SceneryRef, ScenerySelection,
+        },
+    },
+    scenery::{
+        precinct::Precinct, precinct_asset::PrecinctAsset, scenery_element::SceneryElement,
+        PRECINCT_SIZE_F, TIER_OFFSET,
+    },
+    view::{
+        picking::{PickAction, PickEvent},
+        Viewpoint,
+    },
+};
+
+use super::{
+    mode_scenery::{
+        SceneryOverlay, ScenerySelectState, SelectedFacing, SelectedLayer, SelectedPrecinct,
+        SelectedTier, WallSnap,
+    },
+    overlays::{ScenerySelectionOverlay, SelectedPrecinctOverlay},
+};
+
+/// How far from the cursor, in meters, a scenery element can be clicked.
+const PICK_RADIUS: f32 = 0.75;
+
+#[derive(Clone, Component)]
+pub struct PrecinctOverlay;
+
+pub fn enter_interact(commands: Commands) {
+    enter(commands, SceneryOverlay::Interact);
+}
+
+pub fn enter_rect_select(commands: Commands) {
+    enter(commands, SceneryOverlay::RectSelect);
+}
+
+fn enter(mut commands: Commands, overlay: SceneryOverlay) {
+    commands.spawn((SelectedPrecinctOverlay.to_root(), PrecinctOverlay));
+    commands.spawn((ScenerySelectionOverlay.to_root(), PrecinctOverlay));
+    commands.spawn((StateScoped(overlay.clone()), Observer::new(on_pick_event)));
+    commands.spawn((
+        StateScoped(overlay.clone()),
+        Observer::new(
+            |trigger: Trigger<RotateSelection>, mut commands: Commands| {
+                commands.add(EditScenery(SceneryEdit::Rotate(trigger.event().0)));
+            },
+        ),
+    ));
+    commands.spawn((
+        StateScoped(overlay.clone()),
+        Observer::new(
+            |_trigger: Trigger<DeleteSelection>, mut commands: Commands| {
+                commands.add(EditScenery(SceneryEdit::Delete));
+            },
+        ),
+    ));
+    commands.spawn((
+        StateScoped(overlay.clone()),
+        Observer::new(
+            |_trigger: Trigger<DuplicateSelection>, mut commands: Commands| {
+                commands.add(EditScenery(SceneryEdit::Duplicate(Vec3::new(1., 0., 1.))));
+            },
+        ),
+    ));
+    commands.spawn((
+        StateScoped(overlay.clone()),
+        Observer::new(
+            |_trigger: Trigger<CopySelection>,
+             mut commands: Commands,
+             r_selected_tier: Res<SelectedTier>| {
+                commands.add(CopyScenery {
+                    tier: r_selected_tier.0,
+                });
+            },
+        ),
+    ));
+    commands.spawn((StateScoped(overlay), Observer::new(on_paste)));
+    commands.add(RefreshPrefabs);
+}
+
+/// Paste the clipboard at the cursor, rotated by the selected facing.
+#[allow(clippy::too_many_arguments)]
+fn on_paste(
+    _trigger: Trigger<PasteSelection>,
+    mut commands: Commands,
+    r_clipboard: Res<SceneryClipboard>,
+    r_viewpoint: Res<Viewpoint>,
+    r_state: Res<ScenerySelectState>,
+    r_selected_tier: Res<SelectedTier>,
+    r_selected_facing: Res<SelectedFacing>,
+    r_selected_layer: Res<SelectedLayer>,
+) {
+    let (Some(prefab), Some(realm)) = (&r_clipboard.0, r_viewpoint.realm) else {
+        return;
+    };
+    commands.add(PasteScenery {
+        prefab: prefab.clone(),
+        realm,
+        position: r_state.cursor_pos,
+        tier: r_selected_tier.0,
+        turns: r_selected_facing.0,
+        layer: r_selected_layer.0.clone(),
+    });
+}
+
+pub fn exit(
+    mut commands: Commands,
+    q_overlays: Query<Entity, With<PrecinctOverlay>>,
+    mut r_state: ResMut<ScenerySelectState>,
+) {
+    q_overlays.iter().for_each(|e| commands.entity(e).despawn());
+    *r_state = ScenerySelectState::default();
+}
+
+pub fn update(
+    q_precincts: Query<&Precinct>,
+    mut q_elements: Query<(&SceneryElement, &Parent, &mut Transform)>,
+    r_selection: Res<ScenerySelection>,
+    r_overlay: Res<State<SceneryOverlay>>,
+    r_hover_map: Res<HoverMap>,
+    r_snap: Res<State<WallSnap>>,
+    mut r_state: ResMut<ScenerySelectState>,
+) {
+    let mut state = r_state.clone();
+    if let Some(pos) = r_hover_map
+        .get(&PointerId::Mouse)
+        .and_then(|p| p.values().find_map(|hit_data| hit_data.position))
+    {
+        state.cursor_pos = pos.xz();
+    }
+
+    let rect_select = *r_overlay.get() == SceneryOverlay::RectSelect;
+    state.marquee = (state.dragging && rect_select)
+        .then(|| Rect::from_corners(state.anchor_pos, state.cursor_pos));
+    state.offset = if state.dragging && !rect_select {
+        let quanta = match r_snap.get() {
+            WallSnap::Normal | WallSnap::Offset => 1.0,
+            WallSnap::Quarter => 0.25,
+        };
+        ((state.cursor_pos - state.anchor_pos) / quanta).round() * quanta
+    } else {
+        Vec2::ZERO
+    };
+
+    // Show the selection at its dragged location, and record where to draw highlights.
+    state.highlights.clear();
+    if !r_selection.0.is_empty() {
+        let offset = Vec3::new(state.offset.x, 0., state.offset.y);
+        for (element, parent, mut transform) in q_elements.iter_mut() {
+            let Ok(precinct) = q_precincts.get(parent.get()) else {
+                continue;
+            };
+            if !r_selection.0.contains(&SceneryRef {
+                precinct: precinct.asset.clone(),
+                iid: element.iid.clone(),
+            }) {
+                continue;
+            }
+            let position = element.position + offset;
+            if transform.translation != position {
+                transform.translation = position;
+            }
+            let origin = precinct.coords.as_vec2() * PRECINCT_SIZE_F;
+            state
+                .highlights
+                .push(position + Vec3::new(origin.x, 0., origin.y));
+        }
+    }
+
+    if *r_state != state {
+        *r_state = state;
+    }
+}
+
+/// Scenery elements in a realm which can be selected on the given tier, along with their
+/// world-space positions. Elements in locked layers can't be selected.
+fn selectable_scenery(
+    realm: Entity,
+    tier: i16,
+    q_precincts: &Query<(Entity, &Precinct)>,
+    q_elements: &Query<(&SceneryElement, &Parent)>,
+    precinct_assets: &Assets<PrecinctAsset>,
+) -> Vec<(SceneryRef, Vec2)> {
+    let height = tier as f32 + TIER_OFFSET;
+    q_elements
+        .iter()
+        .filter_map(|(element, parent)| {
+            let (_, precinct) = q_precincts.get(parent.get()).ok()?;
+            if precinct.realm != realm || (element.position.y - height).abs() >= 0.5 {
+                return None;
+            }
+            if let Some(asset) = precinct_assets.get(&precinct.asset) {
+                let locked = asset
+                    .scenery
+                    .iter()
+                    .find(|s| s.iid == element.iid)
+                    .is_some_and(|s| asset.is_layer_locked(s.layer.as_deref()));
+                if locked {
+                    return None;
+                }
+            }
+            let origin = precinct.coords.as_vec2() * PRECINCT_SIZE_F;
+            Some((
+                SceneryRef {
+                    precinct: precinct.asset.clone(),
+                    iid: element.iid.clone(),
+                },
+                element.position.xz() + origin,
+            ))
+        })
+        .collect()
+}
+
+/// The selectable scenery element nearest to a world-space position, if any is in reach.
+fn pick_scenery(
+    pos: Vec3,
+    realm: Entity,
+    tier: i16,
+    q_precincts: &Query<(Entity, &Precinct)>,
+    q_elements: &Query<(&SceneryElement, &Parent)>,
+    precinct_assets: &Assets<PrecinctAsset>,
+) -> Option<SceneryRef> {
+    selectable_scenery(realm, tier, q_precincts, q_elements, precinct_assets)
+        .into_iter()
+        .map(|(sref, element_pos)| (sref, element_pos.distance(pos.xz())))
+        .filter(|(_, dist)| *dist < PICK_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(sref, _)| sref)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn on_pick_event(
+    trigger: Trigger<PickEvent>,
+    mut commands: Commands,
+    q_precincts: Query<(Entity, &Precinct)>,
+    q_elements: Query<(&SceneryElement, &Parent)>,
+    r_precinct_assets: Res<Assets<PrecinctAsset>>,
+    r_viewpoint: Res<Viewpoint>,
+    r_keys: Res<ButtonInput<KeyCode>>,
+    r_overlay: Res<State<SceneryOverlay>>,
+    r_selected_tier: Res<SelectedTier>,
+    mut r_selected_precinct: ResMut<SelectedPrecinct>,
+    mut r_selection: ResMut<ScenerySelection>,
+    mut r_state: ResMut<ScenerySelectState>,
+) {
+    let event = trigger.event();
+    let Some(realm) = r_viewpoint.realm else {
+        return;
+    };
+    let tier = r_selected_tier.0;
+    let shift = r_keys.pressed(KeyCode::ShiftLeft) || r_keys.pressed(KeyCode::ShiftRight);
+
+    match event.action {
+        PickAction::Down(pos) => {
+            let precinct_id = q_precincts
+                .iter()
+                .find(|(_, p)| p.realm == realm && p.contains_pt(pos))
+                .map(|(e, _)| e);
+            if r_selected_precinct.0 != precinct_id {
+                r_selected_precinct.0 = precinct_id;
+            }
+
+            let hit = pick_scenery(
+                pos,
+                realm,
+                tier,
+                &q_precincts,
+                &q_elements,
+                &r_precinct_assets,
+            );
+            match hit {
+                // Shift-click toggles an element in or out of the selection.
+                Some(hit) if shift => {
+                    if !r_selection.0.remove(&hit) {
+                        r_selection.0.insert(hit);
+                    }
+                }
+                // Clicking a selected element keeps the selection so that it can be dragged.
+                Some(hit) if r_selection.0.contains(&hit) => {}
+                Some(hit) => {
+                    r_selection.0.clear();
+                    r_selection.0.insert(hit);
+                }
+                None if shift => {}
+                None => {
+                    if !r_selection.0.is_empty() {
+                        r_selection.0.clear();
+                    }
+                }
+            }
+        }
+
+        PickAction::DragStart { pos, .. } => {
+            r_state.anchor_pos = pos.xz();
+            r_state.cursor_pos = pos.xz();
+            r_state.dragging = match r_overlay.get() {
+                SceneryOverlay::RectSelect => true,
+                _ => pick_scenery(
+                    pos,
+                    realm,
+                    tier,
+                    &q_precincts,
+                    &q_elements,
+                    &r_precinct_assets,
+                )
+                .is_some_and(|hit| r_selection.0.contains(&hit)),
+            };
+        }
+
+        PickAction::DragEnd => {
+            if !r_state.dragging {
+                return;
+            }
+            r_state.dragging = false;
+            if *r_overlay.get() == SceneryOverlay::RectSelect {
+                let rect = Rect::from_corners(r_state.anchor_pos, r_state.cursor_pos);
+                if !shift {
+                    r_selection.0.clear();
+                }
+                r_selection.0.extend(
+                    selectable_scenery(realm, tier, &q_precincts, &q_elements, &r_precinct_assets)
+                        .into_iter()
+                        .filter(|(_, pos)| rect.contains(*pos))
+                        .map(|(sref, _)| sref),
+                );
+            } else if r_state.offset != Vec2::ZERO {
+                let offset = r_state.offset;
+                commands.add(EditScenery(SceneryEdit::Move(Vec3::new(
+                    offset.x, 0., offset.y,
+                ))));
+            }
+        }
+
+        PickAction::Leave | PickAction::RightClick | PickAction::DblClick | PickAction::Drag => {}
+    }
+}
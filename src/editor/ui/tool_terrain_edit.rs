@@ -1,15 +1,16 @@
 use crate::{
     editor::{
-        events::{ChangeContourEvent, ModifyTerrainMapEvent, RotateSelection},
+        events::{AutoTileTerrainMap, ChangeContourEvent, ModifyTerrainMapEvent, RotateSelection},
         terrain::{UndoTerrainContourEdit, UndoTerrainMapEdit},
         undo::UndoStack,
         unsaved::{ModifiedState, UnsavedAssets},
         DragShape, EditorMode, SelectedParcel, TerrainDragState,
     },
     terrain::{
+        contour_brush::{ContourBrush, SculptOp},
         terrain_contours::{FloraType, TerrainContoursHandle, TerrainContoursTableAsset},
-        Parcel, ParcelFloraChanged, ParcelWaterChanged, RebuildParcelGroundMesh, ShapeRef,
-        TerrainMap, TerrainMapAsset, PARCEL_SIZE, PARCEL_SIZE_U,
+        Parcel, ParcelFloraChanged, ParcelWater, ParcelWaterChanged, RebuildParcelGroundMesh,
+        ShapeRef, TerrainMap, TerrainMapAsset, PARCEL_SIZE, PARCEL_SIZE_F, PARCEL_SIZE_U,
     },
     view::picking::{PickAction, PickEvent, PickTarget},
 };
@@ -18,8 +19,14 @@ use bevy_mod_picking::{focus::HoverMap, prelude::PointerId};
 use bevy_quill::View;
 
 use super::{
-    mode_terrain::TerrainTool,
-    overlays::{MapBoundsOverlay, SelectedParcelOverlay, TerrainCursorOverlay},
+    mode_terrain::{
+        AutoTileIntent, BiomeBrushRadius, SculptBrushSettings, SelectedBiome, TerrainTool,
+        WaterBrushSettings,
+    },
+    overlays::{
+        AutoTileBrushOverlay, BiomeBrushOverlay, MapBoundsOverlay, SelectedParcelOverlay,
+        TerrainCursorOverlay, TerrainSeamsOverlay, WaterBrushOverlay,
+    },
 };
 
 #[derive(Clone, Component)]
@@ -28,14 +35,76 @@ pub struct ParcelOverlay;
 #[derive(Clone, Component)]
 pub struct MapOverlay;
 
+/// State of the biome brush while hovering or painting.
+#[derive(Resource, Default)]
+pub(crate) struct BiomeBrushState {
+    /// Realm whose terrain map is being painted.
+    pub(crate) realm: Option<Entity>,
+
+    /// Terrain map point (parcel corner) under the cursor.
+    pub(crate) cursor: Option<IVec2>,
+
+    /// Copy of the terrain map at the start of the current stroke, used for undo.
+    pub(crate) stroke_before: Option<TerrainMapAsset>,
+}
+
+/// State of the auto-tiling brush while hovering or painting.
+#[derive(Resource, Default)]
+pub(crate) struct AutoTileStrokeState {
+    /// Realm whose terrain map is being painted.
+    pub(crate) realm: Option<Entity>,
+
+    /// Parcel under the cursor.
+    pub(crate) cursor: Option<IVec2>,
+
+    /// Parcels painted so far in the current stroke.
+    pub(crate) parcels: Vec<IVec2>,
+}
+
+/// State of the water level and flow brushes while hovering or painting.
+#[derive(Resource, Default)]
+pub(crate) struct WaterBrushState {
+    /// Realm whose terrain map is being painted.
+    pub(crate) realm: Option<Entity>,
+
+    /// Parcel under the cursor.
+    pub(crate) cursor: Option<IVec2>,
+
+    /// Position of the cursor when the flow was last painted, used to find the direction of
+    /// the stroke.
+    pub(crate) last_pos: Option<Vec2>,
+
+    /// Copy of the terrain map at the start of the current stroke, used for undo.
+    pub(crate) stroke_before: Option<TerrainMapAsset>,
+}
+
+/// Distance the cursor has to move before the direction of a flow stroke is updated.
+const FLOW_STROKE_STEP: f32 = 2.;
+
 pub fn enter(mut commands: Commands) {
     commands.spawn((SelectedParcelOverlay.to_root(), ParcelOverlay));
     commands.spawn((TerrainCursorOverlay.to_root(), ParcelOverlay));
     commands.spawn((MapBoundsOverlay.to_root(), ParcelOverlay));
+    commands.spawn((BiomeBrushOverlay.to_root(), ParcelOverlay));
+    commands.spawn((TerrainSeamsOverlay.to_root(), ParcelOverlay));
+    commands.spawn((AutoTileBrushOverlay.to_root(), ParcelOverlay));
+    commands.spawn((WaterBrushOverlay.to_root(), ParcelOverlay));
     commands.spawn((
         StateScoped(EditorMode::Terrain),
         Observer::new(on_pick_event),
     ));
+    commands.spawn((
+        StateScoped(EditorMode::Terrain),
+        Observer::new(on_biome_pick_event),
+    ));
+    commands.spawn((
+        StateScoped(EditorMode::Terrain),
+        Observer::new(on_autotile_pick_event),
+    ));
+    commands.spawn((
+        StateScoped(EditorMode::Terrain),
+        Observer::new(on_water_pick_event),
+    ));
     commands.spawn((
         StateScoped(EditorMode::Terrain),
         Observer::new(on_modify_terrain),
@@ -90,12 +159,14 @@ pub fn exit(mut commands: Commands, q_overlays: Query<Entity, With<ParcelOverlay
     q_overlays.iter().for_each(|e| commands.entity(e).despawn());
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn hover(
     mut commands: Commands,
     r_selected_parcel: Res<SelectedParcel>,
     mut r_drag_state: ResMut<TerrainDragState>,
     r_hover_map: Res<HoverMap>,
     r_tool: Res<State<TerrainTool>>,
+    r_sculpt_brush: Res<SculptBrushSettings>,
     r_contours_handle: Res<TerrainContoursHandle>,
     r_contours_asset: ResMut<Assets<TerrainContoursTableAsset>>,
     q_parcels: Query<&Parcel>,
@@ -115,7 +186,8 @@ pub fn hover(
                         match tool {
                             TerrainTool::RaiseDraw
                             | TerrainTool::LowerDraw
-                            | TerrainTool::FlattenDraw => {
+                            | TerrainTool::FlattenDraw
+                            | TerrainTool::SmoothDraw => {
                                 if let Some(pickpos) =
                                     terrain_pick_pos(DragShape::Point, rpos, true)
                                 {
@@ -148,6 +220,7 @@ pub fn hover(
                                             *tool,
                                             &drag_state,
                                             parcel,
+                                            r_sculpt_brush.brush(),
                                             r_contours_handle,
                                             r_contours_asset,
                                         );
@@ -194,7 +267,9 @@ pub fn hover(
                             TerrainTool::DrawTrees
                             | TerrainTool::DrawShrubs
                             | TerrainTool::DrawHerbs
-                            | TerrainTool::EraseFlora => {
+                            | TerrainTool::EraseFlora
+                            | TerrainTool::DrawWater
+                            | TerrainTool::EraseWater => {
                                 if let Some(pickpos) =
                                     terrain_pick_pos(DragShape::DecalRect, rpos, true)
                                 {
@@ -207,6 +282,7 @@ pub fn hover(
                                                 *tool,
                                                 &drag_state,
                                                 parcel,
+                                                r_sculpt_brush.brush(),
                                                 r_contours_handle,
                                                 r_contours_asset,
                                             );
@@ -222,6 +298,11 @@ pub fn hover(
                                     break;
                                 }
                             }
+                            TerrainTool::PaintBiome
+                            | TerrainTool::FillBiome
+                            | TerrainTool::AutoTile
+                            | TerrainTool::WaterLevel
+                            | TerrainTool::WaterFlow => {}
                         }
                         break;
                     }
@@ -235,11 +316,13 @@ pub fn hover(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn on_pick_event(
     trigger: Trigger<PickEvent>,
     mut commands: Commands,
     q_parcels: Query<(Entity, &Parcel)>,
     r_tool: Res<State<TerrainTool>>,
+    r_sculpt_brush: Res<SculptBrushSettings>,
     mut r_selected_parcel: ResMut<SelectedParcel>,
     mut r_drag_state: ResMut<TerrainDragState>,
     r_contours_handle: Res<TerrainContoursHandle>,
@@ -249,6 +332,18 @@ pub fn on_pick_event(
 ) {
     let event = trigger.event();
     let tool = r_tool.get();
+    if matches!(
+        tool,
+        TerrainTool::PaintBiome
+            | TerrainTool::FillBiome
+            | TerrainTool::AutoTile
+            | TerrainTool::WaterLevel
+            | TerrainTool::WaterFlow
+    ) {
+        // Handled by `on_biome_pick_event`, `on_autotile_pick_event` and
+        // `on_water_pick_event`.
+        return;
+    }
     match event.action {
         PickAction::Leave => {
             // *r_parcel_cursor = ParcelCursor::None;
@@ -268,11 +363,14 @@ pub fn on_pick_event(
                         TerrainTool::RaiseDraw
                         | TerrainTool::LowerDraw
                         | TerrainTool::FlattenDraw
+                        | TerrainTool::SmoothDraw
                         | TerrainTool::FlattenRect
                         | TerrainTool::DrawTrees
                         | TerrainTool::DrawShrubs
                         | TerrainTool::DrawHerbs
-                        | TerrainTool::EraseFlora => {
+                        | TerrainTool::EraseFlora
+                        | TerrainTool::DrawWater
+                        | TerrainTool::EraseWater => {
                             let parcel = q_parcels.get(parcel_id).unwrap().1;
                             let shape_ref = parcel.center_shape();
                             if let Some(contours) = r_contours_asset.get(&r_contours_handle.0) {
@@ -292,6 +390,7 @@ pub fn on_pick_event(
                                 *tool,
                                 &r_drag_state,
                                 parcel,
+                                r_sculpt_brush.brush(),
                                 r_contours_handle,
                                 r_contours_asset,
                             );
@@ -331,6 +430,7 @@ pub fn on_pick_event(
                                 *tool,
                                 &r_drag_state,
                                 parcel,
+                                r_sculpt_brush.brush(),
                                 r_contours_handle,
                                 r_contours_asset,
                             );
@@ -381,6 +481,344 @@ pub fn on_modify_terrain(
         .insert(terrain_map.handle.clone(), ModifiedState::Unsaved);
 }
 
+/// Track the terrain map point under the cursor, and continue the current biome stroke.
+pub fn hover_biome(
+    r_tool: Res<State<TerrainTool>>,
+    r_hover_map: Res<HoverMap>,
+    r_selected_biome: Res<SelectedBiome>,
+    r_brush_radius: Res<BiomeBrushRadius>,
+    mut r_brush: ResMut<BiomeBrushState>,
+    q_terrain_map: Query<&TerrainMap>,
+    mut r_terrain_map_assets: ResMut<Assets<TerrainMapAsset>>,
+) {
+    let tool = *r_tool.get();
+    if !matches!(tool, TerrainTool::PaintBiome | TerrainTool::FillBiome) {
+        if r_brush.cursor.is_some() {
+            r_brush.cursor = None;
+        }
+        return;
+    }
+
+    let cursor = r_hover_map
+        .get(&PointerId::Mouse)
+        .and_then(|p| p.values().find_map(|hit_data| hit_data.position))
+        .map(biome_pick_pos);
+    if r_brush.cursor == cursor {
+        return;
+    }
+    r_brush.cursor = cursor;
+
+    // Continue painting if we're in the middle of a stroke.
+    if tool != TerrainTool::PaintBiome || r_brush.stroke_before.is_none() {
+        return;
+    }
+    let (Some(pt), Some(realm)) = (cursor, r_brush.realm) else {
+        return;
+    };
+    let Ok(terrain_map) = q_terrain_map.get(realm) else {
+        return;
+    };
+    if let Some(map) = r_terrain_map_assets.get_mut(&terrain_map.handle) {
+        map.paint_biome(pt, r_brush_radius.0, r_selected_biome.0);
+    }
+}
+
+/// Handle picking events for the biome paint and fill tools.
+pub fn on_biome_pick_event(
+    trigger: Trigger<PickEvent>,
+    r_tool: Res<State<TerrainTool>>,
+    r_selected_biome: Res<SelectedBiome>,
+    r_brush_radius: Res<BiomeBrushRadius>,
+    mut r_brush: ResMut<BiomeBrushState>,
+    q_terrain_map: Query<&TerrainMap>,
+    mut r_terrain_map_assets: ResMut<Assets<TerrainMapAsset>>,
+    mut r_undo_stack: ResMut<UndoStack>,
+    mut r_unsaved: ResMut<UnsavedAssets>,
+) {
+    let tool = *r_tool.get();
+    if !matches!(tool, TerrainTool::PaintBiome | TerrainTool::FillBiome) {
+        return;
+    }
+    match trigger.event().action {
+        PickAction::DragStart { realm, pos } => {
+            let Ok(terrain_map) = q_terrain_map.get(realm) else {
+                warn!("No terrain map for realm: {:?}", realm);
+                return;
+            };
+            let Some(map) = r_terrain_map_assets.get_mut(&terrain_map.handle) else {
+                return;
+            };
+            let pt = biome_pick_pos(pos);
+            match tool {
+                TerrainTool::PaintBiome => {
+                    r_brush.realm = Some(realm);
+                    r_brush.stroke_before = Some(map.clone());
+                    map.paint_biome(pt, r_brush_radius.0, r_selected_biome.0);
+                }
+                TerrainTool::FillBiome => {
+                    let before = map.clone();
+                    if map.fill_biome(pt, r_selected_biome.0) {
+                        r_undo_stack.push(UndoTerrainMapEdit {
+                            label: "Fill Biome",
+                            handle: terrain_map.handle.clone(),
+                            before,
+                            after: map.clone(),
+                        });
+                        r_unsaved
+                            .terrain_maps
+                            .insert(terrain_map.handle.clone(), ModifiedState::Unsaved);
+                    }
+                }
+                _ => unreachable!(),
+            }
+        }
+        PickAction::DragEnd => {
+            let Some(before) = r_brush.stroke_before.take() else {
+                return;
+            };
+            let Some(realm) = r_brush.realm else {
+                return;
+            };
+            let Ok(terrain_map) = q_terrain_map.get(realm) else {
+                return;
+            };
+            let Some(map) = r_terrain_map_assets.get(&terrain_map.handle) else {
+                return;
+            };
+            if map.biomes != before.biomes {
+                r_undo_stack.push(UndoTerrainMapEdit {
+                    label: "Paint Biome",
+                    handle: terrain_map.handle.clone(),
+                    before,
+                    after: map.clone(),
+                });
+                r_unsaved
+                    .terrain_maps
+                    .insert(terrain_map.handle.clone(), ModifiedState::Unsaved);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Track the parcel under the cursor, and add it to the current auto-tile stroke.
+pub fn hover_autotile(
+    r_tool: Res<State<TerrainTool>>,
+    r_hover_map: Res<HoverMap>,
+    mut r_stroke: ResMut<AutoTileStrokeState>,
+) {
+    if *r_tool.get() != TerrainTool::AutoTile {
+        if r_stroke.cursor.is_some() {
+            r_stroke.cursor = None;
+        }
+        return;
+    }
+
+    let cursor = r_hover_map
+        .get(&PointerId::Mouse)
+        .and_then(|p| p.values().find_map(|hit_data| hit_data.position))
+        .map(parcel_pick_pos);
+    if r_stroke.cursor == cursor {
+        return;
+    }
+    r_stroke.cursor = cursor;
+    if let Some(pt) = cursor {
+        if r_stroke.realm.is_some() && !r_stroke.parcels.contains(&pt) {
+            r_stroke.parcels.push(pt);
+        }
+    }
+}
+
+/// Handle picking events for the auto-tiling brush. The painted parcels are collected during
+/// the stroke, and tiled all at once when the stroke ends.
+pub fn on_autotile_pick_event(
+    trigger: Trigger<PickEvent>,
+    mut commands: Commands,
+    r_tool: Res<State<TerrainTool>>,
+    r_intent: Res<AutoTileIntent>,
+    mut r_stroke: ResMut<AutoTileStrokeState>,
+) {
+    if *r_tool.get() != TerrainTool::AutoTile {
+        return;
+    }
+    match trigger.event().action {
+        PickAction::DragStart { realm, pos } => {
+            r_stroke.realm = Some(realm);
+            r_stroke.parcels = vec![parcel_pick_pos(pos)];
+        }
+        PickAction::DragEnd => {
+            let Some(realm) = r_stroke.realm.take() else {
+                return;
+            };
+            commands.trigger(AutoTileTerrainMap {
+                realm,
+                parcels: std::mem::take(&mut r_stroke.parcels),
+                intent: r_intent.0,
+            });
+        }
+        _ => {}
+    }
+}
+
+/// Track the parcel under the cursor, and continue the current water stroke.
+pub fn hover_water(
+    r_tool: Res<State<TerrainTool>>,
+    r_hover_map: Res<HoverMap>,
+    r_settings: Res<WaterBrushSettings>,
+    mut r_brush: ResMut<WaterBrushState>,
+    q_terrain_map: Query<&TerrainMap>,
+    mut r_terrain_map_assets: ResMut<Assets<TerrainMapAsset>>,
+) {
+    let tool = *r_tool.get();
+    if !matches!(tool, TerrainTool::WaterLevel | TerrainTool::WaterFlow) {
+        if r_brush.cursor.is_some() {
+            r_brush.cursor = None;
+        }
+        return;
+    }
+
+    let pos = r_hover_map
+        .get(&PointerId::Mouse)
+        .and_then(|p| p.values().find_map(|hit_data| hit_data.position));
+    let cursor = pos.map(parcel_pick_pos);
+    if r_brush.cursor != cursor {
+        r_brush.cursor = cursor;
+    }
+
+    // Continue painting if we're in the middle of a stroke.
+    if r_brush.stroke_before.is_none() {
+        return;
+    }
+    let (Some(pos), Some(pt), Some(realm)) = (pos, cursor, r_brush.realm) else {
+        return;
+    };
+    let Ok(terrain_map) = q_terrain_map.get(realm) else {
+        return;
+    };
+    match tool {
+        TerrainTool::WaterLevel => {
+            // Only write to the asset when something changes, since every write triggers
+            // asset events.
+            let level = r_settings.level;
+            let paint = |w: &mut ParcelWater| w.level = level;
+            if r_terrain_map_assets
+                .get(&terrain_map.handle)
+                .is_some_and(|map| map.would_paint_water(pt, r_settings.radius, paint))
+            {
+                if let Some(map) = r_terrain_map_assets.get_mut(&terrain_map.handle) {
+                    map.paint_water(pt, r_settings.radius, paint);
+                }
+            }
+        }
+        TerrainTool::WaterFlow => {
+            // The current flows in the direction of the stroke.
+            let pos = pos.xz();
+            let Some(last_pos) = r_brush.last_pos else {
+                r_brush.last_pos = Some(pos);
+                return;
+            };
+            if pos.distance(last_pos) < FLOW_STROKE_STEP {
+                return;
+            }
+            r_brush.last_pos = Some(pos);
+            let flow = (pos - last_pos).normalize() * r_settings.speed;
+            let paint = |w: &mut ParcelWater| w.flow = flow;
+            if r_terrain_map_assets
+                .get(&terrain_map.handle)
+                .is_some_and(|map| map.would_paint_water(pt, r_settings.radius, paint))
+            {
+                if let Some(map) = r_terrain_map_assets.get_mut(&terrain_map.handle) {
+                    map.paint_water(pt, r_settings.radius, paint);
+                }
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Handle picking events for the water level and flow brushes.
+#[allow(clippy::too_many_arguments)]
+pub fn on_water_pick_event(
+    trigger: Trigger<PickEvent>,
+    r_tool: Res<State<TerrainTool>>,
+    r_settings: Res<WaterBrushSettings>,
+    mut r_brush: ResMut<WaterBrushState>,
+    q_terrain_map: Query<&TerrainMap>,
+    mut r_terrain_map_assets: ResMut<Assets<TerrainMapAsset>>,
+    mut r_undo_stack: ResMut<UndoStack>,
+    mut r_unsaved: ResMut<UnsavedAssets>,
+) {
+    let tool = *r_tool.get();
+    if !matches!(tool, TerrainTool::WaterLevel | TerrainTool::WaterFlow) {
+        return;
+    }
+    match trigger.event().action {
+        PickAction::DragStart { realm, pos } => {
+            let Ok(terrain_map) = q_terrain_map.get(realm) else {
+                warn!("No terrain map for realm: {:?}", realm);
+                return;
+            };
+            let Some(map) = r_terrain_map_assets.get_mut(&terrain_map.handle) else {
+                return;
+            };
+            r_brush.realm = Some(realm);
+            r_brush.stroke_before = Some(map.clone());
+            r_brush.last_pos = Some(pos.xz());
+            if tool == TerrainTool::WaterLevel {
+                let level = r_settings.level;
+                map.paint_water(parcel_pick_pos(pos), r_settings.radius, |w| w.level = level);
+            }
+        }
+        PickAction::DragEnd => {
+            r_brush.last_pos = None;
+            let Some(before) = r_brush.stroke_before.take() else {
+                return;
+            };
+            let Some(realm) = r_brush.realm else {
+                return;
+            };
+            let Ok(terrain_map) = q_terrain_map.get(realm) else {
+                return;
+            };
+            let Some(map) = r_terrain_map_assets.get(&terrain_map.handle) else {
+                return;
+            };
+            if map.water != before.water {
+                r_undo_stack.push(UndoTerrainMapEdit {
+                    label: match tool {
+                        TerrainTool::WaterLevel => "Paint Water Level",
+                        _ => "Paint Water Flow",
+                    },
+                    handle: terrain_map.handle.clone(),
+                    before,
+                    after: map.clone(),
+                });
+                r_unsaved
+                    .terrain_maps
+                    .insert(terrain_map.handle.clone(), ModifiedState::Unsaved);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Convert a world position into the coordinates of the parcel containing it.
+fn parcel_pick_pos(pos: Vec3) -> IVec2 {
+    IVec2::new(
+        (pos.x / PARCEL_SIZE_F).floor() as i32,
+        (pos.z / PARCEL_SIZE_F).floor() as i32,
+    )
+}
+
+/// Convert a world position into the nearest terrain map point, which is the corner of a
+/// parcel.
+fn biome_pick_pos(pos: Vec3) -> IVec2 {
+    IVec2::new(
+        (pos.x / PARCEL_SIZE_F).round() as i32,
+        (pos.z / PARCEL_SIZE_F).round() as i32,
+    )
+}
+
 fn terrain_pick_pos(drag_shape: DragShape, pos: Vec2, clamp: bool) -> Option<IVec2> {
     match drag_shape {
         DragShape::None => None,
@@ -413,18 +851,22 @@ fn modify_terrain_contour(
     tool: TerrainTool,
     drag_state: &TerrainDragState,
     parcel: &Parcel,
+    brush: ContourBrush,
     r_contours_handle: Res<TerrainContoursHandle>,
     mut r_contours_asset: ResMut<Assets<TerrainContoursTableAsset>>,
 ) {
     match tool {
-        TerrainTool::RaiseDraw | TerrainTool::LowerDraw | TerrainTool::FlattenDraw => {
-            let new_height = match tool {
-                TerrainTool::RaiseDraw => drag_state.anchor_height + 1,
-                TerrainTool::LowerDraw => drag_state.anchor_height - 1,
-                TerrainTool::FlattenDraw => drag_state.anchor_height,
+        TerrainTool::RaiseDraw
+        | TerrainTool::LowerDraw
+        | TerrainTool::FlattenDraw
+        | TerrainTool::SmoothDraw => {
+            let op = match tool {
+                TerrainTool::RaiseDraw => SculptOp::Raise(drag_state.anchor_height + 1),
+                TerrainTool::LowerDraw => SculptOp::Lower(drag_state.anchor_height - 1),
+                TerrainTool::FlattenDraw => SculptOp::Flatten(drag_state.anchor_height),
+                TerrainTool::SmoothDraw => SculptOp::Smooth,
                 _ => unreachable!(),
-            }
-            .clamp(i8::MIN as i32, i8::MAX as i32);
+            };
             let shape_ref = parcel.center_shape();
             if let Some(contours) = r_contours_asset.get_mut(&r_contours_handle.0) {
                 let mut lock = contours.0.write().unwrap();
@@ -432,12 +874,7 @@ fn modify_terrain_contour(
                     .cursor_pos
                     .clamp(IVec2::ZERO, IVec2::splat(PARCEL_SIZE));
                 let contour = lock.get_mut(shape_ref.shape as usize);
-                contour.set_height_at(
-                    pos.x as usize,
-                    pos.y as usize,
-                    shape_ref.rotation,
-                    new_height as i8,
-                );
+                brush.apply(contour, shape_ref.rotation, pos.as_vec2(), op);
             }
         }
         TerrainTool::RaiseRect | TerrainTool::LowerRect | TerrainTool::FlattenRect => {
@@ -472,6 +909,13 @@ fn modify_terrain_contour(
         | TerrainTool::DrawShrubs
         | TerrainTool::DrawHerbs
         | TerrainTool::EraseFlora => {
+            let flora = match tool {
+                TerrainTool::DrawTrees => FloraType::RandomTree,
+                TerrainTool::DrawShrubs => FloraType::RandomShrub,
+                TerrainTool::DrawHerbs => FloraType::RandomHerb,
+                TerrainTool::EraseFlora => FloraType::None,
+                _ => unreachable!(),
+            };
             let shape_ref = parcel.center_shape();
             if let Some(contours) = r_contours_asset.get_mut(&r_contours_handle.0) {
                 let mut lock = contours.0.write().unwrap();
@@ -479,19 +923,34 @@ fn modify_terrain_contour(
                     .cursor_pos
                     .clamp(IVec2::ZERO, IVec2::splat(PARCEL_SIZE - 1));
                 let contour = lock.get_mut(shape_ref.shape as usize);
-                contour.set_flora_at(
-                    pos.x as usize,
-                    pos.y as usize,
+                brush.apply(
+                    contour,
                     shape_ref.rotation,
-                    match tool {
-                        TerrainTool::DrawTrees => FloraType::RandomTree,
-                        TerrainTool::DrawShrubs => FloraType::RandomShrub,
-                        TerrainTool::DrawHerbs => FloraType::RandomHerb,
-                        TerrainTool::EraseFlora => FloraType::None,
-                        _ => unreachable!(),
-                    },
+                    pos.as_vec2() + Vec2::splat(0.5),
+                    SculptOp::Flora(flora),
+                );
+            }
+        }
+        TerrainTool::DrawWater | TerrainTool::EraseWater => {
+            let shape_ref = parcel.center_shape();
+            if let Some(contours) = r_contours_asset.get_mut(&r_contours_handle.0) {
+                let mut lock = contours.0.write().unwrap();
+                let pos = drag_state
+                    .cursor_pos
+                    .clamp(IVec2::ZERO, IVec2::splat(PARCEL_SIZE - 1));
+                let contour = lock.get_mut(shape_ref.shape as usize);
+                brush.apply(
+                    contour,
+                    shape_ref.rotation,
+                    pos.as_vec2() + Vec2::splat(0.5),
+                    SculptOp::Water(tool == TerrainTool::DrawWater),
                 );
             }
         }
+        TerrainTool::PaintBiome
+        | TerrainTool::FillBiome
+        | TerrainTool::AutoTile
+        | TerrainTool::WaterLevel
+        | TerrainTool::WaterFlow => {}
     }
 }
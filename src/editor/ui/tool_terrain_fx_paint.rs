@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+use bevy_mod_picking::{focus::HoverMap, prelude::PointerId};
+use bevy_quill::View;
+
+use crate::{
+    editor::scenery::{EndTerrainFxStroke, PaintTerrainFx},
+    view::picking::{PickAction, PickEvent},
+};
+
+use super::{
+    mode_scenery::{SceneryOverlay, TerrainFxBrushRadius, TerrainFxBrushState, TerrainFxType},
+    overlays::TerrainFxBrushOverlay,
+};
+
+#[derive(Clone, Component)]
+pub struct TerrainFxOverlay;
+
+pub fn enter(mut commands: Commands) {
+    commands.spawn((TerrainFxBrushOverlay.to_root(), TerrainFxOverlay));
+    commands.spawn((
+        StateScoped(SceneryOverlay::DrawTerrainFx),
+        Observer::new(on_pick_event),
+    ));
+}
+
+pub fn exit(
+    mut commands: Commands,
+    q_overlays: Query<Entity, With<TerrainFxOverlay>>,
+    mut r_brush: ResMut<TerrainFxBrushState>,
+) {
+    q_overlays.iter().for_each(|e| commands.entity(e).despawn());
+    if r_brush.painting {
+        commands.add(EndTerrainFxStroke);
+    }
+    *r_brush = TerrainFxBrushState::default();
+}
+
+/// Track the terrain vertex under the cursor, and paint it if a stroke is in progress.
+pub fn update(
+    mut commands: Commands,
+    r_hover_map: Res<HoverMap>,
+    r_fx_type: Res<TerrainFxType>,
+    r_radius: Res<TerrainFxBrushRadius>,
+    r_server: Res<AssetServer>,
+    mut r_brush: ResMut<TerrainFxBrushState>,
+) {
+    let hit = r_hover_map
+        .get(&PointerId::Mouse)
+        .and_then(|p| p.values().find_map(|hit_data| hit_data.position));
+    let cursor = hit.map(|pos| pos.xz().round().as_ivec2());
+    if r_brush.cursor == cursor {
+        return;
+    }
+    r_brush.cursor = cursor;
+    if let Some(pos) = hit {
+        r_brush.height = pos.y;
+    }
+
+    // Continue painting if we're in the middle of a stroke.
+    let (Some(center), Some(realm), true) = (cursor, r_brush.realm, r_brush.painting) else {
+        return;
+    };
+    commands.add(PaintTerrainFx {
+        realm,
+        center,
+        radius: r_radius.0,
+        fx_type: fx_type_path(&r_server, &r_fx_type),
+    });
+}
+
+/// Exemplar path of the selected terrain effect, or `None` when erasing.
+fn fx_type_path(server: &AssetServer, fx_type: &TerrainFxType) -> Option<String> {
+    fx_type
+        .0
+        .and_then(|id| server.get_path(id))
+        .map(|path| path.to_string())
+}
+
+pub fn on_pick_event(
+    trigger: Trigger<PickEvent>,
+    mut commands: Commands,
+    r_fx_type: Res<TerrainFxType>,
+    r_radius: Res<TerrainFxBrushRadius>,
+    r_server: Res<AssetServer>,
+    mut r_brush: ResMut<TerrainFxBrushState>,
+) {
+    match trigger.event().action {
+        PickAction::DragStart { realm, pos } => {
+            let center = pos.xz().round().as_ivec2();
+            r_brush.realm = Some(realm);
+            r_brush.cursor = Some(center);
+            r_brush.painting = true;
+            commands.add(PaintTerrainFx {
+                realm,
+                center,
+                radius: r_radius.0,
+                fx_type: fx_type_path(&r_server, &r_fx_type),
+            });
+        }
+        PickAction::DragEnd => {
+            if r_brush.painting {
+                r_brush.painting = false;
+                commands.add(EndTerrainFxStroke);
+            }
+        }
+        _ => {}
+    }
+}
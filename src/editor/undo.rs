@@ -2,12 +2,18 @@ use bevy::{ecs::world::Command, prelude::*};
 
 pub trait UndoEntry: Send + Sync + 'static {
     fn label(&self) -> &str;
-    fn undo(&self, world: &mut World) -> Box<dyn RedoEntry>;
+
+    /// Reverts the change, returning the entry which will redo it. Returns `None` if the
+    /// change could not be reverted, in which case the world must be left as it was.
+    fn undo(&self, world: &mut World) -> Option<Box<dyn RedoEntry>>;
 }
 
 pub trait RedoEntry: Send + Sync + 'static {
     fn label(&self) -> &str;
-    fn redo(&self, world: &mut World) -> Box<dyn UndoEntry>;
+
+    /// Re-applies the change, returning the entry which will undo it. Returns `None` if the
+    /// change could not be re-applied, in which case the world must be left as it was.
+    fn redo(&self, world: &mut World) -> Option<Box<dyn UndoEntry>>;
 }
 
 /// Resource that manages the undo / redo stack.
@@ -38,14 +44,21 @@ impl UndoStack {
     }
 
     pub fn undo(&mut self, world: &mut World) {
-        if let Some(entity) = self.undo_stack.pop() {
-            self.redo_stack.push(entity.undo(world));
+        // An entry which fails stays where it is, so that it can be tried again.
+        if let Some(entry) = self.undo_stack.pop() {
+            match entry.undo(world) {
+                Some(redo) => self.redo_stack.push(redo),
+                None => self.undo_stack.push(entry),
+            }
         }
     }
 
     pub fn redo(&mut self, world: &mut World) {
-        if let Some(entity) = self.redo_stack.pop() {
-            self.undo_stack.push(entity.redo(world));
+        if let Some(entry) = self.redo_stack.pop() {
+            match entry.redo(world) {
+                Some(undo) => self.undo_stack.push(undo),
+                None => self.redo_stack.push(entry),
+            }
         }
     }
 }
@@ -65,3 +78,50 @@ impl Command for RedoCommand {
         world.resource_scope(|world, mut stack: Mut<UndoStack>| stack.redo(world));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Entry which fails whenever `fail` is set.
+    #[derive(Clone)]
+    struct Toggle {
+        fail: bool,
+    }
+
+    impl UndoEntry for Toggle {
+        fn label(&self) -> &str {
+            "Toggle"
+        }
+
+        fn undo(&self, _world: &mut World) -> Option<Box<dyn RedoEntry>> {
+            (!self.fail).then(|| Box::new(self.clone()) as Box<dyn RedoEntry>)
+        }
+    }
+
+    impl RedoEntry for Toggle {
+        fn label(&self) -> &str {
+            "Toggle"
+        }
+
+        fn redo(&self, _world: &mut World) -> Option<Box<dyn UndoEntry>> {
+            (!self.fail).then(|| Box::new(self.clone()) as Box<dyn UndoEntry>)
+        }
+    }
+
+    #[test]
+    fn test_failed_undo_stays_on_stack() {
+        let mut world = World::new();
+        let mut stack = UndoStack::default();
+        stack.push(Toggle { fail: true });
+        stack.undo(&mut world);
+        assert_eq!(stack.next_undo_label(), Some("Toggle"));
+        assert_eq!(stack.next_redo_label(), None);
+
+        let mut stack = UndoStack::default();
+        stack.push(Toggle { fail: false });
+        stack.undo(&mut world);
+        assert_eq!(stack.next_undo_label(), None);
+        assert_eq!(stack.next_redo_label(), Some("Toggle"));
+    }
+}
@@ -11,13 +11,17 @@ use bevy::{
 use futures_lite::AsyncWriteExt;
 
 use crate::{
-    scenery::precinct_asset::{PrecinctAsset, PrecinctAssetSaver, PrecinctAssetSaverError},
+    editor::realm::copy_precinct_files,
+    scenery::{
+        precinct_asset::{PrecinctAsset, PrecinctAssetSaver, PrecinctAssetSaverError},
+        precinct_format::PrecinctFormat,
+    },
     terrain::{
         terrain_contours::{TerrainContoursTableAsset, TerrainContoursTableSaver},
         terrain_groups::TerrainGroupsAsset,
         TerrainMapAsset, TerrainMapSaver,
     },
-    world::{RealmData, WorldLocationsAsset},
+    world::{RealmData, RealmDataSaver, WorldLocationsAsset},
 };
 
 #[derive(Debug, Default)]
@@ -29,7 +33,8 @@ pub enum ModifiedState<A: Asset, E> {
 
 #[derive(Default, Resource)]
 pub struct UnsavedAssets {
-    pub realms: HashSet<Handle<RealmData>>,
+    pub realms:
+        HashMap<Handle<RealmData>, ModifiedState<RealmData, <RealmDataSaver as AssetSaver>::Error>>,
     pub terrain_maps: HashMap<
         Handle<TerrainMapAsset>,
         ModifiedState<TerrainMapAsset, <TerrainMapSaver as AssetSaver>::Error>,
@@ -42,6 +47,9 @@ pub struct UnsavedAssets {
     pub precincts:
         HashMap<Handle<PrecinctAsset>, ModifiedState<PrecinctAsset, PrecinctAssetSaverError>>,
     pub locations: HashSet<Handle<WorldLocationsAsset>>,
+    /// Realms which were created as duplicates, mapped to the realm whose precinct files are
+    /// copied to them on the next save.
+    pub precinct_copies: HashMap<String, String>,
 }
 
 impl UnsavedAssets {
@@ -52,6 +60,7 @@ impl UnsavedAssets {
             && self.terrain_contours.is_empty()
             && self.precincts.is_empty()
             && self.locations.is_empty()
+            && self.precinct_copies.is_empty()
     }
 }
 
@@ -69,7 +78,7 @@ impl UnsavedAssets {
 pub struct SaveCommand;
 
 impl SaveCommand {
-    fn get_temp_file_path(path: &AssetPath) -> std::path::PathBuf {
+    pub(crate) fn get_temp_file_path(path: &AssetPath) -> std::path::PathBuf {
         let mut file_path = path.path().to_path_buf();
         file_path.set_extension(format!("{}.new", path.get_full_extension().unwrap()));
         file_path
@@ -78,18 +87,35 @@ impl SaveCommand {
 
 impl Command for SaveCommand {
     fn apply(self, world: &mut World) {
+        let copies: Vec<(String, String)> = world
+            .resource_mut::<UnsavedAssets>()
+            .precinct_copies
+            .drain()
+            .collect();
+        for (to, from) in copies {
+            copy_precinct_files(world, &from, &to);
+        }
+
         let mut system_state: SystemState<(
             Res<AssetServer>,
             Res<Assets<PrecinctAsset>>,
             Res<Assets<TerrainMapAsset>>,
             Res<Assets<TerrainContoursTableAsset>>,
+            Res<Assets<RealmData>>,
             Res<AppTypeRegistry>,
             ResMut<UnsavedAssets>,
         )> = SystemState::new(world);
         let task_pool = AsyncComputeTaskPool::get();
 
-        let (server, precincts, terrain_maps, terrain_contours, type_registry, mut unsaved_assets) =
-            system_state.get_mut(world);
+        let (
+            server,
+            precincts,
+            terrain_maps,
+            terrain_contours,
+            realms,
+            type_registry,
+            mut unsaved_assets,
+        ) = system_state.get_mut(world);
         for (asset_handle, state) in unsaved_assets.precincts.iter_mut() {
             // Don't save if we're already saving
             if matches!(state, ModifiedState::Saving(_)) {
@@ -105,7 +131,8 @@ impl Command for SaveCommand {
                 let file_path = Self::get_temp_file_path(&path);
                 let writer = source.writer().unwrap();
                 let mut write = writer.write(file_path.as_path()).await.unwrap();
-                let saver = PrecinctAssetSaver::new(registry);
+                let saver =
+                    PrecinctAssetSaver::new(registry, PrecinctFormat::from_path(path.path()));
                 let loaded_precinct = LoadedAsset::new_with_dependencies(asset, None);
                 let erased = ErasedLoadedAsset::from(loaded_precinct);
                 let saved = SavedAsset::from_loaded(&erased).unwrap();
@@ -170,6 +197,32 @@ impl Command for SaveCommand {
             });
             *state = ModifiedState::Saving(task);
         }
+
+        for (asset_handle, state) in unsaved_assets.realms.iter_mut() {
+            // Don't save if we're already saving
+            if matches!(state, ModifiedState::Saving(_)) {
+                continue;
+            }
+            let asset = realms.get(asset_handle).unwrap().clone();
+            let asset_handle = asset_handle.clone();
+            let server = server.clone();
+            let task = task_pool.spawn(async move {
+                let path = server.get_path(&asset_handle).unwrap();
+                let source = server.get_source(path.source()).unwrap();
+                let file_path = Self::get_temp_file_path(&path);
+                let writer = source.writer().unwrap();
+                let mut write = writer.write(file_path.as_path()).await.unwrap();
+                let saver = RealmDataSaver;
+                let loaded_realm = LoadedAsset::new_with_dependencies(asset, None);
+                let erased = ErasedLoadedAsset::from(loaded_realm);
+                let saved = SavedAsset::from_loaded(&erased).unwrap();
+                saver.save(&mut *write, saved, &()).await?;
+                write.close().await?;
+                writer.rename(file_path.as_path(), path.path()).await?;
+                Ok(asset_handle)
+            });
+            *state = ModifiedState::Saving(task);
+        }
     }
 }
 
@@ -266,4 +319,32 @@ pub fn receive_asset_saving(mut unsaved: ResMut<UnsavedAssets>) {
             unsaved.terrain_contours.remove(&handle);
         }
     }
+    let finished_saving = unsaved
+        .realms
+        .iter_mut()
+        .filter_map(|(_handle, state)| {
+            if let ModifiedState::Saving(task) = state {
+                let status = block_on(future::poll_once(task));
+                match status {
+                    Some(Ok(handle)) => {
+                        return Some(handle);
+                    }
+                    Some(Err(e)) => {
+                        println!("Error saving realm: {:?}", e);
+                    }
+                    _ => {}
+                }
+            }
+            None
+        })
+        .collect::<Vec<_>>();
+    if !finished_saving.is_empty() {
+        for handle in finished_saving {
+            // This can happen if the asset was modified while saving
+            if matches!(unsaved.realms.get(&handle), Some(ModifiedState::Unsaved)) {
+                continue;
+            }
+            unsaved.realms.remove(&handle);
+        }
+    }
 }
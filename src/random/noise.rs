@@ -1,6 +1,11 @@
+/// Period of the permutation polynomial; noise repeats with this period along each axis.
+const PERIOD: i32 = 289;
+
 /// Permutation polynomial, 1d
 pub fn permute(x: i32) -> i32 {
-    ((34 * x + 1) * x).rem_euclid(289)
+    // Reducing the input first gives the same result, but can't overflow.
+    let x = x.rem_euclid(PERIOD);
+    ((34 * x + 1) * x).rem_euclid(PERIOD)
 }
 
 /// Permutation polynomial, 2d
@@ -27,3 +32,123 @@ pub fn noise2(x: i32, y: i32) -> f32 {
 pub fn noise3(x: i32, y: i32, z: i32) -> f32 {
     permute3(x, y, z) as f32 / 289.0
 }
+
+/// Seed for spatial noise. Each seed shifts the noise by a different offset along each axis,
+/// so that the same coordinates and salt produce different values. Only integer arithmetic
+/// is used, so results are identical on every platform. The zero seed leaves the noise
+/// unchanged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct NoiseSeed {
+    seed: u32,
+    offset: [i32; 3],
+}
+
+impl NoiseSeed {
+    pub const ZERO: NoiseSeed = NoiseSeed {
+        seed: 0,
+        offset: [0; 3],
+    };
+
+    pub fn new(seed: u32) -> Self {
+        if seed == 0 {
+            return Self::ZERO;
+        }
+        let h = hash(seed) as i64;
+        let period = PERIOD as i64;
+        Self {
+            seed,
+            offset: [
+                (h % period) as i32,
+                (h / period % period) as i32,
+                (h / (period * period) % period) as i32,
+            ],
+        }
+    }
+
+    /// The seed value this was created from.
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Seeded spatial noise, integer 1d
+    pub fn noise(&self, x: i32) -> f32 {
+        noise(x.rem_euclid(PERIOD) + self.offset[0])
+    }
+
+    /// Seeded spatial noise, integer 2d
+    pub fn noise2(&self, x: i32, y: i32) -> f32 {
+        noise2(
+            x.rem_euclid(PERIOD) + self.offset[0],
+            y.rem_euclid(PERIOD) + self.offset[1],
+        )
+    }
+
+    /// Seeded spatial noise, integer 3d
+    pub fn noise3(&self, x: i32, y: i32, z: i32) -> f32 {
+        noise3(
+            x.rem_euclid(PERIOD) + self.offset[0],
+            y.rem_euclid(PERIOD) + self.offset[1],
+            z.rem_euclid(PERIOD) + self.offset[2],
+        )
+    }
+}
+
+/// Integer hash used to spread seeds across the noise offsets.
+fn hash(x: u32) -> u32 {
+    let mut x = x;
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^= x >> 16;
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unseeded_noise_is_stable() {
+        assert_eq!(permute3(3, 7, 5), 117);
+        assert_eq!(permute3(-12, 100, 3), 210);
+        assert_eq!(permute3(1000, -5000, 8), 3);
+        // Large coordinates used to overflow.
+        assert_eq!(permute(i32::MAX), permute(i32::MAX.rem_euclid(PERIOD)));
+        for (x, y, z) in [(3, 7, 5), (-12, 100, 3), (1000, -5000, 8)] {
+            assert_eq!(
+                NoiseSeed::ZERO.noise3(x, y, z).to_bits(),
+                noise3(x, y, z).to_bits()
+            );
+        }
+    }
+
+    #[test]
+    fn test_seeded_noise_is_stable() {
+        assert_eq!(NoiseSeed::new(1).offset, [33, 249, 190]);
+        assert_eq!(NoiseSeed::new(42).offset, [222, 250, 26]);
+        let expected = [(1, [283, 36, 67]), (42, [3, 45, 59])];
+        for (seed, values) in expected {
+            let noise = NoiseSeed::new(seed);
+            for (i, (x, y, z)) in [(3, 7, 5), (-12, 100, 3), (1000, -5000, 8)]
+                .into_iter()
+                .enumerate()
+            {
+                assert_eq!(
+                    noise.noise3(x, y, z).to_bits(),
+                    (values[i] as f32 / 289.0).to_bits()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_seeds_differ() {
+        let a = NoiseSeed::new(1);
+        let b = NoiseSeed::new(2);
+        let differing = (0..100)
+            .filter(|i| a.noise3(*i, i * 7, 3) != b.noise3(*i, i * 7, 3))
+            .count();
+        assert!(differing > 90);
+    }
+}
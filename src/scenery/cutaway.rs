@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+
+use crate::view::Viewpoint;
+
+use super::{
+    floor_region::FloorRegion,
+    precinct::Precinct,
+    precinct_asset::{tier_at_height, FLOOR_EDGE_MARGIN},
+    scenery_element::SceneryElement,
+};
+
+/// Cutaway rectangles which the viewpoint is currently inside. Floors and scenery above the
+/// cutaway's tier, within the rectangles, are hidden so that building interiors can be seen.
+#[derive(Resource, Default, Debug, PartialEq)]
+pub struct ActiveCutaway {
+    /// Precinct containing the cutaway.
+    pub precinct: Option<Entity>,
+    /// Tier that the cutaway belongs to; everything above it is hidden.
+    pub level: i32,
+    /// Cutaway rectangles, relative to the precinct. Includes rectangles which touch the one
+    /// the viewpoint is in, so that rooms can be made of several rectangles.
+    pub rects: Vec<Rect>,
+}
+
+/// Highest tier to show in every precinct. The editor sets this so that lower levels of a
+/// building can be worked on without the ones above getting in the way.
+#[derive(Resource, Default, Debug, PartialEq)]
+pub struct TierCeiling(pub Option<i32>);
+
+/// Marks floors and scenery which are hidden by a cutaway or the tier ceiling.
+#[derive(Component)]
+pub struct CutawayHidden;
+
+/// Work out which cutaway, if any, the viewpoint is inside. When tiers are stacked, the
+/// highest tier at or below the viewpoint with a cutaway around it wins.
+pub fn update_active_cutaway(
+    r_viewpoint: Res<Viewpoint>,
+    q_precincts: Query<(Entity, &Precinct)>,
+    mut r_active: ResMut<ActiveCutaway>,
+) {
+    let pos = r_viewpoint.position;
+    let active = r_viewpoint
+        .realm
+        .and_then(|realm| {
+            q_precincts
+                .iter()
+                .find(|(_, p)| p.realm == realm && p.contains_pt(pos))
+        })
+        .and_then(|(entity, precinct)| {
+            let local = pos.xz() - precinct.coords.as_vec2() * super::PRECINCT_SIZE_F;
+            precinct
+                .tiers
+                .iter()
+                .rev()
+                .filter(|tier| tier.level as f32 <= pos.y + 0.5)
+                .find_map(|tier| {
+                    let start = tier.cutaways.iter().position(|r| r.contains(local))?;
+                    Some(ActiveCutaway {
+                        precinct: Some(entity),
+                        level: tier.level,
+                        rects: connected_rects(&tier.cutaways, start),
+                    })
+                })
+        })
+        .unwrap_or_default();
+    if *r_active != active {
+        *r_active = active;
+    }
+}
+
+/// Hide floors and scenery above the active cutaway or the tier ceiling, and show them again
+/// once they no longer apply.
+#[allow(clippy::type_complexity)]
+pub fn apply_cutaways(
+    mut commands: Commands,
+    r_active: Res<ActiveCutaway>,
+    r_ceiling: Res<TierCeiling>,
+    q_precincts: Query<&Precinct>,
+    q_scenery: Query<(
+        Entity,
+        &SceneryElement,
+        &Parent,
+        &Visibility,
+        Has<CutawayHidden>,
+    )>,
+    q_floors: Query<(
+        Entity,
+        &FloorRegion,
+        &Parent,
+        &Visibility,
+        Has<CutawayHidden>,
+    )>,
+    q_changed: Query<
+        (),
+        (
+            Or<(With<SceneryElement>, With<FloorRegion>)>,
+            Or<(
+                Changed<SceneryElement>,
+                Changed<FloorRegion>,
+                Changed<Visibility>,
+            )>,
+        ),
+    >,
+) {
+    if !r_active.is_changed() && !r_ceiling.is_changed() && q_changed.is_empty() {
+        return;
+    }
+    let in_precinct = |parent: &Parent| Some(parent.get()) == r_active.precinct;
+    // Rebuilding a floor's mesh makes it visible again, so check the visibility as well as
+    // the marker. Hidden entities go back to `shown` when the cutaway no longer applies.
+    let mut update =
+        |entity: Entity, hide: bool, visibility: &Visibility, hidden: bool, shown: Visibility| {
+            if hide && (!hidden || *visibility != Visibility::Hidden) {
+                commands
+                    .entity(entity)
+                    .insert((Visibility::Hidden, CutawayHidden));
+            } else if !hide && hidden {
+                commands
+                    .entity(entity)
+                    .insert(shown)
+                    .remove::<CutawayHidden>();
+            }
+        };
+
+    let scenery_tier = |element: &SceneryElement, parent: &Parent| {
+        let precinct = q_precincts.get(parent.get()).ok()?;
+        let floors = precinct
+            .tiers
+            .iter()
+            .flat_map(|tier| tier.floor_regions.iter())
+            .filter_map(|floor| q_floors.get(*floor).ok().map(|(_, floor, ..)| floor));
+        building_tier(element.position, floors)
+    };
+
+    // Anything on a tier above the cutaway's is hidden.
+    for (entity, element, parent, visibility, hidden) in q_scenery.iter() {
+        let tier = scenery_tier(element, parent);
+        let hide = tier.is_some_and(|tier| r_ceiling.0.is_some_and(|level| tier > level))
+            || (in_precinct(parent)
+                && tier.is_some_and(|tier| tier > r_active.level)
+                && r_active
+                    .rects
+                    .iter()
+                    .any(|rect| rect.contains(element.position.xz())));
+        update(entity, hide, visibility, hidden, Visibility::Inherited);
+    }
+    for (entity, floor, parent, visibility, hidden) in q_floors.iter() {
+        let hide = r_ceiling.0.is_some_and(|level| floor.level > level)
+            || (in_precinct(parent)
+                && floor.level > r_active.level
+                && floor.poly.first().is_some_and(|first| {
+                    let bounds = floor
+                        .poly
+                        .iter()
+                        .fold(Rect::from_center_size(*first, Vec2::ZERO), |r, v| {
+                            r.union_point(*v)
+                        });
+                    r_active.rects.iter().any(|rect| overlaps(rect, &bounds))
+                }));
+        update(entity, hide, visibility, hidden, Visibility::Visible);
+    }
+}
+
+/// The tier that scenery at a precinct-relative position belongs to, if it is part of a
+/// building: that is, over the footprint of a floor at or below its height. This takes in
+/// walls on the edges of floors, and roofs and upper storeys with no floor directly beneath.
+/// Scenery on the terrain belongs to no tier, however high up the hillside it is.
+fn building_tier<'a>(pos: Vec3, floors: impl IntoIterator<Item = &'a FloorRegion>) -> Option<i32> {
+    let level = tier_at_height(pos.y);
+    floors
+        .into_iter()
+        .any(|floor| floor.level <= level && floor.contains_pt_within(pos.xz(), FLOOR_EDGE_MARGIN))
+        .then_some(level)
+}
+
+/// Whether two rectangles overlap or share an edge.
+fn overlaps(a: &Rect, b: &Rect) -> bool {
+    a.min.x <= b.max.x && b.min.x <= a.max.x && a.min.y <= b.max.y && b.min.y <= a.max.y
+}
+
+/// Collect the rectangle at `start`, along with all the rectangles connected to it.
+fn connected_rects(rects: &[Rect], start: usize) -> Vec<Rect> {
+    let mut connected = vec![false; rects.len()];
+    connected[start] = true;
+    let mut pending = vec![start];
+    while let Some(index) = pending.pop() {
+        for (other, rect) in rects.iter().enumerate() {
+            if !connected[other] && overlaps(&rects[index], rect) {
+                connected[other] = true;
+                pending.push(other);
+            }
+        }
+    }
+    rects
+        .iter()
+        .zip(connected)
+        .filter_map(|(rect, connected)| connected.then_some(*rect))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connected_rects() {
+        let rects = [
+            Rect::new(0., 0., 4., 4.),
+            Rect::new(10., 0., 14., 4.),
+            Rect::new(4., 2., 8., 6.),
+            Rect::new(6., 6., 10., 8.),
+        ];
+        assert_eq!(
+            connected_rects(&rects, 0),
+            vec![rects[0], rects[2], rects[3]]
+        );
+        assert_eq!(connected_rects(&rects, 1), vec![rects[1]]);
+    }
+
+    #[test]
+    fn test_building_tier() {
+        let floors = [FloorRegion {
+            level: 0,
+            poly: vec![
+                Vec2::new(2., 2.),
+                Vec2::new(8., 2.),
+                Vec2::new(8., 8.),
+                Vec2::new(2., 8.),
+            ],
+            ..default()
+        }];
+        // On the floor, and a wall on its edge.
+        assert_eq!(building_tier(Vec3::new(4., 0., 4.), &floors), Some(0));
+        assert_eq!(building_tier(Vec3::new(8., 0., 5.), &floors), Some(0));
+        // A roof above the top floor, including its eaves.
+        assert_eq!(building_tier(Vec3::new(5., 3., 5.), &floors), Some(3));
+        assert_eq!(building_tier(Vec3::new(1.5, 3., 5.), &floors), Some(3));
+        // Out on the hillside, and below the floor.
+        assert_eq!(building_tier(Vec3::new(12., 3., 5.), &floors), None);
+        assert_eq!(building_tier(Vec3::new(5., -2., 5.), &floors), None);
+    }
+}
@@ -36,6 +36,37 @@ pub struct FloorRegionSer {
 
     #[serde(default)]
     pub holes: Vec<Vec<Vec2>>,
+
+    /// Name of the layer this floor belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<String>,
+}
+
+impl FloorRegion {
+    /// Whether a point lies on the region, outside of its holes.
+    pub fn contains_pt(&self, pt: Vec2) -> bool {
+        point_in_poly(pt, &self.poly) && !self.holes.iter().any(|hole| point_in_poly(pt, hole))
+    }
+
+    /// Whether a point lies on the region, or within `margin` of its edges. See
+    /// [`FloorRegionSer::contains_pt_within`].
+    pub fn contains_pt_within(&self, pt: Vec2, margin: f32) -> bool {
+        contains_pt_within(&self.poly, &self.holes, pt, margin)
+    }
+}
+
+impl FloorRegionSer {
+    /// Whether a point lies on the region, outside of its holes.
+    pub fn contains_pt(&self, pt: Vec2) -> bool {
+        point_in_poly(pt, &self.poly) && !self.holes.iter().any(|hole| point_in_poly(pt, hole))
+    }
+
+    /// Whether a point lies on the region, or within `margin` of its outline or of the edge of
+    /// one of its holes. Walls are placed on the edges of floors, which an exact test would
+    /// count as inside or outside depending on rounding.
+    pub fn contains_pt_within(&self, pt: Vec2, margin: f32) -> bool {
+        contains_pt_within(&self.poly, &self.holes, pt, margin)
+    }
 }
 
 impl PartialEq for FloorRegionSer {
@@ -43,6 +74,7 @@ impl PartialEq for FloorRegionSer {
         self.surface_index == other.surface_index
             && self.poly == other.poly
             && self.holes == other.holes
+            && self.layer == other.layer
     }
 }
 
@@ -58,6 +90,55 @@ pub struct RebuildFloorMesh;
 #[component(storage = "SparseSet")]
 pub struct RebuildFloorMaterials;
 
+/// Even-odd test of whether a point is inside a polygon.
+pub fn point_in_poly(pt: Vec2, poly: &[Vec2]) -> bool {
+    let mut inside = false;
+    let mut prev = match poly.last() {
+        Some(last) => *last,
+        None => return false,
+    };
+    for curr in poly.iter() {
+        if (curr.y > pt.y) != (prev.y > pt.y)
+            && pt.x < prev.x + (pt.y - prev.y) * (curr.x - prev.x) / (curr.y - prev.y)
+        {
+            inside = !inside;
+        }
+        prev = *curr;
+    }
+    inside
+}
+
+fn contains_pt_within(poly: &[Vec2], holes: &[Vec<Vec2>], pt: Vec2, margin: f32) -> bool {
+    if point_in_poly(pt, poly) {
+        holes
+            .iter()
+            .all(|hole| !point_in_poly(pt, hole) || distance_to_ring(pt, hole) <= margin)
+    } else {
+        distance_to_ring(pt, poly) <= margin
+    }
+}
+
+/// Distance from a point to the nearest edge of a closed polygon.
+fn distance_to_ring(pt: Vec2, ring: &[Vec2]) -> f32 {
+    let mut prev = match ring.last() {
+        Some(last) => *last,
+        None => return f32::INFINITY,
+    };
+    let mut distance = f32::INFINITY;
+    for curr in ring.iter() {
+        let edge = *curr - prev;
+        let len2 = edge.length_squared();
+        let t = if len2 > 0. {
+            ((pt - prev).dot(edge) / len2).clamp(0., 1.)
+        } else {
+            0.
+        };
+        distance = distance.min(pt.distance(prev + edge * t));
+        prev = *curr;
+    }
+    distance
+}
+
 fn serialize_poly<S>(poly: &Vec<Vec2>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
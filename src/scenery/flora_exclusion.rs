@@ -0,0 +1,278 @@
+//! Areas of a precinct where flora is not allowed to grow.
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+
+use crate::terrain::{ParcelCache, RebuildParcelTerrainFx, PARCEL_SIZE_U};
+
+use super::{
+    floor_region::point_in_poly,
+    precinct::Precinct,
+    precinct_asset::PrecinctAsset,
+    scenery_aspect::SceneryColliders,
+    scenery_colliders::{ColliderDesc, ColliderShape, ColliderType},
+    scenery_element::SceneryElement,
+    terrain_fx_map::changed_parcels,
+    PRECINCT_SIZE,
+};
+
+/// Width and height of the flora exclusion map, one entry per square meter.
+pub const FLORA_EXCLUSION_MAP_SIZE: usize = PRECINCT_SIZE as usize;
+
+/// Points within a cell which are tested for coverage. A cell is excluded if any of them is
+/// covered, so that flora doesn't poke out from under the edges of a building.
+const CELL_SAMPLES: [Vec2; 5] = [
+    Vec2::new(0.5, 0.5),
+    Vec2::new(0.1, 0.1),
+    Vec2::new(0.9, 0.1),
+    Vec2::new(0.1, 0.9),
+    Vec2::new(0.9, 0.9),
+];
+
+/// Cells of a precinct where flora may not grow, because they are covered by a floor or by the
+/// footprint of a scenery collider. Like terrain effects, this is stored with the precinct and
+/// copied into each parcel when the parcel's terrain effects are rebuilt.
+#[derive(Component)]
+pub struct FloraExclusionMap(
+    pub(crate) [bool; FLORA_EXCLUSION_MAP_SIZE * FLORA_EXCLUSION_MAP_SIZE],
+);
+
+impl FloraExclusionMap {
+    pub fn new() -> Self {
+        Self([false; FLORA_EXCLUSION_MAP_SIZE * FLORA_EXCLUSION_MAP_SIZE])
+    }
+
+    /// Whether flora is excluded from the cell at precinct-relative coordinates `(x, z)`.
+    pub fn is_excluded(&self, x: usize, z: usize) -> bool {
+        self.0[x + z * FLORA_EXCLUSION_MAP_SIZE]
+    }
+
+    /// Exclude every cell covered by a floor polygon, not counting its holes.
+    pub fn exclude_floor(&mut self, poly: &[Vec2], holes: &[Vec<Vec2>]) {
+        let Some(bounds) = poly_bounds(poly) else {
+            return;
+        };
+        self.exclude_cells(bounds, |pt| {
+            point_in_poly(pt, poly) && !holes.iter().any(|hole| point_in_poly(pt, hole))
+        });
+    }
+
+    /// Exclude every cell overlapped by a rectangle with the given center and half-extents,
+    /// rotated around the vertical axis by `angle`.
+    pub fn exclude_footprint(&mut self, center: Vec2, half_size: Vec2, angle: f32) {
+        let rotation = Quat::from_rotation_y(angle);
+        let inverse = rotation.inverse();
+        let corners = [
+            Vec2::new(-half_size.x, -half_size.y),
+            Vec2::new(half_size.x, -half_size.y),
+            Vec2::new(half_size.x, half_size.y),
+            Vec2::new(-half_size.x, half_size.y),
+        ]
+        .map(|corner| center + (rotation * corner.extend(0.).xzy()).xz());
+        let Some(bounds) = poly_bounds(&corners) else {
+            return;
+        };
+        self.exclude_cells(bounds, |pt| {
+            let local = (inverse * (pt - center).extend(0.).xzy()).xz();
+            local.x.abs() <= half_size.x && local.y.abs() <= half_size.y
+        });
+
+        // Footprints smaller than a cell may miss all of the sample points.
+        let cell = center.floor();
+        if cell.cmpge(Vec2::ZERO).all() && cell.cmplt(Vec2::splat(PRECINCT_SIZE as f32)).all() {
+            self.0[cell.x as usize + cell.y as usize * FLORA_EXCLUSION_MAP_SIZE] = true;
+        }
+    }
+
+    /// Exclude the cells within `bounds` for which `covered` is true at any sample point.
+    fn exclude_cells(&mut self, bounds: Rect, covered: impl Fn(Vec2) -> bool) {
+        let max = FLORA_EXCLUSION_MAP_SIZE as f32;
+        let x0 = bounds.min.x.floor().clamp(0., max) as usize;
+        let z0 = bounds.min.y.floor().clamp(0., max) as usize;
+        let x1 = bounds.max.x.ceil().clamp(0., max) as usize;
+        let z1 = bounds.max.y.ceil().clamp(0., max) as usize;
+        for z in z0..z1 {
+            for x in x0..x1 {
+                let origin = Vec2::new(x as f32, z as f32);
+                if CELL_SAMPLES.iter().any(|s| covered(origin + *s)) {
+                    self.0[x + z * FLORA_EXCLUSION_MAP_SIZE] = true;
+                }
+            }
+        }
+    }
+}
+
+impl Default for FloraExclusionMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct RebuildFloraExclusion;
+
+/// Whether a collider keeps flora from growing in its footprint. Sensors, hints and the like
+/// don't correspond to anything solid.
+fn blocks_flora(collider: &ColliderDesc) -> bool {
+    matches!(
+        collider.r#type,
+        ColliderType::Solid | ColliderType::Door | ColliderType::Ladder
+    )
+}
+
+/// Scenery colliders are attached asynchronously, once the element's exemplar has loaded, so
+/// watch for colliders being added, changed or moved and rebuild the owning precinct's map.
+#[allow(clippy::type_complexity)]
+pub fn watch_scenery_footprints(
+    mut commands: Commands,
+    q_changed: Query<
+        &Parent,
+        (
+            With<SceneryColliders>,
+            Or<(Changed<SceneryColliders>, Changed<SceneryElement>)>,
+        ),
+    >,
+    mut removed: RemovedComponents<SceneryColliders>,
+    q_parents: Query<&Parent>,
+    q_precincts: Query<(), With<Precinct>>,
+) {
+    let removed_parents = removed.read().filter_map(|e| q_parents.get(e).ok());
+    for parent in q_changed.iter().chain(removed_parents) {
+        if q_precincts.contains(parent.get()) {
+            commands.entity(parent.get()).insert(RebuildFloraExclusion);
+        }
+    }
+}
+
+pub fn rebuild_flora_exclusion(
+    mut commands: Commands,
+    q_precincts: Query<
+        (
+            Entity,
+            &Precinct,
+            Option<&Children>,
+            Option<&FloraExclusionMap>,
+        ),
+        With<RebuildFloraExclusion>,
+    >,
+    q_elements: Query<(&SceneryElement, &SceneryColliders)>,
+    r_precinct_assets: Res<Assets<PrecinctAsset>>,
+    parcel_cache: Res<ParcelCache>,
+) {
+    for (entity, precinct, children, previous) in q_precincts.iter() {
+        let mut map = FloraExclusionMap::new();
+
+        // Floors on every tier, since flora would poke through upper floors as well.
+        if let Some(asset) = r_precinct_assets.get(&precinct.asset) {
+            for tier in asset.tiers.iter() {
+                for floor in tier.pfloors.iter() {
+                    map.exclude_floor(&floor.poly, &floor.holes);
+                }
+            }
+        }
+
+        // Footprints of solid scenery colliders.
+        for child in children.iter().flat_map(|c| c.iter()) {
+            let Ok((element, colliders)) = q_elements.get(*child) else {
+                continue;
+            };
+            let rotation = Quat::from_rotation_y(element.facing);
+            for collider in colliders.0.iter().filter(|c| blocks_flora(c)) {
+                let size = collider.size.unwrap_or(Vec3::splat(0.5));
+                let half_size = match collider.shape {
+                    ColliderShape::Sphere => Vec2::splat(size.y),
+                    _ => size.xz(),
+                };
+                let offset = rotation * collider.offset.unwrap_or(Vec3::ZERO);
+                map.exclude_footprint(
+                    (element.position + offset).xz(),
+                    half_size,
+                    element.facing + collider.facing.unwrap_or(0.) * FRAC_PI_2,
+                );
+            }
+        }
+
+        // Only the parcels whose cells changed need their flora rebuilt; painting terrain
+        // effects, for instance, rebuilds the precinct without changing any of them.
+        let changed = changed_parcels(precinct.coords, |x, z| {
+            previous.map_or(true, |previous| {
+                (z..z + PARCEL_SIZE_U).any(|row| {
+                    let start = x + row * FLORA_EXCLUSION_MAP_SIZE;
+                    let range = start..start + PARCEL_SIZE_U;
+                    map.0[range.clone()] != previous.0[range]
+                })
+            })
+        });
+
+        commands
+            .entity(entity)
+            .insert(map)
+            .remove::<RebuildFloraExclusion>();
+
+        for parcel in changed
+            .into_iter()
+            .flat_map(|rect| parcel_cache.query(precinct.realm, rect))
+        {
+            commands.entity(parcel).insert(RebuildParcelTerrainFx);
+        }
+    }
+}
+
+fn poly_bounds(poly: &[Vec2]) -> Option<Rect> {
+    let first = *poly.first()?;
+    Some(
+        poly.iter()
+            .fold(Rect::from_corners(first, first), |r, pt| r.union_point(*pt)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn excluded(map: &FloraExclusionMap) -> usize {
+        map.0.iter().filter(|e| **e).count()
+    }
+
+    #[test]
+    fn test_exclude_floor() {
+        let mut map = FloraExclusionMap::new();
+        let poly = [
+            Vec2::new(2., 2.),
+            Vec2::new(6., 2.),
+            Vec2::new(6., 6.),
+            Vec2::new(2., 6.),
+        ];
+        let hole = vec![
+            Vec2::new(3., 3.),
+            Vec2::new(5., 3.),
+            Vec2::new(5., 5.),
+            Vec2::new(3., 5.),
+        ];
+        map.exclude_floor(&poly, &[hole]);
+        assert!(map.is_excluded(2, 2));
+        assert!(map.is_excluded(5, 5));
+        assert!(!map.is_excluded(1, 2));
+        assert!(!map.is_excluded(6, 2));
+        assert!(!map.is_excluded(3, 3));
+        assert_eq!(excluded(&map), 16 - 4);
+    }
+
+    #[test]
+    fn test_exclude_footprint() {
+        let mut map = FloraExclusionMap::new();
+        // A 1x3 meter wall, turned sideways.
+        map.exclude_footprint(Vec2::new(10., 10.5), Vec2::new(1.5, 0.5), FRAC_PI_2);
+        assert!(map.is_excluded(9, 9));
+        assert!(map.is_excluded(10, 11));
+        assert!(!map.is_excluded(11, 10));
+        assert!(!map.is_excluded(8, 10));
+
+        // A small post still excludes the cell it stands in.
+        let mut map = FloraExclusionMap::new();
+        map.exclude_footprint(Vec2::new(20.5, 20.5), Vec2::splat(0.1), 0.);
+        assert_eq!(excluded(&map), 1);
+        assert!(map.is_excluded(20, 20));
+    }
+}
@@ -1,32 +1,45 @@
 use bevy::{pbr::ExtendedMaterial, prelude::*, render::render_resource::Face, utils::HashMap};
 use panoply_exemplar::InstanceType;
-use precinct_cache::{spawn_precincts, PrecinctCache};
+use precinct_cache::{fall_back_precinct_format, spawn_precincts, PrecinctCache};
 
 use crate::materials::{OutlineMaterial, OutlineMaterialExtension};
 
 use self::{
+    cutaway::{apply_cutaways, update_active_cutaway, ActiveCutaway, TierCeiling},
     floor_aspect::{FloorGeometry, FloorNav, NoiseFloorSurface, StdFloorSurface},
     floor_mesh::{
         gen_floor_meshes, insert_floor_meshes, rebuild_floor_materials, update_floor_aspects,
     },
+    flora_exclusion::{rebuild_flora_exclusion, watch_scenery_footprints},
     // floor_noise::FloorNoiseMaterial,
     precinct::read_precinct_data,
     precinct_asset::{PrecinctAsset, PrecinctAssetLoader},
+    precinct_layers::{refresh_layer_conditions, LayerConditions},
+    prefab_asset::{PrefabAsset, PrefabAssetLoader},
     scenery_aspect::{LightSource, ModelComponent, SceneryColliders, SceneryMarks, SceneryModels},
     scenery_colliders::{ColliderDesc, ColliderShape, ColliderType},
-    scenery_element::{spawn_se_model_instances, spawn_se_models, update_se_aspects},
+    scenery_element::{
+        reseed_se_models, spawn_se_model_instances, spawn_se_models, update_se_aspects,
+    },
     terrain_fx_aspect::{TerrainEffect, TerrainHole},
-    terrain_fx_map::{rebuild_parcel_terrain_fx, rebuild_terrain_fx_vertex_attrs},
+    terrain_fx_map::{
+        rebuild_parcel_terrain_fx, rebuild_terrain_fx_vertex_attrs, reload_terrain_effects,
+    },
     wall_aspect::WallSize,
 };
 
+pub mod cutaway;
 pub mod floor_aspect;
 mod floor_mesh;
+mod flora_exclusion;
 // mod floor_noise;
 pub mod floor_region;
 pub mod precinct;
 pub mod precinct_asset;
-mod precinct_cache;
+pub mod precinct_cache;
+pub mod precinct_format;
+pub mod precinct_layers;
+pub mod prefab_asset;
 mod rle;
 mod scenery_aspect;
 mod scenery_colliders;
@@ -35,6 +48,8 @@ mod terrain_fx_aspect;
 mod terrain_fx_map;
 mod wall_aspect;
 
+pub use terrain_fx_map::TERRAIN_FX_MAP_SIZE;
+
 pub const PRECINCT_SIZE: i32 = 64;
 pub const PRECINCT_SIZE_F: f32 = PRECINCT_SIZE as f32;
 
@@ -57,7 +72,12 @@ impl Plugin for SceneryPlugin {
         app.insert_resource(PrecinctCache::new())
             .init_asset_loader::<PrecinctAssetLoader>()
             .init_asset::<PrecinctAsset>()
+            .init_asset_loader::<PrefabAssetLoader>()
+            .init_asset::<PrefabAsset>()
             .init_resource::<FloorOutline>()
+            .init_resource::<LayerConditions>()
+            .init_resource::<ActiveCutaway>()
+            .init_resource::<TierCeiling>()
             .register_type::<StdFloorSurface>()
             .register_type::<NoiseFloorSurface>()
             .register_type::<FloorGeometry>()
@@ -87,6 +107,8 @@ impl Plugin for SceneryPlugin {
                 Update,
                 (
                     spawn_precincts,
+                    fall_back_precinct_format.after(spawn_precincts),
+                    refresh_layer_conditions.before(read_precinct_data),
                     read_precinct_data,
                     // Floor processing
                     update_floor_aspects.after(read_precinct_data),
@@ -95,14 +117,30 @@ impl Plugin for SceneryPlugin {
                     update_se_aspects.after(read_precinct_data),
                     spawn_se_models.after(update_se_aspects),
                     // TerrainFx processing
-                    rebuild_terrain_fx_vertex_attrs.after(read_precinct_data),
-                    rebuild_parcel_terrain_fx.after(rebuild_terrain_fx_vertex_attrs),
+                    reload_terrain_effects,
+                    rebuild_terrain_fx_vertex_attrs
+                        .after(read_precinct_data)
+                        .after(reload_terrain_effects),
+                    // Flora exclusion
+                    watch_scenery_footprints.after(update_se_aspects),
+                    rebuild_flora_exclusion
+                        .after(read_precinct_data)
+                        .after(watch_scenery_footprints),
+                    rebuild_parcel_terrain_fx
+                        .after(rebuild_terrain_fx_vertex_attrs)
+                        .after(rebuild_flora_exclusion),
                     // These poll resource handles so won't run in the same frame anyway
                     insert_floor_meshes,
                     rebuild_floor_materials,
                     spawn_se_model_instances,
+                    // Cutaways
+                    update_active_cutaway.after(read_precinct_data),
+                    apply_cutaways
+                        .after(update_active_cutaway)
+                        .after(rebuild_floor_materials),
                 ),
-            );
+            )
+            .observe(reseed_se_models);
     }
 }
 
@@ -1,14 +1,22 @@
 use panoply_exemplar::*;
 
+use crate::actors::{ActorElement, ActorElementRebuildAspects};
+
 use super::{
     floor_region::{FloorRegion, RebuildFloorAspects},
+    flora_exclusion::RebuildFloraExclusion,
     precinct_asset::{PrecinctAsset, SceneryInstanceId},
+    precinct_layers::LayerConditions,
     rle::rle_decode,
     scenery_element::{SceneryElement, SceneryElementRebuildAspects},
     terrain_fx_map::{RebuildTerrainFxVertexAttrs, TerrainFxMap},
     PRECINCT_SIZE_F,
 };
-use bevy::{prelude::*, render::view::RenderLayers, utils::hashbrown::HashMap};
+use bevy::{
+    prelude::*,
+    render::view::RenderLayers,
+    utils::hashbrown::{HashMap, HashSet},
+};
 
 #[derive(Eq, PartialEq, Hash)]
 pub struct PrecinctKey {
@@ -44,6 +52,7 @@ impl Precinct {
         commands: &mut Commands,
         entity: Entity,
         asset: &PrecinctAsset,
+        conditions: &LayerConditions,
         floor_exemplars: &[Handle<Exemplar>],
         query_floor_regions: &mut Query<(Entity, &mut FloorRegion)>,
     ) {
@@ -52,24 +61,33 @@ impl Precinct {
         for tier in asset.tiers.iter() {
             // Remove old tiers that are no longer in the asset.
             while i < self.tiers.len() && self.tiers[i].level < tier.level {
-                self.tiers.remove(i);
+                despawn_tier(commands, self.tiers.remove(i));
             }
 
             // Create or mutate a new tier
-            let t = if i < self.tiers.len() {
+            let t = if i < self.tiers.len() && self.tiers[i].level == tier.level {
                 &mut self.tiers[i]
             } else {
                 let new_tier = PrecinctTier {
                     level: tier.level,
                     floor_regions: Vec::new(),
+                    cutaways: Vec::new(),
                 };
                 self.tiers.insert(i, new_tier);
                 &mut self.tiers[i]
             };
             i += 1;
 
+            if t.cutaways != tier.cutaways {
+                t.cutaways.clone_from(&tier.cutaways);
+            }
+
             let mut j = 0;
-            for floor in tier.pfloors.iter() {
+            for floor in tier
+                .pfloors
+                .iter()
+                .filter(|floor| asset.is_layer_shown(floor.layer.as_deref(), conditions))
+            {
                 let exemplar: Handle<Exemplar> = floor_exemplars[floor.surface_index].clone();
                 if j < t.floor_regions.len() {
                     let floor_entity = t.floor_regions[j];
@@ -134,7 +152,7 @@ impl Precinct {
 
         // Remove any extra tiers that no longer exist.
         while i < self.tiers.len() {
-            self.tiers.remove(i);
+            despawn_tier(commands, self.tiers.remove(i));
         }
     }
 
@@ -144,6 +162,7 @@ impl Precinct {
         entity: Entity,
         children: Option<&Children>,
         asset: &PrecinctAsset,
+        conditions: &LayerConditions,
         scenery_exemplars: &[Handle<Exemplar>],
         query_scenery_elements: &mut Query<&mut SceneryElement>,
     ) {
@@ -156,7 +175,11 @@ impl Precinct {
             }
         }
 
-        for elt in asset.scenery.iter() {
+        for elt in asset
+            .scenery
+            .iter()
+            .filter(|elt| asset.is_layer_shown(elt.layer.as_deref(), conditions))
+        {
             let mut transform = Transform::from_translation(elt.position);
             let facing = elt.facing * std::f32::consts::PI / 180.;
             transform.rotate(Quat::from_rotation_y(facing));
@@ -212,25 +235,126 @@ impl Precinct {
         entity: Entity,
         asset: &PrecinctAsset,
         fx_exemplars: Vec<Handle<Exemplar>>,
+        previous: Option<&TerrainFxMap>,
     ) {
+        if asset.terrain_fx.is_none() && previous.is_none() {
+            return;
+        }
+        // If all effects were erased, the parcels still need to be cleared.
+        let mut fx = TerrainFxMap::new();
         if let Some(ref encoded) = asset.terrain_fx {
-            let mut fx = TerrainFxMap::new();
             rle_decode(encoded, &mut fx.map).unwrap();
             fx.exemplars = fx_exemplars;
-            commands
-                .entity(entity)
-                .insert((fx, RebuildTerrainFxVertexAttrs));
         }
+        // Keep the resolved effects until they are rebuilt, so that only the parcels whose
+        // effects differ need to be rebuilt.
+        if let Some(previous) = previous {
+            fx.map_vertex_attr = previous.map_vertex_attr;
+        }
+        commands
+            .entity(entity)
+            .insert((fx, RebuildTerrainFxVertexAttrs));
     }
 
     pub fn rebuild_actors(
         &mut self,
-        _commands: &mut Commands,
-        _entity: Entity,
+        commands: &mut Commands,
+        entity: Entity,
+        children: Option<&Children>,
         asset: &PrecinctAsset,
+        conditions: &LayerConditions,
+        query_actors: &mut Query<&mut ActorElement>,
     ) {
-        for _ai in asset.actors.iter() {
-            // let _exemplar = asset_server.load::<Exemplar>(ai.exemplar.clone());
+        let mut child_map = HashMap::<ActorKey, Vec<Entity>>::with_capacity(asset.actors.len());
+        if let Some(children) = children {
+            for child in children.iter() {
+                if let Ok(actor) = query_actors.get(*child) {
+                    child_map
+                        .entry(ActorKey::new(
+                            actor.iid.as_deref(),
+                            &actor.exemplar,
+                            actor.position,
+                        ))
+                        .or_default()
+                        .push(*child);
+                }
+            }
+        }
+
+        // Actor positions are in world coordinates.
+        let origin = self.coords.as_vec2() * PRECINCT_SIZE_F;
+        let origin = Vec3::new(origin.x, 0., origin.y);
+        for (index, ai) in asset
+            .actors
+            .iter()
+            .enumerate()
+            .filter(|(_, ai)| asset.is_layer_shown(ai.layer.as_deref(), conditions))
+        {
+            let position = ai.position - origin;
+            let facing = ai.facing * std::f32::consts::PI / 180.;
+            let transform =
+                Transform::from_translation(position).with_rotation(Quat::from_rotation_y(facing));
+            let key = ActorKey::new(ai.iid.as_deref(), &ai.exemplar, position);
+            if let Some(actor_ent) = child_map.get_mut(&key).and_then(|ents| ents.pop()) {
+                if let Ok(mut actor) = query_actors.get_mut(actor_ent) {
+                    if actor.exemplar == ai.exemplar {
+                        // Inserting or removing actors shifts the indices of the ones after them.
+                        if actor.index != index {
+                            actor.index = index;
+                        }
+                        if actor.position != position || actor.facing != facing {
+                            actor.position = position;
+                            actor.facing = facing;
+                            commands.entity(actor_ent).insert(transform);
+                        }
+                        continue;
+                    }
+                }
+                commands.entity(actor_ent).remove_parent();
+                commands.entity(actor_ent).despawn_recursive();
+            }
+
+            commands
+                .spawn((
+                    ActorElement {
+                        index,
+                        iid: ai.iid.clone(),
+                        exemplar: ai.exemplar.clone(),
+                        facing,
+                        position,
+                    },
+                    ai.aspects.clone(),
+                    SpatialBundle {
+                        transform,
+                        ..default()
+                    },
+                    self.render_layer.clone(),
+                    ActorElementRebuildAspects,
+                ))
+                .set_parent(entity);
+        }
+
+        for actor_ent in child_map.values().flatten() {
+            commands.entity(*actor_ent).remove_parent();
+            commands.entity(*actor_ent).despawn_recursive();
+        }
+    }
+}
+
+/// Identifies an actor across rebuilds of its precinct. Actors with an instance id are
+/// matched by it; anonymous actors are matched by exemplar and position, since their index
+/// changes whenever an earlier actor is added or removed.
+#[derive(PartialEq, Eq, Hash)]
+enum ActorKey {
+    Iid(String),
+    Placed(AssetId<Exemplar>, [u32; 3]),
+}
+
+impl ActorKey {
+    fn new(iid: Option<&str>, exemplar: &Handle<Exemplar>, position: Vec3) -> Self {
+        match iid {
+            Some(iid) => ActorKey::Iid(iid.to_string()),
+            None => ActorKey::Placed(exemplar.id(), position.to_array().map(f32::to_bits)),
         }
     }
 }
@@ -242,13 +366,22 @@ pub struct PrecinctTier {
 
     /// List of polygonal floor regions.
     pub floor_regions: Vec<Entity>,
+
+    /// Areas where the tiers above are hidden when the viewpoint is inside them.
+    pub cutaways: Vec<Rect>,
     // public floorObstacles: ComputedFloorRegionObstacles;
 
     // private floorPhysics: ComputedFloorPhysics;
     // private floorMesh: ComputedFloorMesh;
     // private wallPhysics: ComputedWallPhysics;
-    // private cutawayRects: ICutawayRect[] = [];
-    // private cutawayRectsAtom = createAtom();
+}
+
+/// Despawn the floor regions of a tier which has been removed.
+fn despawn_tier(commands: &mut Commands, tier: PrecinctTier) {
+    for e in tier.floor_regions {
+        commands.entity(e).remove_parent();
+        commands.entity(e).despawn_recursive();
+    }
 }
 
 #[derive(Component)]
@@ -263,77 +396,32 @@ pub struct PrecinctTiersChanged;
 #[component(storage = "SparseSet")]
 pub struct PrecinctRebuildScenery;
 
-/** React when precinct assets change and update the scenery. */
+/** React when precinct assets change and update the scenery. Precincts can also be marked
+ * with [`PrecinctAssetChanged`] to rebuild them without the asset changing. */
 pub fn read_precinct_data(
     mut commands: Commands,
-    mut query_precincts: Query<(Entity, &mut Precinct, Option<&Children>)>,
+    mut query_precincts: Query<(
+        Entity,
+        &mut Precinct,
+        Option<&Children>,
+        Has<PrecinctAssetChanged>,
+        Option<&TerrainFxMap>,
+    )>,
     mut query_floor_regions: Query<(Entity, &mut FloorRegion)>,
     mut query_scenery_elements: Query<&mut SceneryElement>,
+    mut query_actors: Query<&mut ActorElement>,
     mut ev_asset: EventReader<AssetEvent<PrecinctAsset>>,
     assets: ResMut<Assets<PrecinctAsset>>,
     asset_server: Res<AssetServer>,
+    conditions: Res<LayerConditions>,
 ) {
+    let mut changed: HashSet<AssetId<PrecinctAsset>> = HashSet::new();
     for ev in ev_asset.read() {
         match ev {
             AssetEvent::Added { id }
             | AssetEvent::LoadedWithDependencies { id }
             | AssetEvent::Modified { id } => {
-                if let Some((precinct_entity, mut precinct, precinct_children)) =
-                    query_precincts.iter_mut().find(|r| r.1.asset.id() == *id)
-                {
-                    // TODO: Sync cutaway rects
-                    // TODO: Sync nav mesh, physics, light sources, particles, etc.
-                    // TODO: Sync actors
-
-                    let precinct_asset = assets.get(*id).unwrap();
-                    let floor_exemplars: Vec<Handle<Exemplar>> = precinct_asset
-                        .floor_types
-                        .iter()
-                        .map(|s| asset_server.load(s))
-                        .collect();
-
-                    precinct.rebuild_tiers(
-                        &mut commands,
-                        precinct_entity,
-                        precinct_asset,
-                        &floor_exemplars,
-                        &mut query_floor_regions,
-                    );
-
-                    let scenery_exemplars: Vec<Handle<Exemplar>> = precinct_asset
-                        .scenery_types
-                        .iter()
-                        .map(|s| asset_server.load(s))
-                        .collect();
-
-                    precinct.rebuild_scenery_elements(
-                        &mut commands,
-                        precinct_entity,
-                        precinct_children,
-                        precinct_asset,
-                        &scenery_exemplars,
-                        &mut query_scenery_elements,
-                    );
-
-                    let fx_exemplars: Vec<Handle<Exemplar>> = precinct_asset
-                        .terrain_fx_types
-                        .iter()
-                        .map(|s| asset_server.load(s))
-                        .collect();
-
-                    precinct.rebuild_terrain_fx(
-                        &mut commands,
-                        precinct_entity,
-                        precinct_asset,
-                        fx_exemplars,
-                    );
-
-                    precinct.rebuild_actors(&mut commands, precinct_entity, precinct_asset);
-
-                    commands
-                        .entity(precinct_entity)
-                        .remove::<PrecinctAssetChanged>();
-                }
+                changed.insert(*id);
             }
 
             AssetEvent::Removed { id } => {
@@ -343,4 +431,75 @@ pub fn read_precinct_data(
             AssetEvent::Unused { id: _ } => {}
         }
     }
+
+    for (precinct_entity, mut precinct, precinct_children, marked, terrain_fx) in
+        query_precincts.iter_mut()
+    {
+        if !marked && !changed.contains(&precinct.asset.id()) {
+            continue;
+        }
+        // TODO: Sync nav mesh, physics, light sources, particles, etc.
+
+        let Some(precinct_asset) = assets.get(&precinct.asset) else {
+            continue;
+        };
+        let floor_exemplars: Vec<Handle<Exemplar>> = precinct_asset
+            .floor_types
+            .iter()
+            .map(|s| asset_server.load(s))
+            .collect();
+
+        precinct.rebuild_tiers(
+            &mut commands,
+            precinct_entity,
+            precinct_asset,
+            &conditions,
+            &floor_exemplars,
+            &mut query_floor_regions,
+        );
+
+        let scenery_exemplars: Vec<Handle<Exemplar>> = precinct_asset
+            .scenery_types
+            .iter()
+            .map(|s| asset_server.load(s))
+            .collect();
+
+        precinct.rebuild_scenery_elements(
+            &mut commands,
+            precinct_entity,
+            precinct_children,
+            precinct_asset,
+            &conditions,
+            &scenery_exemplars,
+            &mut query_scenery_elements,
+        );
+
+        let fx_exemplars: Vec<Handle<Exemplar>> = precinct_asset
+            .terrain_fx_types
+            .iter()
+            .map(|s| asset_server.load(s))
+            .collect();
+
+        precinct.rebuild_terrain_fx(
+            &mut commands,
+            precinct_entity,
+            precinct_asset,
+            fx_exemplars,
+            terrain_fx,
+        );
+
+        precinct.rebuild_actors(
+            &mut commands,
+            precinct_entity,
+            precinct_children,
+            precinct_asset,
+            &conditions,
+            &mut query_actors,
+        );
+
+        commands
+            .entity(precinct_entity)
+            .insert(RebuildFloraExclusion)
+            .remove::<PrecinctAssetChanged>();
+    }
 }
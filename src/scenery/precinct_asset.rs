@@ -11,10 +11,11 @@ use futures_lite::{AsyncReadExt, AsyncWriteExt};
 use panoply_exemplar::{AspectListDeserializer, InstanceAspects};
 use serde::{
     de::{DeserializeSeed, Visitor},
-    ser::SerializeTuple,
+    ser::{SerializeStruct, SerializeTuple},
     Deserialize, Serialize,
 };
 use std::{
+    collections::BTreeMap,
     fmt::{self, Debug},
     sync::Arc,
 };
@@ -22,7 +23,13 @@ use thiserror::Error;
 
 use crate::actors::{ActorInstance, ActorInstanceListDeserializer};
 
-use super::floor_region::FloorRegionSer;
+use super::{
+    floor_region::FloorRegionSer,
+    precinct_format::PrecinctFormat,
+    precinct_layers::{condition_met, LayerConditions},
+    rle::{rle_decode, rle_encode},
+    terrain_fx_map::TERRAIN_FX_MAP_SIZE,
+};
 
 extern crate rmp_serde as rmps;
 
@@ -52,7 +59,10 @@ pub struct PrecinctAsset {
     /// Table of scenery instances.
     #[serde(default)]
     pub(crate) scenery: Vec<SceneryInstanceData>,
-    // layers?: Record<string, ILayerData>,
+
+    /// Named layers which scenery, floors and actors can be assigned to.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) layers: BTreeMap<String, LayerData>,
 }
 
 impl PrecinctAsset {
@@ -84,6 +94,115 @@ impl PrecinctAsset {
         &mut self.tiers[index]
     }
 
+    /// The tier whose floors something at a precinct-relative position is standing on.
+    /// Things on the terrain don't belong to any tier, however high up they are. Walls along
+    /// the edge of a floor count as standing on it.
+    pub fn standing_tier(&self, pos: Vec3) -> Option<i32> {
+        let level = tier_at_height(pos.y);
+        self.find_tier(level)
+            .filter(|tier| {
+                tier.pfloors
+                    .iter()
+                    .any(|floor| floor.contains_pt_within(pos.xz(), FLOOR_EDGE_MARGIN))
+            })
+            .map(|_| level)
+    }
+
+    /// Insert an empty tier at the given level. The tiers at or above that level, along with
+    /// the scenery and actors standing on their floors, move up by one. `origin` is the
+    /// world-space corner of the precinct, which is needed to place actors.
+    pub fn insert_tier(&mut self, level: i32, origin: Vec2) {
+        self.shift_tiers(level, 1, origin);
+        self.add_tier(level);
+    }
+
+    /// Remove the tier at the given level, along with the scenery and actors standing on its
+    /// floors. The tiers above move down by one.
+    pub fn delete_tier(&mut self, level: i32, origin: Vec2) {
+        let (scenery_tiers, actor_tiers) = self.standing_tiers(origin);
+        let mut on_tier = scenery_tiers.into_iter().map(|tier| tier == Some(level));
+        self.scenery.retain(|_| !on_tier.next().unwrap_or(false));
+        let mut on_tier = actor_tiers.into_iter().map(|tier| tier == Some(level));
+        self.actors.retain(|_| !on_tier.next().unwrap_or(false));
+        self.tiers.retain(|t| t.level != level);
+        self.shift_tiers(level + 1, -1, origin);
+    }
+
+    /// Insert a copy of the tier at the given level directly above it, including the scenery
+    /// and actors standing on its floors. Copied scenery is given new instance ids, and copied
+    /// actors have none.
+    pub fn copy_tier(&mut self, level: i32, origin: Vec2) {
+        let tier = self.find_tier(level).cloned();
+        let (scenery_tiers, actor_tiers) = self.standing_tiers(origin);
+        let scenery: Vec<SceneryInstanceData> = self
+            .scenery
+            .iter()
+            .zip(scenery_tiers)
+            .filter(|(_, tier)| *tier == Some(level))
+            .map(|(instance, _)| instance.clone())
+            .collect();
+        let actors: Vec<ActorInstance> = self
+            .actors
+            .iter()
+            .zip(actor_tiers)
+            .filter(|(_, tier)| *tier == Some(level))
+            .map(|(actor, _)| actor.clone())
+            .collect();
+
+        self.shift_tiers(level + 1, 1, origin);
+        let copy = self.add_tier(level + 1);
+        if let Some(tier) = tier {
+            copy.pfloors = tier.pfloors;
+            copy.cutaways = tier.cutaways;
+        }
+        let mut next_id = self.next_scenery_id();
+        for mut instance in scenery {
+            instance.position.y += 1.;
+            instance.iid = SceneryInstanceId::Internal(next_id);
+            next_id += 1;
+            self.scenery.push(instance);
+        }
+        for mut actor in actors {
+            actor.position.y += 1.;
+            actor.iid = None;
+            self.actors.push(actor);
+        }
+    }
+
+    /// Move the tiers at or above the given level, and everything standing on their floors,
+    /// up or down by `offset`.
+    fn shift_tiers(&mut self, level: i32, offset: i32, origin: Vec2) {
+        let (scenery_tiers, actor_tiers) = self.standing_tiers(origin);
+        for (instance, tier) in self.scenery.iter_mut().zip(scenery_tiers) {
+            if tier.is_some_and(|tier| tier >= level) {
+                instance.position.y += offset as f32;
+            }
+        }
+        for (actor, tier) in self.actors.iter_mut().zip(actor_tiers) {
+            if tier.is_some_and(|tier| tier >= level) {
+                actor.position.y += offset as f32;
+            }
+        }
+        for tier in self.tiers.iter_mut().filter(|t| t.level >= level) {
+            tier.level += offset;
+        }
+    }
+
+    /// The tier that each scenery instance and each actor is standing on.
+    fn standing_tiers(&self, origin: Vec2) -> (Vec<Option<i32>>, Vec<Option<i32>>) {
+        let origin = Vec3::new(origin.x, 0., origin.y);
+        (
+            self.scenery
+                .iter()
+                .map(|instance| self.standing_tier(instance.position))
+                .collect(),
+            self.actors
+                .iter()
+                .map(|actor| self.standing_tier(actor.position - origin))
+                .collect(),
+        )
+    }
+
     /// Return the table index of the given scenery exemplay.
     pub fn scenery_type_index(&self, scenery_type: &str) -> Option<usize> {
         self.scenery_types.iter().position(|st| st == scenery_type)
@@ -104,6 +223,7 @@ impl PrecinctAsset {
         facing: f32,
         position: Vec3,
         instance_id: Option<SceneryInstanceId>,
+        layer: Option<String>,
     ) -> SceneryInstanceId {
         let iid =
             instance_id.unwrap_or_else(|| SceneryInstanceId::Internal(self.next_scenery_id()));
@@ -113,17 +233,23 @@ impl PrecinctAsset {
             position,
             iid: iid.clone(),
             aspects: InstanceAspects::default(),
+            layer,
         });
         iid
     }
 
-    pub fn remove_scenery_elements<F: Fn(&SceneryInstanceId, usize, &Vec3) -> bool>(
+    /// Add a scenery instance as-is, keeping its instance id and aspects.
+    pub fn add_scenery_instance(&mut self, instance: SceneryInstanceData) {
+        self.scenery.push(instance);
+    }
+
+    pub fn remove_scenery_elements<F: Fn(&SceneryInstanceData) -> bool>(
         &mut self,
         filter: F,
     ) -> Vec<SceneryInstanceData> {
         let mut removed: Vec<SceneryInstanceData> = Vec::new();
         self.scenery.retain(|c| {
-            if filter(&c.iid, c.id, &c.position) {
+            if filter(c) {
                 removed.push((*c).clone());
                 false
             } else {
@@ -146,7 +272,159 @@ impl PrecinctAsset {
         index
     }
 
-    fn next_scenery_id(&self) -> usize {
+    /// Return the table index of the given terrain effect type.
+    pub fn terrain_fx_type_index(&self, fx_type: &str) -> Option<usize> {
+        self.terrain_fx_types.iter().position(|ft| ft == fx_type)
+    }
+
+    /// Add a new terrain effect type to the precinct.
+    pub fn add_terrain_fx_type(&mut self, fx_type: String) -> usize {
+        assert!(!self.terrain_fx_types.iter().any(|ft| ft == &fx_type));
+        let index = self.terrain_fx_types.len();
+        self.terrain_fx_types.push(fx_type);
+        index
+    }
+
+    /// Unpack the terrain effect table into a [`TERRAIN_FX_MAP_SIZE`]-square grid of vertices.
+    /// Each entry is one more than an index into the terrain effect types, or zero for none.
+    pub fn decode_terrain_fx(&self) -> Vec<u16> {
+        let mut map = vec![0; TERRAIN_FX_MAP_SIZE * TERRAIN_FX_MAP_SIZE];
+        if let Some(encoded) = &self.terrain_fx {
+            if rle_decode(encoded, &mut map).is_err() {
+                warn!("Invalid terrain effect table");
+            }
+        }
+        map
+    }
+
+    /// Pack a grid of terrain effects produced by [`decode_terrain_fx`](Self::decode_terrain_fx).
+    pub fn encode_terrain_fx(&mut self, map: &[u16]) {
+        self.terrain_fx = if map.iter().all(|fx| *fx == 0) {
+            None
+        } else {
+            Some(rle_encode(map))
+        };
+    }
+
+    /// Paint a terrain effect, or erase effects if `fx_type` is `None`, over a disc of
+    /// vertices. The center is in precinct-local vertex coordinates, and may lie outside the
+    /// precinct; vertices in the 1 meter skirt are painted too, so that they stay in step with
+    /// the neighboring precincts. Returns whether anything changed.
+    pub fn paint_terrain_fx(&mut self, center: IVec2, radius: i32, fx_type: Option<&str>) -> bool {
+        let mut map = self.decode_terrain_fx();
+        let mut value: Option<u16> = fx_type.map(|fx_type| {
+            self.terrain_fx_type_index(fx_type)
+                .map_or(0, |index| index as u16 + 1)
+        });
+        let mut changed = false;
+        for z in -radius..=radius {
+            for x in -radius..=radius {
+                if x * x + z * z > radius * radius {
+                    continue;
+                }
+                // Map entries start one vertex before the precinct's origin.
+                let pt = center + IVec2::new(x, z) + 1;
+                if pt.x < 0
+                    || pt.y < 0
+                    || pt.x >= TERRAIN_FX_MAP_SIZE as i32
+                    || pt.y >= TERRAIN_FX_MAP_SIZE as i32
+                {
+                    continue;
+                }
+                // Only add the effect to the type table once it's actually used.
+                if value == Some(0) {
+                    let index = self.add_terrain_fx_type(fx_type.unwrap().to_string());
+                    value = Some(index as u16 + 1);
+                }
+                let index = pt.x as usize + pt.y as usize * TERRAIN_FX_MAP_SIZE;
+                let fx = value.unwrap_or(0);
+                if map[index] != fx {
+                    map[index] = fx;
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.encode_terrain_fx(&map);
+        }
+        changed
+    }
+
+    /// Add a new, empty layer with a name that isn't already used. Returns the name.
+    pub fn add_layer(&mut self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut index = 2;
+        while self.layers.contains_key(&name) {
+            name = format!("{} {}", base, index);
+            index += 1;
+        }
+        self.layers.insert(name.clone(), LayerData::default());
+        name
+    }
+
+    /// Remove a layer. Anything that was assigned to it goes back to the default layer.
+    pub fn remove_layer(&mut self, name: &str) {
+        if self.layers.remove(name).is_none() {
+            return;
+        }
+        let in_layer = |layer: &Option<String>| layer.as_deref() == Some(name);
+        for elt in self.scenery.iter_mut().filter(|elt| in_layer(&elt.layer)) {
+            elt.layer = None;
+        }
+        for actor in self.actors.iter_mut().filter(|a| in_layer(&a.layer)) {
+            actor.layer = None;
+        }
+        for tier in self.tiers.iter_mut() {
+            for floor in tier.pfloors.iter_mut().filter(|f| in_layer(&f.layer)) {
+                floor.layer = None;
+            }
+        }
+    }
+
+    /// True if any scenery or actor has aspects of its own. These can be loaded, but not yet
+    /// written back out.
+    pub fn has_instance_aspects(&self) -> bool {
+        self.scenery.iter().any(|s| !s.aspects.is_empty())
+            || self.actors.iter().any(|a| !a.aspects.is_empty())
+    }
+
+    /// True if the given layer is locked against editing. Items not in a layer never are.
+    pub fn is_layer_locked(&self, layer: Option<&str>) -> bool {
+        layer
+            .and_then(|name| self.layers.get(name))
+            .is_some_and(|data| data.locked)
+    }
+
+    /// True if items in the given layer can be picked in the editor, i.e. their layer is
+    /// neither hidden nor locked.
+    pub fn is_layer_editable(&self, layer: Option<&str>) -> bool {
+        layer
+            .and_then(|name| self.layers.get(name))
+            .map_or(true, |data| !data.hidden && !data.locked)
+    }
+
+    /// Whether items in the given layer should be instantiated. While editing this follows
+    /// the layer's visibility toggle; in the game, including play mode in the editor,
+    /// editor-only layers are left out and conditional layers are shown once their condition
+    /// is met. Items that aren't in a
+    /// layer, or whose layer is missing from the table, are always shown.
+    pub fn is_layer_shown(&self, layer: Option<&str>, conditions: &LayerConditions) -> bool {
+        let Some(data) = layer.and_then(|name| self.layers.get(name)) else {
+            return true;
+        };
+        if conditions.editing() {
+            !data.hidden
+        } else {
+            !data.editor_only
+                && data
+                    .condition
+                    .as_deref()
+                    .map_or(true, |cond| condition_met(cond, conditions))
+        }
+    }
+
+    /// Return an internal instance id which isn't used by any scenery in this precinct.
+    pub(crate) fn next_scenery_id(&self) -> usize {
         let mut next_id: usize = 0;
         loop {
             let found_id = next_id;
@@ -162,6 +440,39 @@ impl PrecinctAsset {
     }
 }
 
+/// Settings for a named layer.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct LayerData {
+    /// Hidden in the editor.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub(crate) hidden: bool,
+
+    /// Protected from editing.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub(crate) locked: bool,
+
+    /// Left out of the game, e.g. for guides and work in progress.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub(crate) editor_only: bool,
+
+    /// Story flag which controls whether this layer is shown at runtime, see [`condition_met`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) condition: Option<String>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// How far outside a floor's outline something can be and still be standing on it. Walls snap
+/// to the half meter, so this takes in walls centered on the outline or just beside it.
+pub const FLOOR_EDGE_MARGIN: f32 = 0.5;
+
+/// The tier that something placed at the given height is standing on.
+pub fn tier_at_height(height: f32) -> i32 {
+    (height + 0.5).floor() as i32
+}
+
 /** Serialized schema for a tier */
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TierSer {
@@ -201,6 +512,9 @@ pub struct SceneryInstanceData {
 
     /// List of aspects for this instance.
     pub aspects: InstanceAspects,
+
+    /// Name of the layer this instance belongs to.
+    pub layer: Option<String>,
 }
 
 impl Serialize for SceneryInstanceData {
@@ -208,17 +522,79 @@ impl Serialize for SceneryInstanceData {
     where
         S: serde::ser::Serializer,
     {
+        serialize_scenery_instance(
+            serializer,
+            self.id,
+            self.facing,
+            self.position,
+            &self.iid,
+            (!self.aspects.is_empty()).then_some(&self.aspects),
+            self.layer.as_deref(),
+        )
+    }
+}
+
+/// Scenery instances are written as compact tuples in binary formats, and as structs with
+/// named fields in human-readable ones. In the tuple form, the layer comes after the aspects,
+/// which are written as an empty map if there are none.
+pub(crate) fn serialize_scenery_instance<S, A>(
+    serializer: S,
+    id: usize,
+    facing: f32,
+    position: Vec3,
+    iid: &SceneryInstanceId,
+    aspects: Option<&A>,
+    layer: Option<&str>,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::ser::Serializer,
+    A: Serialize,
+{
+    if serializer.is_human_readable() {
+        let mut len = 3;
+        if *iid != SceneryInstanceId::None {
+            len += 1;
+        }
+        if aspects.is_some() {
+            len += 1;
+        }
+        if layer.is_some() {
+            len += 1;
+        }
+        let mut state = serializer.serialize_struct("SceneryInstance", len)?;
+        state.serialize_field("id", &id)?;
+        state.serialize_field("facing", &facing)?;
+        state.serialize_field("position", &position)?;
+        if *iid != SceneryInstanceId::None {
+            state.serialize_field("iid", iid)?;
+        }
+        if let Some(aspects) = aspects {
+            state.serialize_field("aspects", aspects)?;
+        }
+        if let Some(layer) = layer {
+            state.serialize_field("layer", layer)?;
+        }
+        state.end()
+    } else {
         let mut len = 4;
-        if !self.aspects.is_empty() {
+        if aspects.is_some() || layer.is_some() {
+            len += 1;
+        }
+        if layer.is_some() {
             len += 1;
         }
         let mut state = serializer.serialize_tuple(len)?;
-        state.serialize_element(&self.id)?;
-        state.serialize_element(&self.facing)?;
-        state.serialize_element(&self.position)?;
-        state.serialize_element(&self.iid)?;
-        if !self.aspects.is_empty() {
-            state.serialize_element(&self.aspects)?;
+        state.serialize_element(&id)?;
+        state.serialize_element(&facing)?;
+        state.serialize_element(&position)?;
+        state.serialize_element(iid)?;
+        match aspects {
+            Some(aspects) => state.serialize_element(aspects)?,
+            None if layer.is_some() => state.serialize_element(&BTreeMap::<String, ()>::new())?,
+            None => {}
+        }
+        if let Some(layer) = layer {
+            state.serialize_element(layer)?;
         }
         state.end()
     }
@@ -234,7 +610,7 @@ impl<'de, 'a, 'b> Visitor<'de> for CompressedInstanceVisitor<'a, 'b> {
     type Value = SceneryInstanceData;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("a compressed instance tuple")
+        formatter.write_str("a scenery instance tuple or struct")
     }
 
     fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
@@ -267,7 +643,46 @@ impl<'de, 'a, 'b> Visitor<'de> for CompressedInstanceVisitor<'a, 'b> {
             Ok(Some(aspects)) => result.aspects = InstanceAspects(aspects),
             _ => return Ok(result),
         }
+        result.layer = seq.next_element()?;
+
+        Ok(result)
+    }
 
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::MapAccess<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(field_identifier, rename_all = "snake_case")]
+        enum Field {
+            Id,
+            Facing,
+            Position,
+            Iid,
+            Aspects,
+            Layer,
+        }
+
+        let mut id: Option<usize> = None;
+        let mut result: SceneryInstanceData = SceneryInstanceData::default();
+        while let Some(key) = map.next_key()? {
+            match key {
+                Field::Id => id = Some(map.next_value()?),
+                Field::Facing => result.facing = map.next_value()?,
+                Field::Position => result.position = map.next_value()?,
+                Field::Iid => result.iid = map.next_value()?,
+                Field::Aspects => {
+                    result.aspects =
+                        InstanceAspects(map.next_value_seed(AspectListDeserializer {
+                            type_registry: self.type_registry,
+                            load_context: self.load_context,
+                            label_prefix: self.parent_label,
+                        })?);
+                }
+                Field::Layer => result.layer = Some(map.next_value()?),
+            }
+        }
+        result.id = id.ok_or_else(|| serde::de::Error::missing_field("id"))?;
         Ok(result)
     }
 }
@@ -285,7 +700,7 @@ impl<'de, 'a, 'b> DeserializeSeed<'de> for CompressedInstanceDeserializer<'a, 'b
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_seq(CompressedInstanceVisitor {
+        deserializer.deserialize_any(CompressedInstanceVisitor {
             type_registry: self.type_registry,
             load_context: self.load_context,
             parent_label: self.parent_label,
@@ -324,10 +739,12 @@ impl<'de, 'a, 'b> Visitor<'de> for CompressedInstanceListVisitor<'a, 'b> {
     }
 }
 
-struct CompressedInstanceListDeserializer<'a, 'b> {
-    type_registry: &'a TypeRegistry,
-    load_context: &'a mut LoadContext<'b>,
-    parent_label: &'a str,
+/// Deserializes a list of scenery instances, in either tuple or struct form. Also used for
+/// the scenery in prefabs.
+pub(crate) struct CompressedInstanceListDeserializer<'a, 'b> {
+    pub(crate) type_registry: &'a TypeRegistry,
+    pub(crate) load_context: &'a mut LoadContext<'b>,
+    pub(crate) parent_label: &'a str,
 }
 
 impl<'de, 'a, 'b> DeserializeSeed<'de> for CompressedInstanceListDeserializer<'a, 'b> {
@@ -421,7 +838,7 @@ impl<'de, 'a, 'b> DeserializeSeed<'de> for PrecinctAssetDeserializer<'a, 'b> {
                                 })?;
                         }
                         Field::Layers => {
-                            // TODO
+                            precinct.layers = map.next_value()?;
                         }
                     }
                 }
@@ -455,6 +872,8 @@ pub enum PrecinctAssetLoaderError {
     Io(#[from] std::io::Error),
     #[error("Could not decode precinct: {0}")]
     Decode(#[from] rmps::decode::Error),
+    #[error("Could not decode precinct JSON: {0}")]
+    Json(#[from] serde_json::Error),
 }
 
 impl AssetLoader for PrecinctAssetLoader {
@@ -470,17 +889,26 @@ impl AssetLoader for PrecinctAssetLoader {
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let mut deserializer = rmps::Deserializer::from_read_ref(&bytes);
+        let format = PrecinctFormat::from_path(load_context.path());
         let precinct_deserializer = PrecinctAssetDeserializer {
             type_registry: &self.type_registry.read(),
             load_context,
         };
-        let precinct: PrecinctAsset = precinct_deserializer.deserialize(&mut deserializer)?;
+        let precinct: PrecinctAsset = match format {
+            PrecinctFormat::Msgpack => {
+                let mut deserializer = rmps::Deserializer::from_read_ref(&bytes);
+                precinct_deserializer.deserialize(&mut deserializer)?
+            }
+            PrecinctFormat::Json => {
+                let mut deserializer = serde_json::Deserializer::from_slice(&bytes);
+                precinct_deserializer.deserialize(&mut deserializer)?
+            }
+        };
         Ok(precinct)
     }
 
     fn extensions(&self) -> &[&str] {
-        &["msgpack"]
+        &["msgpack", "precinct.json"]
     }
 }
 
@@ -491,17 +919,34 @@ pub enum PrecinctAssetSaverError {
     Io(#[from] std::io::Error),
     #[error("Could not encode precinct: {0}")]
     Encode(#[from] rmps::encode::Error),
+    #[error("Could not encode precinct JSON: {0}")]
+    Json(#[from] serde_json::Error),
     #[error("Could not commit precinct file: {0}")]
     Commit(#[from] AssetWriterError),
 }
 
+/// Encode a precinct in the given format, exactly as it is written to disk.
+pub fn encode_precinct(
+    precinct: &PrecinctAsset,
+    format: PrecinctFormat,
+) -> Result<Vec<u8>, PrecinctAssetSaverError> {
+    Ok(match format {
+        PrecinctFormat::Msgpack => rmps::encode::to_vec_named(precinct)?,
+        PrecinctFormat::Json => serde_json::to_vec_pretty(precinct)?,
+    })
+}
+
 pub struct PrecinctAssetSaver {
     type_registry: TypeRegistryArc,
+    format: PrecinctFormat,
 }
 
 impl PrecinctAssetSaver {
-    pub fn new(type_registry: TypeRegistryArc) -> Self {
-        PrecinctAssetSaver { type_registry }
+    pub fn new(type_registry: TypeRegistryArc, format: PrecinctFormat) -> Self {
+        PrecinctAssetSaver {
+            type_registry,
+            format,
+        }
     }
 }
 
@@ -519,8 +964,233 @@ impl AssetSaver for PrecinctAssetSaver {
     ) -> Result<(), Self::Error> {
         // TODO: Optimize precinct - remove unused types. Should be done in serializer.
         // rmps::encode::write(writer, &*asset)?; // Doesn't work with async writer
-        let v = rmps::encode::to_vec_named(&*asset)?;
+        let v = encode_precinct(&asset, self.format)?;
         writer.write_all(&v).await?;
         Ok(())
     }
 }
+
+/// Load a precinct file through [`PrecinctAssetLoader`], from an in-memory asset source.
+#[cfg(test)]
+pub(crate) fn load_precinct_bytes(path: &str, bytes: Vec<u8>) -> PrecinctAsset {
+    use bevy::asset::{
+        io::{
+            memory::{Dir, MemoryAssetReader},
+            AssetSource, AssetSourceId,
+        },
+        LoadState,
+    };
+    use panoply_exemplar::Exemplar;
+
+    let dir = Dir::default();
+    dir.insert_asset(std::path::Path::new(path), bytes);
+    let mut app = App::new();
+    app.register_asset_source(
+        AssetSourceId::from("memory"),
+        AssetSource::build().with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+    )
+    .add_plugins((MinimalPlugins, AssetPlugin::default()))
+    .init_asset::<Exemplar>()
+    .init_asset::<PrecinctAsset>()
+    .init_asset_loader::<PrecinctAssetLoader>();
+
+    let server = app.world().resource::<AssetServer>().clone();
+    let handle: Handle<PrecinctAsset> = server.load(format!("memory://{}", path));
+    for _ in 0..100 {
+        app.update();
+        match server.get_load_state(&handle) {
+            Some(LoadState::Loaded) => break,
+            Some(LoadState::Failed(e)) => panic!("precinct failed to load: {}", e),
+            _ => std::thread::sleep(std::time::Duration::from_millis(10)),
+        }
+    }
+    let precincts = app.world().resource::<Assets<PrecinctAsset>>();
+    precincts.get(&handle).unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paint_terrain_fx() {
+        let mut precinct = PrecinctAsset::default();
+        assert!(!precinct.paint_terrain_fx(IVec2::new(-5, 10), 1, Some("road")));
+        assert!(precinct.terrain_fx_types.is_empty());
+
+        // Painting at the edge also fills in the skirt.
+        assert!(precinct.paint_terrain_fx(IVec2::new(0, 10), 1, Some("road")));
+        assert_eq!(precinct.terrain_fx_types, vec!["road".to_string()]);
+        let map = precinct.decode_terrain_fx();
+        assert_eq!(map[11 * TERRAIN_FX_MAP_SIZE], 1);
+        assert_eq!(map[1 + 11 * TERRAIN_FX_MAP_SIZE], 1);
+        assert_eq!(map[2 + 11 * TERRAIN_FX_MAP_SIZE], 1);
+        assert_eq!(map[2 + 12 * TERRAIN_FX_MAP_SIZE], 0);
+        assert!(!precinct.paint_terrain_fx(IVec2::new(0, 10), 1, Some("road")));
+
+        assert!(precinct.paint_terrain_fx(IVec2::new(0, 10), 1, None));
+        assert_eq!(precinct.terrain_fx, None);
+    }
+
+    #[test]
+    fn test_tier_management() {
+        let origin = Vec2::new(64., 128.);
+        let floor = FloorRegionSer {
+            poly: vec![
+                Vec2::new(0., 0.),
+                Vec2::new(8., 0.),
+                Vec2::new(8., 8.),
+                Vec2::new(0., 8.),
+            ],
+            ..default()
+        };
+        let mut precinct = PrecinctAsset::default();
+        precinct.add_tier(0);
+        let tier = precinct.add_tier(1);
+        tier.pfloors.push(floor);
+        tier.cutaways.push(Rect::new(0., 0., 4., 4.));
+        // On the terrain, at the same heights as the tiers.
+        precinct.add_scenery_element(0, 0., Vec3::new(1., 0.25, 1.), None, None);
+        precinct.add_scenery_element(0, 0., Vec3::new(20., 1.25, 20.), None, None);
+        precinct.add_scenery_element(0, 0., Vec3::new(20., 3.25, 20.), None, None);
+        // On the floor of tier 1.
+        precinct.add_scenery_element(0, 0., Vec3::new(1., 1.25, 1.), None, None);
+        precinct.actors.push(ActorInstance {
+            position: Vec3::new(65., 1.25, 129.),
+            iid: Some("guard".to_string()),
+            ..default()
+        });
+        let levels = |p: &PrecinctAsset| p.tiers.iter().map(|t| t.level).collect::<Vec<_>>();
+        let heights =
+            |p: &PrecinctAsset| p.scenery.iter().map(|s| s.position.y).collect::<Vec<_>>();
+        let actor_heights =
+            |p: &PrecinctAsset| p.actors.iter().map(|a| a.position.y).collect::<Vec<_>>();
+
+        precinct.insert_tier(1, origin);
+        assert_eq!(levels(&precinct), vec![0, 1, 2]);
+        assert_eq!(heights(&precinct), vec![0.25, 1.25, 3.25, 2.25]);
+        assert_eq!(actor_heights(&precinct), vec![2.25]);
+        assert_eq!(precinct.tiers[2].cutaways.len(), 1);
+
+        precinct.copy_tier(2, origin);
+        assert_eq!(levels(&precinct), vec![0, 1, 2, 3]);
+        assert_eq!(heights(&precinct), vec![0.25, 1.25, 3.25, 2.25, 3.25]);
+        assert_eq!(actor_heights(&precinct), vec![2.25, 3.25]);
+        assert_eq!(precinct.tiers[3].cutaways.len(), 1);
+        assert_eq!(precinct.scenery[4].iid, SceneryInstanceId::Internal(4));
+        assert_eq!(precinct.actors[1].iid, None);
+
+        precinct.delete_tier(1, origin);
+        assert_eq!(levels(&precinct), vec![0, 1, 2]);
+        assert_eq!(heights(&precinct), vec![0.25, 1.25, 3.25, 1.25, 2.25]);
+        assert_eq!(actor_heights(&precinct), vec![1.25, 2.25]);
+
+        precinct.delete_tier(2, origin);
+        assert_eq!(levels(&precinct), vec![0, 1]);
+        assert_eq!(heights(&precinct), vec![0.25, 1.25, 3.25, 1.25]);
+        assert_eq!(actor_heights(&precinct), vec![1.25]);
+    }
+
+    #[test]
+    fn test_tiers_keep_walls_on_floor_edges() {
+        let origin = Vec2::ZERO;
+        let mut precinct = PrecinctAsset::default();
+        precinct.add_tier(1).pfloors.push(FloorRegionSer {
+            poly: vec![
+                Vec2::new(2., 2.),
+                Vec2::new(8., 2.),
+                Vec2::new(8., 8.),
+                Vec2::new(2., 8.),
+            ],
+            holes: vec![vec![
+                Vec2::new(4., 4.),
+                Vec2::new(6., 4.),
+                Vec2::new(6., 6.),
+                Vec2::new(4., 6.),
+            ]],
+            ..default()
+        });
+        // Walls on each side of the outline, at a corner, half a meter outside it, and around
+        // the hole.
+        for pos in [
+            Vec2::new(2., 5.),
+            Vec2::new(8., 5.),
+            Vec2::new(5., 2.),
+            Vec2::new(5., 8.),
+            Vec2::new(8., 8.),
+            Vec2::new(8.5, 5.),
+            Vec2::new(4., 5.),
+            Vec2::new(5., 6.),
+        ] {
+            precinct.add_scenery_element(0, 0., Vec3::new(pos.x, 1., pos.y), None, None);
+        }
+        // Well away from the floor, and in the middle of the hole.
+        precinct.add_scenery_element(0, 0., Vec3::new(12., 1., 5.), None, None);
+        precinct.add_scenery_element(0, 0., Vec3::new(5., 1., 5.), None, None);
+        let heights =
+            |p: &PrecinctAsset| p.scenery.iter().map(|s| s.position.y).collect::<Vec<_>>();
+
+        precinct.insert_tier(1, origin);
+        assert_eq!(
+            heights(&precinct),
+            vec![2., 2., 2., 2., 2., 2., 2., 2., 1., 1.]
+        );
+
+        precinct.copy_tier(2, origin);
+        assert_eq!(precinct.scenery.len(), 18);
+        assert!(precinct.scenery[10..].iter().all(|s| s.position.y == 3.));
+
+        precinct.delete_tier(2, origin);
+        assert_eq!(precinct.scenery.len(), 10);
+        assert_eq!(
+            heights(&precinct),
+            vec![1., 1., 2., 2., 2., 2., 2., 2., 2., 2.]
+        );
+    }
+
+    #[test]
+    fn test_layer_shown_in_game() {
+        let mut precinct = PrecinctAsset::default();
+        for (name, data) in [
+            (
+                "hidden",
+                LayerData {
+                    hidden: true,
+                    ..default()
+                },
+            ),
+            (
+                "guides",
+                LayerData {
+                    editor_only: true,
+                    ..default()
+                },
+            ),
+            (
+                "repaired",
+                LayerData {
+                    condition: Some("bridge_repaired".to_string()),
+                    ..default()
+                },
+            ),
+        ] {
+            precinct.layers.insert(name.to_string(), data);
+        }
+
+        // In the game, which doesn't depend on how the editor feature is compiled.
+        let mut conditions = LayerConditions::default();
+        assert!(precinct.is_layer_shown(None, &conditions));
+        assert!(precinct.is_layer_shown(Some("hidden"), &conditions));
+        assert!(!precinct.is_layer_shown(Some("guides"), &conditions));
+        assert!(!precinct.is_layer_shown(Some("repaired"), &conditions));
+        conditions.set("bridge_repaired", true);
+        assert!(precinct.is_layer_shown(Some("repaired"), &conditions));
+
+        // While editing, only the visibility toggle counts.
+        conditions.set("bridge_repaired", false);
+        conditions.set_editing(true);
+        assert!(!precinct.is_layer_shown(Some("hidden"), &conditions));
+        assert!(precinct.is_layer_shown(Some("guides"), &conditions));
+        assert!(precinct.is_layer_shown(Some("repaired"), &conditions));
+    }
+}
@@ -1,4 +1,8 @@
-use bevy::{math::IRect, prelude::*};
+use bevy::{
+    asset::{io::AssetReaderError, AssetLoadError, AssetLoadFailedEvent},
+    math::IRect,
+    prelude::*,
+};
 
 use crate::{
     view::{QueryRect, Viewpoint},
@@ -7,6 +11,8 @@ use crate::{
 
 use super::{
     precinct::{Precinct, PrecinctKey},
+    precinct_asset::PrecinctAsset,
+    precinct_format::PrecinctFormat,
     PRECINCT_SIZE_F,
 };
 
@@ -31,6 +37,33 @@ impl PrecinctCache {
     pub fn get(&mut self, key: &PrecinctKey) -> Option<Entity> {
         self.precincts.get(key).cloned()
     }
+
+    /// Remove all cached precincts belonging to the given realm, returning their entities so
+    /// that they can be despawned. They will be re-loaded the next time they come into view.
+    pub fn evict_realm(&mut self, realm: Entity) -> Vec<Entity> {
+        let keys: Vec<PrecinctKey> = self
+            .precincts
+            .iter()
+            .filter(|(key, _)| key.realm == realm)
+            .map(|(key, _)| PrecinctKey {
+                realm: key.realm,
+                x: key.x,
+                z: key.z,
+            })
+            .collect();
+        keys.iter()
+            .filter_map(|key| self.precincts.pop(key))
+            .collect()
+    }
+
+    /// Remove all cached precincts, returning their entities so that they can be despawned.
+    pub fn evict_all(&mut self) -> Vec<Entity> {
+        let mut entities = Vec::with_capacity(self.precincts.len());
+        while let Some((_, entity)) = self.precincts.pop_lru() {
+            entities.push(entity);
+        }
+        entities
+    }
 }
 
 /// System that manages the spawning and despawning of Precincts (scenery units) based on proximity
@@ -94,13 +127,11 @@ pub fn spawn_precincts(
 
                     None => {
                         println!("Creating precinct {} {} {}.", realm.name, x, z);
-                        let asset_path = format!(
-                            "scenery/precincts/{}/{}-{}.msgpack",
-                            realm.name,
-                            precinct_coord(x),
-                            precinct_coord(z)
-                        );
-                        let asset = server.load(asset_path);
+                        let asset = server.load(precinct_asset_path(
+                            &realm.name,
+                            IVec2::new(x, z),
+                            realm.precinct_format,
+                        ));
                         let entity = commands.spawn((
                             Name::new(format!("Precinct:{}:{}:{}", realm.name, x, z)),
                             Precinct {
@@ -150,6 +181,61 @@ pub fn spawn_precincts(
     }
 }
 
+/// Handle of a precinct's file in its realm's format, which was not found, kept while the file
+/// in the other format is tried instead.
+#[derive(Component)]
+pub struct PrecinctFormatFallback(Handle<PrecinctAsset>);
+
+/// Precincts are loaded in their realm's format first; when there is no such file, try the
+/// other format. If neither exists the precinct is empty, and goes back to the first handle so
+/// that any scenery added to it is saved in the realm's format.
+pub fn fall_back_precinct_format(
+    mut commands: Commands,
+    mut ev_failed: EventReader<AssetLoadFailedEvent<PrecinctAsset>>,
+    mut q_precincts: Query<(Entity, &mut Precinct, Option<&PrecinctFormatFallback>)>,
+    server: Res<AssetServer>,
+) {
+    for event in ev_failed.read() {
+        if !matches!(
+            event.error,
+            AssetLoadError::AssetReaderError(AssetReaderError::NotFound(_))
+        ) {
+            continue;
+        }
+        for (entity, mut precinct, fallback) in q_precincts
+            .iter_mut()
+            .filter(|(_, precinct, _)| precinct.asset.id() == event.id)
+        {
+            match fallback {
+                None => {
+                    let path = PrecinctFormat::from_path(event.path.path())
+                        .opposite()
+                        .with_extension(event.path.path());
+                    let primary = std::mem::replace(&mut precinct.asset, server.load(path));
+                    commands
+                        .entity(entity)
+                        .insert(PrecinctFormatFallback(primary));
+                }
+                Some(fallback) => {
+                    precinct.asset = fallback.0.clone();
+                    commands.entity(entity).remove::<PrecinctFormatFallback>();
+                }
+            }
+        }
+    }
+}
+
+/// Return the asset path of the precinct file at the given precinct coordinates.
+pub fn precinct_asset_path(realm_name: &str, coords: IVec2, format: PrecinctFormat) -> String {
+    format!(
+        "scenery/precincts/{}/{}-{}.{}",
+        realm_name,
+        precinct_coord(coords.x),
+        precinct_coord(coords.y),
+        format.extension()
+    )
+}
+
 fn precinct_coord(n: i32) -> String {
     if n >= 0 {
         format!("p{:03}", n)
@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// File format of a precinct. Both formats have the same structure; msgpack is compact and is
+/// what the game ships with, while JSON is meant for development so that level changes can be
+/// reviewed as text diffs. The format is detected per file when precincts are loaded, and
+/// each realm has a format in which its new precincts are created.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrecinctFormat {
+    #[default]
+    Msgpack,
+    Json,
+}
+
+impl PrecinctFormat {
+    /// All formats, in the order in which they are tried when loading a precinct.
+    pub const ALL: [PrecinctFormat; 2] = [PrecinctFormat::Msgpack, PrecinctFormat::Json];
+
+    /// File extension used for precincts in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            PrecinctFormat::Msgpack => "msgpack",
+            PrecinctFormat::Json => "precinct.json",
+        }
+    }
+
+    /// Determine the format of a precinct file from its name.
+    pub fn from_path(path: &Path) -> Self {
+        if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(".precinct.json"))
+        {
+            PrecinctFormat::Json
+        } else {
+            PrecinctFormat::Msgpack
+        }
+    }
+
+    /// Return the path of the same precinct stored in this format.
+    pub fn with_extension(self, path: &Path) -> PathBuf {
+        let name = path.to_string_lossy();
+        let stem = Self::ALL
+            .iter()
+            .find_map(|format| name.strip_suffix(&format!(".{}", format.extension())))
+            .unwrap_or(&name);
+        PathBuf::from(format!("{}.{}", stem, self.extension()))
+    }
+
+    /// The other format.
+    pub fn opposite(self) -> Self {
+        match self {
+            PrecinctFormat::Msgpack => PrecinctFormat::Json,
+            PrecinctFormat::Json => PrecinctFormat::Msgpack,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bevy::prelude::*;
+    use panoply_exemplar::Exemplar;
+
+    use super::*;
+    use crate::{
+        actors::ActorInstance,
+        scenery::{
+            floor_region::FloorRegionSer,
+            precinct_asset::{
+                encode_precinct, load_precinct_bytes, PrecinctAsset, SceneryInstanceId,
+            },
+        },
+    };
+
+    fn sample_precinct(server: &AssetServer) -> PrecinctAsset {
+        let mut precinct = PrecinctAsset::default();
+        let wall = precinct.add_scenery_type("walls/brick".to_string());
+        let floor = precinct.add_floor_type("floors/stone".to_string());
+        let layer = precinct.add_layer("Props");
+        precinct.layers.get_mut(&layer).unwrap().condition = Some("gate_open".to_string());
+        precinct.add_scenery_element(wall, 90., Vec3::new(1.5, 0., 2.25), None, None);
+        precinct.add_scenery_element(
+            wall,
+            0.1,
+            Vec3::new(3., 1., 0.3),
+            Some(SceneryInstanceId::External(Arc::new("gate".to_string()))),
+            Some(layer.clone()),
+        );
+        precinct.add_tier(0).pfloors.push(FloorRegionSer {
+            surface_index: floor,
+            poly: vec![Vec2::ZERO, Vec2::X, Vec2::ONE],
+            holes: Vec::new(),
+            layer: Some(layer.clone()),
+        });
+        precinct.terrain_fx = Some(vec![-3, 1, 2]);
+        precinct.actors = vec![
+            ActorInstance {
+                exemplar: server.load("exemplars/guard.json#guard"),
+                position: Vec3::new(0.1, 0., 7.3),
+                facing: 33.3,
+                ..default()
+            },
+            ActorInstance {
+                exemplar: server.load("exemplars/guard.json#captain"),
+                iid: Some("captain".to_string()),
+                layer: Some(layer),
+                ..default()
+            },
+        ];
+        precinct
+    }
+
+    #[test]
+    fn test_convert_roundtrip() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Exemplar>();
+        let server = app.world().resource::<AssetServer>().clone();
+
+        let msgpack = encode_precinct(&sample_precinct(&server), PrecinctFormat::Msgpack).unwrap();
+        let loaded = load_precinct_bytes("test/p000-p000.msgpack", msgpack.clone());
+        let json = encode_precinct(&loaded, PrecinctFormat::Json).unwrap();
+        let text = std::str::from_utf8(&json).unwrap();
+        assert!(text.contains("\"facing\": 90.0"));
+        assert!(text.contains("\"iid\": \"gate\""));
+        assert!(text.contains("\"layer\": \"Props\""));
+        assert!(text.contains("\"exemplar\": \"exemplars/guard.json#captain\""));
+
+        // Converting back gives exactly the same bytes.
+        let loaded = load_precinct_bytes("test/p000-p000.precinct.json", json);
+        let back = encode_precinct(&loaded, PrecinctFormat::Msgpack).unwrap();
+        assert_eq!(back, msgpack);
+    }
+
+    #[test]
+    fn test_format_with_extension() {
+        let path = Path::new("scenery/precincts/a/p000-n001.msgpack");
+        let json = PrecinctFormat::Json.with_extension(path);
+        assert_eq!(
+            json,
+            PathBuf::from("scenery/precincts/a/p000-n001.precinct.json")
+        );
+        assert_eq!(PrecinctFormat::Msgpack.with_extension(&json), path);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            PrecinctFormat::from_path(Path::new("scenery/precincts/a/p000-n001.precinct.json")),
+            PrecinctFormat::Json
+        );
+        assert_eq!(
+            PrecinctFormat::from_path(Path::new("scenery/precincts/a/p000-n001.msgpack")),
+            PrecinctFormat::Msgpack
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use super::{
+    precinct::{Precinct, PrecinctAssetChanged},
+    precinct_asset::PrecinctAsset,
+};
+
+/// Story flags which precinct layer conditions are tested against. Game logic sets a flag
+/// when, for example, a quest is completed, and layers conditioned on it appear.
+#[derive(Resource, Default, Debug)]
+pub struct LayerConditions {
+    flags: HashSet<String>,
+    /// Whether layers are being edited, in which case each layer's visibility toggle decides
+    /// whether it is shown, rather than its condition.
+    editing: bool,
+    /// Flags which have changed since precincts were last refreshed.
+    changed: HashSet<String>,
+    /// Whether every precinct needs to be refreshed.
+    changed_all: bool,
+}
+
+impl LayerConditions {
+    pub fn is_set(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+
+    pub fn set(&mut self, flag: &str, value: bool) {
+        let changed = if value {
+            self.flags.insert(flag.to_string())
+        } else {
+            self.flags.remove(flag)
+        };
+        if changed {
+            self.changed.insert(flag.to_string());
+        }
+    }
+
+    pub fn editing(&self) -> bool {
+        self.editing
+    }
+
+    pub fn set_editing(&mut self, editing: bool) {
+        if self.editing != editing {
+            self.editing = editing;
+            self.changed_all = true;
+        }
+    }
+}
+
+/// Evaluate a layer condition. A condition is a comma-separated list of flags, all of which
+/// must be set; a flag prefixed with `!` must instead be clear. For example,
+/// `"bridge_repaired, !bridge_burned"`.
+pub fn condition_met(condition: &str, conditions: &LayerConditions) -> bool {
+    condition_terms(condition).all(|(flag, negated)| conditions.is_set(flag) != negated)
+}
+
+/// The flags named in a layer condition, each with whether it is negated.
+fn condition_terms(condition: &str) -> impl Iterator<Item = (&str, bool)> {
+    condition
+        .split(',')
+        .map(str::trim)
+        .filter(|term| !term.is_empty())
+        .map(|term| match term.strip_prefix('!') {
+            Some(flag) => (flag.trim(), true),
+            None => (term, false),
+        })
+}
+
+/// Whether any of a precinct's layers has a condition which tests one of `flags`.
+fn uses_flags(asset: &PrecinctAsset, flags: &HashSet<String>) -> bool {
+    asset.layers.values().any(|layer| {
+        layer
+            .condition
+            .as_deref()
+            .is_some_and(|cond| condition_terms(cond).any(|(flag, _)| flags.contains(flag)))
+    })
+}
+
+/// When story flags change, rebuild the precincts whose conditional layers test them. When
+/// switching between editing and playing, rebuild all precincts.
+pub fn refresh_layer_conditions(
+    mut commands: Commands,
+    mut r_conditions: ResMut<LayerConditions>,
+    q_precincts: Query<(Entity, &Precinct)>,
+    r_assets: Res<Assets<PrecinctAsset>>,
+) {
+    if !r_conditions.is_changed() {
+        return;
+    }
+    // Take the pending changes without triggering this system again next frame.
+    let conditions = r_conditions.bypass_change_detection();
+    let changed = std::mem::take(&mut conditions.changed);
+    let changed_all = std::mem::take(&mut conditions.changed_all);
+    if conditions.editing && !changed_all {
+        // Conditions have no effect while editing.
+        return;
+    }
+    for (entity, precinct) in q_precincts.iter() {
+        if changed_all
+            || r_assets
+                .get(&precinct.asset)
+                .is_some_and(|asset| uses_flags(asset, &changed))
+        {
+            commands.entity(entity).insert(PrecinctAssetChanged);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenery::precinct_asset::LayerData;
+
+    #[test]
+    fn test_condition_met() {
+        let mut conditions = LayerConditions::default();
+        assert!(condition_met("", &conditions));
+        assert!(!condition_met("quest_done", &conditions));
+        assert!(condition_met("!quest_done", &conditions));
+        conditions.set("quest_done", true);
+        assert!(condition_met("quest_done", &conditions));
+        assert!(!condition_met("quest_done, gate_open", &conditions));
+        conditions.set("gate_open", true);
+        assert!(condition_met("quest_done, gate_open", &conditions));
+        assert!(!condition_met("quest_done, ! gate_open", &conditions));
+        conditions.set("quest_done", false);
+        assert!(!condition_met("quest_done", &conditions));
+    }
+
+    #[test]
+    fn test_refresh_layer_conditions() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<PrecinctAsset>()
+            .init_resource::<LayerConditions>()
+            .add_systems(Update, refresh_layer_conditions);
+
+        let mut spawn_precinct = |condition: &str| {
+            let mut asset = PrecinctAsset::default();
+            asset.layers.insert(
+                "Layer".to_string(),
+                LayerData {
+                    condition: Some(condition.to_string()),
+                    ..default()
+                },
+            );
+            let handle = app
+                .world_mut()
+                .resource_mut::<Assets<PrecinctAsset>>()
+                .add(asset);
+            app.world_mut()
+                .spawn(Precinct {
+                    realm: Entity::PLACEHOLDER,
+                    render_layer: default(),
+                    coords: IVec2::ZERO,
+                    visible: true,
+                    asset: handle,
+                    tiers: Vec::new(),
+                })
+                .id()
+        };
+        let bridge = spawn_precinct("bridge_repaired, !bridge_burned");
+        let gate = spawn_precinct("gate_open");
+        app.update();
+
+        let changed = |app: &mut App| {
+            let mut q_changed = app
+                .world_mut()
+                .query_filtered::<Entity, With<PrecinctAssetChanged>>();
+            let mut entities: Vec<Entity> = q_changed.iter(app.world()).collect();
+            for entity in entities.iter() {
+                app.world_mut()
+                    .entity_mut(*entity)
+                    .remove::<PrecinctAssetChanged>();
+            }
+            entities.sort();
+            entities
+        };
+
+        // Only the precinct which tests the flag is rebuilt.
+        app.world_mut()
+            .resource_mut::<LayerConditions>()
+            .set("bridge_burned", true);
+        app.update();
+        assert_eq!(changed(&mut app), vec![bridge]);
+
+        // Setting a flag that is already set changes nothing.
+        app.world_mut()
+            .resource_mut::<LayerConditions>()
+            .set("bridge_burned", true);
+        app.update();
+        assert_eq!(changed(&mut app), vec![]);
+
+        // While editing, conditions are ignored, but switching mode rebuilds everything.
+        app.world_mut()
+            .resource_mut::<LayerConditions>()
+            .set_editing(true);
+        app.update();
+        let mut all = vec![bridge, gate];
+        all.sort();
+        assert_eq!(changed(&mut app), all);
+        app.world_mut()
+            .resource_mut::<LayerConditions>()
+            .set("gate_open", true);
+        app.update();
+        assert_eq!(changed(&mut app), vec![]);
+    }
+}
@@ -0,0 +1,358 @@
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, saver::AssetSaver, AssetLoader, LoadContext},
+    prelude::*,
+    reflect::{TypeRegistry, TypeRegistryArc},
+};
+use futures_lite::{AsyncReadExt, AsyncWriteExt};
+use panoply_exemplar::InstanceAspects;
+use serde::{
+    de::{DeserializeSeed, Visitor},
+    Deserialize, Serialize,
+};
+use thiserror::Error;
+
+use super::precinct_asset::{
+    serialize_scenery_instance, CompressedInstanceListDeserializer, SceneryInstanceId,
+};
+
+/// A reusable group of scenery, floors and terrain effects, such as a house. This is also
+/// what the editor clipboard holds.
+///
+/// Positions are relative to the group's origin, which lies on the tier the group was copied
+/// from, so that it can be placed on any tier of any precinct.
+#[derive(TypePath, Asset, Serialize, Debug, Default, Clone)]
+pub struct PrefabAsset {
+    /// Table of scenery exemplars used by this prefab.
+    #[serde(default)]
+    pub(crate) scenery_types: Vec<String>,
+
+    /// Table of floor exemplars used by this prefab.
+    #[serde(default)]
+    pub(crate) floor_types: Vec<String>,
+
+    /// Table of terrain effect exemplars used by this prefab.
+    #[serde(default)]
+    pub(crate) terrain_fx_types: Vec<String>,
+
+    #[serde(default)]
+    pub(crate) scenery: Vec<PrefabScenery>,
+
+    #[serde(default)]
+    pub(crate) floors: Vec<PrefabFloor>,
+
+    #[serde(default)]
+    pub(crate) terrain_fx: Vec<PrefabTerrainFx>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PrefabScenery {
+    /// Index into the scenery types table.
+    pub(crate) id: usize,
+
+    /// Facing direction, in degrees.
+    pub(crate) facing: f32,
+
+    /// Position relative to the prefab origin.
+    pub(crate) position: Vec3,
+
+    /// Instance aspects.
+    pub(crate) aspects: InstanceAspects,
+}
+
+/// Prefab scenery is written the same way as precinct scenery, minus the instance id and layer.
+impl Serialize for PrefabScenery {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        serialize_scenery_instance(
+            serializer,
+            self.id,
+            self.facing,
+            self.position,
+            &SceneryInstanceId::None,
+            (!self.aspects.is_empty()).then_some(&self.aspects),
+            None,
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct PrefabFloor {
+    /// Tier relative to the tier of the prefab origin.
+    pub(crate) tier: i32,
+
+    /// Index into the floor types table.
+    pub(crate) surface_index: usize,
+
+    /// Outline relative to the prefab origin.
+    pub(crate) poly: Vec<Vec2>,
+
+    #[serde(default)]
+    pub(crate) holes: Vec<Vec<Vec2>>,
+}
+
+/// A terrain effect on a single vertex of the terrain effect grid.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct PrefabTerrainFx {
+    /// Vertex coordinates relative to the prefab origin.
+    pub(crate) offset: IVec2,
+
+    /// Index into the terrain effect types table.
+    pub(crate) id: usize,
+}
+
+impl PrefabAsset {
+    pub fn is_empty(&self) -> bool {
+        self.scenery.is_empty() && self.floors.is_empty() && self.terrain_fx.is_empty()
+    }
+
+    /// Rotate the prefab around its origin by a number of quarter turns, in the same
+    /// direction that rotating the selection turns scenery.
+    pub fn rotate(&mut self, turns: i32) {
+        let turns = turns.rem_euclid(4);
+        if turns == 0 {
+            return;
+        }
+        let rotate = |v: Vec2| match turns {
+            1 => Vec2::new(-v.y, v.x),
+            2 => -v,
+            _ => Vec2::new(v.y, -v.x),
+        };
+        for scenery in self.scenery.iter_mut() {
+            let xz = rotate(scenery.position.xz());
+            scenery.position = Vec3::new(xz.x, scenery.position.y, xz.y);
+            scenery.facing = (scenery.facing - turns as f32 * 90.).rem_euclid(360.);
+        }
+        for floor in self.floors.iter_mut() {
+            floor.poly.iter_mut().for_each(|v| *v = rotate(*v));
+            floor
+                .holes
+                .iter_mut()
+                .flatten()
+                .for_each(|v| *v = rotate(*v));
+        }
+        for fx in self.terrain_fx.iter_mut() {
+            fx.offset = rotate(fx.offset.as_vec2()).as_ivec2();
+        }
+    }
+}
+
+/// Add a name to a type table if it isn't already there, and return its index.
+pub(crate) fn type_index(types: &mut Vec<String>, name: &str) -> usize {
+    match types.iter().position(|t| t == name) {
+        Some(index) => index,
+        None => {
+            types.push(name.to_string());
+            types.len() - 1
+        }
+    }
+}
+
+struct PrefabAssetDeserializer<'a, 'b> {
+    type_registry: &'a TypeRegistry,
+    load_context: &'a mut LoadContext<'b>,
+}
+
+impl<'de, 'a, 'b> DeserializeSeed<'de> for PrefabAssetDeserializer<'a, 'b> {
+    type Value = PrefabAsset;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(field_identifier, rename_all = "snake_case")]
+        enum Field {
+            SceneryTypes,
+            FloorTypes,
+            TerrainFxTypes,
+            Scenery,
+            Floors,
+            TerrainFx,
+        }
+
+        struct PrefabVisitor<'a, 'b> {
+            type_registry: &'a TypeRegistry,
+            load_context: &'a mut LoadContext<'b>,
+        }
+
+        impl<'de, 'a, 'b> Visitor<'de> for PrefabVisitor<'a, 'b> {
+            type Value = PrefabAsset;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("struct Prefab")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut prefab = PrefabAsset::default();
+                while let Some(key) = map.next_key()? {
+                    match key {
+                        Field::SceneryTypes => prefab.scenery_types = map.next_value()?,
+                        Field::FloorTypes => prefab.floor_types = map.next_value()?,
+                        Field::TerrainFxTypes => prefab.terrain_fx_types = map.next_value()?,
+                        Field::Scenery => {
+                            prefab.scenery = map
+                                .next_value_seed(CompressedInstanceListDeserializer {
+                                    type_registry: self.type_registry,
+                                    load_context: self.load_context,
+                                    parent_label: "scenery",
+                                })?
+                                .into_iter()
+                                .map(|instance| PrefabScenery {
+                                    id: instance.id,
+                                    facing: instance.facing,
+                                    position: instance.position,
+                                    aspects: instance.aspects,
+                                })
+                                .collect();
+                        }
+                        Field::Floors => prefab.floors = map.next_value()?,
+                        Field::TerrainFx => prefab.terrain_fx = map.next_value()?,
+                    }
+                }
+                Ok(prefab)
+            }
+        }
+
+        deserializer.deserialize_map(PrefabVisitor {
+            type_registry: self.type_registry,
+            load_context: self.load_context,
+        })
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum PrefabLoaderError {
+    #[error("Could not load prefab: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not decode prefab: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+pub struct PrefabAssetLoader {
+    type_registry: TypeRegistryArc,
+}
+
+impl FromWorld for PrefabAssetLoader {
+    fn from_world(world: &mut World) -> Self {
+        PrefabAssetLoader {
+            type_registry: world.resource::<AppTypeRegistry>().0.clone(),
+        }
+    }
+}
+
+impl AssetLoader for PrefabAssetLoader {
+    type Asset = PrefabAsset;
+    type Error = PrefabLoaderError;
+    type Settings = ();
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut deserializer = serde_json::Deserializer::from_slice(&bytes);
+        let prefab = PrefabAssetDeserializer {
+            type_registry: &self.type_registry.read(),
+            load_context,
+        }
+        .deserialize(&mut deserializer)?;
+        Ok(prefab)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["prefab.json"]
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum PrefabSaverError {
+    #[error("Could not save prefab: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not encode prefab: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Default)]
+pub struct PrefabAssetSaver;
+
+impl AssetSaver for PrefabAssetSaver {
+    type Asset = PrefabAsset;
+    type Settings = ();
+    type OutputLoader = PrefabAssetLoader;
+    type Error = PrefabSaverError;
+
+    async fn save<'a>(
+        &'a self,
+        writer: &'a mut bevy::asset::io::Writer,
+        asset: bevy::asset::saver::SavedAsset<'a, Self::Asset>,
+        _settings: &'a Self::Settings,
+    ) -> Result<(), Self::Error> {
+        let v = serde_json::to_vec_pretty(&*asset)?;
+        writer.write_all(&v).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate() {
+        let mut prefab = PrefabAsset {
+            scenery: vec![PrefabScenery {
+                id: 0,
+                facing: 0.,
+                position: Vec3::new(2., 0.5, 1.),
+                aspects: InstanceAspects::default(),
+            }],
+            floors: vec![PrefabFloor {
+                tier: 0,
+                surface_index: 0,
+                poly: vec![Vec2::ZERO, Vec2::new(2., 0.), Vec2::new(2., 1.)],
+                holes: Vec::new(),
+            }],
+            terrain_fx: vec![PrefabTerrainFx {
+                offset: IVec2::new(3, -1),
+                id: 0,
+            }],
+            ..default()
+        };
+        prefab.rotate(1);
+        assert_eq!(prefab.scenery[0].position, Vec3::new(-1., 0.5, 2.));
+        assert_eq!(prefab.scenery[0].facing, 270.);
+        assert_eq!(prefab.floors[0].poly[2], Vec2::new(-1., 2.));
+        assert_eq!(prefab.terrain_fx[0].offset, IVec2::new(1, 3));
+        prefab.rotate(3);
+        assert_eq!(prefab.scenery[0].position, Vec3::new(2., 0.5, 1.));
+        assert_eq!(prefab.scenery[0].facing, 0.);
+        assert_eq!(prefab.terrain_fx[0].offset, IVec2::new(3, -1));
+    }
+
+    #[test]
+    fn test_serialize_scenery() {
+        let scenery = PrefabScenery {
+            id: 1,
+            facing: 90.,
+            position: Vec3::new(1., 0., 2.),
+            aspects: InstanceAspects::default(),
+        };
+        let json = serde_json::to_value(&scenery).unwrap();
+        assert_eq!(json["id"], 1);
+        assert_eq!(json["facing"], 90.);
+        assert!(json.get("iid").is_none());
+        assert!(json.get("aspects").is_none());
+    }
+}
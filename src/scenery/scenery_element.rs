@@ -2,11 +2,17 @@ use bevy::{asset::LoadState, gltf::Gltf, prelude::*, render::view::RenderLayers}
 
 use panoply_exemplar::*;
 
-use crate::models::PropagateRenderLayers;
+use crate::{
+    models::PropagateRenderLayers,
+    random::NoiseSeed,
+    world::{Realm, RealmSeedChanged},
+};
 
 use super::{
+    precinct::Precinct,
     precinct_asset::SceneryInstanceId,
     scenery_aspect::{ModelComponent, SceneryModels},
+    PRECINCT_SIZE_F,
 };
 
 #[derive(Debug, Component, Clone, Default)]
@@ -30,6 +36,10 @@ pub struct SceneryElementMesh {
     pub handle: Handle<Gltf>,
     pub label: String,
     pub placement: ModelComponent,
+    /// Seed of the realm, used for random variation of the placement.
+    pub seed: NoiseSeed,
+    /// Noise coordinates derived from the element's world position.
+    pub noise_coords: IVec2,
 }
 
 pub fn update_se_aspects(
@@ -51,14 +61,37 @@ pub fn update_se_aspects(
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub fn spawn_se_models(
     mut commands: Commands,
-    mut query: Query<(Entity, &SceneryModels, &RenderLayers), With<SceneryElementRebuildModels>>,
+    mut query: Query<
+        (
+            Entity,
+            &SceneryModels,
+            &RenderLayers,
+            Option<&SceneryElement>,
+            Option<&Parent>,
+        ),
+        With<SceneryElementRebuildModels>,
+    >,
+    q_precincts: Query<&Precinct>,
+    q_realms: Query<&Realm>,
     // mut meshes: ResMut<Assets<Mesh>>,
     server: Res<AssetServer>,
 ) {
-    for (entity, models, layers) in query.iter_mut() {
+    for (entity, models, layers, element, parent) in query.iter_mut() {
         commands.entity(entity).clear_children();
+        let precinct = parent.and_then(|p| q_precincts.get(p.get()).ok());
+        let seed = precinct
+            .and_then(|p| q_realms.get(p.realm).ok())
+            .map_or(NoiseSeed::ZERO, |r| r.seed);
+        let noise_coords = match (element, precinct) {
+            (Some(element), Some(precinct)) => {
+                let world = element.position.xz() + precinct.coords.as_vec2() * PRECINCT_SIZE_F;
+                (world * 16.).round().as_ivec2()
+            }
+            _ => IVec2::ZERO,
+        };
         for model in models.0.iter() {
             if let Some((fname, fragment)) = model.asset.split_once('#') {
                 let handle: Handle<Gltf> = server.load(fname.to_owned());
@@ -68,6 +101,8 @@ pub fn spawn_se_models(
                             handle,
                             label: String::from(fragment),
                             placement: model.clone(),
+                            seed,
+                            noise_coords,
                         },
                         layers.clone(),
                     ))
@@ -93,7 +128,12 @@ pub fn spawn_se_model_instances(
             if let Some(gltf) = asset {
                 if let Some(scene_handle) = gltf.named_scenes.get(mesh.label.as_str()) {
                     let mut transform = Transform::from_translation(Vec3::new(0., 0., 0.));
-                    component_transform(&mut transform, &mesh.placement);
+                    component_transform(
+                        &mut transform,
+                        &mesh.placement,
+                        mesh.seed,
+                        mesh.noise_coords,
+                    );
                     commands.entity(entity).insert((
                         SceneBundle {
                             scene: scene_handle.clone(),
@@ -113,43 +153,60 @@ pub fn spawn_se_model_instances(
     }
 }
 
-fn component_transform(transform: &mut Transform, placement: &ModelComponent) {
-    // const nx = Math.round(position.x * 16);
-    // const ny = Math.round(position.z * 16);
-    // if (xRotationVariance) {
-    //   xRotation = MathUtils.euclideanModulo(
-    //     xRotation + (noise3(nx, ny, 11) - 0.5) * xRotationVariance,
-    //     360
-    //   );
-    // }
-    // if (yRotationVariance) {
-    //   yRotation = MathUtils.euclideanModulo(
-    //     yRotation + (noise3(nx, ny, 13) - 0.5) * yRotationVariance,
-    //     360
-    //   );
-    // }
-    // if (zRotationVariance) {
-    //   zRotation = MathUtils.euclideanModulo(
-    //     zRotation + (noise3(nx, ny, 14) - 0.5) * zRotationVariance,
-    //     360
-    //   );
-    // }
+/// Rebuild the models of every scenery element in a realm whose seed has changed, so that
+/// their random variation is recomputed.
+pub fn reseed_se_models(
+    trigger: Trigger<RealmSeedChanged>,
+    mut commands: Commands,
+    q_elements: Query<(Entity, &Parent), With<SceneryModels>>,
+    q_precincts: Query<&Precinct>,
+) {
+    let realm = trigger.event().realm;
+    for (entity, parent) in q_elements.iter() {
+        if q_precincts
+            .get(parent.get())
+            .is_ok_and(|precinct| precinct.realm == realm)
+        {
+            commands.entity(entity).insert(SceneryElementRebuildModels);
+        }
+    }
+}
+
+fn component_transform(
+    transform: &mut Transform,
+    placement: &ModelComponent,
+    seed: NoiseSeed,
+    noise_coords: IVec2,
+) {
+    // Rotation in degrees, randomly varied by up to half the variance either way.
+    let rotation = |rotation: Option<f32>, variance: Option<f32>, salt: i32| match variance {
+        Some(variance) => Some(
+            (rotation.unwrap_or(0.)
+                + (seed.noise3(noise_coords.x, noise_coords.y, salt) - 0.5) * variance)
+                .rem_euclid(360.),
+        ),
+        None => rotation,
+    };
     if let Some(offset) = placement.offset {
         transform.translation += offset;
     }
-    if let Some(rot) = placement.x_rotation {
+    if let Some(rot) = rotation(placement.x_rotation, placement.x_rotation_variance, 11) {
         transform.rotate_x(rot * std::f32::consts::PI / 180.0);
     }
-    if let Some(rot) = placement.y_rotation {
+    if let Some(rot) = rotation(placement.y_rotation, placement.y_rotation_variance, 13) {
         transform.rotate_y(rot * std::f32::consts::PI / 180.0);
     }
-    if let Some(rot) = placement.z_rotation {
+    if let Some(rot) = rotation(placement.z_rotation, placement.z_rotation_variance, 14) {
         transform.rotate_z(rot * std::f32::consts::PI / 180.0);
     }
-    // if (scaleVariance) {
-    //   scale += (noise3(nx, ny, 17) - 0.5) * scaleVariance;
-    // }
-    if let Some(scale) = placement.scale {
+    let scale = match placement.scale_variance {
+        Some(variance) => Some(
+            placement.scale.unwrap_or(1.)
+                + (seed.noise3(noise_coords.x, noise_coords.y, 17) - 0.5) * variance,
+        ),
+        None => placement.scale,
+    };
+    if let Some(scale) = scale {
         transform.scale = Vec3::new(scale, scale, scale);
     }
 }
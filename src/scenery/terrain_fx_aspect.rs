@@ -1,4 +1,3 @@
-use crate::terrain::TerrainTypes;
 use bevy::prelude::*;
 use panoply_exemplar::*;
 
@@ -9,7 +8,8 @@ use super::TERRAIN_FX_TYPE;
 #[derive(Component, Debug, Reflect, Clone, Default)]
 #[reflect(Aspect, Default)]
 pub struct TerrainEffect {
-    pub effect: TerrainTypes,
+    /// Names of the layers in `terrain.effects.json` which this effect applies.
+    pub effect: Vec<String>,
     pub effect_strength: Option<f32>,
     pub elevation: Option<f32>,
     pub continuous_x: Option<bool>,
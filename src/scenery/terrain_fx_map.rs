@@ -1,13 +1,15 @@
 use bevy::{asset::LoadState, prelude::*};
 
 use crate::terrain::{
-    Parcel, ParcelCache, ParcelFloraChanged, ParcelTerrainFx, RebuildParcelGroundMesh,
-    RebuildParcelTerrainFx, TerrainFxVertexAttr, TerrainOptions, PARCEL_SIZE,
-    PARCEL_TERRAIN_FX_AREA, PARCEL_TERRAIN_FX_STRIDE,
+    Parcel, ParcelCache, ParcelFloraChanged, ParcelFloraMask, ParcelTerrainFx,
+    RebuildParcelGroundMesh, RebuildParcelTerrainFx, TerrainEffectsAsset, TerrainEffectsHandle,
+    TerrainFxVertexAttr, TerrainOptions, PARCEL_SIZE, PARCEL_SIZE_U, PARCEL_TERRAIN_FX_AREA,
+    PARCEL_TERRAIN_FX_STRIDE,
 };
 use panoply_exemplar::*;
 
 use super::{
+    flora_exclusion::FloraExclusionMap,
     precinct::{Precinct, PrecinctKey},
     precinct_cache::PrecinctCache,
     terrain_fx_aspect::{TerrainEffect, TerrainHole},
@@ -43,13 +45,38 @@ impl TerrainFxMap {
 #[component(storage = "SparseSet")]
 pub struct RebuildTerrainFxVertexAttrs;
 
+/// When the terrain effects table changes, the effect layer indices need to be resolved again.
+pub fn reload_terrain_effects(
+    mut commands: Commands,
+    mut ev_effects: EventReader<AssetEvent<TerrainEffectsAsset>>,
+    query: Query<Entity, With<TerrainFxMap>>,
+) {
+    let mut changed = false;
+    for ev in ev_effects.read() {
+        if let AssetEvent::Modified { .. } = ev {
+            changed = true;
+        }
+    }
+    if changed {
+        for entity in query.iter() {
+            commands.entity(entity).insert(RebuildTerrainFxVertexAttrs);
+        }
+    }
+}
+
 pub fn rebuild_terrain_fx_vertex_attrs(
     mut commands: Commands,
     mut query: Query<(Entity, &Precinct, &mut TerrainFxMap), With<RebuildTerrainFxVertexAttrs>>,
     parcel_cache: Res<ParcelCache>,
     exemplar_assets: Res<Assets<Exemplar>>,
     server: Res<AssetServer>,
+    effects_handle: Res<TerrainEffectsHandle>,
+    effects_assets: Res<Assets<TerrainEffectsAsset>>,
 ) {
+    let Some(effects) = effects_assets.get(&effects_handle.0) else {
+        return;
+    };
+    let effects = effects.0.lock().unwrap();
     for (entity, precinct, mut terrain_fx) in query.iter_mut() {
         let all_loaded = terrain_fx
             .exemplars
@@ -73,7 +100,7 @@ pub fn rebuild_terrain_fx_vertex_attrs(
 
                     // Terrain effects
                     if let Some(eff) = aspect.as_any().downcast_ref::<TerrainEffect>() {
-                        vxt_attr.effect = eff.effect;
+                        vxt_attr.effect = effects.lookup(&eff.effect);
                         vxt_attr.effect_strength = eff.effect_strength.unwrap_or(0.);
                         vxt_attr.elevation = eff.elevation.unwrap_or(0.);
                         if eff.continuous_x.unwrap_or(false) {
@@ -100,28 +127,56 @@ pub fn rebuild_terrain_fx_vertex_attrs(
                 map_vertex_attr[index] = fx_table[*id as usize - 1];
             }
         }
+
+        // Only rebuild the parcels whose effects changed, unless the map is new.
+        let added = terrain_fx.is_added();
+        let changed = changed_parcels(precinct.coords, |x, z| {
+            added
+                || (0..PARCEL_TERRAIN_FX_STRIDE).any(|row| {
+                    let start = x + (z + row) * TERRAIN_FX_MAP_SIZE;
+                    let range = start..start + PARCEL_TERRAIN_FX_STRIDE;
+                    map_vertex_attr[range.clone()] != terrain_fx.map_vertex_attr[range]
+                })
+        });
         terrain_fx.map_vertex_attr = map_vertex_attr;
 
         commands
             .entity(entity)
             .remove::<RebuildTerrainFxVertexAttrs>();
 
-        let rect = IRect::new(
-            precinct.coords.x * PRECINCT_SIZE / PARCEL_SIZE,
-            precinct.coords.y * PRECINCT_SIZE / PARCEL_SIZE,
-            (precinct.coords.x + 1) * PRECINCT_SIZE / PARCEL_SIZE,
-            (precinct.coords.y + 1) * PRECINCT_SIZE / PARCEL_SIZE,
-        );
-        for parcel in parcel_cache.query(precinct.realm, rect) {
+        for parcel in changed
+            .into_iter()
+            .flat_map(|rect| parcel_cache.query(precinct.realm, rect))
+        {
             commands.entity(parcel).insert(RebuildParcelTerrainFx);
         }
     }
 }
 
+/// The parcels of a precinct for which `changed` returns true, given the parcel's offset in
+/// meters from the corner of the precinct. Each parcel is returned as a rectangle of parcel
+/// coordinates, suitable for [`ParcelCache::query`].
+pub(crate) fn changed_parcels(
+    precinct: IVec2,
+    changed: impl Fn(usize, usize) -> bool,
+) -> Vec<IRect> {
+    const PARCELS: i32 = PRECINCT_SIZE / PARCEL_SIZE;
+    let mut result = Vec::new();
+    for z in 0..PARCELS {
+        for x in 0..PARCELS {
+            if changed((x * PARCEL_SIZE) as usize, (z * PARCEL_SIZE) as usize) {
+                let coords = precinct * PARCELS + IVec2::new(x, z);
+                result.push(IRect::from_corners(coords, coords + IVec2::ONE));
+            }
+        }
+    }
+    result
+}
+
 pub fn rebuild_parcel_terrain_fx(
     mut commands: Commands,
     mut q_parcels: Query<(Entity, &mut Parcel), With<RebuildParcelTerrainFx>>,
-    q_precincts: Query<(&Precinct, &TerrainFxMap)>,
+    q_precincts: Query<(&Precinct, Option<&TerrainFxMap>, Option<&FloraExclusionMap>)>,
     mut precinct_cache: ResMut<PrecinctCache>,
 ) {
     for (entity, mut parcel) in q_parcels.iter_mut() {
@@ -137,7 +192,8 @@ pub fn rebuild_parcel_terrain_fx(
             commands.entity(entity).remove::<RebuildParcelTerrainFx>();
             continue;
         };
-        let Ok((precinct, terrain_fx_map)) = q_precincts.get(precinct_entity) else {
+        let Ok((precinct, terrain_fx_map, flora_exclusion)) = q_precincts.get(precinct_entity)
+        else {
             // println!("No precinct for parcel {:?}", parcel.coords);
             commands.entity(entity).remove::<RebuildParcelTerrainFx>();
             continue;
@@ -146,16 +202,29 @@ pub fn rebuild_parcel_terrain_fx(
         let z_offset = parcel.coords.y * PARCEL_SIZE - precinct.coords.y * PRECINCT_SIZE;
         assert!((0..PRECINCT_SIZE).contains(&x_offset));
         assert!((0..PRECINCT_SIZE).contains(&z_offset));
-        for z in 0..PARCEL_TERRAIN_FX_STRIDE {
-            for x in 0..PARCEL_TERRAIN_FX_STRIDE {
-                let fx_x = x + x_offset as usize;
-                let fx_z = z + z_offset as usize;
-                let fx_index = fx_x + fx_z * TERRAIN_FX_MAP_SIZE;
-                terrain_fx[x + z * PARCEL_TERRAIN_FX_STRIDE] =
-                    terrain_fx_map.map_vertex_attr[fx_index];
+        if let Some(terrain_fx_map) = terrain_fx_map {
+            for z in 0..PARCEL_TERRAIN_FX_STRIDE {
+                for x in 0..PARCEL_TERRAIN_FX_STRIDE {
+                    let fx_x = x + x_offset as usize;
+                    let fx_z = z + z_offset as usize;
+                    let fx_index = fx_x + fx_z * TERRAIN_FX_MAP_SIZE;
+                    terrain_fx[x + z * PARCEL_TERRAIN_FX_STRIDE] =
+                        terrain_fx_map.map_vertex_attr[fx_index];
+                }
             }
         }
         parcel.terrain_fx = ParcelTerrainFx(terrain_fx);
+
+        let mut flora_mask = ParcelFloraMask::default();
+        if let Some(flora_exclusion) = flora_exclusion {
+            for z in 0..PARCEL_SIZE_U {
+                for x in 0..PARCEL_SIZE_U {
+                    flora_mask.0[x + z * PARCEL_SIZE_U] =
+                        flora_exclusion.is_excluded(x + x_offset as usize, z + z_offset as usize);
+                }
+            }
+        }
+        parcel.flora_mask = flora_mask;
         // println!("Rebuilt terrain fx for parcel {:?}", parcel.coords);
         commands
             .entity(entity)
@@ -27,10 +27,50 @@ pub enum BiomeSurfaceType {
     Chaparral = 8,
 }
 
-#[derive(Default, Serialize, Deserialize)]
+/// Number of distinct ground surface types; each gets one layer in the ground texture array.
+pub const NUM_SURFACE_TYPES: usize = 9;
+
+#[derive(Serialize, Deserialize)]
 pub struct FloraTableEntry {
     pub proto: Option<String>,
-    probability: f32,
+    pub probability: f32,
+    /// Smallest and largest scale factor of the model.
+    #[serde(default = "default_flora_scale")]
+    pub scale: [f32; 2],
+    /// Rotate each instance by a random angle around the vertical axis.
+    #[serde(default)]
+    pub random_yaw: bool,
+    /// Tilt each instance to match the slope of the terrain.
+    #[serde(default)]
+    pub align_to_slope: bool,
+    /// Average number of instances placed in each flora cell.
+    #[serde(default = "default_flora_density")]
+    pub density: f32,
+    /// Minimum distance between this instance and any other flora in the same parcel.
+    #[serde(default)]
+    pub min_spacing: f32,
+}
+
+impl Default for FloraTableEntry {
+    fn default() -> Self {
+        Self {
+            proto: None,
+            probability: 0.,
+            scale: default_flora_scale(),
+            random_yaw: false,
+            align_to_slope: false,
+            density: default_flora_density(),
+            min_spacing: 0.,
+        }
+    }
+}
+
+fn default_flora_scale() -> [f32; 2] {
+    [0.5, 0.8]
+}
+
+fn default_flora_density() -> f32 {
+    1.
 }
 
 impl Choice for FloraTableEntry {
@@ -43,6 +83,13 @@ impl Choice for FloraTableEntry {
 pub struct BiomeData {
     pub name: String,
     pub surface: BiomeSurfaceType,
+    /// Texture for this biome's surface type. Biomes which share a surface type share a
+    /// texture, so only the first one which specifies a texture is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texture: Option<String>,
+    /// Number of texture repeats per world unit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texture_scale: Option<f32>,
     pub trees: Vec<FloraTableEntry>,
     pub shrubs: Vec<FloraTableEntry>,
     pub herbs: Vec<FloraTableEntry>,
@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+
+use super::{
+    terrain_contours::{contour_cell, FloraType, TerrainContour},
+    PARCEL_SIZE_U,
+};
+
+/// An operation applied to a terrain contour by a sculpt brush.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SculptOp {
+    /// Raise heights towards the given height, never lowering them.
+    Raise(i32),
+    /// Lower heights towards the given height, never raising them.
+    Lower(i32),
+    /// Move heights towards the given height.
+    Flatten(i32),
+    /// Average each height with its neighbors.
+    Smooth,
+    /// Paint the flora type of the cells under the brush.
+    Flora(FloraType),
+    /// Paint or erase water in the cells under the brush.
+    Water(bool),
+}
+
+/// A circular brush used to sculpt terrain contours.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContourBrush {
+    /// Radius of the brush, in terrain units. A radius of zero affects a single point.
+    pub radius: f32,
+    /// Fraction of the radius, measured inward from the rim, over which the strength of the
+    /// brush fades out.
+    pub falloff: f32,
+}
+
+impl ContourBrush {
+    /// Strength of the brush at a given distance from its center, from 0 to 1.
+    pub fn weight(&self, dist: f32) -> f32 {
+        if dist > self.radius + 0.001 {
+            return 0.;
+        }
+        let inner = self.radius * (1. - self.falloff.clamp(0., 1.));
+        if dist <= inner {
+            1.
+        } else {
+            ((self.radius - dist) / (self.radius - inner)).clamp(0., 1.)
+        }
+    }
+
+    /// Apply the brush to a contour. `center` is in the coordinate frame of the parcel, which
+    /// is the contour rotated by `rotation`. Returns true if anything changed.
+    pub fn apply(
+        &self,
+        contour: &mut TerrainContour,
+        rotation: u8,
+        center: Vec2,
+        op: SculptOp,
+    ) -> bool {
+        let mut changed = false;
+        if let SculptOp::Flora(_) | SculptOp::Water(_) = op {
+            for y in 0..PARCEL_SIZE_U {
+                for x in 0..PARCEL_SIZE_U {
+                    let pos = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                    if self.weight(pos.distance(center)) <= 0. {
+                        continue;
+                    }
+                    match op {
+                        SculptOp::Flora(flora) => {
+                            let (xr, yr) = contour_cell(x, y, rotation);
+                            if contour.flora.get(xr, yr) != flora {
+                                contour.set_flora_at(x, y, rotation, flora);
+                                changed = true;
+                            }
+                        }
+                        SculptOp::Water(water) => {
+                            if contour.water_at(x, y, rotation) != water {
+                                contour.set_water_at(x, y, rotation, water);
+                                changed = true;
+                            }
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+            }
+            return changed;
+        }
+
+        // Read from a snapshot so that smoothing doesn't depend on the order of updates.
+        let before = contour.clone();
+        for y in 0..=PARCEL_SIZE_U {
+            for x in 0..=PARCEL_SIZE_U {
+                let w = self.weight(Vec2::new(x as f32, y as f32).distance(center));
+                if w <= 0. {
+                    continue;
+                }
+                let h = before.unscaled_height_at(x, y, rotation);
+                let blend = |target: f32| (h as f32 + (target - h as f32) * w).round() as i32;
+                let new_height = match op {
+                    SculptOp::Raise(target) => blend(target as f32).max(h),
+                    SculptOp::Lower(target) => blend(target as f32).min(h),
+                    SculptOp::Flatten(target) => blend(target as f32),
+                    SculptOp::Smooth => {
+                        let mut total = 0;
+                        let mut count = 0;
+                        for ny in y.saturating_sub(1)..=(y + 1).min(PARCEL_SIZE_U) {
+                            for nx in x.saturating_sub(1)..=(x + 1).min(PARCEL_SIZE_U) {
+                                total += before.unscaled_height_at(nx, ny, rotation);
+                                count += 1;
+                            }
+                        }
+                        blend(total as f32 / count as f32)
+                    }
+                    SculptOp::Flora(_) | SculptOp::Water(_) => unreachable!(),
+                }
+                .clamp(i8::MIN as i32, i8::MAX as i32);
+                if new_height != h {
+                    contour.set_height_at(x, y, rotation, new_height as i8);
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::test_fixtures::flat_contour;
+
+    #[test]
+    fn test_weight() {
+        let brush = ContourBrush {
+            radius: 4.,
+            falloff: 0.5,
+        };
+        assert_eq!(brush.weight(0.), 1.);
+        assert_eq!(brush.weight(2.), 1.);
+        assert_eq!(brush.weight(3.), 0.5);
+        assert_eq!(brush.weight(5.), 0.);
+        let point = ContourBrush {
+            radius: 0.,
+            falloff: 0.5,
+        };
+        assert_eq!(point.weight(0.), 1.);
+        assert_eq!(point.weight(1.), 0.);
+    }
+
+    #[test]
+    fn test_raise_and_smooth() {
+        let mut contour = flat_contour(0, 0);
+        let brush = ContourBrush {
+            radius: 2.,
+            falloff: 0.,
+        };
+        assert!(brush.apply(&mut contour, 1, Vec2::new(8., 8.), SculptOp::Raise(4)));
+        assert_eq!(contour.unscaled_height_at(8, 8, 1), 4);
+        assert_eq!(contour.unscaled_height_at(10, 8, 1), 4);
+        assert_eq!(contour.unscaled_height_at(11, 8, 1), 0);
+        // Raising never lowers.
+        assert!(!brush.apply(&mut contour, 1, Vec2::new(8., 8.), SculptOp::Raise(2)));
+
+        let point = ContourBrush {
+            radius: 0.,
+            falloff: 0.,
+        };
+        assert!(point.apply(&mut contour, 1, Vec2::new(10., 9.), SculptOp::Smooth));
+        let h = contour.unscaled_height_at(10, 9, 1);
+        assert!(h > 0 && h < 4);
+    }
+
+    #[test]
+    fn test_paint_flora() {
+        let mut contour = flat_contour(0, 0);
+        let brush = ContourBrush {
+            radius: 0.,
+            falloff: 0.,
+        };
+        let rotation = 3;
+        assert!(brush.apply(
+            &mut contour,
+            rotation,
+            Vec2::new(2.5, 5.5),
+            SculptOp::Flora(FloraType::RandomShrub),
+        ));
+        let (xr, yr) = contour_cell(2, 5, rotation);
+        assert_eq!(contour.flora.get(xr, yr), FloraType::RandomShrub);
+        assert_eq!(
+            contour
+                .flora
+                .elts()
+                .iter()
+                .filter(|f| **f != FloraType::None)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_paint_water() {
+        let mut contour = flat_contour(0, 0);
+        let brush = ContourBrush {
+            radius: 2.,
+            falloff: 0.5,
+        };
+        let rotation = 1;
+        assert!(brush.apply(
+            &mut contour,
+            rotation,
+            Vec2::new(8., 8.),
+            SculptOp::Water(true)
+        ));
+        assert!(contour.has_water);
+        assert!(contour.water_at(7, 7, rotation));
+        assert!(contour.water_at(8, 9, rotation));
+        assert!(!contour.water_at(10, 8, rotation));
+        assert!(!contour.water_at(0, 0, rotation));
+        assert_eq!(contour.water.elts().iter().filter(|w| **w).count(), 12);
+
+        // Painting again changes nothing; erasing everything clears the flag.
+        assert!(!brush.apply(
+            &mut contour,
+            rotation,
+            Vec2::new(8., 8.),
+            SculptOp::Water(true)
+        ));
+        assert!(brush.apply(
+            &mut contour,
+            rotation,
+            Vec2::new(8., 8.),
+            SculptOp::Water(false)
+        ));
+        assert!(!contour.has_water);
+    }
+
+    #[test]
+    fn test_legacy_water() {
+        let mut contour = flat_contour(0, 0);
+        contour.has_water = true;
+        assert!(contour.water_at(3, 4, 2));
+        contour.set_water_at(3, 4, 2, false);
+        assert!(!contour.water_at(3, 4, 2));
+        assert!(contour.water_at(4, 4, 2));
+        assert!(contour.has_water);
+    }
+}
@@ -2,20 +2,24 @@ use std::sync::{Arc, Mutex, RwLock};
 
 use crate::{
     models::PropagateRenderLayers,
-    random::{noise3, WeightedChoice},
-    world::Realm,
+    random::{NoiseSeed, WeightedChoice},
+    world::{Realm, RealmSeedChanged},
 };
 
 use super::{
-    biome::{BiomesAsset, BiomesHandle, BiomesTable},
+    biome::{BiomesAsset, BiomesHandle, BiomesTable, FloraTableEntry},
+    flora_billboard::{
+        billboard_bounds, build_billboard_mesh, FloraBillboard, FloraBillboardBatch,
+        FloraBillboardMaterials, NUM_BILLBOARD_TEXTURES,
+    },
     parcel::{Parcel, ParcelFloraChanged, ShapeRef},
     rotator::RotatingSquareArray,
     terrain_contours::{
         FloraType, TerrainContoursHandle, TerrainContoursTable, TerrainContoursTableAsset,
     },
     terrain_map::TerrainMap,
-    ParcelTerrainFx, RebuildParcelTerrainFx, PARCEL_HEIGHT_SCALE, PARCEL_SIZE, PARCEL_SIZE_F,
-    PARCEL_SIZE_U,
+    ParcelFloraMask, ParcelTerrainFx, RebuildParcelTerrainFx, TerrainOptions, PARCEL_HEIGHT_SCALE,
+    PARCEL_SIZE, PARCEL_SIZE_F, PARCEL_SIZE_U,
 };
 use bevy::{
     asset::LoadState,
@@ -24,6 +28,14 @@ use bevy::{
     utils::HashMap,
 };
 use futures_lite::future;
+use std::f32::consts::TAU;
+
+/// Largest number of instances placed in a single flora cell.
+const MAX_CLUSTER_SIZE: usize = 8;
+
+/// Identifies a flora instance within a parcel: the coordinates of its cell, and its index
+/// within the cell.
+type FloraKey = (UVec2, u32);
 
 #[derive(Component)]
 pub struct ComputeFloraTask(Task<Option<FloraPlacementResult>>);
@@ -35,12 +47,18 @@ pub struct FloraPlacementResult {
     /// Map of model resource names to instances, used in building the instance components.
     /// Each terrain parcel or scenery precinct will have one of these, which specifies how many
     /// instances of each model are placed in the world, and where they are located.
-    models: HashMap<String, Vec<(UVec2, Transform)>>,
+    models: HashMap<String, Vec<(FloraKey, Transform)>>,
+
+    /// Billboard meshes shown in place of the models when the parcel is far away, one for each
+    /// billboard texture that is used.
+    billboards: Vec<FloraBillboardBatch>,
 }
 
 #[derive(Debug, Component, Default)]
 pub struct FloraInstance {
     pub coords: UVec2,
+    /// Index of the instance within its cell, when there's more than one.
+    pub index: u32,
     pub handle: Handle<Gltf>,
     pub label: String,
     pub transform: Transform,
@@ -64,10 +82,9 @@ pub fn gen_flora(
     let pool = AsyncComputeTaskPool::get();
 
     for (entity, parcel) in q_parcels.iter_mut() {
-        let realm = q_realms.get(parcel.realm);
-        if realm.is_err() {
+        let Ok((realm, _)) = q_realms.get(parcel.realm) else {
             return;
-        }
+        };
 
         // Ensure that both the terrain contours and biomes are loaded.
         if server.load_state(&ts_handle.0) != LoadState::Loaded
@@ -92,15 +109,20 @@ pub fn gen_flora(
         let biome_indices = parcel.biomes;
         let coords = IVec2::new(parcel.coords.x * PARCEL_SIZE, parcel.coords.y * PARCEL_SIZE);
         let terrain_fx = parcel.terrain_fx;
+        let flora_mask = parcel.flora_mask;
+        let seed = realm.seed;
         let task = pool.spawn(async move {
             let mut result = FloraPlacementResult {
                 models: HashMap::new(),
+                billboards: Vec::new(),
             };
             if compute_flora_placement(
                 coords,
+                seed,
                 shape_ref,
                 &contours,
                 &terrain_fx,
+                &flora_mask,
                 biome_indices,
                 &biomes,
                 &mut result,
@@ -118,6 +140,7 @@ pub fn gen_flora(
 }
 
 /// Consumes the output of the compute task and creates instances for trees and.
+#[allow(clippy::too_many_arguments)]
 pub fn insert_flora(
     mut commands: Commands,
     mut q_parcels: Query<(Entity, &mut Parcel, &mut ComputeFloraTask)>,
@@ -125,11 +148,13 @@ pub fn insert_flora(
     q_children: Query<&Children>,
     q_realms: Query<(&Realm, &TerrainMap)>,
     server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    r_billboard_materials: Res<FloraBillboardMaterials>,
 ) {
     for (entity, mut parcel, mut task) in q_parcels.iter_mut() {
         if let Ok((realm, _terrain)) = q_realms.get(parcel.realm) {
             if let Some(task_result) = future::block_on(future::poll_once(&mut task.0)) {
-                let mut existing_flora = HashMap::<UVec2, Entity>::new();
+                let mut existing_flora = HashMap::<FloraKey, Entity>::new();
                 // Remove existing flora
                 if let Some(flora_entity) = parcel.flora_entity {
                     // println!("Dropping flora {}", flora_entity);
@@ -137,7 +162,7 @@ pub fn insert_flora(
                         existing_flora.reserve(children.len());
                         for child in children.iter() {
                             if let Ok((flora, _)) = q_flora.get(*child) {
-                                existing_flora.insert(flora.coords, *child);
+                                existing_flora.insert((flora.coords, flora.index), *child);
                             }
                         }
                     }
@@ -151,7 +176,10 @@ pub fn insert_flora(
                         None => {
                             let child = commands
                                 .spawn((
-                                    SpatialBundle { ..default() },
+                                    SpatialBundle {
+                                        visibility: parcel.flora_lod.model_visibility(),
+                                        ..default()
+                                    },
                                     ParcelFlora,
                                     Name::new("Flora"),
                                     realm.layer.clone(),
@@ -169,8 +197,8 @@ pub fn insert_flora(
                     for (model, value) in flora_placement.models.drain() {
                         if let Some((fname, fragment)) = model.split_once('#') {
                             let handle: Handle<Gltf> = server.load(fname.to_owned());
-                            for (ipos, transform) in value {
-                                if let Some(flora) = existing_flora.remove(&ipos) {
+                            for (key, transform) in value {
+                                if let Some(flora) = existing_flora.remove(&key) {
                                     // Update old flora in place
                                     if let Ok((mut flora_cmp, mut transform_cmp)) =
                                         q_flora.get_mut(flora)
@@ -193,7 +221,8 @@ pub fn insert_flora(
                                         commands
                                             .spawn((
                                                 FloraInstance {
-                                                    coords: ipos,
+                                                    coords: key.0,
+                                                    index: key.1,
                                                     handle: handle.clone(),
                                                     label: fragment.to_string(),
                                                     transform,
@@ -214,6 +243,42 @@ pub fn insert_flora(
                     for (_, child_entity) in existing_flora.iter() {
                         commands.entity(*child_entity).despawn_recursive();
                     }
+
+                    // Replace billboards
+                    let billboard_entity = match parcel.billboard_entity {
+                        Some(billboard_ent) => {
+                            commands.entity(billboard_ent).despawn_descendants();
+                            billboard_ent
+                        }
+                        None => {
+                            let child = commands
+                                .spawn((
+                                    SpatialBundle {
+                                        visibility: parcel.flora_lod.billboard_visibility(),
+                                        ..default()
+                                    },
+                                    Name::new("FloraBillboards"),
+                                    realm.layer.clone(),
+                                ))
+                                .id();
+                            commands.entity(entity).add_child(child);
+                            parcel.billboard_entity = Some(child);
+                            child
+                        }
+                    };
+                    for batch in flora_placement.billboards.drain(..) {
+                        commands
+                            .spawn((
+                                MaterialMeshBundle {
+                                    mesh: meshes.add(batch.mesh),
+                                    material: r_billboard_materials.0[batch.texture].clone(),
+                                    ..default()
+                                },
+                                batch.bounds,
+                                realm.layer.clone(),
+                            ))
+                            .set_parent(billboard_entity);
+                    }
                 }
 
                 commands.entity(entity).remove::<ComputeFloraTask>();
@@ -253,11 +318,14 @@ pub fn spawn_flora_model_instances(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn compute_flora_placement(
     origin: IVec2,
+    seed: NoiseSeed,
     shape_ref: ShapeRef,
     contours: &Arc<RwLock<TerrainContoursTable>>,
     terrain_fx: &ParcelTerrainFx,
+    flora_mask: &ParcelFloraMask,
     biome_indices: [u8; 4],
     biomes: &Arc<Mutex<BiomesTable>>,
     out: &mut FloraPlacementResult,
@@ -282,11 +350,21 @@ fn compute_flora_placement(
         center.height.elts(),
     );
 
+    // Flora placed so far, and its spacing, used to keep instances apart.
+    let mut placed: Vec<(Vec2, f32)> = Vec::new();
+
+    // Billboards for each billboard texture.
+    let mut billboards: [Vec<FloraBillboard>; NUM_BILLBOARD_TEXTURES] = Default::default();
+
     for x in 0..PARCEL_SIZE_U {
         for z in 0..PARCEL_SIZE_U {
-            // Don't place flora on roads or other terrain fx.
+            // Don't place flora on roads or other terrain fx, or in terrain holes.
             let fx = terrain_fx.get(x + 1, z + 1);
-            if !fx.effect.is_empty() {
+            if !fx.effect.is_empty() || fx.options.contains(TerrainOptions::Hole) {
+                continue;
+            }
+            // Don't place flora under floors or scenery.
+            if flora_mask.is_excluded(x, z) {
                 continue;
             }
             let feature = flora.get(x, z);
@@ -297,12 +375,12 @@ fn compute_flora_placement(
             // Weighted random selection of biome N or N+1.
             let gx = origin.x + x as i32;
             let gz = origin.y + z as i32;
-            let xt: usize = if (x as f32 / PARCEL_SIZE_F + noise3(gx, gz, 5)) < 1. {
+            let xt: usize = if (x as f32 / PARCEL_SIZE_F + seed.noise3(gx, gz, 5)) < 1. {
                 0
             } else {
                 1
             };
-            let zt: usize = if (z as f32 / PARCEL_SIZE_F + noise3(gx, gz, 6)) < 1. {
+            let zt: usize = if (z as f32 / PARCEL_SIZE_F + seed.noise3(gx, gz, 6)) < 1. {
                 0
             } else {
                 2
@@ -312,7 +390,7 @@ fn compute_flora_placement(
                 continue;
             }
             let biome = &biomes_table.biomes[biome_index];
-            let feature_selection = noise3(gx, gz, 3);
+            let feature_selection = seed.noise3(gx, gz, 3);
             let feature_model = match feature {
                 FloraType::None => unreachable!(),
                 FloraType::RandomTree => WeightedChoice::choice(&biome.trees, feature_selection),
@@ -320,35 +398,43 @@ fn compute_flora_placement(
                 FloraType::RandomHerb => WeightedChoice::choice(&biome.herbs, feature_selection),
             };
 
-            let tx = x as f32 + 0.2 + noise3(gx, gz, 4) * 0.6;
-            let tz = z as f32 + 0.2 + noise3(gx, gz, 5) * 0.6;
-            let ty: f32 = heights.get_interpolated(tx, tz) * PARCEL_HEIGHT_SCALE;
-            // + match feature {
-            //     FloraType::None => unreachable!(),
-            //     FloraType::RandomTree => 1.25,
-            //     FloraType::RandomShrub => 0.5,
-            //     FloraType::RandomHerb => 0.75,
-            // };
-
-            let translation = Vec3::new(tx, ty, tz);
-            let rotation = Quat::IDENTITY;
-            // let mut scale = Vec3::new(1., 1., 1.);
             if let Some(entry) = feature_model {
                 if let Some(ref model) = entry.proto {
-                    let scale = noise3(gx, gz, 7) * 0.3 + 0.5;
-                    // println!("Biome: {gx} {gz} {}", model);
-                    let entry = out
-                        .models
-                        .entry(model.clone())
-                        .or_insert(Vec::with_capacity(6));
-                    entry.push((
-                        UVec2::new(x as u32, z as u32),
-                        Transform {
-                            translation,
-                            rotation,
-                            scale: Vec3::new(scale, scale, scale),
-                        },
-                    ))
+                    for (index, placement) in scatter_flora(entry, seed, gx, gz).iter().enumerate()
+                    {
+                        let pos = Vec2::new(x as f32, z as f32) + placement.offset;
+                        if placed.iter().any(|(other, spacing)| {
+                            other.distance(pos) < spacing.max(entry.min_spacing)
+                        }) {
+                            continue;
+                        }
+                        placed.push((pos, entry.min_spacing));
+
+                        let ty: f32 = heights.get_interpolated(pos.x, pos.y) * PARCEL_HEIGHT_SCALE;
+                        let mut rotation = Quat::from_rotation_y(placement.yaw);
+                        if entry.align_to_slope {
+                            rotation =
+                                Quat::from_rotation_arc(Vec3::Y, slope_normal(&heights, pos))
+                                    * rotation;
+                        }
+                        if let Some((texture, size)) = FloraBillboard::for_flora(feature) {
+                            billboards[texture].push(FloraBillboard {
+                                position: Vec3::new(pos.x, ty, pos.y),
+                                size,
+                            });
+                        }
+                        out.models
+                            .entry(model.clone())
+                            .or_insert(Vec::with_capacity(6))
+                            .push((
+                                (UVec2::new(x as u32, z as u32), index as u32),
+                                Transform {
+                                    translation: Vec3::new(pos.x, ty, pos.y),
+                                    rotation,
+                                    scale: Vec3::splat(placement.scale),
+                                },
+                            ));
+                    }
                 }
             }
 
@@ -458,5 +544,124 @@ fn compute_flora_placement(
     //     return null;
     //   }
 
+    for (texture, billboards) in billboards.iter().enumerate() {
+        if let Some(bounds) = billboard_bounds(billboards) {
+            out.billboards.push(FloraBillboardBatch {
+                texture,
+                mesh: build_billboard_mesh(billboards),
+                bounds,
+            });
+        }
+    }
+
     true
 }
+
+/// Where to place one instance of a flora model, relative to the corner of its cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FloraPlacement {
+    offset: Vec2,
+    scale: f32,
+    yaw: f32,
+}
+
+/// Regenerate flora for every parcel in a realm whose seed has changed.
+pub fn reseed_flora(
+    trigger: Trigger<RealmSeedChanged>,
+    mut commands: Commands,
+    q_parcels: Query<(Entity, &Parcel)>,
+) {
+    let realm = trigger.event().realm;
+    for (entity, parcel) in q_parcels.iter() {
+        if parcel.realm == realm {
+            commands.entity(entity).insert(ParcelFloraChanged);
+        }
+    }
+}
+
+/// Compute the placement of each instance of `entry` in the flora cell at global coordinates
+/// `(gx, gz)`. The result depends only on the entry, the seed and the coordinates.
+fn scatter_flora(
+    entry: &FloraTableEntry,
+    seed: NoiseSeed,
+    gx: i32,
+    gz: i32,
+) -> Vec<FloraPlacement> {
+    let density = entry.density.clamp(0., MAX_CLUSTER_SIZE as f32);
+    let mut count = density as usize;
+    if seed.noise3(gx, gz, 8) < density.fract() {
+        count += 1;
+    }
+    let [min_scale, max_scale] = entry.scale;
+    (0..count.min(MAX_CLUSTER_SIZE))
+        .map(|i| {
+            // The first instance uses the same noise channels as the original single-instance
+            // placement, so existing terrain keeps its look.
+            let (ox, oz, os, oy) = match i as i32 {
+                0 => (4, 5, 7, 6),
+                n => (16 + n * 4, 17 + n * 4, 18 + n * 4, 19 + n * 4),
+            };
+            let margin = if i == 0 { 0.2 } else { 0.1 };
+            FloraPlacement {
+                offset: Vec2::new(
+                    margin + seed.noise3(gx, gz, ox) * (1. - margin * 2.),
+                    margin + seed.noise3(gx, gz, oz) * (1. - margin * 2.),
+                ),
+                scale: min_scale + seed.noise3(gx, gz, os) * (max_scale - min_scale),
+                yaw: if entry.random_yaw {
+                    seed.noise3(gx, gz, oy) * TAU
+                } else {
+                    0.
+                },
+            }
+        })
+        .collect()
+}
+
+/// Surface normal of the terrain at a point within the parcel.
+fn slope_normal(heights: &RotatingSquareArray<i8>, pos: Vec2) -> Vec3 {
+    let height = |x: f32, z: f32| {
+        heights.get_interpolated(x.clamp(0., PARCEL_SIZE_F), z.clamp(0., PARCEL_SIZE_F))
+            * PARCEL_HEIGHT_SCALE
+    };
+    let dx = height(pos.x + 0.5, pos.y) - height(pos.x - 0.5, pos.y);
+    let dz = height(pos.x, pos.y + 0.5) - height(pos.x, pos.y - 0.5);
+    Vec3::new(-dx, 1., -dz).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scatter_flora() {
+        let single = FloraTableEntry::default();
+        let placements = scatter_flora(&single, NoiseSeed::ZERO, 3, 7);
+        assert_eq!(placements.len(), 1);
+        assert_eq!(placements, scatter_flora(&single, NoiseSeed::ZERO, 3, 7));
+        assert!((0.5..=0.8).contains(&placements[0].scale));
+        assert_eq!(placements[0].yaw, 0.);
+
+        let cluster = FloraTableEntry {
+            scale: [1., 2.],
+            random_yaw: true,
+            density: 3.,
+            ..default()
+        };
+        let placements = scatter_flora(&cluster, NoiseSeed::ZERO, 3, 7);
+        assert_eq!(placements.len(), 3);
+        for placement in placements.iter() {
+            assert!((1. ..=2.).contains(&placement.scale));
+            assert!((0. ..TAU).contains(&placement.yaw));
+            assert!(placement.offset.cmpge(Vec2::ZERO).all());
+            assert!(placement.offset.cmple(Vec2::ONE).all());
+        }
+        assert_ne!(placements[1].offset, placements[2].offset);
+
+        // A different seed gives a different arrangement, reproducibly.
+        let seed = NoiseSeed::new(7);
+        let reseeded = scatter_flora(&cluster, seed, 3, 7);
+        assert_eq!(reseeded, scatter_flora(&cluster, seed, 3, 7));
+        assert_ne!(reseeded, placements);
+    }
+}
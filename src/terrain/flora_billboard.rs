@@ -0,0 +1,298 @@
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    reflect::TypePath,
+    render::{
+        mesh::{Indices, MeshVertexAttribute, MeshVertexBufferLayoutRef},
+        primitives::Aabb,
+        render_asset::RenderAssetUsages,
+        render_resource::{
+            AsBindGroup, PrimitiveTopology, RenderPipelineDescriptor, ShaderRef,
+            SpecializedMeshPipelineError, VertexFormat,
+        },
+    },
+};
+
+use crate::view::Viewpoint;
+
+use super::{terrain_contours::FloraType, Parcel, PARCEL_SIZE_F};
+
+/// Offset of each billboard vertex from the base of the billboard: horizontal distance along
+/// the camera's right vector, and height.
+pub const ATTRIBUTE_BILLBOARD_OFFSET: MeshVertexAttribute =
+    MeshVertexAttribute::new("billboard_offset", 0x1001, VertexFormat::Float32x2);
+
+/// Number of billboard textures, one for each random flora type.
+pub const NUM_BILLBOARD_TEXTURES: usize = 3;
+
+/// Texture of the billboard used for each random flora type.
+const BILLBOARD_TEXTURES: [&str; NUM_BILLBOARD_TEXTURES] = [
+    "textures/pine.png",
+    "textures/shrub.png",
+    "textures/herb.png",
+];
+
+/// Parcels further than this from the viewpoint show billboards instead of flora models.
+const FLORA_BILLBOARD_DISTANCE: f32 = 64.;
+
+/// How far past the switching distance a parcel has to move before changing detail level,
+/// so that parcels near the boundary don't flicker back and forth.
+const FLORA_LOD_HYSTERESIS: f32 = 8.;
+
+/// Flora level of detail for a parcel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloraLod {
+    /// Flora is shown as full glTF models.
+    #[default]
+    Models,
+    /// Flora is shown as camera-facing billboards, batched into one mesh per texture.
+    Billboards,
+}
+
+impl FloraLod {
+    /// Level of detail for a parcel whose center is `distance` from the viewpoint.
+    pub fn update(self, distance: f32) -> Self {
+        match self {
+            FloraLod::Models if distance > FLORA_BILLBOARD_DISTANCE + FLORA_LOD_HYSTERESIS => {
+                FloraLod::Billboards
+            }
+            FloraLod::Billboards if distance < FLORA_BILLBOARD_DISTANCE - FLORA_LOD_HYSTERESIS => {
+                FloraLod::Models
+            }
+            lod => lod,
+        }
+    }
+
+    pub fn model_visibility(self) -> Visibility {
+        match self {
+            FloraLod::Models => Visibility::Inherited,
+            FloraLod::Billboards => Visibility::Hidden,
+        }
+    }
+
+    pub fn billboard_visibility(self) -> Visibility {
+        match self {
+            FloraLod::Models => Visibility::Hidden,
+            FloraLod::Billboards => Visibility::Inherited,
+        }
+    }
+}
+
+/// A single billboard, standing upright on the terrain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloraBillboard {
+    /// Position of the bottom center of the billboard, relative to the parcel.
+    pub position: Vec3,
+    /// Width and height of the billboard.
+    pub size: Vec2,
+}
+
+impl FloraBillboard {
+    /// Texture index and size of the billboard for a flora type.
+    pub fn for_flora(flora: FloraType) -> Option<(usize, Vec2)> {
+        match flora {
+            FloraType::None => None,
+            FloraType::RandomTree => Some((0, Vec2::new(1.5, 2.5))),
+            FloraType::RandomShrub => Some((1, Vec2::new(1.0, 1.0))),
+            FloraType::RandomHerb => Some((2, Vec2::new(1.0, 1.5))),
+        }
+    }
+}
+
+/// A batch of billboards sharing a texture, built off the main thread.
+pub struct FloraBillboardBatch {
+    pub texture: usize,
+    pub mesh: Mesh,
+    pub bounds: Aabb,
+}
+
+/// Build a single mesh containing a quad for each billboard. All four vertices of a quad are
+/// placed at the base of the billboard; the vertex shader spreads them out to face the camera.
+pub fn build_billboard_mesh(billboards: &[FloraBillboard]) -> Mesh {
+    let mut position: Vec<[f32; 3]> = Vec::with_capacity(billboards.len() * 4);
+    let mut uv: Vec<[f32; 2]> = Vec::with_capacity(billboards.len() * 4);
+    let mut offset: Vec<[f32; 2]> = Vec::with_capacity(billboards.len() * 4);
+    let mut indices: Vec<u32> = Vec::with_capacity(billboards.len() * 6);
+    for (i, billboard) in billboards.iter().enumerate() {
+        let base = (i * 4) as u32;
+        for [u, v] in [[0., 1.], [1., 1.], [1., 0.], [0., 0.]] {
+            position.push(billboard.position.to_array());
+            uv.push([u, v]);
+            offset.push([(u - 0.5) * billboard.size.x, (1. - v) * billboard.size.y]);
+        }
+        indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    );
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, position);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uv);
+    mesh.insert_attribute(ATTRIBUTE_BILLBOARD_OFFSET, offset);
+    mesh.insert_indices(Indices::U32(indices));
+    mesh
+}
+
+/// Bounding box of a set of billboards in any orientation. The mesh vertices only record the
+/// bases of the billboards, so the bounds can't be computed from the mesh.
+pub fn billboard_bounds(billboards: &[FloraBillboard]) -> Option<Aabb> {
+    billboards
+        .iter()
+        .map(|b| {
+            let half_width = b.size.x * 0.5;
+            (
+                b.position - Vec3::new(half_width, 0., half_width),
+                b.position + Vec3::new(half_width, b.size.y, half_width),
+            )
+        })
+        .reduce(|(min_a, max_a), (min_b, max_b)| (min_a.min(min_b), max_a.max(max_b)))
+        .map(|(min, max)| Aabb::from_min_max(min, max))
+}
+
+#[derive(AsBindGroup, TypePath, Debug, Clone, Asset)]
+pub struct FloraBillboardMaterial {
+    #[texture(1)]
+    #[sampler(2)]
+    pub texture: Handle<Image>,
+}
+
+impl Material for FloraBillboardMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "terrain/shaders/billboard.wgsl".into()
+    }
+
+    fn vertex_shader() -> ShaderRef {
+        "terrain/shaders/billboard.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Mask(0.5)
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_UV_0.at_shader_location(1),
+            ATTRIBUTE_BILLBOARD_OFFSET.at_shader_location(2),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        Ok(())
+    }
+}
+
+/// Billboard material for each billboard texture.
+#[derive(Resource, Default)]
+pub struct FloraBillboardMaterials(pub Vec<Handle<FloraBillboardMaterial>>);
+
+pub fn create_billboard_materials(
+    mut materials: ResMut<Assets<FloraBillboardMaterial>>,
+    mut resource: ResMut<FloraBillboardMaterials>,
+    asset_server: Res<AssetServer>,
+) {
+    resource.0 = BILLBOARD_TEXTURES
+        .iter()
+        .map(|path| {
+            materials.add(FloraBillboardMaterial {
+                texture: asset_server.load(*path),
+            })
+        })
+        .collect();
+}
+
+/// Switch parcels between flora models and billboards based on distance from the viewpoint.
+pub fn update_flora_lod(
+    r_viewpoint: Res<Viewpoint>,
+    mut q_parcels: Query<&mut Parcel>,
+    mut q_visibility: Query<&mut Visibility>,
+) {
+    let Some(realm) = r_viewpoint.realm else {
+        return;
+    };
+    let viewpoint = r_viewpoint.position.xz();
+    for mut parcel in q_parcels.iter_mut() {
+        if parcel.realm != realm {
+            continue;
+        }
+        let center = (parcel.coords.as_vec2() + 0.5) * PARCEL_SIZE_F;
+        let lod = parcel.flora_lod.update(center.distance(viewpoint));
+        if lod == parcel.flora_lod {
+            continue;
+        }
+        parcel.flora_lod = lod;
+        if let Some(mut visibility) = parcel
+            .flora_entity
+            .and_then(|e| q_visibility.get_mut(e).ok())
+        {
+            *visibility = lod.model_visibility();
+        }
+        if let Some(mut visibility) = parcel
+            .billboard_entity
+            .and_then(|e| q_visibility.get_mut(e).ok())
+        {
+            *visibility = lod.billboard_visibility();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::mesh::VertexAttributeValues;
+
+    #[test]
+    fn test_lod_hysteresis() {
+        let near = FLORA_BILLBOARD_DISTANCE - FLORA_LOD_HYSTERESIS * 2.;
+        let edge = FLORA_BILLBOARD_DISTANCE;
+        let far = FLORA_BILLBOARD_DISTANCE + FLORA_LOD_HYSTERESIS * 2.;
+        assert_eq!(FloraLod::Models.update(edge), FloraLod::Models);
+        assert_eq!(FloraLod::Models.update(far), FloraLod::Billboards);
+        assert_eq!(FloraLod::Billboards.update(edge), FloraLod::Billboards);
+        assert_eq!(FloraLod::Billboards.update(near), FloraLod::Models);
+    }
+
+    #[test]
+    fn test_billboard_mesh() {
+        let billboards = [
+            FloraBillboard {
+                position: Vec3::new(1., 2., 3.),
+                size: Vec2::new(1.5, 2.5),
+            },
+            FloraBillboard {
+                position: Vec3::new(5., 0., 7.),
+                size: Vec2::new(1., 1.),
+            },
+        ];
+        let mesh = build_billboard_mesh(&billboards);
+        assert_eq!(mesh.count_vertices(), 8);
+        let Some(Indices::U32(indices)) = mesh.indices() else {
+            panic!("expected u32 indices");
+        };
+        assert_eq!(indices, &[0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7]);
+
+        let Some(VertexAttributeValues::Float32x3(position)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("expected positions");
+        };
+        assert!(position[..4].iter().all(|p| *p == [1., 2., 3.]));
+        let Some(VertexAttributeValues::Float32x2(offset)) =
+            mesh.attribute(ATTRIBUTE_BILLBOARD_OFFSET)
+        else {
+            panic!("expected offsets");
+        };
+        assert_eq!(offset[0], [-0.75, 0.]);
+        assert_eq!(offset[2], [0.75, 2.5]);
+        assert_eq!(offset[7], [-0.5, 1.]);
+
+        let bounds = billboard_bounds(&billboards).unwrap();
+        assert_eq!(Vec3::from(bounds.min()), Vec3::new(0.25, 0., 2.25));
+        assert_eq!(Vec3::from(bounds.max()), Vec3::new(5.5, 4.5, 7.5));
+        assert!(billboard_bounds(&[]).is_none());
+    }
+}
@@ -1,16 +1,25 @@
 use bevy::{
+    asset::LoadState,
     pbr::{MaterialPipeline, MaterialPipelineKey},
     prelude::*,
     reflect::TypePath,
     render::{
         mesh::{MeshVertexAttribute, MeshVertexBufferLayoutRef},
+        render_asset::RenderAssetUsages,
         render_resource::{
-            AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
-            VertexFormat,
+            AsBindGroup, Extent3d, RenderPipelineDescriptor, ShaderRef,
+            SpecializedMeshPipelineError, TextureDimension, TextureFormat, TextureViewDescriptor,
+            TextureViewDimension, VertexFormat,
         },
+        texture::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor},
     },
 };
 
+use super::{
+    biome::{BiomeData, BiomesAsset, BiomesHandle, NUM_SURFACE_TYPES},
+    TerrainEffectsAsset, TerrainEffectsHandle, MAX_TERRAIN_EFFECT_LAYERS,
+};
+
 pub const ATTRIBUTE_TERRAIN_STYLE: MeshVertexAttribute =
     MeshVertexAttribute::new("terrain_style", 2, VertexFormat::Uint32x2);
 
@@ -21,21 +30,15 @@ pub struct GroundMaterial {
     #[sampler(2)]
     pub noise: Handle<Image>,
 
-    #[texture(3)]
+    /// Texture array with one layer per `BiomeSurfaceType`.
+    #[texture(3, dimension = "2d_array")]
     #[sampler(4)]
-    pub grass: Handle<Image>,
+    pub surfaces: Handle<Image>,
 
-    #[texture(5)]
+    /// Texture array with one layer per terrain effect layer.
+    #[texture(5, dimension = "2d_array")]
     #[sampler(6)]
-    pub dirt: Handle<Image>,
-
-    #[texture(7)]
-    #[sampler(8)]
-    pub moss: Handle<Image>,
-
-    #[texture(9)]
-    #[sampler(10)]
-    pub cobbles: Handle<Image>,
+    pub effects: Handle<Image>,
 
     #[texture(20, sample_type = "u_int")]
     #[sampler(21)]
@@ -46,6 +49,17 @@ pub struct GroundMaterial {
 
     #[uniform(23)]
     pub realm_offset: Vec2,
+
+    /// Texture scale of each surface type, packed four to a vector.
+    #[uniform(24)]
+    pub surface_scale: [Vec4; 3],
+
+    /// Blend mode, texture scale and whether there is a texture, for each effect layer.
+    #[uniform(25)]
+    pub effect_params: [Vec4; MAX_TERRAIN_EFFECT_LAYERS],
+
+    #[uniform(26)]
+    pub effect_tint: [LinearRgba; MAX_TERRAIN_EFFECT_LAYERS],
 }
 
 impl Material for GroundMaterial {
@@ -76,3 +90,440 @@ impl Material for GroundMaterial {
         Ok(())
     }
 }
+
+/// The ground surface texture array, shared by all ground materials. It is rebuilt whenever
+/// the biomes table or any of the surface textures change.
+pub const GROUND_SURFACES_HANDLE: Handle<Image> =
+    Handle::weak_from_u128(0x5e1f_37a2_9c4b_4d0e_8a61_2f3b_c07d_91e4);
+
+/// The terrain effect texture array, shared by all ground materials. It is rebuilt whenever
+/// the terrain effects table or any of the effect textures change.
+pub const GROUND_EFFECTS_HANDLE: Handle<Image> =
+    Handle::weak_from_u128(0x0b7c_62d4_13e8_4f5a_9d27_c4e1_58a3_6f02);
+
+/// Texture scale used for surfaces which don't specify one.
+const DEFAULT_SURFACE_SCALE: f32 = 0.35;
+
+/// Color of texture layers which have no texture.
+const MISSING_LAYER_COLOR: [u8; 4] = [128, 128, 128, 255];
+
+/// Individual textures which are combined into a texture array.
+#[derive(Default)]
+struct TextureArraySources {
+    /// Texture for each layer, if any.
+    layers: Vec<Option<Handle<Image>>>,
+    /// Whether the texture array is up to date with the sources.
+    built: bool,
+}
+
+impl TextureArraySources {
+    fn depends_on(&self, id: AssetId<Image>) -> bool {
+        self.layers.iter().flatten().any(|h| h.id() == id)
+    }
+
+    /// Rebuild the texture array if it is out of date and all of the sources have either
+    /// loaded or failed to load; those which failed are left blank. Returns true if the array
+    /// was rebuilt.
+    fn build(
+        &mut self,
+        handle: &Handle<Image>,
+        images: &mut Assets<Image>,
+        server: &AssetServer,
+    ) -> bool {
+        if self.built {
+            return false;
+        }
+        let mut layers: Vec<Option<&Image>> = Vec::with_capacity(self.layers.len());
+        for source in self.layers.iter() {
+            match source {
+                Some(source) => match images.get(source) {
+                    Some(image) => layers.push(Some(image)),
+                    None => match server.get_load_state(source) {
+                        Some(LoadState::Failed(_)) => {
+                            warn!(
+                                "Ground texture {:?} failed to load, its layer will be blank.",
+                                source.path()
+                            );
+                            layers.push(None);
+                        }
+                        _ => return false,
+                    },
+                },
+                None => layers.push(None),
+            }
+        }
+        let array = stack_texture_layers(&layers, layers.len().max(1));
+        images.insert(handle, array);
+        self.built = true;
+        true
+    }
+}
+
+/// Source textures and parameters for the ground texture arrays.
+#[derive(Resource)]
+pub struct GroundTextures {
+    surfaces: TextureArraySources,
+    effects: TextureArraySources,
+    /// Texture scale for each surface type.
+    surface_scale: [Vec4; 3],
+    /// Blend mode, texture scale and whether there is a texture, for each effect layer.
+    effect_params: [Vec4; MAX_TERRAIN_EFFECT_LAYERS],
+    effect_tint: [LinearRgba; MAX_TERRAIN_EFFECT_LAYERS],
+}
+
+impl GroundTextures {
+    fn apply(&self, material: &mut GroundMaterial) {
+        material.surface_scale = self.surface_scale;
+        material.effect_params = self.effect_params;
+        material.effect_tint = self.effect_tint;
+    }
+}
+
+impl FromWorld for GroundTextures {
+    fn from_world(world: &mut World) -> Self {
+        // Until the textures load, use placeholders so that terrain can render.
+        let mut images = world.resource_mut::<Assets<Image>>();
+        images.insert(
+            &GROUND_SURFACES_HANDLE,
+            stack_texture_layers(&[], NUM_SURFACE_TYPES),
+        );
+        images.insert(&GROUND_EFFECTS_HANDLE, stack_texture_layers(&[], 1));
+        GroundTextures {
+            surfaces: TextureArraySources::default(),
+            effects: TextureArraySources::default(),
+            surface_scale: [Vec4::splat(DEFAULT_SURFACE_SCALE); 3],
+            effect_params: [Vec4::ZERO; MAX_TERRAIN_EFFECT_LAYERS],
+            effect_tint: [LinearRgba::WHITE; MAX_TERRAIN_EFFECT_LAYERS],
+        }
+    }
+}
+
+/// Texture path and scale for each surface type, taken from the first biome of that surface
+/// type which specifies a texture.
+pub fn surface_textures(biomes: &[BiomeData]) -> Vec<Option<(String, f32)>> {
+    let mut result = vec![None; NUM_SURFACE_TYPES];
+    for biome in biomes.iter() {
+        let Some(texture) = biome.texture.as_ref() else {
+            continue;
+        };
+        let slot = &mut result[biome.surface as usize];
+        match slot {
+            None => {
+                *slot = Some((
+                    texture.clone(),
+                    biome.texture_scale.unwrap_or(DEFAULT_SURFACE_SCALE),
+                ))
+            }
+            Some((path, _)) if path != texture => {
+                warn!(
+                    "Biome {} has texture {}, but its surface type already uses {}.",
+                    biome.name, texture, path
+                );
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Combine textures into a texture array with `count` layers. Every layer takes the size of
+/// the first texture; textures of other sizes are tiled or cropped to fit, and missing
+/// textures are filled with a neutral color.
+pub fn stack_texture_layers(layers: &[Option<&Image>], count: usize) -> Image {
+    let (width, height) = layers
+        .iter()
+        .flatten()
+        .next()
+        .map_or((1, 1), |image| (image.width(), image.height()));
+    let layer_size = (width * height * 4) as usize;
+    let mut data = Vec::with_capacity(layer_size * count);
+    for layer in 0..count {
+        let source = layers.get(layer).copied().flatten().and_then(|image| {
+            match image.texture_descriptor.format {
+                TextureFormat::Rgba8UnormSrgb => Some(image.clone()),
+                format => {
+                    let converted = image.convert(TextureFormat::Rgba8UnormSrgb);
+                    if converted.is_none() {
+                        warn!(
+                            "Ground texture layer {} has format {:?}, which can't be converted; \
+                             the layer will be blank.",
+                            layer, format
+                        );
+                    }
+                    converted
+                }
+            }
+        });
+        match source {
+            Some(image) => {
+                let (w, h) = (image.width(), image.height());
+                if (w, h) != (width, height) {
+                    warn!(
+                        "Ground texture layer {} is {}x{}, but the array is {}x{}; it will be \
+                         tiled or cropped to fit.",
+                        layer, w, h, width, height
+                    );
+                }
+                for y in 0..height {
+                    for x in 0..width {
+                        let i = (((y % h) * w + (x % w)) * 4) as usize;
+                        data.extend_from_slice(&image.data[i..i + 4]);
+                    }
+                }
+            }
+            None => {
+                for _ in 0..width * height {
+                    data.extend_from_slice(&MISSING_LAYER_COLOR);
+                }
+            }
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: count as u32,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::D2Array),
+        ..default()
+    });
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        label: Some("Ground textures".to_string()),
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        mag_filter: ImageFilterMode::Linear,
+        min_filter: ImageFilterMode::Linear,
+        ..default()
+    });
+    image
+}
+
+/// Load the textures named in the biomes and terrain effects tables, and rebuild the ground
+/// texture arrays once they are all available.
+#[allow(clippy::too_many_arguments)]
+pub fn update_ground_textures(
+    mut ev_biomes: EventReader<AssetEvent<BiomesAsset>>,
+    mut ev_effects: EventReader<AssetEvent<TerrainEffectsAsset>>,
+    mut ev_images: EventReader<AssetEvent<Image>>,
+    mut ev_materials: EventReader<AssetEvent<GroundMaterial>>,
+    mut r_textures: ResMut<GroundTextures>,
+    mut r_images: ResMut<Assets<Image>>,
+    mut r_materials: ResMut<Assets<GroundMaterial>>,
+    r_biomes_handle: Res<BiomesHandle>,
+    r_biomes: Res<Assets<BiomesAsset>>,
+    r_effects_handle: Res<TerrainEffectsHandle>,
+    r_effects: Res<Assets<TerrainEffectsAsset>>,
+    server: Res<AssetServer>,
+) {
+    let mut params_changed = false;
+    for ev in ev_biomes.read() {
+        match ev {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::LoadedWithDependencies { id }
+                if *id == r_biomes_handle.0.id() =>
+            {
+                let Some(biomes) = r_biomes.get(*id) else {
+                    continue;
+                };
+                let textures = surface_textures(&biomes.0.lock().unwrap().biomes);
+                let mut scale = [Vec4::splat(DEFAULT_SURFACE_SCALE); 3];
+                for (i, texture) in textures.iter().enumerate() {
+                    if let Some((_, s)) = texture {
+                        scale[i / 4][i % 4] = *s;
+                    }
+                }
+                r_textures.surfaces = TextureArraySources {
+                    layers: textures
+                        .into_iter()
+                        .map(|texture| texture.map(|(path, _)| server.load(path)))
+                        .collect(),
+                    built: false,
+                };
+                r_textures.surface_scale = scale;
+                params_changed = true;
+            }
+            _ => {}
+        }
+    }
+
+    for ev in ev_effects.read() {
+        match ev {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::LoadedWithDependencies { id }
+                if *id == r_effects_handle.0.id() =>
+            {
+                let Some(effects) = r_effects.get(*id) else {
+                    continue;
+                };
+                let effects = effects.0.lock().unwrap();
+                let layers = &effects.layers[..effects.layers.len().min(MAX_TERRAIN_EFFECT_LAYERS)];
+                let mut params = [Vec4::ZERO; MAX_TERRAIN_EFFECT_LAYERS];
+                let mut tint = [LinearRgba::WHITE; MAX_TERRAIN_EFFECT_LAYERS];
+                for (i, layer) in layers.iter().enumerate() {
+                    params[i] = Vec4::new(
+                        layer.blend as u32 as f32,
+                        layer.texture_scale,
+                        if layer.texture.is_some() { 1. } else { 0. },
+                        0.,
+                    );
+                    tint[i] = Srgba::rgb(layer.tint[0], layer.tint[1], layer.tint[2]).into();
+                }
+                r_textures.effects = TextureArraySources {
+                    layers: layers
+                        .iter()
+                        .map(|layer| layer.texture.as_ref().map(|path| server.load(path)))
+                        .collect(),
+                    built: false,
+                };
+                r_textures.effect_params = params;
+                r_textures.effect_tint = tint;
+                params_changed = true;
+            }
+            _ => {}
+        }
+    }
+
+    // Rebuild if any of the source textures are reloaded.
+    for ev in ev_images.read() {
+        if let AssetEvent::Modified { id } = ev {
+            if r_textures.surfaces.depends_on(*id) {
+                r_textures.surfaces.built = false;
+            }
+            if r_textures.effects.depends_on(*id) {
+                r_textures.effects.built = false;
+            }
+        }
+    }
+
+    // Newly-created materials don't know the texture parameters.
+    for ev in ev_materials.read() {
+        if let AssetEvent::Added { id } = ev {
+            if let Some(material) = r_materials.get_mut(*id) {
+                r_textures.apply(material);
+            }
+        }
+    }
+
+    let textures = r_textures.as_mut();
+    let rebuilt = textures
+        .surfaces
+        .build(&GROUND_SURFACES_HANDLE, &mut r_images, &server)
+        | textures
+            .effects
+            .build(&GROUND_EFFECTS_HANDLE, &mut r_images, &server);
+    if rebuilt || params_changed {
+        // Mutating the materials causes their bind groups to be rebuilt with the new arrays.
+        for (_, material) in r_materials.iter_mut() {
+            textures.apply(material);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::biome::BiomeSurfaceType;
+
+    fn biome(surface: BiomeSurfaceType, texture: Option<&str>) -> BiomeData {
+        BiomeData {
+            name: "test".to_string(),
+            surface,
+            texture: texture.map(|t| t.to_string()),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_surface_textures() {
+        let textures = surface_textures(&[
+            biome(BiomeSurfaceType::Grass, Some("grass.png")),
+            biome(BiomeSurfaceType::Grass, Some("grass2.png")),
+            biome(BiomeSurfaceType::Snow, None),
+            biome(BiomeSurfaceType::Snow, Some("snow.png")),
+        ]);
+        assert_eq!(textures.len(), NUM_SURFACE_TYPES);
+        assert_eq!(
+            textures[BiomeSurfaceType::Grass as usize],
+            Some(("grass.png".to_string(), DEFAULT_SURFACE_SCALE))
+        );
+        assert_eq!(
+            textures[BiomeSurfaceType::Snow as usize],
+            Some(("snow.png".to_string(), DEFAULT_SURFACE_SCALE))
+        );
+        assert_eq!(textures[BiomeSurfaceType::Rock as usize], None);
+    }
+
+    #[test]
+    fn test_stack_texture_layers() {
+        let image = |size: u32, color: [u8; 4]| {
+            Image::new_fill(
+                Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                &color,
+                TextureFormat::Rgba8UnormSrgb,
+                RenderAssetUsages::default(),
+            )
+        };
+        let large = image(4, [255, 0, 0, 255]);
+        let small = image(2, [0, 255, 0, 255]);
+        let array = stack_texture_layers(&[None, Some(&large), Some(&small)], NUM_SURFACE_TYPES);
+        assert_eq!(array.width(), 4);
+        assert_eq!(array.height(), 4);
+        assert_eq!(
+            array.texture_descriptor.size.depth_or_array_layers,
+            NUM_SURFACE_TYPES as u32
+        );
+        let layer_size = 4 * 4 * 4;
+        assert_eq!(array.data.len(), layer_size * NUM_SURFACE_TYPES);
+        assert_eq!(array.data[0..4], MISSING_LAYER_COLOR);
+        assert_eq!(array.data[layer_size..layer_size + 4], [255, 0, 0, 255]);
+        // The smaller texture is tiled to fill the layer.
+        let last = layer_size * 3 - 4;
+        assert_eq!(array.data[last..last + 4], [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn test_build_with_failed_texture() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Image>();
+        let server = app.world().resource::<AssetServer>().clone();
+        let missing: Handle<Image> = server.load("textures/missing.png");
+        for _ in 0..100 {
+            app.update();
+            if matches!(server.get_load_state(&missing), Some(LoadState::Failed(_))) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let mut sources = TextureArraySources {
+            layers: vec![Some(missing), None],
+            built: false,
+        };
+        let handle = Handle::<Image>::weak_from_u128(0x51f0_27aa);
+        let rebuilt = app
+            .world_mut()
+            .resource_scope(|world, mut images: Mut<Assets<Image>>| {
+                sources.build(&handle, &mut images, world.resource::<AssetServer>())
+            });
+        assert!(rebuilt);
+        let images = app.world().resource::<Assets<Image>>();
+        let array = images.get(&handle).unwrap();
+        assert_eq!(array.texture_descriptor.size.depth_or_array_layers, 2);
+        assert_eq!(array.data[0..4], MISSING_LAYER_COLOR);
+    }
+}
@@ -3,7 +3,7 @@ use std::sync::{Arc, RwLock};
 use crate::{
     terrain::{
         ground_material::ATTRIBUTE_TERRAIN_STYLE, TerrainOptions, TerrainTypes,
-        PARCEL_MESH_STRIDE_U, PARCEL_TERRAIN_FX_SIZE,
+        MAX_PARCEL_EFFECT_LAYERS, PARCEL_MESH_STRIDE_U, PARCEL_TERRAIN_FX_SIZE,
     },
     world::Realm,
 };
@@ -164,10 +164,21 @@ fn compute_ground_mesh(
     let mut position: Vec<[f32; 3]> = Vec::with_capacity(PARCEL_MESH_VERTEX_COUNT);
     let mut normal: Vec<[f32; 3]> = Vec::with_capacity(PARCEL_MESH_VERTEX_COUNT);
     let mut indices: Vec<u32> = Vec::with_capacity((PARCEL_MESH_SIZE.pow(2)) as usize);
-    let mut terrain_style_f: Vec<[f32; 3]> = vec![[0., 0., 0.]; PARCEL_MESH_VERTEX_COUNT];
     let mut terrain_style: Vec<[u32; 2]> = vec![[0, 0]; PARCEL_MESH_VERTEX_COUNT];
     let mut terrain_elevation_offset: Vec<f32> = vec![0.; PARCEL_MESH_VERTEX_COUNT];
 
+    // Effect layers used anywhere in the parcel, including the skirt.
+    let mut parcel_layers = TerrainTypes::default();
+    for z in 0..PARCEL_TERRAIN_FX_SIZE {
+        for x in 0..PARCEL_TERRAIN_FX_SIZE {
+            parcel_layers |= terrain_fx.get(x, z).effect;
+        }
+    }
+    let layers: Vec<usize> = parcel_layers.iter().collect();
+    // Strength of each layer at each vertex.
+    let mut layer_strength: Vec<Vec<f32>> = vec![vec![0.; PARCEL_MESH_VERTEX_COUNT]; layers.len()];
+    let mut local_strength = vec![[[0.0; 5]; 5]; layers.len()];
+
     for z in -1..PARCEL_SIZE + 1 {
         for x in -1..PARCEL_SIZE + 1 {
             let tfx = terrain_fx.get((x + 1) as usize, (z + 1) as usize);
@@ -177,11 +188,9 @@ fn compute_ground_mesh(
             let z_span = if cont_z { -1..=1 } else { 0..=0 };
 
             let elevation = tfx.elevation;
-            let mut local_strength = [[[0.0; 5]; 5]; 3];
-            // maxStrength.fill(0);
-            for fx in 0..3 {
-                let fx_mask = TerrainTypes(1 << fx);
-                let has_effect = tfx.effect.contains(fx_mask);
+            local_strength.fill([[0.0; 5]; 5]);
+            for (fx, layer) in layers.iter().enumerate() {
+                let has_effect = tfx.effect.contains(*layer);
                 if has_effect {
                     // For each tile that has a terrain effect, determine whether any neighboring tiles
                     // have the same effect. Fill in the corners and sides of a 5x5 array.
@@ -196,7 +205,7 @@ fn compute_ground_mesh(
                             }
                             let adjacent =
                                 terrain_fx.get((x + rx + 1) as usize, (z + rz + 1) as usize);
-                            let has_adjacent = adjacent.effect.contains(fx_mask);
+                            let has_adjacent = adjacent.effect.contains(*layer);
                             let mut adjacent_strength = if has_adjacent {
                                 adjacent.effect_strength
                             } else {
@@ -210,8 +219,8 @@ fn compute_ground_mesh(
                                     terrain_fx.get((x + rx + 1) as usize, (z + 1) as usize);
                                 let adjacent_z =
                                     terrain_fx.get((x + 1) as usize, (z + rz + 1) as usize);
-                                if adjacent_x.effect.contains(fx_mask)
-                                    && adjacent_z.effect.contains(fx_mask)
+                                if adjacent_x.effect.contains(*layer)
+                                    && adjacent_z.effect.contains(*layer)
                                 {
                                     adjacent_strength = adjacent_strength.max(
                                         (adjacent_x.effect_strength + adjacent_z.effect_strength)
@@ -285,26 +294,43 @@ fn compute_ground_mesh(
                     }
 
                     let index = (zs * PARCEL_MESH_STRIDE + xs) as usize;
-
-                    let fx_cobbles = local_strength[0][xl as usize][zl as usize];
-                    let fx_soil = local_strength[1][xl as usize][zl as usize];
-                    let fx_earth = local_strength[2][xl as usize][zl as usize];
-                    let tsf = &mut terrain_style_f[index];
-                    tsf[0] = tsf[0].max(fx_cobbles);
-                    tsf[1] = tsf[1].max(fx_soil);
-                    tsf[2] = tsf[2].max(fx_earth);
+                    for (fx, strength) in layer_strength.iter_mut().enumerate() {
+                        strength[index] =
+                            strength[index].max(local_strength[fx][xl as usize][zl as usize]);
+                    }
                 }
             }
         }
     }
 
-    for (index, tsf) in terrain_style_f.iter().enumerate() {
-        let fx_cobbles = (tsf[0] * 255.0) as u32;
-        let fx_soil = (tsf[1] * 255.0) as u32;
-        let fx_earth = (tsf[2] * 255.0) as u32;
-        let terrain_style_0: u32 = pack_u32(fx_cobbles, fx_soil, fx_earth, 0);
-        let terrain_style_1: u32 = 0;
-        terrain_style[index] = [terrain_style_0, terrain_style_1];
+    // The vertex attribute has room for a limited number of layers, so keep the strongest.
+    // Every vertex in the parcel lists the same layers, so that they interpolate smoothly.
+    let mut slots: Vec<usize> = (0..layers.len()).collect();
+    if slots.len() > MAX_PARCEL_EFFECT_LAYERS {
+        let total = |fx: &usize| -> f32 { layer_strength[*fx].iter().sum() };
+        slots.sort_by(|a, b| total(b).total_cmp(&total(a)));
+        slots.truncate(MAX_PARCEL_EFFECT_LAYERS);
+        slots.sort();
+    }
+    let mut slot_layers = [0u32; MAX_PARCEL_EFFECT_LAYERS];
+    for (slot, fx) in slots.iter().enumerate() {
+        slot_layers[slot] = layers[*fx] as u32;
+    }
+    let terrain_style_0 = pack_u32(
+        slot_layers[0],
+        slot_layers[1],
+        slot_layers[2],
+        slot_layers[3],
+    );
+    for (index, style) in terrain_style.iter_mut().enumerate() {
+        let mut strength = [0u32; MAX_PARCEL_EFFECT_LAYERS];
+        for (slot, fx) in slots.iter().enumerate() {
+            strength[slot] = (layer_strength[*fx][index] * 255.0) as u32;
+        }
+        *style = [
+            terrain_style_0,
+            pack_u32(strength[0], strength[1], strength[2], strength[3]),
+        ];
     }
 
     // Generate vertices
@@ -1,6 +1,8 @@
 #![allow(dead_code)]
-mod biome;
+pub mod biome;
+pub mod contour_brush;
 mod flora;
+mod flora_billboard;
 mod ground_material;
 mod ground_mesh;
 mod parcel;
@@ -8,10 +10,15 @@ mod parcel_cache;
 mod plugin;
 pub mod rotator;
 mod square;
+pub mod terrain_autotile;
 pub mod terrain_contours;
+pub mod terrain_edges;
 mod terrain_fx;
+pub mod terrain_generator;
 pub mod terrain_groups;
 mod terrain_map;
+#[cfg(test)]
+mod test_fixtures;
 mod water_material;
 mod water_mesh;
 
@@ -20,6 +27,9 @@ pub const PARCEL_SIZE_F: f32 = PARCEL_SIZE as f32;
 pub const PARCEL_SIZE_U: usize = PARCEL_SIZE as usize;
 pub const PARCEL_HEIGHT_SCALE: f32 = 0.5;
 
+/// Height of the water surface in parcels whose water level hasn't been set.
+pub const DEFAULT_WATER_LEVEL: f32 = -0.4;
+
 pub const PARCEL_WATER_RESOLUTION: usize = 32;
 pub const PARCEL_WATER_RESOLUTION_S: i32 = 32;
 pub const PARCEL_WATER_STRIDE: usize = PARCEL_WATER_RESOLUTION + 1;
@@ -40,16 +50,18 @@ pub const PARCEL_TERRAIN_FX_AREA: usize = PARCEL_TERRAIN_FX_SIZE * PARCEL_TERRAI
 pub use ground_material::GroundMaterial;
 pub use ground_mesh::*;
 pub use parcel::Parcel;
-pub use parcel::ParcelTerrainFx;
 pub use parcel::{
     ParcelFloraChanged, ParcelThumbnail, ParcelWaterChanged, RebuildParcelGroundMesh,
     RebuildParcelTerrainFx, ShapeRef,
 };
+pub use parcel::{ParcelFloraMask, ParcelTerrainFx};
 pub use parcel_cache::*;
 pub use plugin::*;
 pub use terrain_fx::*;
+pub(crate) use terrain_map::{convert_parcel_to_precinct, PARCELS_PER_PRECINCT};
 #[allow(unused_imports)]
 pub use terrain_map::{
-    create_ground_material, TerrainMap, TerrainMapAsset, TerrainMapChanged, TerrainMapSaver,
+    create_ground_material, ParcelWater, TerrainMap, TerrainMapAsset, TerrainMapChanged,
+    TerrainMapLoadError, TerrainMapSaver, TerrainMapSaverError,
 };
 pub use water_mesh::ComputeWaterMeshTask;
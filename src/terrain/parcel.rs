@@ -1,8 +1,11 @@
 use bevy::prelude::*;
 
-use crate::terrain::{PARCEL_TERRAIN_FX_SIZE, PARCEL_TERRAIN_FX_STRIDE};
+use crate::terrain::{PARCEL_SIZE_U, PARCEL_TERRAIN_FX_SIZE, PARCEL_TERRAIN_FX_STRIDE};
 
-use super::{TerrainFxVertexAttr, PARCEL_TERRAIN_FX_AREA};
+use super::{
+    flora_billboard::FloraLod, terrain_map::ParcelWater, TerrainFxVertexAttr,
+    PARCEL_TERRAIN_FX_AREA,
+};
 #[derive(Eq, PartialEq, Hash)]
 pub struct ParcelKey {
     pub realm: Entity,
@@ -14,7 +17,7 @@ pub const ADJACENT_COUNT: usize = 9;
 pub const CENTER_SHAPE: usize = 4;
 
 // A reference to a terrain shape
-#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Default, Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct ShapeRef {
     pub shape: u16,
     pub rotation: u8,
@@ -41,6 +44,23 @@ impl ParcelTerrainFx {
     }
 }
 
+/// Cells of a parcel where flora may not grow, such as under floors or scenery.
+#[derive(Copy, Clone, PartialEq)]
+pub struct ParcelFloraMask(pub [bool; PARCEL_SIZE_U * PARCEL_SIZE_U]);
+
+impl ParcelFloraMask {
+    #[inline(always)]
+    pub fn is_excluded(&self, x: usize, z: usize) -> bool {
+        self.0[x + z * PARCEL_SIZE_U]
+    }
+}
+
+impl Default for ParcelFloraMask {
+    fn default() -> Self {
+        Self([false; PARCEL_SIZE_U * PARCEL_SIZE_U])
+    }
+}
+
 #[derive(Component)]
 pub struct Parcel {
     pub realm: Entity,
@@ -51,6 +71,9 @@ pub struct Parcel {
     /// Biome ids assigned to each corner.
     pub biomes: [u8; 4],
 
+    /// Water surface of this parcel and its neighbors.
+    pub water: [ParcelWater; ADJACENT_COUNT],
+
     /// Entity that represents the ground mesh of this parcel.
     pub ground_entity: Option<Entity>,
 
@@ -60,8 +83,17 @@ pub struct Parcel {
     /// Entity that contains the flora instances for this parcel.
     pub flora_entity: Option<Entity>,
 
+    /// Entity that contains the flora billboards for this parcel.
+    pub billboard_entity: Option<Entity>,
+
+    /// Whether flora is currently shown as models or billboards.
+    pub flora_lod: FloraLod,
+
     /// Terrain effects for this parcel.
     pub terrain_fx: ParcelTerrainFx,
+
+    /// Cells where flora is excluded by floors and scenery.
+    pub flora_mask: ParcelFloraMask,
 }
 
 impl Parcel {
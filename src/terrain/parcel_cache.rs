@@ -1,4 +1,4 @@
-use bevy::{asset::LoadState, math::IRect, prelude::*};
+use bevy::{math::IRect, prelude::*};
 use bevy_mod_picking::{
     events::{Down, Drag, DragEnd, DragStart, Pointer},
     prelude::{ListenerMut, On},
@@ -14,8 +14,9 @@ use crate::{
 
 use super::{
     parcel::{
-        Parcel, ParcelFloraChanged, ParcelKey, ParcelTerrainFx, ParcelWaterChanged,
-        RebuildParcelGroundMesh, RebuildParcelTerrainFx, ShapeRef, ADJACENT_COUNT,
+        Parcel, ParcelFloraChanged, ParcelFloraMask, ParcelKey, ParcelTerrainFx,
+        ParcelWaterChanged, RebuildParcelGroundMesh, RebuildParcelTerrainFx, ShapeRef,
+        ADJACENT_COUNT,
     },
     terrain_map::{TerrainMap, TerrainMapAsset},
     ParcelThumbnail, TerrainFxVertexAttr, PARCEL_SIZE_F, PARCEL_TERRAIN_FX_AREA,
@@ -39,6 +40,24 @@ impl ParcelCache {
         self.parcels.len()
     }
 
+    /// Remove all cached parcels belonging to the given realm, returning their entities so
+    /// that they can be despawned.
+    pub fn evict_realm(&mut self, realm: Entity) -> Vec<Entity> {
+        let keys: Vec<ParcelKey> = self
+            .parcels
+            .iter()
+            .filter(|(key, _)| key.realm == realm)
+            .map(|(key, _)| ParcelKey {
+                realm: key.realm,
+                x: key.x,
+                z: key.z,
+            })
+            .collect();
+        keys.iter()
+            .filter_map(|key| self.parcels.pop(key))
+            .collect()
+    }
+
     /// Query all parcels within a given rectangle.
     pub fn query(&self, realm: Entity, rect: IRect) -> ParcelRectIterator {
         ParcelRectIterator {
@@ -90,7 +109,6 @@ pub fn spawn_parcels(
     mut q_parcels: Query<(&mut Parcel, Option<&ParcelThumbnail>)>,
     q_realms: Query<(&Realm, &TerrainMap)>,
     terrain_map_assets: Res<Assets<TerrainMapAsset>>,
-    server: Res<AssetServer>,
 ) {
     if viewpoint.realm.is_none() {
         return;
@@ -124,12 +142,11 @@ pub fn spawn_parcels(
     let mut fetch_parcels = |rect: &QueryRect| {
         let realm_id = rect.realm;
         if let Ok((realm, terrain)) = q_realms.get(rect.realm) {
-            if server.load_state(&terrain.handle) != LoadState::Loaded {
+            // Maps created in the editor exist only in memory until they are saved, so their
+            // load state isn't meaningful; use whatever map is present.
+            let Some(terrain_map) = terrain_map_assets.get(&terrain.handle) else {
                 return;
-            }
-            let terrain_map = terrain_map_assets
-                .get(&terrain.handle)
-                .expect("expecting terrain map");
+            };
 
             // Set parcels within the query rect as visible; also load missing parcels.
             for z in rect.bounds.min.y..rect.bounds.max.y {
@@ -142,6 +159,7 @@ pub fn spawn_parcels(
                     let mut contours: [ShapeRef; 9] = [ShapeRef::new(); ADJACENT_COUNT];
                     terrain_map.adjacent_shapes(&mut contours, IVec2::new(x, z));
                     let biomes = terrain_map.adjacent_biomes(IVec2::new(x, z));
+                    let water = terrain_map.adjacent_water(IVec2::new(x, z));
                     let entity = parcel_cache.parcels.get(&key);
                     match entity {
                         Some(entity) => {
@@ -158,6 +176,10 @@ pub fn spawn_parcels(
                                         RebuildParcelTerrainFx,
                                     ));
                                 }
+                                if parcel.water != water {
+                                    parcel.water = water;
+                                    commands.entity(*entity).insert(ParcelWaterChanged);
+                                }
                                 parcel.visible = true;
                             }
                         }
@@ -172,12 +194,16 @@ pub fn spawn_parcels(
                                     visible: true,
                                     contours,
                                     biomes,
+                                    water,
                                     ground_entity: None,
                                     water_entity: None,
                                     flora_entity: None,
+                                    billboard_entity: None,
+                                    flora_lod: default(),
                                     terrain_fx: ParcelTerrainFx(
                                         [TerrainFxVertexAttr::default(); PARCEL_TERRAIN_FX_AREA],
                                     ),
+                                    flora_mask: ParcelFloraMask::default(),
                                 },
                                 Name::new(format!("Parcel:{}:{}:{}", realm.name, x, z)),
                                 SpatialBundle {
@@ -259,3 +285,54 @@ pub fn spawn_parcels(
         cache.pop_lru();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_parcels_for_unsaved_map() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<TerrainMapAsset>()
+            .insert_resource(ParcelCache::new())
+            .init_resource::<Viewpoint>()
+            .add_systems(Update, spawn_parcels);
+
+        // Like a realm created in the editor: the file doesn't exist, so the load fails, but
+        // the map is present in memory.
+        let handle = app
+            .world()
+            .resource::<AssetServer>()
+            .load::<TerrainMapAsset>("terrain/maps/unsaved.terrain");
+        app.world_mut()
+            .resource_mut::<Assets<TerrainMapAsset>>()
+            .insert(
+                handle.id(),
+                TerrainMapAsset::new(IRect::new(0, 0, 4, 4), 0, 0),
+            );
+        let realm = app
+            .world_mut()
+            .spawn((
+                Realm {
+                    name: "unsaved".to_string(),
+                    ..default()
+                },
+                TerrainMap {
+                    handle,
+                    ground_material: Handle::default(),
+                    needs_rebuild_biomes: false,
+                },
+            ))
+            .id();
+        {
+            let mut viewpoint = app.world_mut().resource_mut::<Viewpoint>();
+            viewpoint.realm = Some(realm);
+            viewpoint.camera_distance = 20.;
+        }
+
+        app.update();
+        let mut q_parcels = app.world_mut().query::<&Parcel>();
+        assert!(q_parcels.iter(app.world()).any(|p| p.realm == realm));
+    }
+}
@@ -5,20 +5,25 @@ use bevy::{
 
 use super::{
     biome::{BiomesAsset, BiomesHandle, BiomesLoader},
-    flora::{gen_flora, insert_flora, spawn_flora_model_instances},
+    flora::{gen_flora, insert_flora, reseed_flora, spawn_flora_model_instances},
+    flora_billboard::{
+        create_billboard_materials, update_flora_lod, FloraBillboardMaterial,
+        FloraBillboardMaterials,
+    },
     gen_ground_meshes,
-    ground_material::GroundMaterial,
+    ground_material::{update_ground_textures, GroundMaterial, GroundTextures},
     insert_ground_meshes, spawn_parcels,
     terrain_contours::{
         TerrainContoursHandle, TerrainContoursTableAsset, TerrainContoursTableLoader,
     },
     terrain_map::{
-        insert_terrain_maps, update_ground_material, update_terrain_maps, TerrainMapAsset,
-        TerrainMapLoader, TerrainMapsHandleResource,
+        insert_terrain_maps, report_terrain_map_errors, update_ground_material,
+        update_terrain_maps, TerrainMapAsset, TerrainMapLoadErrors, TerrainMapLoader,
+        TerrainMapsHandleResource,
     },
     water_material::{create_water_material, WaterMaterial, WaterMaterialResource},
     water_mesh::{gen_water_meshes, insert_water_meshes},
-    ParcelCache, TerrainTypes,
+    ParcelCache, TerrainEffectsAsset, TerrainEffectsHandle, TerrainEffectsLoader, TerrainTypes,
 };
 
 pub struct TerrainPlugin;
@@ -29,19 +34,26 @@ impl Plugin for TerrainPlugin {
             .register_asset_loader(TerrainContoursTableLoader)
             .register_asset_loader(TerrainMapLoader)
             .register_asset_loader(BiomesLoader)
+            .register_asset_loader(TerrainEffectsLoader)
             .register_type::<TerrainTypes>()
             .init_asset::<TerrainContoursTableAsset>()
             .init_asset::<TerrainMapAsset>()
             .init_asset::<BiomesAsset>()
+            .init_asset::<TerrainEffectsAsset>()
             .init_resource::<BiomesHandle>()
+            .init_resource::<TerrainEffectsHandle>()
             .init_resource::<TerrainContoursHandle>()
             .init_resource::<TerrainMapsHandleResource>()
+            .init_resource::<TerrainMapLoadErrors>()
             .init_resource::<WaterMaterialResource>()
+            .init_resource::<GroundTextures>()
+            .init_resource::<FloraBillboardMaterials>()
             .add_plugins((
                 MaterialPlugin::<GroundMaterial>::default(),
                 MaterialPlugin::<WaterMaterial>::default(),
+                MaterialPlugin::<FloraBillboardMaterial>::default(),
             ))
-            .add_systems(Startup, create_water_material)
+            .add_systems(Startup, (create_water_material, create_billboard_materials))
             .add_systems(
                 Update,
                 (
@@ -52,13 +64,17 @@ impl Plugin for TerrainPlugin {
                     insert_ground_meshes,
                     insert_water_meshes,
                     insert_flora,
+                    update_flora_lod.after(insert_flora),
                     insert_terrain_maps,
                     update_terrain_maps,
+                    report_terrain_map_errors.after(update_terrain_maps),
                     update_ground_material,
+                    update_ground_textures,
                     config_textures_modes,
                     spawn_flora_model_instances,
                 ),
-            );
+            )
+            .observe(reseed_flora);
     }
 }
 
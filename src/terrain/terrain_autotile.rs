@@ -0,0 +1,290 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use super::{
+    terrain_contours::TerrainContoursTable,
+    terrain_edges::{edge_mismatch, ContourEdgeIndex, ContourEdges, Side},
+    terrain_groups::TerrainGroupsTable,
+    ShapeRef, TerrainMapAsset,
+};
+
+/// How far the raise and lower intents move a parcel, in unscaled contour height units.
+const HEIGHT_STEP: f32 = 4.;
+
+/// Penalty per unit of height difference along an edge shared with a neighboring parcel.
+const MISMATCH_PENALTY: f32 = 1000.;
+
+/// Penalty for changing a parcel next to the painted area; these only change when needed to
+/// keep the edges continuous.
+const CHANGE_PENALTY: f32 = 100.;
+
+/// How strongly the flat intent prefers contours with little relief.
+const FLATNESS_WEIGHT: f32 = 10.;
+
+/// Number of passes over the affected parcels after the initial placement.
+const RELAX_PASSES: usize = 3;
+
+/// What the user wants the painted parcels to become.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+pub enum TileIntent {
+    #[default]
+    Raise,
+    Lower,
+    Flat,
+    Water,
+}
+
+/// A contour in a specific rotation which the auto-tiler can place.
+struct Tile<'a> {
+    edges: &'a ContourEdges,
+    water: bool,
+    /// Difference between the highest and lowest points of the contour.
+    relief: f32,
+}
+
+/// Replace the shapes of `parcels` according to `intent`, choosing contours from the visible
+/// terrain groups so that the edges between parcels stay continuous. Parcels adjacent to the
+/// painted area may also change if that's needed to avoid seams. Returns the number of seams
+/// remaining around the affected parcels. `index` must have been built from `contours`.
+pub fn autotile(
+    map: &mut TerrainMapAsset,
+    parcels: &[IVec2],
+    intent: TileIntent,
+    contours: &TerrainContoursTable,
+    index: &ContourEdgeIndex,
+    groups: &TerrainGroupsTable,
+) -> usize {
+    let tiles: Vec<Tile> = index
+        .candidates(groups.0.iter().filter(|group| group.visible))
+        .into_iter()
+        .filter_map(|edges| {
+            // The index can briefly lag behind edits to the contours table.
+            let contour = contours
+                .list()
+                .iter()
+                .find(|c| c.id == edges.shape.shape as usize)?;
+            let (lo, hi) = contour
+                .height
+                .elts()
+                .iter()
+                .fold((i8::MAX, i8::MIN), |(lo, hi), h| (lo.min(*h), hi.max(*h)));
+            Some(Tile {
+                edges,
+                water: contour.has_water,
+                relief: (hi as f32 - lo as f32).max(0.),
+            })
+        })
+        .collect();
+    if tiles.is_empty() {
+        return 0;
+    }
+
+    let mut painted: Vec<IVec2> = parcels
+        .iter()
+        .copied()
+        .filter(|pt| map.contains_pt(*pt))
+        .collect();
+    painted.sort_by_key(|pt| (pt.y, pt.x));
+    painted.dedup();
+    let painted_set: HashSet<IVec2> = painted.iter().copied().collect();
+    let mut ring: Vec<IVec2> = painted
+        .iter()
+        .flat_map(|pt| Side::ALL.map(|side| *pt + side.offset()))
+        .filter(|pt| map.contains_pt(*pt) && !painted_set.contains(pt))
+        .collect();
+    ring.sort_by_key(|pt| (pt.y, pt.x));
+    ring.dedup();
+
+    // Remember what each parcel looked like before, to compute targets and change costs.
+    let original = |map: &TerrainMapAsset, pt: IVec2| {
+        let shape = map.shape_at(pt);
+        let edges = index.get(shape);
+        let water = contours
+            .list()
+            .iter()
+            .find(|c| c.id == shape.shape as usize)
+            .map_or(false, |c| c.has_water);
+        (shape, edges.map_or(0., |e| e.mean_height), water)
+    };
+    let painted: Vec<(IVec2, f32)> = painted
+        .iter()
+        .map(|pt| (*pt, original(map, *pt).1))
+        .collect();
+    let ring: Vec<(IVec2, ShapeRef, f32, bool)> = ring
+        .iter()
+        .filter(|pt| index.get(map.shape_at(**pt)).is_some())
+        .map(|pt| {
+            let (shape, mean, water) = original(map, *pt);
+            (*pt, shape, mean, water)
+        })
+        .collect();
+
+    let place = |map: &mut TerrainMapAsset,
+                 pt: IVec2,
+                 neighbors: &dyn Fn(IVec2) -> bool,
+                 cost: &dyn Fn(&Tile) -> Option<f32>|
+     -> bool {
+        let mut best: Option<(f32, ShapeRef)> = None;
+        for tile in tiles.iter() {
+            let Some(cost) = cost(tile) else {
+                continue;
+            };
+            let mut mismatch = 0;
+            for side in Side::ALL {
+                let neighbor_pt = pt + side.offset();
+                if !map.contains_pt(neighbor_pt) || !neighbors(neighbor_pt) {
+                    continue;
+                }
+                if let Some(neighbor) = index.get(map.shape_at(neighbor_pt)) {
+                    mismatch +=
+                        edge_mismatch(tile.edges.edge(side), neighbor.edge(side.opposite()));
+                }
+            }
+            let score = mismatch as f32 * MISMATCH_PENALTY + cost;
+            if best.map_or(true, |(s, _)| score < s) {
+                best = Some((score, tile.edges.shape));
+            }
+        }
+        match best {
+            Some((_, shape)) if shape != map.shape_at(pt) => {
+                map.set_shape_at(pt, shape);
+                true
+            }
+            _ => false,
+        }
+    };
+
+    let painted_cost = |mean: f32| {
+        move |tile: &Tile| {
+            let water = intent == TileIntent::Water;
+            if tile.water != water {
+                return None;
+            }
+            let height = (tile.edges.mean_height - mean).abs();
+            Some(match intent {
+                TileIntent::Raise => (tile.edges.mean_height - (mean + HEIGHT_STEP)).abs(),
+                TileIntent::Lower => (tile.edges.mean_height - (mean - HEIGHT_STEP)).abs(),
+                TileIntent::Flat => height + tile.relief * FLATNESS_WEIGHT,
+                TileIntent::Water => height,
+            })
+        }
+    };
+    let ring_cost = |shape: ShapeRef, mean: f32, water: bool| {
+        move |tile: &Tile| {
+            if tile.water != water {
+                return None;
+            }
+            let change = if tile.edges.shape == shape {
+                0.
+            } else {
+                CHANGE_PENALTY
+            };
+            Some(change + (tile.edges.mean_height - mean).abs())
+        }
+    };
+
+    // Place the painted parcels first, matching only each other, then let the surrounding
+    // parcels adapt to them.
+    let mut placed: HashSet<IVec2> = HashSet::new();
+    for (pt, mean) in painted.iter() {
+        let is_placed = |p: IVec2| placed.contains(&p);
+        place(map, *pt, &is_placed, &painted_cost(*mean));
+        placed.insert(*pt);
+    }
+    for (pt, shape, mean, water) in ring.iter() {
+        place(map, *pt, &|_| true, &ring_cost(*shape, *mean, *water));
+    }
+
+    // Relax until everything settles, now considering all neighbors.
+    for _ in 0..RELAX_PASSES {
+        let mut changed = false;
+        for (pt, mean) in painted.iter() {
+            changed |= place(map, *pt, &|_| true, &painted_cost(*mean));
+        }
+        for (pt, shape, mean, water) in ring.iter() {
+            changed |= place(map, *pt, &|_| true, &ring_cost(*shape, *mean, *water));
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    index
+        .find_seams(map)
+        .iter()
+        .filter(|seam| {
+            painted_set.contains(&seam.coords)
+                || painted_set.contains(&(seam.coords + seam.side.offset()))
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::IRect;
+
+    use super::*;
+    use crate::terrain::{
+        terrain_contours::TerrainContour,
+        test_fixtures::{flat_contour, groups, ramp_contour},
+    };
+
+    #[test]
+    fn test_autotile_raise() {
+        let contours = TerrainContoursTable::from_contours(vec![
+            flat_contour(0, 0),
+            flat_contour(1, 4),
+            ramp_contour(2, 0, 4),
+        ]);
+        let mut map = TerrainMapAsset::new(IRect::new(0, 0, 5, 1), 0, 0);
+        let seams = autotile(
+            &mut map,
+            &[IVec2::new(2, 0)],
+            TileIntent::Raise,
+            &contours,
+            &ContourEdgeIndex::new(&contours),
+            &groups(vec![0, 1, 2]),
+        );
+        assert_eq!(seams, 0);
+        assert_eq!(map.shape_at(IVec2::new(2, 0)).shape, 1);
+        // The neighbors become ramps up to the raised parcel.
+        assert_eq!(
+            map.shape_at(IVec2::new(1, 0)),
+            ShapeRef {
+                shape: 2,
+                rotation: 0
+            }
+        );
+        assert_eq!(
+            map.shape_at(IVec2::new(3, 0)),
+            ShapeRef {
+                shape: 2,
+                rotation: 2
+            }
+        );
+        assert_eq!(map.shape_at(IVec2::new(0, 0)).shape, 0);
+        assert_eq!(map.shape_at(IVec2::new(4, 0)).shape, 0);
+    }
+
+    #[test]
+    fn test_autotile_water() {
+        let contours = TerrainContoursTable::from_contours(vec![
+            flat_contour(0, 0),
+            TerrainContour {
+                has_water: true,
+                ..flat_contour(1, 0)
+            },
+        ]);
+        let mut map = TerrainMapAsset::new(IRect::new(0, 0, 3, 3), 0, 0);
+        let seams = autotile(
+            &mut map,
+            &[IVec2::new(1, 1)],
+            TileIntent::Water,
+            &contours,
+            &ContourEdgeIndex::new(&contours),
+            &groups(vec![0, 1]),
+        );
+        assert_eq!(seams, 0);
+        assert_eq!(map.shape_at(IVec2::new(1, 1)).shape, 1);
+        assert_eq!(map.shape_at(IVec2::new(0, 1)).shape, 0);
+    }
+}
@@ -62,6 +62,55 @@ pub struct TerrainContour {
     pub has_terrain: bool,
     #[serde(alias = "hasWater")]
     pub has_water: bool,
+
+    /// Which cells of the contour are covered by water. Empty for contours whose water has
+    /// never been painted, in which case `has_water` applies to every cell.
+    #[serde(
+        default = "empty_water_array",
+        serialize_with = "serialize_water_array",
+        deserialize_with = "deserialize_water_array"
+    )]
+    pub water: SquareArray<bool>,
+}
+
+fn empty_water_array() -> SquareArray<bool> {
+    SquareArray::new(0, false)
+}
+
+fn deserialize_water_array<'de, D>(deserializer: D) -> Result<SquareArray<bool>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let data = serde_bytes::ByteBuf::deserialize(deserializer)?;
+    if data.is_empty() {
+        return Ok(empty_water_array());
+    }
+    let mut res = SquareArray::<bool>::new(FLORA_STRIDE, false);
+    assert_eq!(data.len(), FLORA_STRIDE * FLORA_STRIDE);
+    for i in 0..data.len() {
+        res.set(i % FLORA_STRIDE, i / FLORA_STRIDE, data[i] != 0);
+    }
+    Ok(res)
+}
+
+fn serialize_water_array<S>(data: &SquareArray<bool>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    let res: Vec<u8> = data.elts().iter().map(|w| *w as u8).collect();
+    serializer.serialize_bytes(res.as_ref())
+}
+
+/// Convert the coordinates of a cell within a parcel to the coordinates of the same cell in
+/// the contour, undoing the rotation of the parcel.
+pub fn contour_cell(x: usize, y: usize, rotation: u8) -> (usize, usize) {
+    match rotation {
+        0 => (x, y),
+        1 => (y, PARCEL_SIZE_U - 1 - x),
+        2 => (PARCEL_SIZE_U - 1 - x, PARCEL_SIZE_U - 1 - y),
+        3 => (PARCEL_SIZE_U - 1 - y, x),
+        _ => panic!("Invalid rotation"),
+    }
 }
 
 fn deserialize_height_array<'de, D>(deserializer: D) -> Result<SquareArray<i8>, D::Error>
@@ -155,21 +204,37 @@ impl TerrainContour {
 
     /// Set the flora type at a given point in the terrain contour, accounting for rotation.
     pub fn set_flora_at(&mut self, x: usize, y: usize, rotation: u8, value: FloraType) {
-        let (xr, yr) = match rotation {
-            0 => (x, y),
-            1 => (y, PARCEL_SIZE_U - 1 - x),
-            2 => (PARCEL_SIZE_U - 1 - x, PARCEL_SIZE_U - 1 - y),
-            3 => (PARCEL_SIZE_U - 1 - y, x),
-            _ => panic!("Invalid rotation"),
-        };
+        let (xr, yr) = contour_cell(x, y, rotation);
         self.flora.set(xr, yr, value);
     }
 
+    /// Get whether there is water at a given cell in the terrain contour, accounting for
+    /// rotation.
+    pub fn water_at(&self, x: usize, y: usize, rotation: u8) -> bool {
+        if self.water.size() == 0 {
+            return self.has_water;
+        }
+        let (xr, yr) = contour_cell(x, y, rotation);
+        self.water.get(xr, yr)
+    }
+
+    /// Set whether there is water at a given cell in the terrain contour, accounting for
+    /// rotation. `has_water` is updated to reflect whether any cell has water.
+    pub fn set_water_at(&mut self, x: usize, y: usize, rotation: u8, value: bool) {
+        if self.water.size() == 0 {
+            self.water = SquareArray::new(FLORA_STRIDE, self.has_water);
+        }
+        let (xr, yr) = contour_cell(x, y, rotation);
+        self.water.set(xr, yr, value);
+        self.has_water = self.water.elts().contains(&true);
+    }
+
     pub fn clone_from(&mut self, other: &TerrainContour) {
         self.height.copy_from_slice(other.height.elts());
         self.flora.copy_from_slice(other.flora.elts());
         self.has_terrain = other.has_terrain;
         self.has_water = other.has_water;
+        self.water = other.water.clone();
     }
 }
 
@@ -183,6 +248,18 @@ pub struct TerrainContoursTable {
 }
 
 impl TerrainContoursTable {
+    /// Build a table from a list of contours, indexing them by id.
+    pub fn from_contours(shapes: Vec<TerrainContour>) -> Self {
+        let mut by_id = Vec::with_capacity(shapes.len());
+        for (index, shape) in shapes.iter().enumerate() {
+            if by_id.len() <= shape.id {
+                by_id.resize(shape.id + 1, 0);
+            }
+            by_id[shape.id] = index;
+        }
+        Self { shapes, by_id }
+    }
+
     /// Get a reference to a terrain shape by it's id.
     pub fn get(&self, id: usize) -> &TerrainContour {
         assert!(id < self.by_id.len());
@@ -229,19 +306,7 @@ impl AssetLoader for TerrainContoursTableLoader {
         reader.read_to_end(&mut bytes).await?;
         let shapes: Vec<TerrainContour> =
             rmps::from_slice(&bytes).expect("unable to decode terrain shape");
-        let num_shapes = shapes.len();
-        let mut res = TerrainContoursTable {
-            shapes,
-            by_id: Vec::with_capacity(num_shapes),
-        };
-
-        for (index, shape) in res.shapes.iter().enumerate() {
-            if res.by_id.len() <= shape.id {
-                res.by_id.resize(shape.id + 1, 0);
-            }
-            res.by_id[shape.id] = index;
-        }
-
+        let res = TerrainContoursTable::from_contours(shapes);
         Ok(TerrainContoursTableAsset(Arc::new(RwLock::new(res))))
     }
 
@@ -0,0 +1,329 @@
+use bevy::{math::IRect, prelude::*, utils::HashMap};
+
+use super::{
+    terrain_contours::{TerrainContour, TerrainContoursTable},
+    terrain_groups::TerrainGroup,
+    ShapeRef, TerrainMapAsset, PARCEL_SIZE_U,
+};
+
+/// Number of height samples along the edge of a parcel.
+pub const EDGE_LENGTH: usize = PARCEL_SIZE_U + 1;
+
+/// Heights along one edge of a contour, ordered by increasing x or z.
+pub type EdgeProfile = [i8; EDGE_LENGTH];
+
+/// One of the four sides of a parcel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    North = 0,
+    East = 1,
+    South = 2,
+    West = 3,
+}
+
+impl Side {
+    pub const ALL: [Side; 4] = [Side::North, Side::East, Side::South, Side::West];
+
+    /// Offset, in parcels, of the neighbor on this side.
+    pub fn offset(self) -> IVec2 {
+        match self {
+            Side::North => IVec2::NEG_Y,
+            Side::East => IVec2::X,
+            Side::South => IVec2::Y,
+            Side::West => IVec2::NEG_X,
+        }
+    }
+
+    /// The side of the neighboring parcel which touches this side.
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::North => Side::South,
+            Side::East => Side::West,
+            Side::South => Side::North,
+            Side::West => Side::East,
+        }
+    }
+}
+
+/// The edge height profiles of a contour in a specific rotation.
+#[derive(Debug, Clone)]
+pub struct ContourEdges {
+    pub shape: ShapeRef,
+    pub edges: [EdgeProfile; 4],
+    /// Average height of the whole contour, unscaled.
+    pub mean_height: f32,
+}
+
+impl ContourEdges {
+    pub fn new(contour: &TerrainContour, rotation: u8) -> Self {
+        let height = |x: usize, y: usize| contour.unscaled_height_at(x, y, rotation) as i8;
+        let mut edges = [[0; EDGE_LENGTH]; 4];
+        let mut total = 0;
+        for i in 0..EDGE_LENGTH {
+            edges[Side::North as usize][i] = height(i, 0);
+            edges[Side::East as usize][i] = height(PARCEL_SIZE_U, i);
+            edges[Side::South as usize][i] = height(i, PARCEL_SIZE_U);
+            edges[Side::West as usize][i] = height(0, i);
+            for j in 0..EDGE_LENGTH {
+                total += height(i, j) as i32;
+            }
+        }
+        Self {
+            shape: ShapeRef {
+                shape: contour.id as u16,
+                rotation,
+            },
+            edges,
+            mean_height: total as f32 / (EDGE_LENGTH * EDGE_LENGTH) as f32,
+        }
+    }
+
+    /// Height profile of the given side.
+    pub fn edge(&self, side: Side) -> &EdgeProfile {
+        &self.edges[side as usize]
+    }
+}
+
+/// Sum of the height differences between two edge profiles; zero means a seamless join.
+pub fn edge_mismatch(a: &EdgeProfile, b: &EdgeProfile) -> i32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (*x as i32 - *y as i32).abs())
+        .sum()
+}
+
+/// A visible seam between a parcel and its neighbor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Seam {
+    /// Coordinates of the parcel.
+    pub coords: IVec2,
+    /// Side of the parcel where the seam is; always `East` or `South`.
+    pub side: Side,
+    /// Shape of the parcel.
+    pub shape: ShapeRef,
+    /// Shape of the neighboring parcel on `side`.
+    pub neighbor: ShapeRef,
+    /// Total height difference along the seam.
+    pub mismatch: i32,
+}
+
+/// Edge profiles of every contour in every rotation, indexed so that shapes which can sit
+/// next to each other without a seam can be found quickly.
+#[derive(Default)]
+pub struct ContourEdgeIndex {
+    shapes: HashMap<ShapeRef, ContourEdges>,
+    /// For each side, the shapes having a given profile on that side.
+    by_edge: [HashMap<EdgeProfile, Vec<ShapeRef>>; 4],
+}
+
+impl ContourEdgeIndex {
+    pub fn new(contours: &TerrainContoursTable) -> Self {
+        let mut index = Self::default();
+        for contour in contours.list() {
+            for rotation in 0..4 {
+                let edges = ContourEdges::new(contour, rotation);
+                for side in Side::ALL {
+                    index.by_edge[side as usize]
+                        .entry(*edges.edge(side))
+                        .or_default()
+                        .push(edges.shape);
+                }
+                index.shapes.insert(edges.shape, edges);
+            }
+        }
+        for by_side in index.by_edge.iter_mut() {
+            for shapes in by_side.values_mut() {
+                shapes.sort_by_key(|s| (s.shape, s.rotation));
+            }
+        }
+        index
+    }
+
+    /// Edge profiles for a given shape, if it exists.
+    pub fn get(&self, shape: ShapeRef) -> Option<&ContourEdges> {
+        self.shapes.get(&shape)
+    }
+
+    /// Edge profiles of every contour in `groups`, in all four rotations. This is what the
+    /// terrain generator and the auto-tiler choose from. Contours which no longer exist are
+    /// skipped.
+    pub fn candidates<'g>(
+        &self,
+        groups: impl IntoIterator<Item = &'g TerrainGroup>,
+    ) -> Vec<&ContourEdges> {
+        let mut ids: Vec<u16> = groups
+            .into_iter()
+            .flat_map(|group| group.contours.iter().map(|id| *id as u16))
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids.iter()
+            .flat_map(|id| {
+                (0..4).filter_map(move |rotation| {
+                    self.get(ShapeRef {
+                        shape: *id,
+                        rotation,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Shapes whose edge on `side` has exactly the given profile.
+    pub fn matching(&self, side: Side, profile: &EdgeProfile) -> &[ShapeRef] {
+        self.by_edge[side as usize]
+            .get(profile)
+            .map_or(&[], |shapes| shapes.as_slice())
+    }
+
+    /// Height mismatch between shape `a` and shape `b`, where `b` is the neighbor on `side`
+    /// of `a`. Returns `None` if either shape is unknown.
+    pub fn mismatch(&self, a: ShapeRef, side: Side, b: ShapeRef) -> Option<i32> {
+        let a = self.get(a)?;
+        let b = self.get(b)?;
+        Some(edge_mismatch(a.edge(side), b.edge(side.opposite())))
+    }
+
+    /// Shapes which could be placed at `pt` without creating a seam with any of the
+    /// neighboring parcels in the map.
+    pub fn compatible_shapes(&self, map: &TerrainMapAsset, pt: IVec2) -> Vec<ShapeRef> {
+        let mut result: Option<Vec<ShapeRef>> = None;
+        for side in Side::ALL {
+            let neighbor_pt = pt + side.offset();
+            if !map.contains_pt(neighbor_pt) {
+                continue;
+            }
+            let Some(neighbor) = self.get(map.shape_at(neighbor_pt)) else {
+                continue;
+            };
+            let matches = self.matching(side, neighbor.edge(side.opposite()));
+            result = Some(match result {
+                Some(prev) => prev.into_iter().filter(|s| matches.contains(s)).collect(),
+                None => matches.to_vec(),
+            });
+        }
+        result.unwrap_or_else(|| {
+            let mut all: Vec<ShapeRef> = self.shapes.keys().copied().collect();
+            all.sort_by_key(|s| (s.shape, s.rotation));
+            all
+        })
+    }
+
+    /// Find all the seams between adjacent parcels in a terrain map.
+    pub fn find_seams(&self, map: &TerrainMapAsset) -> Vec<Seam> {
+        self.find_seams_in(map, map.bounds)
+    }
+
+    /// Find the seams on the east and south sides of the parcels within `rect`, which is
+    /// half-open like the bounds of the map.
+    pub fn find_seams_in(&self, map: &TerrainMapAsset, rect: IRect) -> Vec<Seam> {
+        let rect = rect.intersect(map.bounds);
+        let mut seams = Vec::new();
+        for z in rect.min.y..rect.max.y {
+            for x in rect.min.x..rect.max.x {
+                let coords = IVec2::new(x, z);
+                let shape = map.shape_at(coords);
+                for side in [Side::East, Side::South] {
+                    let neighbor_pt = coords + side.offset();
+                    if !map.contains_pt(neighbor_pt) {
+                        continue;
+                    }
+                    let neighbor = map.shape_at(neighbor_pt);
+                    if let Some(mismatch) = self.mismatch(shape, side, neighbor) {
+                        if mismatch > 0 {
+                            seams.push(Seam {
+                                coords,
+                                side,
+                                shape,
+                                neighbor,
+                                mismatch,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        seams
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::IRect;
+
+    use super::*;
+    use crate::terrain::test_fixtures::{edge_index, groups, ramp_contour};
+
+    fn shape(shape: u16, rotation: u8) -> ShapeRef {
+        ShapeRef { shape, rotation }
+    }
+
+    #[test]
+    fn test_rotated_edges() {
+        let contours = TerrainContoursTable::from_contours(vec![ramp_contour(0, 0, 4)]);
+        let index = ContourEdgeIndex::new(&contours);
+        let edges = index.get(shape(0, 0)).unwrap();
+        assert_eq!(edges.edge(Side::West), &[0; EDGE_LENGTH]);
+        assert_eq!(edges.edge(Side::East), &[4; EDGE_LENGTH]);
+        // A half turn swaps the high and low sides.
+        let edges = index.get(shape(0, 2)).unwrap();
+        assert_eq!(edges.edge(Side::West), &[4; EDGE_LENGTH]);
+        assert_eq!(edges.edge(Side::East), &[0; EDGE_LENGTH]);
+        assert_eq!(
+            index.mismatch(shape(0, 0), Side::East, shape(0, 2)),
+            Some(0)
+        );
+        assert_eq!(
+            index.mismatch(shape(0, 0), Side::East, shape(0, 0)),
+            Some(4 * EDGE_LENGTH as i32)
+        );
+    }
+
+    #[test]
+    fn test_candidates() {
+        let index = edge_index(vec![ramp_contour(0, 0, 0), ramp_contour(1, 0, 4)]);
+        // Contour 7 doesn't exist, and contour 1 is listed twice.
+        let groups = groups(vec![1, 7, 1]);
+        let shapes: Vec<ShapeRef> = index
+            .candidates(groups.0.iter())
+            .iter()
+            .map(|edges| edges.shape)
+            .collect();
+        assert_eq!(shapes, (0..4).map(|r| shape(1, r)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_compatible_shapes() {
+        let contours = TerrainContoursTable::from_contours(vec![
+            ramp_contour(0, 0, 0),
+            ramp_contour(1, 4, 4),
+            ramp_contour(2, 0, 4),
+        ]);
+        let index = ContourEdgeIndex::new(&contours);
+        // Low ground to the west, high ground to the east.
+        let mut map = TerrainMapAsset::new(IRect::new(0, 0, 3, 1), 0, 0);
+        map.set_shape_at(IVec2::new(2, 0), shape(1, 0));
+        let compatible = index.compatible_shapes(&map, IVec2::new(1, 0));
+        assert_eq!(compatible, vec![shape(2, 0)]);
+
+        assert_eq!(index.find_seams(&map).len(), 1);
+        map.set_shape_at(IVec2::new(1, 0), shape(2, 0));
+        assert!(index.find_seams(&map).is_empty());
+    }
+
+    #[test]
+    fn test_find_seams_in() {
+        let contours =
+            TerrainContoursTable::from_contours(vec![ramp_contour(0, 0, 0), ramp_contour(1, 4, 4)]);
+        let index = ContourEdgeIndex::new(&contours);
+        let mut map = TerrainMapAsset::new(IRect::new(0, 0, 4, 4), 0, 0);
+        map.set_shape_at(IVec2::new(2, 2), shape(1, 0));
+        assert_eq!(index.find_seams(&map).len(), 4);
+        // Only the seams east and south of the parcels within the rect.
+        let seams = index.find_seams_in(&map, IRect::new(2, 2, 4, 4));
+        assert_eq!(seams.len(), 2);
+        assert!(seams.iter().all(|seam| seam.coords == IVec2::new(2, 2)));
+        // The rect is clipped to the map.
+        assert_eq!(index.find_seams_in(&map, IRect::new(-5, -5, 9, 9)).len(), 4);
+    }
+}
@@ -2,82 +2,196 @@
 //! stamped copies of a single mesh, which can lead to a repetitive appearance. TerrainFx allows
 //! for a more varied appearance by overriding the terrain mesh. TerrainFx are stored with the
 //! precinct, and are applied to the terrain parcels when they are stamped.
-use bevy::prelude::*;
-use bitflags::bitflags;
-use serde::{
-    de::{SeqAccess, Visitor},
-    ser::SerializeSeq,
-    Deserialize, Deserializer, Serialize,
+//!
+//! The kinds of effect - cobbles, mud, snow and so on - are defined in `terrain.effects.json`,
+//! each with a texture and a blend mode, so new ones can be added without changing the shader.
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+    reflect::TypePath,
 };
+use bitflags::bitflags;
+use futures_lite::AsyncReadExt;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// Maximum number of layers in the terrain effects table.
+pub const MAX_TERRAIN_EFFECT_LAYERS: usize = 32;
 
-/// Types of terrain effects.
+/// Maximum number of distinct effect layers which can appear within a single parcel.
+pub const MAX_PARCEL_EFFECT_LAYERS: usize = 4;
+
+/// Set of terrain effect layers, by index into the `TerrainEffectsTable`.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Default, Reflect)]
-#[reflect(Default, Deserialize, Serialize)]
-pub struct TerrainTypes(pub u8);
+#[reflect(Default)]
+pub struct TerrainTypes(pub u32);
 
-bitflags! {
-    impl TerrainTypes: u8 {
-        /// Cobblestone road
-        const Cobbles = 1 << 0;
-        /// Dark, earthy soil
-        const Soil = 1 << 1;
-        /// Trodden path or trail
-        const Path = 1 << 2;
+impl TerrainTypes {
+    /// True if there are no layers in the set.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// True if the set contains the given layer.
+    pub fn contains(&self, layer: usize) -> bool {
+        layer < MAX_TERRAIN_EFFECT_LAYERS && self.0 & (1 << layer) != 0
+    }
+
+    /// Add a layer to the set.
+    pub fn insert(&mut self, layer: usize) {
+        assert!(layer < MAX_TERRAIN_EFFECT_LAYERS);
+        self.0 |= 1 << layer;
+    }
+
+    /// Iterate over the layers in the set, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> {
+        let bits = self.0;
+        (0..MAX_TERRAIN_EFFECT_LAYERS).filter(move |layer| bits & (1 << layer) != 0)
     }
 }
 
-impl Serialize for TerrainTypes {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::ser::Serializer,
-    {
-        let mut seq = serializer.serialize_seq(Some(self.bits().count_ones() as usize))?;
-        if self.contains(TerrainTypes::Cobbles) {
-            seq.serialize_element("cobbles")?;
-        }
-        if self.contains(TerrainTypes::Soil) {
-            seq.serialize_element("soil")?;
-        }
-        if self.contains(TerrainTypes::Path) {
-            seq.serialize_element("path")?;
+impl std::ops::BitOr for TerrainTypes {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        TerrainTypes(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for TerrainTypes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// How a terrain effect layer is combined with the ground surface.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TerrainBlendMode {
+    /// Wear away the top surface, exposing the ground underneath (paths, trodden earth).
+    #[default]
+    Under,
+    /// Replace the ground underneath with the layer texture (tilled soil, mud).
+    Replace,
+    /// Paving stones; the bright parts of the texture show through first (cobbles, gravel).
+    Paving,
+    /// Cover the top surface with the layer texture (snow drifts).
+    Cover,
+    /// Multiply the final color by the layer tint (scorch marks).
+    Tint,
+}
+
+/// A kind of terrain effect, such as cobbles or mud. Terrain effect exemplars refer to these
+/// by name.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TerrainEffectLayer {
+    pub name: String,
+    /// Texture for the layer. If absent, the layer uses the ground's dirt texture.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texture: Option<String>,
+    /// Number of texture repeats per world unit.
+    #[serde(default = "default_texture_scale")]
+    pub texture_scale: f32,
+    #[serde(default)]
+    pub blend: TerrainBlendMode,
+    /// Color multiplied with the texture, as sRGB components.
+    #[serde(default = "default_tint")]
+    pub tint: [f32; 3],
+}
+
+impl Default for TerrainEffectLayer {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            texture: None,
+            texture_scale: default_texture_scale(),
+            blend: TerrainBlendMode::default(),
+            tint: default_tint(),
         }
-        seq.end()
     }
 }
 
-impl<'de> Deserialize<'de> for TerrainTypes {
-    fn deserialize<D>(deserializer: D) -> Result<TerrainTypes, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        struct TerrainTypeFlagsVisitor;
-        impl<'de> Visitor<'de> for TerrainTypeFlagsVisitor {
-            type Value = TerrainTypes;
+fn default_texture_scale() -> f32 {
+    0.45
+}
 
-            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("a sequence of points")
-            }
+fn default_tint() -> [f32; 3] {
+    [1., 1., 1.]
+}
 
-            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-            where
-                A: SeqAccess<'de>,
-            {
-                let mut flags = TerrainTypes::default();
-                while let Some(ty) = seq.next_element::<&str>()? {
-                    match ty {
-                        "cobbles" => flags |= TerrainTypes::Cobbles,
-                        "soil" => flags |= TerrainTypes::Soil,
-                        "path" => flags |= TerrainTypes::Path,
-                        _ => {
-                            warn!("Unknown terrain effect type: {}", ty);
-                        }
-                    }
-                }
-                Ok(flags)
+pub struct TerrainEffectsTable {
+    pub layers: Vec<TerrainEffectLayer>,
+}
+
+impl TerrainEffectsTable {
+    /// Look up the set of layers with the given names. Unknown names are ignored.
+    pub fn lookup(&self, names: &[String]) -> TerrainTypes {
+        let mut result = TerrainTypes::default();
+        for name in names.iter() {
+            match self.layers.iter().position(|layer| layer.name == *name) {
+                Some(index) if index < MAX_TERRAIN_EFFECT_LAYERS => result.insert(index),
+                _ => warn!("Unknown terrain effect type: {}", name),
             }
         }
+        result
+    }
+}
+
+#[derive(TypePath, Asset)]
+pub struct TerrainEffectsAsset(pub Arc<Mutex<TerrainEffectsTable>>);
+
+#[derive(Default)]
+pub struct TerrainEffectsLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum TerrainEffectsLoaderError {
+    #[error("Could not load terrain effects: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not decode terrain effects: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl AssetLoader for TerrainEffectsLoader {
+    type Asset = TerrainEffectsAsset;
+    type Settings = ();
+    type Error = TerrainEffectsLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let layers: Vec<TerrainEffectLayer> = serde_json::from_slice(&bytes)?;
+        if layers.len() > MAX_TERRAIN_EFFECT_LAYERS {
+            warn!(
+                "Too many terrain effect layers: {}, only the first {} will be used.",
+                layers.len(),
+                MAX_TERRAIN_EFFECT_LAYERS
+            );
+        }
+
+        Ok(TerrainEffectsAsset(Arc::new(Mutex::new(
+            TerrainEffectsTable { layers },
+        ))))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["effects.json"]
+    }
+}
+
+#[derive(Resource)]
+pub struct TerrainEffectsHandle(pub Handle<TerrainEffectsAsset>);
 
-        deserializer.deserialize_seq(TerrainTypeFlagsVisitor)
+impl FromWorld for TerrainEffectsHandle {
+    fn from_world(world: &mut World) -> Self {
+        let server = world.resource::<AssetServer>();
+        TerrainEffectsHandle(server.load("terrain/terrain.effects.json"))
     }
 }
 
@@ -101,10 +215,34 @@ bitflags! {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct TerrainFxVertexAttr {
     pub(crate) effect: TerrainTypes,
     pub(crate) effect_strength: f32,
     pub(crate) elevation: f32,
     pub(crate) options: TerrainOptions,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_layers() {
+        let table = TerrainEffectsTable {
+            layers: ["path", "soil", "cobbles"]
+                .iter()
+                .map(|name| TerrainEffectLayer {
+                    name: name.to_string(),
+                    ..default()
+                })
+                .collect(),
+        };
+        let layers = table.lookup(&["cobbles".to_string(), "path".to_string()]);
+        assert!(layers.contains(0));
+        assert!(!layers.contains(1));
+        assert!(layers.contains(2));
+        assert_eq!(layers.iter().collect::<Vec<_>>(), vec![0, 2]);
+        assert!(table.lookup(&["lava".to_string()]).is_empty());
+    }
+}
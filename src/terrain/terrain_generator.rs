@@ -0,0 +1,315 @@
+use bevy::{math::IRect, prelude::*};
+
+use crate::random::NoiseSeed;
+
+use super::{
+    terrain_edges::{edge_mismatch, ContourEdgeIndex, Side},
+    terrain_groups::TerrainGroupsTable,
+    ShapeRef, TerrainMapAsset,
+};
+
+/// Penalty per unit of height difference along an edge shared with a neighboring parcel. This
+/// is large enough that matching edges always win over matching the target elevation.
+const MISMATCH_PENALTY: f32 = 1000.;
+
+/// Rule for assigning a biome based on generated elevation and moisture, both of which are
+/// in the range 0..1.
+#[derive(Debug, Clone, Copy)]
+pub struct BiomeRule {
+    pub biome: u8,
+    pub max_elevation: f32,
+    pub max_moisture: f32,
+}
+
+/// Generates terrain maps procedurally from noise. Contours are chosen from the given
+/// terrain groups so that the heights along the edges of adjacent parcels line up, while
+/// the overall elevation follows a smooth noise field.
+#[derive(Debug, Clone)]
+pub struct TerrainGenerator {
+    /// Random seed, different seeds produce different maps.
+    pub seed: i32,
+
+    /// Approximate size of hills and valleys, in parcels.
+    pub feature_size: f32,
+
+    /// Number of noise octaves to combine.
+    pub octaves: u32,
+
+    /// Names of the terrain groups to draw contours from. If empty, all visible groups
+    /// are used.
+    pub groups: Vec<String>,
+
+    /// How much randomness to add when choosing between contours with similar elevation.
+    pub jitter: f32,
+
+    /// Rules for assigning biomes; the first matching rule wins. Parcels which match no rule
+    /// are left with the map's default biome.
+    pub biomes: Vec<BiomeRule>,
+}
+
+impl Default for TerrainGenerator {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            feature_size: 8.,
+            octaves: 3,
+            groups: Vec::new(),
+            jitter: 1.,
+            biomes: Vec::new(),
+        }
+    }
+}
+
+impl TerrainGenerator {
+    /// Fill the parcels of `map` within `region` with generated terrain. Parcels outside the
+    /// region are left alone, but the generated parcels will try to match their edges.
+    /// `index` must have been built from the current terrain contours. Returns the number of
+    /// parcels which could not be matched exactly to their neighbors.
+    pub fn generate(
+        &self,
+        map: &mut TerrainMapAsset,
+        region: IRect,
+        index: &ContourEdgeIndex,
+        groups: &TerrainGroupsTable,
+    ) -> usize {
+        let region = region.intersect(map.bounds);
+        let candidates = index.candidates(groups.0.iter().filter(|group| {
+            if self.groups.is_empty() {
+                group.visible
+            } else {
+                self.groups.contains(&group.name)
+            }
+        }));
+        if candidates.is_empty() || region.is_empty() {
+            return 0;
+        }
+
+        // Map the elevation noise onto the range of heights the contours can provide.
+        let (min_height, max_height) =
+            candidates.iter().fold((f32::MAX, f32::MIN), |(lo, hi), c| {
+                (lo.min(c.mean_height), hi.max(c.mean_height))
+            });
+
+        // Neighboring parcels may use contours which aren't in the selected groups.
+        let neighbor = |shape: ShapeRef| index.get(shape);
+        let seed = self.noise_seed();
+
+        let mut mismatched = 0;
+        for z in region.min.y..region.max.y {
+            for x in region.min.x..region.max.x {
+                let pt = IVec2::new(x, z);
+                let elevation = self.elevation(pt);
+                let target = min_height + (max_height - min_height) * elevation;
+
+                // Parcels to the west and north have already been placed; parcels to the east
+                // and south are only fixed if they are outside the region.
+                let west = map
+                    .contains_pt(pt - IVec2::X)
+                    .then(|| neighbor(map.shape_at(pt - IVec2::X)))
+                    .flatten();
+                let north = map
+                    .contains_pt(pt - IVec2::Y)
+                    .then(|| neighbor(map.shape_at(pt - IVec2::Y)))
+                    .flatten();
+                let east = (x + 1 >= region.max.x && map.contains_pt(pt + IVec2::X))
+                    .then(|| neighbor(map.shape_at(pt + IVec2::X)))
+                    .flatten();
+                let south = (z + 1 >= region.max.y && map.contains_pt(pt + IVec2::Y))
+                    .then(|| neighbor(map.shape_at(pt + IVec2::Y)))
+                    .flatten();
+
+                let mut best: Option<(f32, i32, ShapeRef)> = None;
+                for (i, c) in candidates.iter().enumerate() {
+                    let mut mismatch = 0;
+                    for (side, neighbor) in [
+                        (Side::West, west),
+                        (Side::North, north),
+                        (Side::East, east),
+                        (Side::South, south),
+                    ] {
+                        if let Some(n) = neighbor {
+                            mismatch += edge_mismatch(n.edge(side.opposite()), c.edge(side));
+                        }
+                    }
+                    let score = mismatch as f32 * MISMATCH_PENALTY
+                        + (c.mean_height - target).abs()
+                        + seed.noise3(x, z, i as i32) * self.jitter;
+                    if best.map_or(true, |(s, _, _)| score < s) {
+                        best = Some((score, mismatch, c.shape));
+                    }
+                }
+
+                let (_, mismatch, shape) = best.unwrap();
+                if mismatch > 0 {
+                    mismatched += 1;
+                }
+                map.set_shape_at(pt, shape);
+                if let Some(biome) = self.biome(pt, elevation) {
+                    map.set_biome_at(pt, biome);
+                }
+            }
+        }
+        mismatched
+    }
+
+    /// Seed for the noise functions. Seed zero produces the same maps as the unseeded noise.
+    fn noise_seed(&self) -> NoiseSeed {
+        NoiseSeed::new(self.seed as u32)
+    }
+
+    /// Generated elevation at a parcel, in the range 0..1.
+    pub fn elevation(&self, pt: IVec2) -> f32 {
+        fractal_noise(
+            pt.as_vec2() / self.feature_size.max(1.),
+            self.octaves,
+            self.noise_seed(),
+            0,
+        )
+    }
+
+    /// Generated moisture at a parcel, in the range 0..1.
+    pub fn moisture(&self, pt: IVec2) -> f32 {
+        fractal_noise(
+            pt.as_vec2() / self.feature_size.max(1.),
+            self.octaves,
+            self.noise_seed(),
+            101,
+        )
+    }
+
+    fn biome(&self, pt: IVec2, elevation: f32) -> Option<u8> {
+        let moisture = self.moisture(pt);
+        self.biomes
+            .iter()
+            .find(|rule| elevation <= rule.max_elevation && moisture <= rule.max_moisture)
+            .map(|rule| rule.biome)
+    }
+}
+
+/// Smoothly interpolated lattice noise, in the range 0..1.
+fn value_noise(pos: Vec2, seed: NoiseSeed, salt: i32) -> f32 {
+    let cell = pos.floor();
+    let t = pos - cell;
+    let t = t * t * (Vec2::splat(3.) - 2. * t);
+    let x = cell.x as i32;
+    let z = cell.y as i32;
+    let n00 = seed.noise3(x, z, salt);
+    let n10 = seed.noise3(x + 1, z, salt);
+    let n01 = seed.noise3(x, z + 1, salt);
+    let n11 = seed.noise3(x + 1, z + 1, salt);
+    let a = n00 + (n10 - n00) * t.x;
+    let b = n01 + (n11 - n01) * t.x;
+    a + (b - a) * t.y
+}
+
+/// Sum of several octaves of value noise, normalized to the range 0..1.
+fn fractal_noise(pos: Vec2, octaves: u32, seed: NoiseSeed, salt: i32) -> f32 {
+    let mut total = 0.;
+    let mut amplitude = 1.;
+    let mut weight = 0.;
+    let mut scale = 1.;
+    for octave in 0..octaves.max(1) {
+        total += value_noise(pos * scale, seed, salt + octave as i32) * amplitude;
+        weight += amplitude;
+        amplitude *= 0.5;
+        scale *= 2.;
+    }
+    total / weight
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::test_fixtures::{edge_index, flat_contour, groups, ramp_contour};
+
+    fn check_edges(map: &TerrainMapAsset, index: &ContourEdgeIndex) {
+        assert!(index.find_seams(map).is_empty());
+    }
+
+    #[test]
+    fn test_generate_edges_match() {
+        // Elevation varies across the map, but with only flat contours the edges can only
+        // match if every parcel is at the same height.
+        let index = edge_index(vec![flat_contour(0, 0), flat_contour(1, 4)]);
+        let mut map = TerrainMapAsset::new(IRect::new(-4, -4, 4, 4), 0, 0);
+        let generator = TerrainGenerator {
+            feature_size: 2.,
+            ..default()
+        };
+        let bounds = map.bounds;
+        let mismatched = generator.generate(&mut map, bounds, &index, &groups(vec![0, 1]));
+        assert_eq!(mismatched, 0);
+        check_edges(&map, &index);
+    }
+
+    #[test]
+    fn test_generate_region_matches_border() {
+        let index = edge_index(vec![
+            flat_contour(0, 0),
+            flat_contour(1, 4),
+            ramp_contour(2, 0, 4),
+        ]);
+        // Surround the region with high ground; the interior must rise to meet it.
+        let mut map = TerrainMapAsset::new(IRect::new(0, 0, 5, 5), 1, 0);
+        let generator = TerrainGenerator::default();
+        let mismatched = generator.generate(
+            &mut map,
+            IRect::new(1, 1, 4, 4),
+            &index,
+            &groups(vec![0, 1, 2]),
+        );
+        assert_eq!(mismatched, 0);
+        check_edges(&map, &index);
+    }
+
+    #[test]
+    fn test_generate_deterministic() {
+        let index = edge_index(vec![
+            flat_contour(0, 0),
+            flat_contour(1, 4),
+            ramp_contour(2, 0, 4),
+        ]);
+        let groups = groups(vec![0, 1, 2]);
+        let generator = TerrainGenerator {
+            seed: 3,
+            feature_size: 2.,
+            ..default()
+        };
+        let mut a = TerrainMapAsset::new(IRect::new(0, 0, 6, 6), 0, 0);
+        let mut b = a.clone();
+        let bounds = a.bounds;
+        generator.generate(&mut a, bounds, &index, &groups);
+        generator.generate(&mut b, bounds, &index, &groups);
+        assert_eq!(a.shapes, b.shapes);
+    }
+
+    #[test]
+    fn test_generate_biomes() {
+        let index = edge_index(vec![flat_contour(0, 0)]);
+        let mut map = TerrainMapAsset::new(IRect::new(0, 0, 4, 4), 0, 9);
+        let generator = TerrainGenerator {
+            biomes: vec![BiomeRule {
+                biome: 2,
+                max_elevation: 1.,
+                max_moisture: 1.,
+            }],
+            ..default()
+        };
+        let bounds = map.bounds;
+        generator.generate(&mut map, bounds, &index, &groups(vec![0]));
+        assert!(map.biomes.iter().all(|b| *b == 2));
+    }
+
+    #[test]
+    fn test_fractal_noise_range() {
+        for z in -20..20 {
+            for x in -20..20 {
+                let pos = Vec2::new(x as f32 * 0.37, z as f32 * 0.37);
+                for seed in [NoiseSeed::ZERO, NoiseSeed::new(12345)] {
+                    let n = fractal_noise(pos, 3, seed, 5);
+                    assert!((0. ..=1.).contains(&n));
+                }
+            }
+        }
+    }
+}
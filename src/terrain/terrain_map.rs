@@ -1,9 +1,10 @@
 extern crate rmp_serde as rmps;
 use bevy::{
     asset::{
-        io::{AssetWriterError, Reader},
+        io::{AssetReaderError, AssetWriterError, Reader},
         saver::AssetSaver,
-        AssetLoader, LoadContext, LoadedFolder, RecursiveDependencyLoadState,
+        AssetLoadError, AssetLoadFailedEvent, AssetLoader, LoadContext, LoadedFolder,
+        RecursiveDependencyLoadState,
     },
     math::IRect,
     prelude::*,
@@ -13,6 +14,7 @@ use bevy::{
         render_resource::{Extent3d, TextureDimension, TextureFormat},
         texture::ImageSampler,
     },
+    utils::HashMap,
 };
 use futures_lite::{AsyncReadExt, AsyncWriteExt};
 use serde::{Deserialize, Serialize};
@@ -22,11 +24,38 @@ use crate::{scenery::PRECINCT_SIZE, world::Realm};
 
 use super::{
     biome::{BiomesAsset, BiomesHandle},
-    ground_material::GroundMaterial,
+    ground_material::{GroundMaterial, GROUND_EFFECTS_HANDLE, GROUND_SURFACES_HANDLE},
     parcel::{ShapeRef, ADJACENT_COUNT},
-    PARCEL_SIZE,
+    DEFAULT_WATER_LEVEL, MAX_TERRAIN_EFFECT_LAYERS, PARCEL_SIZE,
 };
 
+/// Identifies a terrain map file. Files written before the header was introduced start
+/// directly with the msgpack-encoded map, which can never begin with these bytes.
+const TERRAIN_MAP_MAGIC: [u8; 4] = *b"PTRN";
+
+/// Format version written by the saver. Bump this, and add a branch to [`migrate_terrain_map`],
+/// whenever the layout of [`TerrainMapAsset`] changes incompatibly.
+pub const TERRAIN_MAP_VERSION: u16 = 1;
+
+/// Water surface of a single parcel.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ParcelWater {
+    /// Height of the water surface.
+    pub level: f32,
+
+    /// Direction and speed of the current, in meters per second.
+    pub flow: Vec2,
+}
+
+impl Default for ParcelWater {
+    fn default() -> Self {
+        Self {
+            level: DEFAULT_WATER_LEVEL,
+            flow: Vec2::ZERO,
+        }
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, TypePath, Asset, Clone)]
 pub struct TerrainMapAsset {
     /** Boundary of the map relative to the world origin. */
@@ -43,9 +72,35 @@ pub struct TerrainMapAsset {
 
     /** Biome to use when off the edge of the map. */
     pub default_biome: u8,
+
+    /** Array of water surfaces; empty if every parcel has the default water surface. */
+    #[serde(default)]
+    pub water: Vec<ParcelWater>,
+}
+
+/// Parcel coords within `radius` of `center`, for circular brushes.
+fn brush_parcels(center: IVec2, radius: i32) -> impl Iterator<Item = IVec2> {
+    let r2 = radius * radius;
+    (-radius..=radius)
+        .flat_map(move |z| (-radius..=radius).map(move |x| IVec2::new(x, z)))
+        .filter(move |d| d.x * d.x + d.y * d.y <= r2)
+        .map(move |d| center + d)
 }
 
 impl TerrainMapAsset {
+    /// Create a new map with the given bounds, filled with the default shape and biome.
+    pub fn new(bounds: IRect, default_shape: u16, default_biome: u8) -> Self {
+        let area = (bounds.width().max(0) * bounds.height().max(0)) as usize;
+        Self {
+            bounds,
+            shapes: vec![default_shape << 2; area],
+            biomes: vec![default_biome; area],
+            default_shape,
+            default_biome,
+            water: Vec::new(),
+        }
+    }
+
     /// Returns true if the terrain map includes the given coordinates. The bounds is
     /// considered a half-open interval: a point at `min` is considered inside, at `max` is
     /// considered outside.
@@ -111,6 +166,182 @@ impl TerrainMapAsset {
             self.biome_at(pt + IVec2::new(1, 1)),
         ]
     }
+
+    /// Set the biome index at the given parcel coords. Returns true if the biome was changed.
+    pub fn set_biome_at(&mut self, pt: IVec2, biome: u8) -> bool {
+        if self.contains_pt(pt) {
+            let index = ((pt.y - self.bounds.min.y) * self.bounds.width() + pt.x
+                - self.bounds.min.x) as usize;
+            if self.biomes[index] != biome {
+                self.biomes[index] = biome;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Set the biome index for all points within `radius` of `center` (a circular brush).
+    /// Returns true if any biome was changed.
+    pub fn paint_biome(&mut self, center: IVec2, radius: i32, biome: u8) -> bool {
+        let mut changed = false;
+        let r2 = radius * radius;
+        for z in -radius..=radius {
+            for x in -radius..=radius {
+                if x * x + z * z <= r2 {
+                    changed |= self.set_biome_at(center + IVec2::new(x, z), biome);
+                }
+            }
+        }
+        changed
+    }
+
+    /// Replace the contiguous region of same-biome points containing `start` with `biome`.
+    /// Returns true if any biome was changed.
+    pub fn fill_biome(&mut self, start: IVec2, biome: u8) -> bool {
+        if !self.contains_pt(start) {
+            return false;
+        }
+        let target = self.biome_at(start);
+        if target == biome {
+            return false;
+        }
+        let mut stack = vec![start];
+        while let Some(pt) = stack.pop() {
+            if !self.contains_pt(pt) || self.biome_at(pt) != target {
+                continue;
+            }
+            self.set_biome_at(pt, biome);
+            stack.push(pt + IVec2::new(1, 0));
+            stack.push(pt + IVec2::new(-1, 0));
+            stack.push(pt + IVec2::new(0, 1));
+            stack.push(pt + IVec2::new(0, -1));
+        }
+        true
+    }
+
+    /// Return the water surface at the given parcel coords.
+    pub fn water_at(&self, pt: IVec2) -> ParcelWater {
+        if self.contains_pt(pt) && !self.water.is_empty() {
+            return self.water[((pt.y - self.bounds.min.y) * self.bounds.width() + pt.x
+                - self.bounds.min.x) as usize];
+        }
+        ParcelWater::default()
+    }
+
+    /// Return the water surface at the given parcel coords as well as all neighboring parcels.
+    pub fn adjacent_water(&self, pt: IVec2) -> [ParcelWater; ADJACENT_COUNT] {
+        let mut out = [ParcelWater::default(); ADJACENT_COUNT];
+        for z in [-1, 0, 1] {
+            for x in [-1, 0, 1] {
+                out[(z * 3 + x + 4) as usize] = self.water_at(pt + IVec2::new(x, z))
+            }
+        }
+        out
+    }
+
+    /// Set the water surface at the given parcel coords. Returns true if it was changed.
+    pub fn set_water_at(&mut self, pt: IVec2, water: ParcelWater) -> bool {
+        if !self.contains_pt(pt) || self.water_at(pt) == water {
+            return false;
+        }
+        if self.water.is_empty() {
+            self.water = vec![ParcelWater::default(); self.shapes.len()];
+        }
+        let index =
+            ((pt.y - self.bounds.min.y) * self.bounds.width() + pt.x - self.bounds.min.x) as usize;
+        self.water[index] = water;
+        true
+    }
+
+    /// Modify the water surface of every parcel within `radius` of `center` (a circular
+    /// brush). Returns true if any parcel was changed.
+    pub fn paint_water(
+        &mut self,
+        center: IVec2,
+        radius: i32,
+        paint: impl Fn(&mut ParcelWater),
+    ) -> bool {
+        let mut changed = false;
+        for pt in brush_parcels(center, radius) {
+            let mut water = self.water_at(pt);
+            paint(&mut water);
+            changed |= self.set_water_at(pt, water);
+        }
+        changed
+    }
+
+    /// Returns true if [`paint_water`] with the same arguments would change any parcel.
+    ///
+    /// [`paint_water`]: TerrainMapAsset::paint_water
+    pub fn would_paint_water(
+        &self,
+        center: IVec2,
+        radius: i32,
+        paint: impl Fn(&mut ParcelWater),
+    ) -> bool {
+        brush_parcels(center, radius).any(|pt| {
+            let before = self.water_at(pt);
+            let mut water = before;
+            paint(&mut water);
+            self.contains_pt(pt) && water != before
+        })
+    }
+
+    /// Return a copy of this map with new bounds. Parcels within both the old and new bounds
+    /// keep their shapes and biomes; newly-added parcels are filled with `default_shape` and
+    /// `default_biome`, and parcels outside the new bounds are discarded.
+    pub fn resized(&self, bounds: IRect) -> TerrainMapAsset {
+        let width = bounds.width().max(0);
+        let mut result = TerrainMapAsset::new(bounds, self.default_shape, self.default_biome);
+        let overlap = bounds.intersect(self.bounds);
+        for z in overlap.min.y..overlap.max.y {
+            for x in overlap.min.x..overlap.max.x {
+                let src = ((z - self.bounds.min.y) * self.bounds.width() + x - self.bounds.min.x)
+                    as usize;
+                let dst = ((z - bounds.min.y) * width + x - bounds.min.x) as usize;
+                result.shapes[dst] = self.shapes[src];
+                result.biomes[dst] = self.biomes[src];
+                if !self.water.is_empty() {
+                    if result.water.is_empty() {
+                        result.water = vec![ParcelWater::default(); result.shapes.len()];
+                    }
+                    result.water[dst] = self.water[src];
+                }
+            }
+        }
+        result
+    }
+
+    /// Check that the map's arrays match its bounds.
+    pub fn validate(&self) -> Result<(), TerrainMapLoaderError> {
+        if self.bounds.width() < 0 || self.bounds.height() < 0 {
+            return Err(TerrainMapLoaderError::InvalidBounds(self.bounds));
+        }
+        let expected = self.bounds.width() as usize * self.bounds.height() as usize;
+        let check = |field: &'static str, actual: usize| {
+            if actual == expected {
+                Ok(())
+            } else {
+                Err(TerrainMapLoaderError::SizeMismatch {
+                    field,
+                    expected,
+                    actual,
+                })
+            }
+        };
+        check("shapes", self.shapes.len())?;
+        check("biomes", self.biomes.len())?;
+        if !self.water.is_empty() {
+            check("water", self.water.len())?;
+        }
+        Ok(())
+    }
+
+    /// Move the map relative to the world origin, preserving its contents.
+    pub fn translate(&mut self, offset: IVec2) {
+        self.bounds.min += offset;
+        self.bounds.max += offset;
+    }
 }
 
 #[derive(Component, Default)]
@@ -130,11 +361,72 @@ pub struct TerrainMap {
 #[component(storage = "SparseSet")]
 pub struct TerrainMapChanged;
 
+/// Attached to a realm whose terrain map failed to load, with a description of the problem.
+#[derive(Component, Debug, Clone)]
+pub struct TerrainMapLoadError(pub String);
+
+/// Load errors for terrain maps, keyed by realm name. Maps can fail to load before their realm
+/// has been spawned, so errors are kept here until the realm appears.
+#[derive(Resource, Default)]
+pub struct TerrainMapLoadErrors(HashMap<String, String>);
+
 #[non_exhaustive]
 #[derive(Debug, Error)]
 pub enum TerrainMapLoaderError {
     #[error("Could not load terrain map: {0}")]
     Io(#[from] std::io::Error),
+    #[error("Could not decode terrain map: {0}")]
+    Decode(#[from] rmps::decode::Error),
+    #[error("Terrain map header is truncated")]
+    TruncatedHeader,
+    #[error("Unsupported terrain map version {0}, latest is {TERRAIN_MAP_VERSION}")]
+    UnsupportedVersion(u16),
+    #[error("Terrain map has invalid bounds: {0:?}")]
+    InvalidBounds(IRect),
+    #[error("Terrain map {field} has {actual} entries, expected {expected}")]
+    SizeMismatch {
+        field: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+/// Decode the contents of a `.terrain` file, upgrading older formats and validating the result.
+pub fn decode_terrain_map(bytes: &[u8]) -> Result<TerrainMapAsset, TerrainMapLoaderError> {
+    let (version, body) = match bytes.strip_prefix(&TERRAIN_MAP_MAGIC) {
+        Some(rest) => {
+            if rest.len() < 2 {
+                return Err(TerrainMapLoaderError::TruncatedHeader);
+            }
+            (u16::from_le_bytes([rest[0], rest[1]]), &rest[2..])
+        }
+        // Files from before the header was added.
+        None => (0, bytes),
+    };
+    let map = migrate_terrain_map(version, body)?;
+    map.validate()?;
+    Ok(map)
+}
+
+/// Decode the body of a terrain map written with the given format version, converting it to
+/// the current layout.
+fn migrate_terrain_map(
+    version: u16,
+    body: &[u8],
+) -> Result<TerrainMapAsset, TerrainMapLoaderError> {
+    match version {
+        // Version 1 only added the header; the body is unchanged.
+        0 | 1 => Ok(rmps::from_slice(body)?),
+        _ => Err(TerrainMapLoaderError::UnsupportedVersion(version)),
+    }
+}
+
+/// Encode a terrain map in the current format, including the header.
+pub fn encode_terrain_map(map: &TerrainMapAsset) -> Result<Vec<u8>, rmps::encode::Error> {
+    let mut bytes = Vec::from(TERRAIN_MAP_MAGIC);
+    bytes.extend_from_slice(&TERRAIN_MAP_VERSION.to_le_bytes());
+    bytes.extend(rmps::encode::to_vec_named(map)?);
+    Ok(bytes)
 }
 
 #[derive(Default)]
@@ -153,12 +445,7 @@ impl AssetLoader for TerrainMapLoader {
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
-        let map: TerrainMapAsset =
-            rmps::from_slice(&bytes).expect("unable to decode terrain map data");
-        let area = (map.bounds.width() * map.bounds.height()) as usize;
-        assert!(map.shapes.len() == area);
-        assert!(map.biomes.len() == area);
-        Ok(map)
+        decode_terrain_map(&bytes)
     }
 
     fn extensions(&self) -> &[&str] {
@@ -193,7 +480,7 @@ impl AssetSaver for TerrainMapSaver {
         asset: bevy::asset::saver::SavedAsset<'a, Self::Asset>,
         _settings: &'a Self::Settings,
     ) -> Result<(), TerrainMapSaverError> {
-        let v = rmps::encode::to_vec_named(&*asset)?;
+        let v = encode_terrain_map(&asset)?;
         writer.write_all(&v).await?;
         Ok(())
     }
@@ -210,6 +497,7 @@ impl FromWorld for TerrainMapsHandleResource {
 }
 
 /** Discover terrain map assets, load them, and bind them to realm entities. */
+#[allow(clippy::too_many_arguments)]
 pub fn insert_terrain_maps(
     mut commands: Commands,
     server: Res<AssetServer>,
@@ -218,17 +506,28 @@ pub fn insert_terrain_maps(
     mut images: ResMut<Assets<Image>>,
     terrain_folder: Res<TerrainMapsHandleResource>,
     terrain_folder_asset: Res<Assets<LoadedFolder>>,
+    tm_assets: Res<Assets<TerrainMapAsset>>,
 ) {
     if let Some(st) = server.get_recursive_dependency_load_state(&terrain_folder.0) {
-        if st != RecursiveDependencyLoadState::Loaded {
+        // A map which failed to load shouldn't prevent the others from being used.
+        if !matches!(
+            st,
+            RecursiveDependencyLoadState::Loaded | RecursiveDependencyLoadState::Failed
+        ) {
             return;
         }
     }
 
     let files: &LoadedFolder = terrain_folder_asset.get(&terrain_folder.0).unwrap();
-    for (entity, realm) in query.iter_mut() {
+    for (entity, mut realm) in query.iter_mut() {
         let terrain_path = format!("terrain/maps/{}.terrain", realm.name);
-        if !files.handles.iter().any(|handle| {
+        // Maps which were created in the editor won't be in the folder until they are saved.
+        let created = server
+            .get_handle::<TerrainMapAsset>(&terrain_path)
+            .and_then(|handle| tm_assets.get(&handle));
+        if let Some(tm) = created {
+            realm.update_bounds(tm.bounds, convert_parcel_to_precinct(&tm.bounds));
+        } else if !files.handles.iter().any(|handle| {
             server
                 .get_path(handle.id())
                 .unwrap()
@@ -274,6 +573,7 @@ pub fn update_terrain_maps(
                     if realm.parcel_bounds != tm.bounds {
                         realm.update_bounds(tm.bounds, convert_parcel_to_precinct(&tm.bounds))
                     }
+                    commands.entity(re).remove::<TerrainMapLoadError>();
                     if terrain.is_none() {
                         commands.entity(re).insert((
                             TerrainMap {
@@ -305,11 +605,18 @@ pub fn update_terrain_maps(
                     if realm.parcel_bounds != tm.bounds {
                         realm.update_bounds(tm.bounds, convert_parcel_to_precinct(&tm.bounds))
                     }
-                    commands.entity(entity).insert(TerrainMapChanged);
+                    commands
+                        .entity(entity)
+                        .insert(TerrainMapChanged)
+                        .remove::<TerrainMapLoadError>();
                 }
             }
 
             AssetEvent::Removed { id } => {
+                // Maps deleted in the editor may have already dropped their last handle.
+                if server.get_path(*id).is_none() {
+                    continue;
+                }
                 let map_name = asset_name_from_id(&server, id);
                 println!("Terrain map removed: [{}].", map_name);
                 for (entity, realm, _terrain) in query.iter_mut() {
@@ -325,9 +632,65 @@ pub fn update_terrain_maps(
     }
 }
 
+/** Record terrain maps which failed to load on their realms, so that the editor can report
+ * the problem. */
+pub fn report_terrain_map_errors(
+    mut commands: Commands,
+    query: Query<(Entity, &Realm, Option<&TerrainMapLoadError>)>,
+    mut errors: ResMut<TerrainMapLoadErrors>,
+    mut ev_failed: EventReader<AssetLoadFailedEvent<TerrainMapAsset>>,
+    mut ev_asset: EventReader<AssetEvent<TerrainMapAsset>>,
+    server: Res<AssetServer>,
+    tm_assets: Res<Assets<TerrainMapAsset>>,
+) {
+    for ev in ev_failed.read() {
+        // Maps created in the editor haven't been written yet, so loading them fails, but the
+        // map itself is already present in memory.
+        if tm_assets.contains(ev.id)
+            && matches!(
+                ev.error,
+                AssetLoadError::AssetReaderError(AssetReaderError::NotFound(_))
+            )
+        {
+            continue;
+        }
+        let realm_name = asset_name_from_path(ev.path.path());
+        error!("Terrain map [{}] failed to load: {}", realm_name, ev.error);
+        errors.0.insert(realm_name, ev.error.to_string());
+    }
+
+    // A map which loads successfully, for example after being fixed and reloaded, clears
+    // its error.
+    for ev in ev_asset.read() {
+        if let AssetEvent::Added { id }
+        | AssetEvent::LoadedWithDependencies { id }
+        | AssetEvent::Modified { id } = ev
+        {
+            if let Some(path) = server.get_path(*id) {
+                errors.0.remove(&asset_name_from_path(path.path()));
+            }
+        }
+    }
+
+    if errors.0.is_empty() {
+        return;
+    }
+    for (entity, realm, reported) in query.iter() {
+        if let Some(message) = errors.0.get(&realm.name) {
+            if reported.map_or(true, |r| &r.0 != message) {
+                commands
+                    .entity(entity)
+                    .insert(TerrainMapLoadError(message.clone()));
+            }
+        }
+    }
+}
+
 fn asset_name_from_id(server: &Res<AssetServer>, id: &AssetId<TerrainMapAsset>) -> String {
-    let asset_path = server.get_path(*id).unwrap();
-    let path = asset_path.path();
+    asset_name_from_path(server.get_path(*id).unwrap().path())
+}
+
+fn asset_name_from_path(path: &std::path::Path) -> String {
     let filename = path.file_name().expect("Asset has no file name!");
     let filename_str = filename.to_str().unwrap();
     let dot = filename_str.find('.').unwrap_or(filename_str.len());
@@ -410,27 +773,182 @@ pub fn create_ground_material(
 
     materials.add(GroundMaterial {
         noise: asset_server.load("terrain/textures/noise.png"),
-        grass: asset_server.load("terrain/textures/grass.png"),
-        dirt: asset_server.load("terrain/textures/dirt.png"),
-        moss: asset_server.load("terrain/textures/moss.png"),
-        cobbles: asset_server.load("terrain/textures/cobbles.png"),
+        surfaces: GROUND_SURFACES_HANDLE,
+        effects: GROUND_EFFECTS_HANDLE,
         water_color: Srgba::rgb(0.0, 0.1, 0.3).into(),
         realm_offset: Vec2::new(0., 0.),
+        surface_scale: [Vec4::ZERO; 3],
+        effect_params: [Vec4::ZERO; MAX_TERRAIN_EFFECT_LAYERS],
+        effect_tint: [LinearRgba::WHITE; MAX_TERRAIN_EFFECT_LAYERS],
         biomes,
     })
 }
 
-const PARCELS_PER_PRECINCT: i32 = PRECINCT_SIZE / PARCEL_SIZE;
+pub(crate) const PARCELS_PER_PRECINCT: i32 = PRECINCT_SIZE / PARCEL_SIZE;
 
-fn convert_parcel_to_precinct(parcel_bounds: &IRect) -> IRect {
+/// Compute the range of precincts which overlap the given parcel bounds.
+pub(crate) fn convert_parcel_to_precinct(parcel_bounds: &IRect) -> IRect {
     IRect {
         min: IVec2::new(
-            parcel_bounds.min.x / PARCELS_PER_PRECINCT,
-            parcel_bounds.min.y / PARCELS_PER_PRECINCT,
+            parcel_bounds.min.x.div_euclid(PARCELS_PER_PRECINCT),
+            parcel_bounds.min.y.div_euclid(PARCELS_PER_PRECINCT),
         ),
         max: IVec2::new(
-            (parcel_bounds.max.x + PARCELS_PER_PRECINCT - 1) / PARCELS_PER_PRECINCT,
-            (parcel_bounds.max.y + PARCELS_PER_PRECINCT - 1) / PARCELS_PER_PRECINCT,
+            (parcel_bounds.max.x + PARCELS_PER_PRECINCT - 1).div_euclid(PARCELS_PER_PRECINCT),
+            (parcel_bounds.max.y + PARCELS_PER_PRECINCT - 1).div_euclid(PARCELS_PER_PRECINCT),
         ),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_map() -> TerrainMapAsset {
+        TerrainMapAsset {
+            bounds: IRect::new(-2, -2, 2, 2),
+            shapes: vec![0; 16],
+            biomes: vec![0; 16],
+            default_shape: 0,
+            default_biome: 0,
+            water: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_paint_biome() {
+        let mut map = test_map();
+        assert!(map.paint_biome(IVec2::new(0, 0), 1, 3));
+        assert_eq!(map.biome_at(IVec2::new(0, 0)), 3);
+        assert_eq!(map.biome_at(IVec2::new(1, 0)), 3);
+        assert_eq!(map.biome_at(IVec2::new(0, -1)), 3);
+        assert_eq!(map.biome_at(IVec2::new(1, 1)), 0);
+        assert!(!map.paint_biome(IVec2::new(0, 0), 1, 3));
+    }
+
+    #[test]
+    fn test_fill_biome() {
+        let mut map = test_map();
+        // Wall off the left column.
+        for z in -2..2 {
+            map.set_biome_at(IVec2::new(-1, z), 1);
+        }
+        assert!(map.fill_biome(IVec2::new(1, 1), 2));
+        assert_eq!(map.biome_at(IVec2::new(0, -2)), 2);
+        assert_eq!(map.biome_at(IVec2::new(-1, 0)), 1);
+        assert_eq!(map.biome_at(IVec2::new(-2, 0)), 0);
+        assert!(!map.fill_biome(IVec2::new(1, 1), 2));
+    }
+
+    #[test]
+    fn test_paint_water() {
+        let mut map = test_map();
+        assert_eq!(map.water_at(IVec2::new(0, 0)), ParcelWater::default());
+        assert!(map.paint_water(IVec2::new(0, 0), 1, |w| w.level = 2.));
+        assert_eq!(map.water.len(), 16);
+        assert_eq!(map.water_at(IVec2::new(0, 0)).level, 2.);
+        assert_eq!(map.water_at(IVec2::new(-1, 0)).level, 2.);
+        assert_eq!(map.water_at(IVec2::new(1, 1)).level, DEFAULT_WATER_LEVEL);
+        assert!(!map.would_paint_water(IVec2::new(0, 0), 1, |w| w.level = 2.));
+        assert!(!map.paint_water(IVec2::new(0, 0), 1, |w| w.level = 2.));
+        assert!(map.would_paint_water(IVec2::new(0, 0), 1, |w| w.level = 3.));
+
+        assert!(map.paint_water(IVec2::new(1, 1), 0, |w| w.flow = Vec2::X));
+        let adjacent = map.adjacent_water(IVec2::new(0, 0));
+        assert_eq!(adjacent[8].flow, Vec2::X);
+        assert_eq!(adjacent[8].level, DEFAULT_WATER_LEVEL);
+        assert_eq!(adjacent[4].level, 2.);
+
+        // Off the edge of the map the water is always the default.
+        assert!(!map.set_water_at(IVec2::new(5, 5), adjacent[4]));
+        assert_eq!(map.water_at(IVec2::new(5, 5)), ParcelWater::default());
+    }
+
+    #[test]
+    fn test_resized() {
+        let mut map = test_map();
+        map.default_shape = 5;
+        map.default_biome = 7;
+        let rotated = ShapeRef {
+            shape: 3,
+            rotation: 2,
+        };
+        map.set_shape_at(IVec2::new(1, 1), rotated);
+        map.set_biome_at(IVec2::new(1, 1), 4);
+        map.paint_water(IVec2::new(1, 1), 0, |w| w.level = 1.);
+
+        // Extend to the right and downward.
+        let grown = map.resized(IRect::new(-2, -2, 4, 3));
+        assert_eq!(grown.shapes.len(), 30);
+        assert_eq!(grown.shape_at(IVec2::new(1, 1)), rotated);
+        assert_eq!(grown.biome_at(IVec2::new(1, 1)), 4);
+        assert_eq!(grown.shape_at(IVec2::new(3, 2)).shape, 5);
+        assert_eq!(grown.biome_at(IVec2::new(3, 2)), 7);
+        assert_eq!(grown.water_at(IVec2::new(1, 1)).level, 1.);
+        assert_eq!(grown.water_at(IVec2::new(3, 2)), ParcelWater::default());
+
+        // Crop to the lower-right quadrant.
+        let cropped = grown.resized(IRect::new(0, 0, 2, 2));
+        assert_eq!(cropped.shapes.len(), 4);
+        assert_eq!(cropped.biome_at(IVec2::new(1, 1)), 4);
+        assert_eq!(cropped.biome_at(IVec2::new(-1, -1)), 7);
+    }
+
+    #[test]
+    fn test_decode_terrain_map() {
+        let mut map = test_map();
+        map.set_biome_at(IVec2::new(1, 1), 4);
+        let bytes = encode_terrain_map(&map).unwrap();
+        assert_eq!(bytes[..4], TERRAIN_MAP_MAGIC);
+        let decoded = decode_terrain_map(&bytes).unwrap();
+        assert_eq!(decoded.bounds, map.bounds);
+        assert_eq!(decoded.biomes, map.biomes);
+
+        // Files without a header are still readable.
+        let legacy = rmps::encode::to_vec_named(&map).unwrap();
+        assert_eq!(decode_terrain_map(&legacy).unwrap().biomes, map.biomes);
+    }
+
+    #[test]
+    fn test_decode_terrain_map_errors() {
+        let bytes = encode_terrain_map(&test_map()).unwrap();
+        assert!(matches!(
+            decode_terrain_map(&bytes[..5]),
+            Err(TerrainMapLoaderError::TruncatedHeader)
+        ));
+        assert!(matches!(
+            decode_terrain_map(&bytes[..bytes.len() - 3]),
+            Err(TerrainMapLoaderError::Decode(_))
+        ));
+        assert!(matches!(
+            decode_terrain_map(&[]),
+            Err(TerrainMapLoaderError::Decode(_))
+        ));
+
+        let mut future = bytes.clone();
+        future[4..6].copy_from_slice(&(TERRAIN_MAP_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            decode_terrain_map(&future),
+            Err(TerrainMapLoaderError::UnsupportedVersion(v)) if v == TERRAIN_MAP_VERSION + 1
+        ));
+
+        let mut short = test_map();
+        short.biomes.pop();
+        assert!(matches!(
+            decode_terrain_map(&encode_terrain_map(&short).unwrap()),
+            Err(TerrainMapLoaderError::SizeMismatch {
+                field: "biomes",
+                expected: 16,
+                actual: 15
+            })
+        ));
+    }
+
+    #[test]
+    fn test_convert_parcel_to_precinct() {
+        assert_eq!(
+            convert_parcel_to_precinct(&IRect::new(-5, 0, 3, 8)),
+            IRect::new(-2, 0, 1, 2)
+        );
+    }
+}
@@ -0,0 +1,48 @@
+//! Terrain contours and groups shared by the terrain unit tests.
+
+use super::{
+    square::SquareArray,
+    terrain_contours::{FloraType, TerrainContour, TerrainContoursTable},
+    terrain_edges::{ContourEdgeIndex, EDGE_LENGTH},
+    terrain_groups::{TerrainGroup, TerrainGroupsTable},
+    PARCEL_SIZE_U,
+};
+
+/// A contour which is flat at the given height.
+pub(crate) fn flat_contour(id: usize, height: i8) -> TerrainContour {
+    TerrainContour {
+        id,
+        height: SquareArray::new(EDGE_LENGTH, height),
+        flora: SquareArray::new(PARCEL_SIZE_U, FloraType::None),
+        has_terrain: true,
+        has_water: false,
+        water: SquareArray::new(0, false),
+    }
+}
+
+/// A contour which ramps from `low` on the west edge to `high` on the east edge.
+pub(crate) fn ramp_contour(id: usize, low: i8, high: i8) -> TerrainContour {
+    let mut contour = flat_contour(id, 0);
+    for x in 0..EDGE_LENGTH {
+        let h = low as i32 + (high as i32 - low as i32) * x as i32 / PARCEL_SIZE_U as i32;
+        for z in 0..EDGE_LENGTH {
+            contour.height.set(x, z, h as i8);
+        }
+    }
+    contour
+}
+
+/// Edge index for a table made of the given contours.
+pub(crate) fn edge_index(contours: Vec<TerrainContour>) -> ContourEdgeIndex {
+    ContourEdgeIndex::new(&TerrainContoursTable::from_contours(contours))
+}
+
+/// A single visible group containing the given contours.
+pub(crate) fn groups(contours: Vec<usize>) -> TerrainGroupsTable {
+    TerrainGroupsTable(vec![TerrainGroup {
+        name: "test".to_string(),
+        visible: true,
+        color: Default::default(),
+        contours,
+    }])
+}
@@ -17,14 +17,13 @@ use super::{
     parcel::{Parcel, ParcelWaterChanged, ShapeRef, ADJACENT_COUNT},
     square::SquareArray,
     terrain_contours::{TerrainContoursHandle, TerrainContoursTable, TerrainContoursTableAsset},
-    terrain_map::TerrainMap,
+    terrain_map::{ParcelWater, TerrainMap},
     water_material::{WaterMaterialResource, ATTRIBUTE_DEPTH_MOTION},
-    PARCEL_HEIGHT_SCALE, PARCEL_MESH_STRIDE, PARCEL_MESH_VERTEX_COUNT, PARCEL_WATER_RESOLUTION,
+    DEFAULT_WATER_LEVEL, PARCEL_HEIGHT_SCALE, PARCEL_MESH_SCALE, PARCEL_MESH_STRIDE,
+    PARCEL_MESH_VERTEX_COUNT, PARCEL_SIZE_F, PARCEL_SIZE_U, PARCEL_WATER_RESOLUTION,
     PARCEL_WATER_VERTEX_COUNT,
 };
 
-const WATER_HEIGHT: f32 = -0.4;
-
 /// Spawns a task for each parcel to compute the water mesh geometry.
 pub fn gen_water_meshes(
     mut commands: Commands,
@@ -58,7 +57,8 @@ pub fn gen_water_meshes(
         //     parcel.coords
         // );
         let shape_refs = parcel.contours;
-        let task = pool.spawn(async move { compute_water_mesh(shape_refs, &shapes) });
+        let water = parcel.water;
+        let task = pool.spawn(async move { compute_water_mesh(shape_refs, water, &shapes) });
         commands
             .entity(entity)
             .insert(ComputeWaterMeshTask(task))
@@ -129,11 +129,15 @@ pub fn insert_water_meshes(
 
 fn compute_water_mesh(
     shape_refs: [ShapeRef; ADJACENT_COUNT],
+    water: [ParcelWater; ADJACENT_COUNT],
     shapes: &Arc<RwLock<TerrainContoursTable>>,
 ) -> Option<Mesh> {
     let shapes_table = shapes.read().unwrap();
     let terrain_shape = shapes_table.get(shape_refs[4].shape as usize);
-    if !terrain_shape.has_water {
+    // The terrain shape only decides whether there is water when the water level around the
+    // parcel hasn't been edited; otherwise there is water wherever the terrain is below it.
+    let default_level = water.iter().all(|w| w.level == DEFAULT_WATER_LEVEL);
+    if default_level && !terrain_shape.has_water {
         return None;
     }
 
@@ -157,15 +161,31 @@ fn compute_water_mesh(
 
     let n = Vec3::new(0., 1., 0.);
 
+    // Whether the terrain at a point of the smoothed height map is below the water surface.
+    // At the default level, only the cells of the contour painted with water are filled.
+    let rotation = shape_refs[4].rotation;
+    let submerged = |x: usize, z: usize| {
+        let pos = Vec2::new(x as f32, z as f32) * PARCEL_MESH_SCALE;
+        if default_level {
+            let cell = pos.as_uvec2().min(UVec2::splat(PARCEL_SIZE_U as u32 - 1));
+            if !terrain_shape.water_at(cell.x as usize, cell.y as usize, rotation) {
+                return false;
+            }
+        }
+        let surface = sample_water(&water, pos);
+        shm.get(x, z) * PARCEL_HEIGHT_SCALE < surface.level - DEFAULT_WATER_LEVEL
+    };
+
     let mut vertex_at = |x: usize, z: usize| {
         return match index_map.get(&UVec2::new(x as u32, z as u32)) {
             Some(&index) => index,
             None => {
                 let depth = shm.get(x * 2, z * 2);
+                let surface = sample_water(&water, Vec2::new(x as f32 * 0.5, z as f32 * 0.5));
                 let index = position.len() as u32;
-                position.push([x as f32 * 0.5, WATER_HEIGHT, z as f32 * 0.5]);
+                position.push([x as f32 * 0.5, surface.level, z as f32 * 0.5]);
                 normal.push(n.to_array());
-                depth_motion.push([depth * -PARCEL_HEIGHT_SCALE, 0., 0.]);
+                depth_motion.push([depth * -PARCEL_HEIGHT_SCALE, surface.flow.x, surface.flow.y]);
                 index
             }
         };
@@ -173,11 +193,11 @@ fn compute_water_mesh(
 
     for z in 0..PARCEL_WATER_RESOLUTION {
         for x in 0..PARCEL_WATER_RESOLUTION {
-            let da = shm.get(x * 2, z * 2);
-            let db = shm.get(x * 2 + 1, z * 2);
-            let dc = shm.get(x * 2 + 1, z * 2 + 1);
-            let dd = shm.get(x * 2, z * 2 + 1);
-            if da < 0. || db < 0. || dc < 0. || dd < 0. {
+            if submerged(x * 2, z * 2)
+                || submerged(x * 2 + 1, z * 2)
+                || submerged(x * 2 + 1, z * 2 + 1)
+                || submerged(x * 2, z * 2 + 1)
+            {
                 let a = vertex_at(x, z);
                 let b = vertex_at(x, z + 1);
                 let c = vertex_at(x + 1, z + 1);
@@ -192,6 +212,10 @@ fn compute_water_mesh(
         }
     }
 
+    if indices.is_empty() {
+        return None;
+    }
+
     mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, position);
     mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normal);
     mesh.insert_attribute(ATTRIBUTE_DEPTH_MOTION, depth_motion);
@@ -199,3 +223,56 @@ fn compute_water_mesh(
     mesh.compute_aabb();
     Some(mesh)
 }
+
+/// Water surface at a point within the parcel, interpolated between the centers of the parcel
+/// and its neighbors so that the surface is continuous across parcel boundaries.
+fn sample_water(water: &[ParcelWater; ADJACENT_COUNT], pos: Vec2) -> ParcelWater {
+    let p = pos / PARCEL_SIZE_F - 0.5;
+    let cell = p.floor();
+    let t = p - cell;
+    let at = |dx: i32, dz: i32| {
+        let x = (cell.x as i32 + dx + 1).clamp(0, 2);
+        let z = (cell.y as i32 + dz + 1).clamp(0, 2);
+        water[(z * 3 + x) as usize]
+    };
+    let (w00, w10, w01, w11) = (at(0, 0), at(1, 0), at(0, 1), at(1, 1));
+    let lerp2 = |a: f32, b: f32, c: f32, d: f32| {
+        let top = a + (b - a) * t.x;
+        let bottom = c + (d - c) * t.x;
+        top + (bottom - top) * t.y
+    };
+    ParcelWater {
+        level: lerp2(w00.level, w10.level, w01.level, w11.level),
+        flow: Vec2::new(
+            lerp2(w00.flow.x, w10.flow.x, w01.flow.x, w11.flow.x),
+            lerp2(w00.flow.y, w10.flow.y, w01.flow.y, w11.flow.y),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_water() {
+        let mut water = [ParcelWater::default(); ADJACENT_COUNT];
+        water[4].level = 1.;
+        water[4].flow = Vec2::X;
+        water[5].level = 3.;
+
+        // At the center of the parcel, the parcel's own surface.
+        let center = sample_water(&water, Vec2::splat(PARCEL_SIZE_F * 0.5));
+        assert_eq!(center.level, 1.);
+        assert_eq!(center.flow, Vec2::X);
+
+        // Halfway to the east neighbor, the average of the two.
+        let east = sample_water(&water, Vec2::new(PARCEL_SIZE_F, PARCEL_SIZE_F * 0.5));
+        assert_eq!(east.level, 2.);
+        assert_eq!(east.flow, Vec2::new(0.5, 0.));
+
+        // Default water everywhere gives the default level.
+        let still = [ParcelWater::default(); ADJACENT_COUNT];
+        assert_eq!(sample_water(&still, Vec2::ZERO).level, DEFAULT_WATER_LEVEL);
+    }
+}
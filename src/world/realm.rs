@@ -1,18 +1,21 @@
 use std::f32::consts::PI;
 
-use bevy::asset::io::Reader;
+use bevy::asset::io::{AssetWriterError, Reader};
+use bevy::asset::saver::{AssetSaver, SavedAsset};
 use bevy::asset::{AssetLoader, LoadContext, LoadedFolder};
 use bevy::pbr::CascadeShadowConfigBuilder;
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
 use bevy::render::view::RenderLayers;
-use futures_lite::AsyncReadExt;
+use futures_lite::{AsyncReadExt, AsyncWriteExt};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::view::layers::ReservedLayers;
+use crate::{
+    random::NoiseSeed, scenery::precinct_format::PrecinctFormat, view::layers::ReservedLayers,
+};
 
-#[derive(Default, Serialize, Deserialize, Clone, Copy)]
+#[derive(Default, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
 pub enum RealmLighting {
     /// Used for interior spaces like caves and dungeons.
     Interior,
@@ -22,10 +25,18 @@ pub enum RealmLighting {
     Exterior,
 }
 
-#[derive(Default, Serialize, Deserialize, TypePath, Asset)]
+#[derive(Default, Serialize, Deserialize, TypePath, Asset, Clone)]
 pub struct RealmData {
     /** Type of lighting for this realm. */
     pub lighting: RealmLighting,
+
+    /** Seed for procedural variation such as flora placement. */
+    #[serde(default)]
+    pub seed: u32,
+
+    /** File format in which new precincts are created. */
+    #[serde(default)]
+    pub precinct_format: PrecinctFormat,
 }
 
 #[derive(Component, Default, Asset, TypePath)]
@@ -42,6 +53,12 @@ pub struct Realm {
     /// Type of lighting for this realm.
     pub lighting: RealmLighting,
 
+    /// Seed for all procedural variation within this realm.
+    pub seed: NoiseSeed,
+
+    /// File format in which new precincts are created.
+    pub precinct_format: PrecinctFormat,
+
     /// Boundary of the map, in parcels, relative to the world origin - sync'd from TerrainMap.
     pub parcel_bounds: IRect,
 
@@ -49,6 +66,13 @@ pub struct Realm {
     pub precinct_bounds: IRect,
 }
 
+/// Triggered when the seed of a realm changes, so that procedural variation can be
+/// regenerated.
+#[derive(Clone, Debug, Event)]
+pub struct RealmSeedChanged {
+    pub realm: Entity,
+}
+
 /// Marker component for realms which are used for generating thumbnails and which aren't part of
 /// the world.
 #[derive(Component, Default, Asset, TypePath)]
@@ -93,6 +117,39 @@ impl AssetLoader for RealmsLoader {
     }
 }
 
+#[derive(Default)]
+pub struct RealmDataSaver;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum RealmDataSaverError {
+    #[error("Could not save realm: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not encode realm: {0}")]
+    Encode(#[from] serde_json::Error),
+    #[error("Could not commit realm: {0}")]
+    Commit(#[from] AssetWriterError),
+}
+
+impl AssetSaver for RealmDataSaver {
+    type Asset = RealmData;
+    type Error = RealmDataSaverError;
+    type Settings = ();
+
+    type OutputLoader = RealmsLoader;
+
+    async fn save<'a>(
+        &'a self,
+        writer: &'a mut bevy::asset::io::Writer,
+        asset: SavedAsset<'a, Self::Asset>,
+        _settings: &'a Self::Settings,
+    ) -> Result<(), RealmDataSaverError> {
+        let v = serde_json::to_vec_pretty(&*asset)?;
+        writer.write_all(&v).await?;
+        Ok(())
+    }
+}
+
 #[derive(Resource)]
 pub struct RealmsHandleResource(pub Handle<LoadedFolder>);
 
@@ -122,9 +179,14 @@ pub fn sync_realms(
                 let layer_index = r_layers.next_unused();
 
                 let mut exists = false;
-                for (_, mut comp) in q_realms.iter_mut() {
+                for (entity, mut comp) in q_realms.iter_mut() {
                     if comp.name == realm_name {
                         comp.lighting = realm.lighting;
+                        comp.precinct_format = realm.precinct_format;
+                        if comp.seed.seed() != realm.seed {
+                            comp.seed = NoiseSeed::new(realm.seed);
+                            commands.trigger(RealmSeedChanged { realm: entity });
+                        }
                         exists = true;
                     }
                 }
@@ -137,6 +199,8 @@ pub fn sync_realms(
                         layer: render_layer.clone(),
                         name: realm_name.clone(),
                         lighting: realm.lighting,
+                        seed: NoiseSeed::new(realm.seed),
+                        precinct_format: realm.precinct_format,
                         parcel_bounds: IRect::default(),
                         precinct_bounds: IRect::default(),
                     });
@@ -175,14 +239,23 @@ pub fn sync_realms(
                 let realm = assets.get(*id).unwrap();
                 let realm_name = realm_name_from_id(&server, id);
                 println!("Realm modified: [{}].", realm_name);
-                for (_, mut comp) in q_realms.iter_mut() {
+                for (entity, mut comp) in q_realms.iter_mut() {
                     if comp.name == realm_name {
                         comp.lighting = realm.lighting;
+                        comp.precinct_format = realm.precinct_format;
+                        if comp.seed.seed() != realm.seed {
+                            comp.seed = NoiseSeed::new(realm.seed);
+                            commands.trigger(RealmSeedChanged { realm: entity });
+                        }
                     }
                 }
             }
 
             AssetEvent::Removed { id } => {
+                // Realms deleted in the editor may have already dropped their last handle.
+                if server.get_path(*id).is_none() {
+                    continue;
+                }
                 let realm_name = realm_name_from_id(&server, id);
                 println!("Realm removed: [{}].", realm_name);
                 let mut layers_to_remove = RenderLayers::none();